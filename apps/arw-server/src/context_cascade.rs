@@ -278,6 +278,7 @@ async fn persist_summary(state: &AppState, summary: &CascadeSummary) -> Result<V
         value: summary.value.clone(),
         embed: None,
         embed_hint: None,
+        normalize_on_insert: false,
         tags: Some(summary.tags.clone()),
         score: summary.score,
         prob: summary.prob,
@@ -295,6 +296,7 @@ async fn persist_summary(state: &AppState, summary: &CascadeSummary) -> Result<V
         links: None,
         extra: Some(summary.extra.clone()),
         hash: None,
+        on_conflict: Default::default(),
     };
     let hash = record.compute_hash();
     record.hash = Some(hash.clone());
@@ -187,6 +187,7 @@ impl MemoryUpsertInput {
             value: normalized.value,
             embed: embedding,
             embed_hint,
+            normalize_on_insert: false,
             tags: if tags.is_empty() { None } else { Some(tags) },
             score: normalized.score,
             prob: normalized.prob,
@@ -208,6 +209,7 @@ impl MemoryUpsertInput {
             links,
             extra,
             hash: None,
+            on_conflict: Default::default(),
         }
     }
 }
@@ -539,6 +539,7 @@ async fn upsert_thread(
         value: Value::Object(value_obj),
         embed: existing_embed,
         embed_hint: existing_embed_hint,
+        normalize_on_insert: false,
         tags: if tags.is_empty() { None } else { Some(tags) },
         score: Some(avg_weight as f64),
         prob: Some(avg_weight as f64),
@@ -563,6 +564,7 @@ async fn upsert_thread(
         links: None,
         extra: Some(Value::Object(extra_map)),
         hash: None,
+        on_conflict: Default::default(),
     };
 
     state
@@ -1,10 +1,10 @@
-use anyhow::{anyhow, Result};
-use arw_memory_core::{MemoryInsertArgs, MemoryInsertOwned, MemoryStore};
+use anyhow::{anyhow, Context as _, Result};
+use arw_memory_core::{MemoryImportOptions, MemoryInsertArgs, MemoryInsertOwned, MemoryStore};
 use chrono::{DateTime, Utc};
 use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -14,7 +14,12 @@ use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
 use uuid::Uuid;
 
-pub use arw_memory_core::{MemoryGcCandidate, MemoryGcReason};
+pub use arw_memory_core::{
+    MemoryFilters, MemoryGcCandidate, MemoryGcReason, MemoryLaneConfig, ReadContext, TagMatch,
+};
+
+#[cfg(test)]
+mod test_support;
 
 #[derive(Clone)]
 pub struct Kernel {
@@ -24,11 +29,51 @@ pub struct Kernel {
     checkpoint: Option<Arc<CheckpointCtl>>,
     prune: Option<Arc<PruneCtl>>,
     autotune: Option<Arc<AutotuneCtl>>,
+    maintenance: Option<Arc<MaintenanceCtl>>,
     blocking: BlockingPool,
+    /// Workspace this handle is bound to; `None` is the unscoped operator
+    /// view. See [`Kernel::scoped_to_workspace`].
+    workspace: Option<String>,
+    /// Opened via [`Kernel::open_read_only`]; mutating APIs refuse early.
+    read_only: bool,
+    clock: Arc<dyn Clock>,
+    event_subs: Arc<Mutex<Vec<EventSubscriber>>>,
+    memory_compactor: Arc<Mutex<Option<MemoryCompactor>>>,
+}
+
+/// Lane-overflow summarization callback registered via
+/// [`Kernel::register_memory_compactor`]. Receives the lane and its
+/// hydrated overflow records; returning a record folds the batch into
+/// that summary before deletion, returning `None` leaves the batch alone.
+pub type MemoryCompactor =
+    Arc<dyn Fn(&str, &[serde_json::Value]) -> Result<Option<MemoryInsertOwned>> + Send + Sync>;
+
+/// Returned (inside `anyhow::Error`) by mutating APIs on a kernel opened
+/// with [`Kernel::open_read_only`]; downcast to tell "read-only handle"
+/// apart from real storage failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOnlyKernelError;
+
+impl std::fmt::Display for ReadOnlyKernelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("kernel was opened read-only")
+    }
+}
+
+impl std::error::Error for ReadOnlyKernelError {}
+
+/// One live subscription registered via [`Kernel::subscribe_events_filtered`].
+/// Prefix matching happens before the channel push so narrowly-interested
+/// consumers aren't woken for unrelated kinds.
+struct EventSubscriber {
+    prefixes: Vec<String>,
+    tx: tokio::sync::mpsc::UnboundedSender<EventRow>,
 }
 
 pub struct KernelSession {
     conn: ManagedConnection,
+    workspace: Option<String>,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Clone)]
@@ -39,6 +84,9 @@ struct KernelPragmas {
     cache_pages: i64,
     temp_store: String,
     mmap_bytes: Option<i64>,
+    /// Open connections with `SQLITE_OPEN_READONLY` and skip pragmas that
+    /// would write to the database file. See [`Kernel::open_read_only`].
+    read_only: bool,
 }
 
 struct PoolShared {
@@ -48,13 +96,34 @@ struct PoolShared {
     target_size: AtomicUsize,
     min_size: usize,
     max_ceiling: usize,
+    /// Recycle a pooled connection after this long, regardless of health.
+    max_lifetime: Option<Duration>,
+    /// Recycle a pooled connection after this many checkouts.
+    max_uses: Option<u64>,
 }
 
 struct PoolState {
-    conns: Vec<Connection>,
+    conns: Vec<PooledConn>,
     created: usize,
 }
 
+/// A pooled connection plus the bookkeeping the recycling policy needs.
+struct PooledConn {
+    conn: Connection,
+    created_at: Instant,
+    uses: u64,
+}
+
+impl PooledConn {
+    fn new(conn: Connection) -> Self {
+        Self {
+            conn,
+            created_at: Instant::now(),
+            uses: 1,
+        }
+    }
+}
+
 #[derive(Default)]
 struct WaitStats {
     count: u64,
@@ -62,7 +131,7 @@ struct WaitStats {
 }
 
 struct ManagedConnection {
-    conn: Option<Connection>,
+    conn: Option<PooledConn>,
     pool: Arc<PoolShared>,
 }
 
@@ -81,6 +150,403 @@ struct AutotuneCtl {
     handle: Mutex<Option<thread::JoinHandle<()>>>,
 }
 
+struct MaintenanceCtl {
+    stop: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+/// Retention policy for the events table, applied by the background prune
+/// loop. Built from the environment; all knobs are optional.
+#[derive(Clone, Debug, Default)]
+struct EventRetention {
+    /// Cap on total rows; the oldest non-exempt rows beyond it are deleted.
+    max_rows: Option<u64>,
+    /// Age limit for every kind not matched by an override below.
+    max_age: Option<Duration>,
+    /// Kind prefixes the global rules never touch (e.g. `egress.`).
+    exempt_prefixes: Vec<String>,
+    /// Per-kind age overrides, matched by prefix. These win over `max_age`
+    /// and are not subject to `exempt_prefixes`.
+    kind_max_age: Vec<(String, Duration)>,
+}
+
+impl EventRetention {
+    fn from_env() -> Self {
+        let max_rows = std::env::var("ARW_EVENTS_MAX_ROWS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .or(Some(100_000))
+            .filter(|v| *v > 0);
+        let max_age = std::env::var("ARW_EVENTS_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .or(Some(7))
+            .filter(|v| *v > 0)
+            .map(|d| Duration::from_secs(d.saturating_mul(86_400)));
+        // Comma-separated kind prefixes, e.g. "egress.,policy."
+        let exempt_prefixes: Vec<String> = std::env::var("ARW_EVENTS_RETENTION_EXEMPT")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        // Comma-separated "prefix=days" pairs, e.g. "models.download.progress=1"
+        let kind_max_age: Vec<(String, Duration)> = std::env::var("ARW_EVENTS_RETENTION_OVERRIDES")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .filter_map(|pair| {
+                        let (prefix, days) = pair.split_once('=')?;
+                        let prefix = prefix.trim();
+                        let days = days.trim().parse::<u64>().ok().filter(|d| *d > 0)?;
+                        (!prefix.is_empty()).then(|| {
+                            (
+                                prefix.to_string(),
+                                Duration::from_secs(days.saturating_mul(86_400)),
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            max_rows,
+            max_age,
+            exempt_prefixes,
+            kind_max_age,
+        }
+    }
+
+    fn is_noop(&self) -> bool {
+        self.max_rows.is_none() && self.max_age.is_none() && self.kind_max_age.is_empty()
+    }
+}
+
+/// Retention policy for the egress ledger, applied by the same prune loop.
+/// Off by default: the ledger is an audit trail, so rows only leave it when
+/// an operator opts in. Rollups are folded before any rows are removed, so
+/// aggregates survive rotation and purges.
+#[derive(Clone, Debug, Default)]
+struct EgressRetention {
+    /// Age limit for raw ledger rows; `None` keeps them forever.
+    max_age: Option<Duration>,
+    /// When set, expired rows are rotated into gzip NDJSON segments under
+    /// this directory instead of being deleted outright.
+    archive_dir: Option<PathBuf>,
+}
+
+impl EgressRetention {
+    fn from_env() -> Self {
+        let max_age = std::env::var("ARW_EGRESS_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .map(|d| Duration::from_secs(d.saturating_mul(86_400)));
+        let archive_dir = std::env::var("ARW_EGRESS_ARCHIVE_DIR")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        Self {
+            max_age,
+            archive_dir,
+        }
+    }
+}
+
+/// One forward-only schema change, applied at most once per database and
+/// recorded in `schema_version`.
+struct SchemaMigration {
+    version: i64,
+    description: &'static str,
+    /// Column this migration introduces. Installs that predate the version
+    /// table may already have it from the old ad-hoc `ALTER TABLE` backfills;
+    /// when present the step is stamped without re-running its DDL. An empty
+    /// column name means the DDL is idempotent (`IF NOT EXISTS`) and always
+    /// runs — used for index-only migrations.
+    column: (&'static str, &'static str),
+    sql: &'static str,
+}
+
+/// Ordered, append-only list of schema changes since the version table was
+/// introduced. Never reorder or edit entries; add new versions at the end.
+const SCHEMA_MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration {
+        version: 1,
+        description: "egress_ledger: extended metadata blob",
+        column: ("egress_ledger", "meta"),
+        sql: "ALTER TABLE egress_ledger ADD COLUMN meta TEXT",
+    },
+    SchemaMigration {
+        version: 2,
+        description: "orchestrator_jobs: retained data history",
+        column: ("orchestrator_jobs", "data_history"),
+        sql: "ALTER TABLE orchestrator_jobs ADD COLUMN data_history TEXT",
+    },
+    SchemaMigration {
+        version: 3,
+        description: "config_snapshots: content digest for dedup",
+        column: ("config_snapshots", "sha256"),
+        sql: "ALTER TABLE config_snapshots ADD COLUMN sha256 TEXT",
+    },
+    SchemaMigration {
+        version: 4,
+        description: "actions: mergeable worker metadata",
+        column: ("actions", "meta"),
+        sql: "ALTER TABLE actions ADD COLUMN meta TEXT",
+    },
+    SchemaMigration {
+        version: 5,
+        description: "events: full-text index over kind and payload",
+        column: ("events_fts", "kind"),
+        sql: r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS events_fts USING fts5(
+              id UNINDEXED,
+              kind,
+              payload
+            );
+            INSERT INTO events_fts(id, kind, payload)
+              SELECT id, kind, payload FROM events WHERE typeof(payload)='text';
+        "#,
+    },
+    SchemaMigration {
+        version: 6,
+        description: "actions: dequeue priority",
+        column: ("actions", "priority"),
+        sql: r#"
+            ALTER TABLE actions ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+            CREATE INDEX IF NOT EXISTS idx_actions_queue ON actions(state, priority DESC, created);
+        "#,
+    },
+    SchemaMigration {
+        version: 7,
+        description: "actions: scheduled eligibility (run_after)",
+        column: ("actions", "run_after"),
+        sql: "ALTER TABLE actions ADD COLUMN run_after TEXT",
+    },
+    SchemaMigration {
+        version: 8,
+        description: "actions: retry attempt counter",
+        column: ("actions", "attempts"),
+        sql: "ALTER TABLE actions ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0",
+    },
+    SchemaMigration {
+        version: 9,
+        description: "actions: prerequisite dependency edges",
+        column: ("action_deps", "action_id"),
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS action_deps (
+              action_id TEXT NOT NULL,
+              depends_on TEXT NOT NULL,
+              created TEXT NOT NULL,
+              PRIMARY KEY (action_id, depends_on)
+            );
+            CREATE INDEX IF NOT EXISTS idx_action_deps_on ON action_deps(depends_on);
+        "#,
+    },
+    SchemaMigration {
+        version: 10,
+        description: "leases: transition audit trail",
+        column: ("lease_events", "lease_id"),
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS lease_events (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              lease_id TEXT NOT NULL,
+              event TEXT NOT NULL,
+              subject TEXT,
+              capability TEXT,
+              reason TEXT,
+              actor TEXT,
+              time TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_lease_events_lease ON lease_events(lease_id, time);
+            CREATE INDEX IF NOT EXISTS idx_lease_events_subject ON lease_events(subject, capability);
+        "#,
+    },
+    SchemaMigration {
+        version: 11,
+        description: "egress: hourly rollup table and cursor",
+        column: ("egress_rollups", "bucket_start"),
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS egress_rollups (
+              bucket_start TEXT NOT NULL,
+              dest_host TEXT NOT NULL DEFAULT '',
+              proj TEXT NOT NULL DEFAULT '',
+              posture TEXT NOT NULL DEFAULT '',
+              decision TEXT NOT NULL DEFAULT '',
+              events INTEGER NOT NULL,
+              bytes_in INTEGER NOT NULL,
+              bytes_out INTEGER NOT NULL,
+              PRIMARY KEY (bucket_start, dest_host, proj, posture, decision)
+            );
+            CREATE TABLE IF NOT EXISTS egress_rollup_cursor (
+              id INTEGER PRIMARY KEY CHECK (id = 1),
+              last_event_id INTEGER NOT NULL
+            );
+        "#,
+    },
+    SchemaMigration {
+        version: 12,
+        description: "events: optional tamper-evident hash chain",
+        column: ("events", "chain_hash"),
+        sql: "ALTER TABLE events ADD COLUMN chain_hash TEXT",
+    },
+    SchemaMigration {
+        version: 13,
+        description: "multi-workspace scoping for events, actions, and leases",
+        column: ("events", "workspace_id"),
+        sql: r#"
+            ALTER TABLE events ADD COLUMN workspace_id TEXT;
+            ALTER TABLE actions ADD COLUMN workspace_id TEXT;
+            ALTER TABLE leases ADD COLUMN workspace_id TEXT;
+        "#,
+    },
+    SchemaMigration {
+        version: 14,
+        description: "soft-delete tombstones for actions and staging actions",
+        column: ("actions", "deleted_at"),
+        sql: r#"
+            ALTER TABLE actions ADD COLUMN deleted_at TEXT;
+            ALTER TABLE staging_actions ADD COLUMN deleted_at TEXT;
+        "#,
+    },
+    SchemaMigration {
+        version: 15,
+        description: "actions: incremental output chunks for streaming",
+        column: ("action_output_chunks", "seq"),
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS action_output_chunks (
+              action_id TEXT NOT NULL,
+              seq INTEGER NOT NULL,
+              data TEXT NOT NULL,
+              created TEXT NOT NULL,
+              PRIMARY KEY (action_id, seq)
+            );
+        "#,
+    },
+    SchemaMigration {
+        version: 16,
+        description: "contributions: daily rollup table and cursor",
+        column: ("contribution_rollups", "bucket_start"),
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS contribution_rollups (
+              bucket_start TEXT NOT NULL,
+              subject TEXT NOT NULL DEFAULT '',
+              kind TEXT NOT NULL DEFAULT '',
+              unit TEXT NOT NULL DEFAULT '',
+              events INTEGER NOT NULL,
+              qty REAL NOT NULL,
+              PRIMARY KEY (bucket_start, subject, kind, unit)
+            );
+            CREATE TABLE IF NOT EXISTS contribution_rollup_cursor (
+              id INTEGER PRIMARY KEY CHECK (id = 1),
+              last_event_id INTEGER NOT NULL
+            );
+        "#,
+    },
+    SchemaMigration {
+        version: 17,
+        description: "contributions: per-capability quota limits",
+        column: ("quotas", "max_qty"),
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS quotas (
+              subject TEXT NOT NULL,
+              kind TEXT NOT NULL,
+              max_qty REAL NOT NULL,
+              window_secs INTEGER NOT NULL,
+              created TEXT NOT NULL,
+              updated TEXT NOT NULL,
+              PRIMARY KEY (subject, kind)
+            );
+        "#,
+    },
+    SchemaMigration {
+        version: 18,
+        description: "config_snapshots: human labels and annotations",
+        column: ("config_snapshots", "label"),
+        sql: r#"
+            ALTER TABLE config_snapshots ADD COLUMN label TEXT;
+            ALTER TABLE config_snapshots ADD COLUMN note TEXT;
+        "#,
+    },
+    SchemaMigration {
+        version: 19,
+        description: "config_snapshots: rollback provenance and pinning",
+        column: ("config_snapshots", "pinned"),
+        sql: r#"
+            ALTER TABLE config_snapshots ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE config_snapshots ADD COLUMN rolled_back_from TEXT;
+        "#,
+    },
+    SchemaMigration {
+        version: 20,
+        description: "orchestrator_jobs: deadlines and terminal reasons",
+        column: ("orchestrator_jobs", "deadline"),
+        sql: r#"
+            ALTER TABLE orchestrator_jobs ADD COLUMN deadline TEXT;
+            ALTER TABLE orchestrator_jobs ADD COLUMN reason TEXT;
+        "#,
+    },
+    SchemaMigration {
+        version: 21,
+        description: "orchestrator_jobs: parent/child hierarchies",
+        column: ("orchestrator_jobs", "parent_id"),
+        sql: "ALTER TABLE orchestrator_jobs ADD COLUMN parent_id TEXT",
+    },
+    SchemaMigration {
+        version: 22,
+        description: "orchestrator_jobs: worker claims with expiring leases",
+        column: ("orchestrator_jobs", "worker_id"),
+        sql: r#"
+            ALTER TABLE orchestrator_jobs ADD COLUMN worker_id TEXT;
+            ALTER TABLE orchestrator_jobs ADD COLUMN lease_until TEXT;
+        "#,
+    },
+    SchemaMigration {
+        version: 23,
+        description: "logic_units: retained manifest version history",
+        column: ("logic_unit_versions", "version"),
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS logic_unit_versions (
+              unit_id TEXT NOT NULL,
+              version INTEGER NOT NULL,
+              manifest TEXT NOT NULL,
+              status TEXT NOT NULL,
+              created TEXT NOT NULL,
+              PRIMARY KEY (unit_id, version)
+            );
+        "#,
+    },
+    SchemaMigration {
+        version: 24,
+        description: "persona_proposals: per-reviewer votes with approval threshold",
+        column: ("persona_proposals", "required_approvals"),
+        sql: r#"
+            ALTER TABLE persona_proposals ADD COLUMN required_approvals INTEGER NOT NULL DEFAULT 1;
+            CREATE TABLE IF NOT EXISTS persona_proposal_votes (
+              proposal_id TEXT NOT NULL,
+              reviewer TEXT NOT NULL,
+              decision TEXT NOT NULL,
+              note TEXT,
+              updated TEXT NOT NULL,
+              PRIMARY KEY (proposal_id, reviewer)
+            );
+        "#,
+    },
+    SchemaMigration {
+        version: 25,
+        description: "events: composite actor/proj indexes for attribution queries",
+        column: ("events", ""),
+        sql: r#"
+            CREATE INDEX IF NOT EXISTS idx_events_actor ON events(actor, id);
+            CREATE INDEX IF NOT EXISTS idx_events_proj ON events(proj, id);
+        "#,
+    },
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersonaEntry {
     pub id: String,
@@ -133,6 +599,22 @@ pub struct PersonaProposal {
     pub status: String,
     pub created: String,
     pub updated: String,
+    #[serde(default = "default_required_approvals")]
+    pub required_approvals: i64,
+}
+
+fn default_required_approvals() -> i64 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonaProposalVote {
+    pub proposal_id: String,
+    pub reviewer: String,
+    pub decision: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    pub updated: String,
 }
 
 #[derive(Debug, Clone)]
@@ -268,6 +750,22 @@ impl PoolShared {
         }
     }
 
+    /// Whether the recycling policy says this idle connection is done,
+    /// independent of whether it still works.
+    fn is_stale(&self, pooled: &PooledConn) -> bool {
+        if let Some(max) = self.max_lifetime {
+            if pooled.created_at.elapsed() >= max {
+                return true;
+            }
+        }
+        if let Some(max) = self.max_uses {
+            if pooled.uses >= max {
+                return true;
+            }
+        }
+        false
+    }
+
     fn shrink_to(&self, target: usize) {
         let mut guard = self.state.lock().expect("pool mutex poisoned");
         while guard.created > target {
@@ -287,13 +785,13 @@ impl Deref for ManagedConnection {
     type Target = Connection;
 
     fn deref(&self) -> &Self::Target {
-        self.conn.as_ref().expect("connection already taken")
+        &self.conn.as_ref().expect("connection already taken").conn
     }
 }
 
 impl DerefMut for ManagedConnection {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.conn.as_mut().expect("connection already taken")
+        &mut self.conn.as_mut().expect("connection already taken").conn
     }
 }
 
@@ -365,6 +863,29 @@ impl AutotuneCtl {
     }
 }
 
+impl MaintenanceCtl {
+    fn new(stop: Arc<AtomicBool>, handle: thread::JoinHandle<()>) -> Self {
+        Self {
+            stop,
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+}
+
+impl Drop for MaintenanceCtl {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self
+            .handle
+            .lock()
+            .expect("maintenance join mutex poisoned")
+            .take()
+        {
+            let _ = handle.join();
+        }
+    }
+}
+
 impl Drop for AutotuneCtl {
     fn drop(&mut self) {
         self.stop.store(true, Ordering::Relaxed);
@@ -457,6 +978,10 @@ impl BlockingPool {
         Ok(Self { state })
     }
 
+    fn queue_depth(&self) -> usize {
+        self.state.queue.lock().map(|q| q.len()).unwrap_or(0)
+    }
+
     async fn run<F, R>(&self, job: F) -> Result<R>
     where
         F: FnOnce() -> Result<R> + Send + 'static,
@@ -584,6 +1109,7 @@ impl KernelPragmas {
             cache_pages,
             temp_store: "MEMORY".to_string(),
             mmap_bytes,
+            read_only: false,
         }
     }
 }
@@ -613,6 +1139,59 @@ pub struct EventRow {
     pub payload: serde_json::Value,
 }
 
+/// One page of events plus cursors for walking older/newer history.
+/// Items are always newest-first; cursors are opaque strings.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventPage {
+    pub items: Vec<EventRow>,
+    /// Continues in the direction travelled (older by default); `None` when
+    /// this page exhausted that direction.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub next_cursor: Option<String>,
+    /// Walks back toward newer events from the top of this page.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub prev_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// Summary of one `archive_events` run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventArchiveReport {
+    /// Segment file names written under the destination directory, in order.
+    pub segments: Vec<String>,
+    /// Events moved out of SQLite.
+    pub events: u64,
+    /// The RFC3339 bound the run archived up to (exclusive).
+    pub before: String,
+}
+
+/// Outcome of a CAS blob sweep; with `dry_run` set nothing was removed and
+/// the delete counters describe what a real run would reclaim.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CasGcReport {
+    /// Blobs examined under `blobs/`.
+    pub scanned: u64,
+    /// Blobs kept because they are referenced or too young to judge.
+    pub kept: u64,
+    /// Unreferenced blobs removed (or that would be, in dry-run).
+    pub deleted: u64,
+    /// Bytes reclaimed by the removed blobs.
+    pub deleted_bytes: u64,
+    pub dry_run: bool,
+}
+
+/// One row of the `artifacts` table. `bytes` holds the inline payload for
+/// small artifacts; larger ones live in the CAS blob directory under the
+/// same digest and leave it `None`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArtifactRow {
+    pub sha256: String,
+    pub mime: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+    pub meta: Option<JsonValue>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ActionRow {
     pub id: String,
@@ -621,10 +1200,21 @@ pub struct ActionRow {
     pub policy_ctx: Option<serde_json::Value>,
     pub idem_key: Option<String>,
     pub state: String,
+    /// Higher values dequeue first; ties fall back to FIFO by created time.
+    #[serde(default)]
+    pub priority: i64,
+    /// RFC3339 timestamp before which the action is not eligible to run.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub run_after: Option<String>,
+    /// Failed attempts so far; bumped by [`Kernel::fail_action_with_retry`].
+    #[serde(default)]
+    pub attempts: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub meta: Option<serde_json::Value>,
     pub created: String,
     pub updated: String,
 }
@@ -661,11 +1251,78 @@ pub struct StagingAction {
     pub updated: String,
 }
 
+/// Source of row timestamps. Production code uses [`SystemClock`]; tests can
+/// inject a [`MockClock`] to drive TTL expiry and recency deterministically.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Default wall-clock implementation.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Manually advanced clock for deterministic tests.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    pub fn advance(&self, delta: chrono::Duration) {
+        let mut guard = self.now.lock().expect("mock clock poisoned");
+        *guard += delta;
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().expect("mock clock poisoned") = now;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("mock clock poisoned")
+    }
+}
+
 impl Kernel {
     pub fn open(dir: &Path) -> Result<Self> {
+        Self::open_with_clock(dir, Arc::new(SystemClock))
+    }
+
+    /// Open the kernel with an explicit [`Clock`] for row timestamps.
+    pub fn open_with_clock(dir: &Path, clock: Arc<dyn Clock>) -> Result<Self> {
+        Self::open_inner(dir, clock, false)
+    }
+
+    /// Open an existing database with `SQLITE_OPEN_READONLY`. No schema
+    /// init or migrations run, no maintenance threads start, and mutating
+    /// APIs fail with [`ReadOnlyKernelError`]. Meant for analytics sidecars
+    /// and debug tooling pointed at a live database.
+    pub fn open_read_only(dir: &Path) -> Result<Self> {
+        Self::open_inner(dir, Arc::new(SystemClock), true)
+    }
+
+    fn open_inner(dir: &Path, clock: Arc<dyn Clock>, read_only: bool) -> Result<Self> {
         let db_path = dir.join("events.sqlite");
         let need_init = !db_path.exists();
-        let pragmas = Arc::new(KernelPragmas::from_env());
+        if read_only && need_init {
+            anyhow::bail!("no kernel database at {}", db_path.display());
+        }
+        let mut pragma_values = KernelPragmas::from_env();
+        pragma_values.read_only = read_only;
+        let pragmas = Arc::new(pragma_values);
         // Keep the SQLite pool small by default to avoid lock storms in dev/local runs.
         let pool_min_size = std::env::var("ARW_SQLITE_POOL_MIN")
             .ok()
@@ -686,14 +1343,27 @@ impl Kernel {
             .unwrap_or(2)
             .clamp(pool_min_size, pool_max_ceiling)
             .min(4);
-        let conn = Connection::open(&db_path)?;
+        let conn = Self::open_db(&db_path, &pragmas)?;
         Kernel::apply_pragmas(&conn, &pragmas)?;
-        if need_init {
-            Self::init_schema(&conn)?;
+        if !read_only {
+            if need_init {
+                Self::init_schema(&conn)?;
+            }
+            Self::run_schema_migrations(&conn, false)?;
+            MemoryStore::migrate(&conn)?;
         }
+        let pool_max_lifetime = std::env::var("ARW_SQLITE_POOL_MAX_LIFETIME_SEC")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .map(Duration::from_secs);
+        let pool_max_uses = std::env::var("ARW_SQLITE_POOL_MAX_USES")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|v| *v > 0);
         let pool = Arc::new(PoolShared {
             state: Mutex::new(PoolState {
-                conns: vec![conn],
+                conns: vec![PooledConn::new(conn)],
                 created: 1,
             }),
             wait_stats: Mutex::new(WaitStats::default()),
@@ -701,6 +1371,8 @@ impl Kernel {
             target_size: AtomicUsize::new(initial_target),
             min_size: pool_min_size,
             max_ceiling: pool_max_ceiling,
+            max_lifetime: pool_max_lifetime,
+            max_uses: pool_max_uses,
         });
         {
             let guard = pool.state.lock().expect("pool mutex poisoned");
@@ -714,8 +1386,18 @@ impl Kernel {
             checkpoint: None,
             prune: None,
             autotune: None,
+            maintenance: None,
             blocking,
+            clock,
+            workspace: None,
+            read_only,
+            event_subs: Arc::new(Mutex::new(Vec::new())),
+            memory_compactor: Arc::new(Mutex::new(None)),
         };
+        if read_only {
+            // A read-only handle has nothing to checkpoint, prune or tune.
+            return Ok(kernel);
+        }
         let checkpoint_secs = match std::env::var("ARW_SQLITE_CHECKPOINT_SEC")
             .ok()
             .and_then(|v| v.parse::<u64>().ok())
@@ -733,20 +1415,11 @@ impl Kernel {
             .and_then(|v| v.parse::<u64>().ok())
             .unwrap_or(300);
         if prune_secs > 0 {
-            let max_rows = std::env::var("ARW_EVENTS_MAX_ROWS")
-                .ok()
-                .and_then(|v| v.parse::<u64>().ok())
-                .or(Some(100_000))
-                .filter(|v| *v > 0);
-            let max_age_days = std::env::var("ARW_EVENTS_RETENTION_DAYS")
-                .ok()
-                .and_then(|v| v.parse::<u64>().ok())
-                .or(Some(7))
-                .filter(|v| *v > 0);
             let _ = kernel.start_prune_loop(
                 Duration::from_secs(prune_secs),
-                max_rows,
-                max_age_days.map(|d| Duration::from_secs(d.saturating_mul(86_400))),
+                EventRetention::from_env(),
+                EgressRetention::from_env(),
+                Kernel::idem_ttl(),
             );
         }
 
@@ -767,11 +1440,24 @@ impl Kernel {
                 .unwrap_or(50.0);
             let _ = kernel.start_autotune_loop(interval, wait_threshold_ms);
         }
+
+        let maintenance_secs = std::env::var("ARW_SQLITE_MAINTENANCE_SEC")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(86_400);
+        if maintenance_secs > 0 {
+            let _ = kernel.start_maintenance_loop(Duration::from_secs(maintenance_secs));
+        }
         Ok(kernel)
     }
 
-    fn start_checkpoint_loop(&mut self, interval: Duration) -> Result<()> {
-        if interval.is_zero() || self.checkpoint.is_some() {
+    /// Periodic `PRAGMA optimize` / `ANALYZE` / incremental vacuum so query
+    /// plans stay healthy on long-lived installs. `ARW_SQLITE_MAINTENANCE_SEC`
+    /// sets the cadence (default daily, 0 disables). Ticks that land while
+    /// the pool is saturated are deferred rather than competing for a
+    /// connection.
+    fn start_maintenance_loop(&mut self, interval: Duration) -> Result<()> {
+        if interval.is_zero() || self.maintenance.is_some() {
             return Ok(());
         }
         let stop_flag = Arc::new(AtomicBool::new(false));
@@ -780,7 +1466,7 @@ impl Kernel {
         let pragmas = self.pragmas.clone();
         let stop_clone = stop_flag.clone();
         let handle = thread::Builder::new()
-            .name("arw-kernel-checkpoint".into())
+            .name("arw-kernel-maintenance".into())
             .spawn(move || loop {
                 if stop_clone.load(Ordering::Relaxed) {
                     break;
@@ -800,16 +1486,130 @@ impl Kernel {
                 let Some(pool) = pool_weak.upgrade() else {
                     break;
                 };
-                match Kernel::checkout_connection(&db_path, &pragmas, &pool) {
-                    Ok(conn) => {
+                // Only run in an idle window: every created connection is
+                // checked in, or the pool can still grow one for us.
+                let busy = {
+                    let guard = pool.state.lock().expect("pool mutex poisoned");
+                    guard.created > 0
+                        && guard.conns.is_empty()
+                        && guard.created >= pool.target_size.load(Ordering::Relaxed)
+                };
+                if busy {
+                    #[cfg(feature = "metrics")]
+                    metrics::counter!("arw_kernel_maintenance_deferred").increment(1);
+                    continue;
+                }
+                let checkout = Kernel::checkout_connection(&db_path, &pragmas, &pool);
+                #[cfg(feature = "metrics")]
+                if checkout.is_err() {
+                    metrics::counter!("arw_kernel_maintenance_failures").increment(1);
+                }
+                if let Ok(conn) = checkout {
+                    let started = Instant::now();
+                    let ok = conn
+                        .execute_batch("PRAGMA optimize; ANALYZE; PRAGMA incremental_vacuum(128);")
+                        .is_ok();
+                    let _elapsed = started.elapsed();
+                    #[cfg(feature = "metrics")]
+                    {
+                        if ok {
+                            metrics::counter!("arw_kernel_maintenance_runs").increment(1);
+                        } else {
+                            metrics::counter!("arw_kernel_maintenance_failures").increment(1);
+                        }
+                        metrics::histogram!("arw_kernel_maintenance_duration_ms")
+                            .record(_elapsed.as_secs_f64() * 1000.0);
+                    }
+                    let _ = ok;
+                }
+            })
+            .map_err(|e| anyhow!("failed to spawn maintenance thread: {e}"))?;
+        self.maintenance = Some(Arc::new(MaintenanceCtl::new(stop_flag, handle)));
+        Ok(())
+    }
+
+    /// WAL size (bytes) below which a checkpoint tick is skipped.
+    /// `ARW_SQLITE_CHECKPOINT_WAL_MB` overrides the 4 MiB default; 0 restores
+    /// the old fixed-interval behavior of truncating on every tick.
+    fn checkpoint_wal_threshold_bytes() -> u64 {
+        std::env::var("ARW_SQLITE_CHECKPOINT_WAL_MB")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(4)
+            .saturating_mul(1024 * 1024)
+    }
+
+    fn start_checkpoint_loop(&mut self, interval: Duration) -> Result<()> {
+        if interval.is_zero() || self.checkpoint.is_some() {
+            return Ok(());
+        }
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let pool_weak: Weak<PoolShared> = Arc::downgrade(&self.pool);
+        let db_path = self.db_path.clone();
+        let pragmas = self.pragmas.clone();
+        let stop_clone = stop_flag.clone();
+        let threshold = Self::checkpoint_wal_threshold_bytes();
+        let wal_path = {
+            let mut os = db_path.clone().into_os_string();
+            os.push("-wal");
+            PathBuf::from(os)
+        };
+        // Busy databases checkpoint every `interval`; idle ones back off up
+        // to 8x so the thread isn't stat-ing a cold file twice a minute.
+        let max_delay = interval.saturating_mul(8);
+        let handle = thread::Builder::new()
+            .name("arw-kernel-checkpoint".into())
+            .spawn(move || {
+                let mut delay = interval;
+                loop {
+                    if stop_clone.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let mut slept = Duration::ZERO;
+                    while slept < delay {
+                        if stop_clone.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let step = Duration::from_millis(500).min(delay.saturating_sub(slept));
+                        thread::sleep(step);
+                        slept = slept.saturating_add(step);
+                    }
+                    if stop_clone.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Some(pool) = pool_weak.upgrade() else {
+                        break;
+                    };
+                    let wal_before = std::fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
+                    if wal_before < threshold {
+                        #[cfg(feature = "metrics")]
+                        metrics::counter!("arw_kernel_checkpoint_skipped").increment(1);
+                        delay = delay.saturating_mul(2).min(max_delay);
+                        continue;
+                    }
+                    let checkout = Kernel::checkout_connection(&db_path, &pragmas, &pool);
+                    #[cfg(feature = "metrics")]
+                    if checkout.is_err() {
+                        metrics::counter!("arw_kernel_checkpoint_failures").increment(1);
+                    }
+                    if let Ok(conn) = checkout {
                         #[cfg(feature = "metrics")]
                         metrics::counter!("arw_kernel_checkpoint_runs").increment(1);
+                        let started = Instant::now();
                         let _ = conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);");
-                    }
-                    Err(_) => {
+                        let _elapsed = started.elapsed();
+                        let _reclaimed = wal_before.saturating_sub(
+                            std::fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0),
+                        );
                         #[cfg(feature = "metrics")]
-                        metrics::counter!("arw_kernel_checkpoint_failures").increment(1);
+                        {
+                            metrics::histogram!("arw_kernel_checkpoint_duration_ms")
+                                .record(_elapsed.as_secs_f64() * 1000.0);
+                            metrics::counter!("arw_kernel_checkpoint_wal_reclaimed_bytes")
+                                .increment(_reclaimed);
+                        }
                     }
+                    delay = interval;
                 }
             })
             .map_err(|e| anyhow!("failed to spawn checkpoint thread: {e}"))?;
@@ -820,10 +1620,13 @@ impl Kernel {
     fn start_prune_loop(
         &mut self,
         interval: Duration,
-        max_rows: Option<u64>,
-        max_age: Option<Duration>,
+        retention: EventRetention,
+        egress: EgressRetention,
+        idem_ttl: Option<Duration>,
     ) -> Result<()> {
-        if interval.is_zero() || (max_rows.is_none() && max_age.is_none()) || self.prune.is_some() {
+        // Retention knobs are all optional, but the loop always runs the
+        // orchestrator deadline sweep, so it starts whenever enabled.
+        if interval.is_zero() || self.prune.is_some() {
             return Ok(());
         }
         let stop_flag = Arc::new(AtomicBool::new(false));
@@ -852,14 +1655,71 @@ impl Kernel {
                 let Some(pool) = pool_weak.upgrade() else {
                     break;
                 };
-                match Kernel::checkout_connection(&db_path, &pragmas, &pool) {
-                    Ok(conn) => {
-                        let _ = Kernel::prune_events(&conn, max_rows, max_age);
-                    }
-                    Err(_) => {
+                let checkout = Kernel::checkout_connection(&db_path, &pragmas, &pool);
+                #[cfg(feature = "metrics")]
+                if checkout.is_err() {
+                    metrics::counter!("arw_kernel_prune_failures").increment(1);
+                }
+                if let Ok(mut conn) = checkout {
+                    if !retention.is_noop() {
+                            let _res = Kernel::prune_events(&conn, &retention);
+                            #[cfg(feature = "metrics")]
+                            match _res {
+                                Ok(pruned) => {
+                                    metrics::counter!("arw_kernel_events_pruned").increment(pruned)
+                                }
+                                Err(_) => {
+                                    metrics::counter!("arw_kernel_prune_failures").increment(1)
+                                }
+                            }
+                        }
+                        if let Some(age) = egress.max_age {
+                            let cutoff = (chrono::Utc::now() - age)
+                                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+                            let _res = match &egress.archive_dir {
+                                Some(dir) => Kernel::rotate_egress_conn(
+                                    &mut conn,
+                                    &cutoff,
+                                    dir,
+                                    Kernel::egress_segment_rows(),
+                                )
+                                .map(|r| r.events),
+                                None => Kernel::purge_egress_conn(&mut conn, &cutoff),
+                            };
+                            #[cfg(feature = "metrics")]
+                            match _res {
+                                Ok(pruned) => {
+                                    metrics::counter!("arw_kernel_egress_pruned").increment(pruned)
+                                }
+                                Err(_) => {
+                                    metrics::counter!("arw_kernel_prune_failures").increment(1)
+                                }
+                            }
+                        }
+                        if let Some(ttl) = idem_ttl {
+                            let cutoff = (chrono::Utc::now() - ttl)
+                                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+                            let _res = conn.execute(
+                                "UPDATE actions SET idem_key=NULL WHERE idem_key IS NOT NULL AND created < ?",
+                                params![cutoff],
+                            );
+                            #[cfg(feature = "metrics")]
+                            match _res {
+                                Ok(n) => metrics::counter!("arw_kernel_idem_keys_pruned")
+                                    .increment(n as u64),
+                                Err(_) => {
+                                    metrics::counter!("arw_kernel_prune_failures").increment(1)
+                                }
+                            }
+                        }
+                        let now = chrono::Utc::now()
+                            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+                        let _res = Kernel::sweep_overdue_jobs_conn(&conn, &now);
                         #[cfg(feature = "metrics")]
-                        metrics::counter!("arw_kernel_prune_failures").increment(1);
-                    }
+                        match _res {
+                            Ok(n) => metrics::counter!("arw_kernel_jobs_timed_out").increment(n),
+                            Err(_) => metrics::counter!("arw_kernel_prune_failures").increment(1),
+                        }
                 }
             })
             .map_err(|e| anyhow!("failed to spawn prune thread: {e}"))?;
@@ -867,28 +1727,67 @@ impl Kernel {
         Ok(())
     }
 
-    fn prune_events(
-        conn: &Connection,
-        max_rows: Option<u64>,
-        max_age: Option<Duration>,
-    ) -> rusqlite::Result<()> {
-        if let Some(age) = max_age {
-            let cutoff = chrono::Utc::now() - age;
-            let cutoff_str = cutoff.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-            let _ = conn.execute("DELETE FROM events WHERE time < ?", [cutoff_str]);
+    /// Apply a retention policy to the events table, returning the number of
+    /// rows deleted. Overrides are applied first so their kinds are fully
+    /// owned by the override; the global rules then skip both overridden and
+    /// exempt prefixes.
+    fn prune_events(conn: &Connection, retention: &EventRetention) -> rusqlite::Result<u64> {
+        let now = chrono::Utc::now();
+        let mut pruned: u64 = 0;
+        for (prefix, age) in &retention.kind_max_age {
+            let cutoff = (now - *age).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            pruned += conn.execute(
+                "DELETE FROM events WHERE kind LIKE ?1 || '%' AND time < ?2",
+                params![prefix, cutoff],
+            )? as u64;
+        }
+        let skip_prefixes: Vec<&str> = retention
+            .exempt_prefixes
+            .iter()
+            .map(|p| p.as_str())
+            .chain(retention.kind_max_age.iter().map(|(p, _)| p.as_str()))
+            .collect();
+        let skip_clause: String = skip_prefixes
+            .iter()
+            .enumerate()
+            .map(|(i, _)| format!(" AND kind NOT LIKE ?{} || '%'", i + 2))
+            .collect();
+        if let Some(age) = retention.max_age {
+            let cutoff = (now - age).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            let mut args: Vec<&dyn rusqlite::ToSql> = vec![&cutoff];
+            for p in &skip_prefixes {
+                args.push(p);
+            }
+            pruned += conn.execute(
+                &format!("DELETE FROM events WHERE time < ?1{}", skip_clause),
+                args.as_slice(),
+            )? as u64;
         }
-        if let Some(max_rows) = max_rows {
+        if let Some(max_rows) = retention.max_rows {
             let total: i64 = conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))?;
             let excess = total.saturating_sub(max_rows as i64);
             if excess > 0 {
-                let _ = conn.execute(
-                    "DELETE FROM events WHERE id IN (SELECT id FROM events ORDER BY id ASC LIMIT ?)",
-                    [excess],
-                );
+                let mut args: Vec<&dyn rusqlite::ToSql> = vec![&excess];
+                for p in &skip_prefixes {
+                    args.push(p);
+                }
+                pruned += conn.execute(
+                    &format!(
+                        "DELETE FROM events WHERE id IN (SELECT id FROM events WHERE 1=1{} ORDER BY id ASC LIMIT ?1)",
+                        skip_clause
+                    ),
+                    args.as_slice(),
+                )? as u64;
                 let _ = conn.execute("PRAGMA wal_checkpoint(TRUNCATE);", []);
             }
         }
-        Ok(())
+        if pruned > 0 {
+            let _ = conn.execute(
+                "DELETE FROM events_fts WHERE id NOT IN (SELECT id FROM events)",
+                [],
+            );
+        }
+        Ok(pruned)
     }
 
     fn start_autotune_loop(&mut self, interval: Duration, wait_threshold_ms: f64) -> Result<()> {
@@ -958,7 +1857,16 @@ impl Kernel {
     }
 
     fn apply_pragmas(conn: &Connection, pragmas: &KernelPragmas) -> rusqlite::Result<()> {
-        conn.pragma_update(None, "journal_mode", &pragmas.journal_mode)?;
+        // Keying must precede any statement that touches the database file.
+        #[cfg(feature = "sqlcipher")]
+        if let Some(key) = Self::sqlcipher_key() {
+            conn.pragma_update(None, "key", &key)?;
+        }
+        if !pragmas.read_only {
+            // Switching journal modes writes to the file; a read-only handle
+            // takes the database as it finds it.
+            conn.pragma_update(None, "journal_mode", &pragmas.journal_mode)?;
+        }
         conn.pragma_update(None, "synchronous", &pragmas.synchronous)?;
         conn.busy_timeout(std::time::Duration::from_millis(pragmas.busy_timeout_ms))?;
         let _ = conn.pragma_update(None, "cache_size", pragmas.cache_pages);
@@ -969,21 +1877,71 @@ impl Kernel {
         Ok(())
     }
 
-    fn init_schema(conn: &Connection) -> Result<()> {
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS events (
-              id INTEGER PRIMARY KEY AUTOINCREMENT,
+    /// SQLCipher key material: `ARW_SQLCIPHER_KEY` wins, then the contents
+    /// of `ARW_SQLCIPHER_KEY_FILE` (trimmed). `None` opens plaintext, which
+    /// keeps keyless dev databases working under the feature.
+    #[cfg(feature = "sqlcipher")]
+    fn sqlcipher_key() -> Option<String> {
+        if let Ok(key) = std::env::var("ARW_SQLCIPHER_KEY") {
+            let key = key.trim().to_string();
+            if !key.is_empty() {
+                return Some(key);
+            }
+        }
+        let path = std::env::var("ARW_SQLCIPHER_KEY_FILE").ok()?;
+        let key = std::fs::read_to_string(path.trim()).ok()?;
+        let key = key.trim().to_string();
+        (!key.is_empty()).then_some(key)
+    }
+
+    /// Re-encrypt the database under a new key (`PRAGMA rekey`). Pooled
+    /// connections keyed before the rotation keep working, but the new key
+    /// must land in `ARW_SQLCIPHER_KEY`/`ARW_SQLCIPHER_KEY_FILE` before any
+    /// further connections are opened — including the next process start —
+    /// or they will fail to read the re-encrypted file.
+    #[cfg(feature = "sqlcipher")]
+    pub fn rotate_sqlcipher_key(&self, new_key: &str) -> Result<()> {
+        let new_key = new_key.trim();
+        if new_key.is_empty() {
+            return Err(anyhow!("refusing to rekey with an empty key"));
+        }
+        let conn = self.conn()?;
+        conn.pragma_update(None, "rekey", new_key)
+            .context("sqlcipher rekey failed")?;
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    pub async fn rotate_sqlcipher_key_async(&self, new_key: String) -> Result<()> {
+        self.run_blocking(move |k| k.rotate_sqlcipher_key(&new_key))
+            .await
+    }
+
+    fn init_schema(conn: &Connection) -> Result<()> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS events (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
               time TEXT NOT NULL,
               kind TEXT NOT NULL,
               actor TEXT,
               proj TEXT,
               corr_id TEXT,
-              payload TEXT NOT NULL
+              payload TEXT NOT NULL,
+              chain_hash TEXT,              -- tamper-evident chain, when enabled
+              workspace_id TEXT             -- tenant scope; NULL = unscoped/legacy
             );
             CREATE INDEX IF NOT EXISTS idx_events_kind ON events(kind);
             CREATE INDEX IF NOT EXISTS idx_events_time ON events(time);
             CREATE INDEX IF NOT EXISTS idx_events_corr ON events(corr_id);
+            CREATE INDEX IF NOT EXISTS idx_events_actor ON events(actor, id);
+            CREATE INDEX IF NOT EXISTS idx_events_proj ON events(proj, id);
+
+            CREATE VIRTUAL TABLE IF NOT EXISTS events_fts USING fts5(
+              id UNINDEXED,
+              kind,
+              payload
+            );
 
             CREATE TABLE IF NOT EXISTS artifacts (
               sha256 TEXT PRIMARY KEY,
@@ -1001,13 +1959,40 @@ impl Kernel {
               state TEXT,
               output TEXT,
               error TEXT,
+              meta TEXT,                    -- worker annotations, separate from output
+              priority INTEGER NOT NULL DEFAULT 0,
+              run_after TEXT,               -- not eligible to dequeue before this time
+              attempts INTEGER NOT NULL DEFAULT 0,
+              workspace_id TEXT,            -- tenant scope; NULL = unscoped/legacy
+              deleted_at TEXT,              -- soft-delete tombstone; NULL = live
               created TEXT NOT NULL,
               updated TEXT NOT NULL
             );
             CREATE INDEX IF NOT EXISTS idx_actions_state_created ON actions(state, created);
+            CREATE INDEX IF NOT EXISTS idx_actions_queue ON actions(state, priority DESC, created);
+
+            -- Prerequisite edges: an action dequeues only after everything it
+            -- depends on has completed.
+            CREATE TABLE IF NOT EXISTS action_deps (
+              action_id TEXT NOT NULL,
+              depends_on TEXT NOT NULL,
+              created TEXT NOT NULL,
+              PRIMARY KEY (action_id, depends_on)
+            );
+            CREATE INDEX IF NOT EXISTS idx_action_deps_on ON action_deps(depends_on);
             CREATE INDEX IF NOT EXISTS idx_actions_updated ON actions(updated);
             CREATE INDEX IF NOT EXISTS idx_actions_idem ON actions(idem_key);
 
+            -- Incremental output from long-running actions, streamed to
+            -- clients before the final blob lands on the action row.
+            CREATE TABLE IF NOT EXISTS action_output_chunks (
+              action_id TEXT NOT NULL,
+              seq INTEGER NOT NULL,
+              data TEXT NOT NULL,
+              created TEXT NOT NULL,
+              PRIMARY KEY (action_id, seq)
+            );
+
             -- Contribution ledger: append-only accounting of work/resources
             CREATE TABLE IF NOT EXISTS contributions (
               id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -1023,6 +2008,35 @@ impl Kernel {
             CREATE INDEX IF NOT EXISTS idx_contrib_subject ON contributions(subject);
             CREATE INDEX IF NOT EXISTS idx_contrib_time ON contributions(time);
 
+            -- Daily contribution rollups so billing exports and dashboards
+            -- don't scan raw ledger rows. Group columns store '' (never NULL)
+            -- so the PK dedupes buckets.
+            CREATE TABLE IF NOT EXISTS contribution_rollups (
+              bucket_start TEXT NOT NULL,
+              subject TEXT NOT NULL DEFAULT '',
+              kind TEXT NOT NULL DEFAULT '',
+              unit TEXT NOT NULL DEFAULT '',
+              events INTEGER NOT NULL,
+              qty REAL NOT NULL,
+              PRIMARY KEY (bucket_start, subject, kind, unit)
+            );
+            CREATE TABLE IF NOT EXISTS contribution_rollup_cursor (
+              id INTEGER PRIMARY KEY CHECK (id = 1),
+              last_event_id INTEGER NOT NULL
+            );
+
+            -- Quotas: per-(subject, kind) qty ceilings over a sliding window,
+            -- enforced atomically against the contribution ledger.
+            CREATE TABLE IF NOT EXISTS quotas (
+              subject TEXT NOT NULL,
+              kind TEXT NOT NULL,
+              max_qty REAL NOT NULL,
+              window_secs INTEGER NOT NULL,
+              created TEXT NOT NULL,
+              updated TEXT NOT NULL,
+              PRIMARY KEY (subject, kind)
+            );
+
             -- Leases: capability grants with TTL and optional budget
             CREATE TABLE IF NOT EXISTS leases (
               id TEXT PRIMARY KEY,
@@ -1032,12 +2046,27 @@ impl Kernel {
               ttl_until TEXT NOT NULL,
               budget REAL,
               policy_ctx TEXT,
+              workspace_id TEXT,            -- tenant scope; NULL = unscoped/legacy
               created TEXT NOT NULL,
               updated TEXT NOT NULL
             );
             CREATE INDEX IF NOT EXISTS idx_leases_subject ON leases(subject);
             CREATE INDEX IF NOT EXISTS idx_leases_cap ON leases(capability);
 
+            -- Audit trail of lease transitions (grant | renew | revoke | expire)
+            CREATE TABLE IF NOT EXISTS lease_events (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              lease_id TEXT NOT NULL,
+              event TEXT NOT NULL,
+              subject TEXT,
+              capability TEXT,
+              reason TEXT,
+              actor TEXT,
+              time TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_lease_events_lease ON lease_events(lease_id, time);
+            CREATE INDEX IF NOT EXISTS idx_lease_events_subject ON lease_events(subject, capability);
+
             CREATE TABLE IF NOT EXISTS research_watcher_items (
               id TEXT PRIMARY KEY,
               source TEXT,
@@ -1065,6 +2094,7 @@ impl Kernel {
               decided_by TEXT,
               decided_at TEXT,
               action_id TEXT,
+              deleted_at TEXT,              -- soft-delete tombstone; NULL = live
               created TEXT NOT NULL,
               updated TEXT NOT NULL
             );
@@ -1088,10 +2118,33 @@ impl Kernel {
             );
             CREATE INDEX IF NOT EXISTS idx_egress_time ON egress_ledger(time);
 
+            -- Hourly egress rollups so dashboards don't scan the raw ledger.
+            -- Group columns store '' (never NULL) so the PK dedupes buckets.
+            CREATE TABLE IF NOT EXISTS egress_rollups (
+              bucket_start TEXT NOT NULL,
+              dest_host TEXT NOT NULL DEFAULT '',
+              proj TEXT NOT NULL DEFAULT '',
+              posture TEXT NOT NULL DEFAULT '',
+              decision TEXT NOT NULL DEFAULT '',
+              events INTEGER NOT NULL,
+              bytes_in INTEGER NOT NULL,
+              bytes_out INTEGER NOT NULL,
+              PRIMARY KEY (bucket_start, dest_host, proj, posture, decision)
+            );
+            CREATE TABLE IF NOT EXISTS egress_rollup_cursor (
+              id INTEGER PRIMARY KEY CHECK (id = 1),
+              last_event_id INTEGER NOT NULL
+            );
+
             -- Config snapshots: persisted effective config for Patch Engine
             CREATE TABLE IF NOT EXISTS config_snapshots (
               id TEXT PRIMARY KEY,
               config TEXT NOT NULL,
+              sha256 TEXT,
+              label TEXT,                   -- human name, e.g. "pre-rollout"
+              note TEXT,                    -- free-form annotation
+              pinned INTEGER NOT NULL DEFAULT 0, -- exempt from retention pruning
+              rolled_back_from TEXT,        -- snapshot id this one restored
               created TEXT NOT NULL
             );
 
@@ -1101,11 +2154,18 @@ impl Kernel {
               status TEXT NOT NULL,
               goal TEXT,
               data TEXT,
+              data_history TEXT,
               progress REAL,
+              deadline TEXT,                -- overdue jobs are failed by the sweep
+              reason TEXT,                  -- why the job is cancelled/failed
+              parent_id TEXT,               -- spawning job for sub-jobs
+              worker_id TEXT,               -- claiming worker while running
+              lease_until TEXT,             -- claim expiry; overdue claims requeue
               created TEXT NOT NULL,
               updated TEXT NOT NULL
             );
             CREATE INDEX IF NOT EXISTS idx_orch_status ON orchestrator_jobs(status);
+            CREATE INDEX IF NOT EXISTS idx_orch_parent ON orchestrator_jobs(parent_id);
 
             -- Logic Units: persisted manifests
             CREATE TABLE IF NOT EXISTS logic_units (
@@ -1115,6 +2175,15 @@ impl Kernel {
               created TEXT NOT NULL,
               updated TEXT NOT NULL
             );
+            -- Every manifest write, kept so a bad update can be reverted.
+            CREATE TABLE IF NOT EXISTS logic_unit_versions (
+              unit_id TEXT NOT NULL,
+              version INTEGER NOT NULL,
+              manifest TEXT NOT NULL,
+              status TEXT NOT NULL,
+              created TEXT NOT NULL,
+              PRIMARY KEY (unit_id, version)
+            );
 
             -- Personas: worldview, traits, and empathy telemetry (feature-gated consumers)
             CREATE TABLE IF NOT EXISTS persona_entries (
@@ -1143,10 +2212,22 @@ impl Kernel {
               leases_required TEXT,
               status TEXT NOT NULL,
               created TEXT NOT NULL,
-              updated TEXT NOT NULL
+              updated TEXT NOT NULL,
+              required_approvals INTEGER NOT NULL DEFAULT 1
             );
             CREATE INDEX IF NOT EXISTS idx_persona_proposals_status ON persona_proposals(status);
 
+            -- One row per reviewer per proposal; the proposal status is
+            -- computed from these, not flipped directly.
+            CREATE TABLE IF NOT EXISTS persona_proposal_votes (
+              proposal_id TEXT NOT NULL,
+              reviewer TEXT NOT NULL,
+              decision TEXT NOT NULL,   -- approve | reject | abstain
+              note TEXT,
+              updated TEXT NOT NULL,
+              PRIMARY KEY (proposal_id, reviewer)
+            );
+
             CREATE TABLE IF NOT EXISTS persona_history (
               id INTEGER PRIMARY KEY AUTOINCREMENT,
               persona_id TEXT NOT NULL,
@@ -1170,18 +2251,167 @@ impl Kernel {
             CREATE INDEX IF NOT EXISTS idx_persona_vibe_samples_persona ON persona_vibe_samples(persona_id, recorded_at DESC);
             "#,
         )?;
-        // Backfill optional columns for older installations (ignore errors if already present)
-        let _ = conn.execute("ALTER TABLE egress_ledger ADD COLUMN meta TEXT", []);
-        MemoryStore::migrate(conn)?;
         Ok(())
     }
 
+    fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(1)?;
+            if name == column {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Apply any pending schema migrations, stamping each one in the
+    /// `schema_version` table. Forward-only: versions already stamped are
+    /// skipped and failures abort the open instead of being swallowed.
+    ///
+    /// With `dry_run` set, pending versions are returned without applying or
+    /// stamping anything.
+    fn run_schema_migrations(conn: &Connection, dry_run: bool) -> Result<Vec<i64>> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_version (
+              version INTEGER PRIMARY KEY,
+              description TEXT NOT NULL,
+              applied TEXT NOT NULL
+            );
+            "#,
+        )?;
+        let applied: HashSet<i64> = {
+            let mut stmt = conn.prepare("SELECT version FROM schema_version")?;
+            let rows = stmt.query_map([], |r| r.get::<_, i64>(0))?;
+            rows.collect::<std::result::Result<_, _>>()?
+        };
+        let mut touched: Vec<i64> = Vec::new();
+        for m in SCHEMA_MIGRATIONS {
+            if applied.contains(&m.version) {
+                continue;
+            }
+            touched.push(m.version);
+            if dry_run {
+                continue;
+            }
+            let (table, column) = m.column;
+            let backfilled = !column.is_empty() && Self::column_exists(conn, table, column)?;
+            if !backfilled {
+                conn.execute_batch(m.sql).with_context(|| {
+                    format!("schema migration {} failed: {}", m.version, m.description)
+                })?;
+            }
+            conn.execute(
+                "INSERT INTO schema_version(version, description, applied) VALUES(?, ?, ?)",
+                rusqlite::params![m.version, m.description, Utc::now().to_rfc3339()],
+            )?;
+        }
+        Ok(touched)
+    }
+
+    /// Highest schema version stamped on this database (0 for databases that
+    /// predate the version table and have not been reopened since).
+    pub fn schema_version(&self) -> Result<i64> {
+        let conn = self.conn()?;
+        let v: Option<i64> =
+            conn.query_row("SELECT MAX(version) FROM schema_version", [], |r| r.get(0))?;
+        Ok(v.unwrap_or(0))
+    }
+
+    /// Versions the next open would apply, in order; empty when up to date.
+    pub fn pending_schema_migrations(&self) -> Result<Vec<i64>> {
+        let conn = self.conn()?;
+        Self::run_schema_migrations(&conn, true)
+    }
+
     fn conn(&self) -> Result<ManagedConnection> {
         Self::checkout_connection(&self.db_path, &self.pragmas, &self.pool)
     }
 
+    /// Record one public-operation duration under the `metrics` feature.
+    /// No-op otherwise, so the hot paths can call it unconditionally.
+    fn observe_op(_op: &'static str, _started: Instant) {
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("arw_kernel_op_duration_ms", "op" => _op)
+            .record(_started.elapsed().as_secs_f64() * 1000.0);
+    }
+
+    /// Row-count companion to [`Self::observe_op`], so operators can tell a
+    /// slow query from a large result set.
+    fn observe_op_rows(_op: &'static str, _rows: u64) {
+        #[cfg(feature = "metrics")]
+        metrics::counter!("arw_kernel_op_rows", "op" => _op).increment(_rows);
+    }
+
+    fn now_rfc3339(&self) -> String {
+        self.clock
+            .now()
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+
+    /// Delete every row from every kernel table and reset autoincrement
+    /// sequences, leaving the schema intact. Lets integration suites reuse
+    /// one kernel across cases instead of opening a fresh `TempDir` each
+    /// time. Test-only: compiled under `cfg(test)` or the `testing` feature
+    /// and never meant for production code paths.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn truncate_all(&self) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        // Children before parents so the order stays FK-safe even when
+        // foreign_keys is enabled on the connection.
+        conn.execute_batch(
+            r#"
+            BEGIN IMMEDIATE;
+            DELETE FROM memory_links;
+            DELETE FROM memory_fts;
+            DELETE FROM memory_ann;
+            DELETE FROM memory_tags;
+            DELETE FROM memory_revisions;
+            DELETE FROM memory_records;
+            DELETE FROM memory_lanes;
+            DELETE FROM memory_embed_models;
+            DELETE FROM persona_vibe_samples;
+            DELETE FROM persona_history;
+            DELETE FROM persona_proposal_votes;
+            DELETE FROM persona_proposals;
+            DELETE FROM persona_entries;
+            DELETE FROM logic_unit_versions;
+            DELETE FROM logic_units;
+            DELETE FROM orchestrator_jobs;
+            DELETE FROM config_snapshots;
+            DELETE FROM egress_rollups;
+            DELETE FROM egress_rollup_cursor;
+            DELETE FROM egress_ledger;
+            DELETE FROM staging_actions;
+            DELETE FROM research_watcher_items;
+            DELETE FROM lease_events;
+            DELETE FROM leases;
+            DELETE FROM contribution_rollups;
+            DELETE FROM contribution_rollup_cursor;
+            DELETE FROM contributions;
+            DELETE FROM quotas;
+            DELETE FROM action_deps;
+            DELETE FROM action_output_chunks;
+            DELETE FROM actions;
+            DELETE FROM artifacts;
+            DELETE FROM events;
+            DELETE FROM events_fts;
+            DELETE FROM sqlite_sequence;
+            COMMIT;
+            "#,
+        )?;
+        Ok(())
+    }
+
     pub fn session(&self) -> Result<KernelSession> {
-        Ok(KernelSession { conn: self.conn()? })
+        Ok(KernelSession {
+            conn: self.conn()?,
+            workspace: self.workspace.clone(),
+            clock: self.clock.clone(),
+        })
     }
 
     async fn run_blocking<F, R>(&self, job: F) -> Result<R>
@@ -1201,14 +2431,30 @@ impl Kernel {
         let mut guard = pool.state.lock().expect("pool mutex poisoned");
         let mut wait_start: Option<Instant> = None;
         loop {
-            if let Some(conn) = guard.conns.pop() {
+            if let Some(mut pooled) = guard.conns.pop() {
+                let stale = pool.is_stale(&pooled);
                 pool.record_metrics(&guard);
                 drop(guard);
+                // Probe outside the lock; a broken or aged-out connection is
+                // closed and replaced on the next loop pass instead of being
+                // handed back to the caller.
+                if stale || !Self::validate_connection(&pooled.conn) {
+                    #[cfg(feature = "metrics")]
+                    metrics::counter!("arw_kernel_pool_recycled").increment(1);
+                    drop(pooled);
+                    guard = pool.state.lock().expect("pool mutex poisoned");
+                    if guard.created > 0 {
+                        guard.created -= 1;
+                    }
+                    pool.record_metrics(&guard);
+                    continue;
+                }
+                pooled.uses = pooled.uses.saturating_add(1);
                 if let Some(start) = wait_start {
                     pool.record_wait(start.elapsed());
                 }
                 return Ok(ManagedConnection {
-                    conn: Some(conn),
+                    conn: Some(pooled),
                     pool: pool.clone(),
                 });
             }
@@ -1217,7 +2463,19 @@ impl Kernel {
                 guard.created += 1;
                 pool.record_metrics(&guard);
                 drop(guard);
-                let conn = Connection::open(db_path)?;
+                let conn = match Self::open_db(db_path, pragmas) {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        let mut guard = pool.state.lock().expect("pool mutex poisoned");
+                        if guard.created > 0 {
+                            guard.created -= 1;
+                        }
+                        pool.record_metrics(&guard);
+                        drop(guard);
+                        pool.cvar.notify_one();
+                        return Err(e);
+                    }
+                };
                 if let Err(e) = Kernel::apply_pragmas(&conn, pragmas) {
                     let mut guard = pool.state.lock().expect("pool mutex poisoned");
                     if guard.created > 0 {
@@ -1232,7 +2490,7 @@ impl Kernel {
                     pool.record_wait(start.elapsed());
                 }
                 return Ok(ManagedConnection {
-                    conn: Some(conn),
+                    conn: Some(PooledConn::new(conn)),
                     pool: pool.clone(),
                 });
             }
@@ -1243,6 +2501,88 @@ impl Kernel {
         }
     }
 
+    /// Cheap liveness probe run on every checkout of an idle connection.
+    fn validate_connection(conn: &Connection) -> bool {
+        conn.execute_batch("SELECT 1;").is_ok()
+    }
+
+    fn open_db(db_path: &Path, pragmas: &KernelPragmas) -> Result<Connection> {
+        let conn = if pragmas.read_only {
+            Connection::open_with_flags(
+                db_path,
+                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX
+                    | rusqlite::OpenFlags::SQLITE_OPEN_URI,
+            )?
+        } else {
+            Connection::open(db_path)?
+        };
+        Ok(conn)
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(ReadOnlyKernelError.into());
+        }
+        Ok(())
+    }
+
+    /// Payload bytes below this threshold stay as plain text; compressing
+    /// tiny JSON blobs costs more than it saves.
+    #[cfg(feature = "compress-events")]
+    fn compress_min_bytes() -> usize {
+        std::env::var("ARW_EVENTS_COMPRESS_MIN_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(512)
+    }
+
+    #[cfg(feature = "compress-events")]
+    fn compress_payload(json: &str) -> std::io::Result<Vec<u8>> {
+        use std::io::Write as _;
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(json.as_bytes())?;
+        enc.finish()
+    }
+
+    #[cfg(feature = "compress-events")]
+    fn decompress_payload(raw: &[u8]) -> Option<Vec<u8>> {
+        use std::io::Read as _;
+        let mut out = Vec::new();
+        flate2::read::GzDecoder::new(raw)
+            .read_to_end(&mut out)
+            .ok()?;
+        Some(out)
+    }
+
+    /// Decode a payload cell. Plain rows are TEXT; rows written with the
+    /// `compress-events` feature are gzip BLOBs. Without the feature,
+    /// compressed rows decode to `{}` rather than erroring.
+    fn payload_from_sql(raw: rusqlite::types::ValueRef<'_>) -> serde_json::Value {
+        match raw {
+            rusqlite::types::ValueRef::Text(t) => {
+                serde_json::from_slice(t).unwrap_or_else(|_| serde_json::json!({}))
+            }
+            #[cfg(feature = "compress-events")]
+            rusqlite::types::ValueRef::Blob(b) => Self::decompress_payload(b)
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .unwrap_or_else(|| serde_json::json!({})),
+            _ => serde_json::json!({}),
+        }
+    }
+
+    /// Encode a serialized payload for storage, compressing large payloads
+    /// when the `compress-events` feature is enabled.
+    fn payload_to_sql(json: String) -> Value {
+        #[cfg(feature = "compress-events")]
+        if json.len() >= Self::compress_min_bytes() {
+            if let Ok(bytes) = Self::compress_payload(&json) {
+                return Value::Blob(bytes);
+            }
+        }
+        Value::Text(json)
+    }
+
     fn map_event_row(row: &rusqlite::Row) -> rusqlite::Result<EventRow> {
         let id: i64 = row.get(0)?;
         let time: String = row.get(1)?;
@@ -1250,8 +2590,7 @@ impl Kernel {
         let actor: Option<String> = row.get(3)?;
         let proj: Option<String> = row.get(4)?;
         let corr_id: Option<String> = row.get(5)?;
-        let payload_s: String = row.get(6)?;
-        let payload = serde_json::from_str(&payload_s).unwrap_or_else(|_| serde_json::json!({}));
+        let payload = Self::payload_from_sql(row.get_ref(6)?);
         Ok(EventRow {
             id,
             time,
@@ -1263,39 +2602,383 @@ impl Kernel {
         })
     }
 
+    /// Actor and project attribution carried inside an envelope payload
+    /// (`actor`/`proj` string fields), mirroring how `corr_id` travels.
+    fn envelope_attribution(payload: &JsonValue) -> (Option<String>, Option<String>) {
+        let field = |name: &str| {
+            payload
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        };
+        (field("actor"), field("proj"))
+    }
+
+    /// True when appended events should extend the tamper-evident hash
+    /// chain (`ARW_EVENTS_HASH_CHAIN=1`). Chained appends pay for one extra
+    /// read and an immediate transaction per write.
+    fn hash_chain_enabled() -> bool {
+        std::env::var("ARW_EVENTS_HASH_CHAIN")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+    }
+
+    fn chain_hash(prev: &str, time: &str, kind: &str, payload_json: &str) -> String {
+        use sha2::Digest as _;
+        let mut h = sha2::Sha256::new();
+        for part in [prev, time, kind, payload_json] {
+            h.update(part.as_bytes());
+            h.update([0u8]);
+        }
+        format!("{:x}", h.finalize())
+    }
+
+    /// Hash of the newest event row; unchained rows (and an empty log)
+    /// contribute the empty string, which doubles as the genesis value.
+    fn last_chain_hash(conn: &Connection) -> rusqlite::Result<String> {
+        Ok(conn
+            .query_row(
+                "SELECT COALESCE(chain_hash,'') FROM events ORDER BY id DESC LIMIT 1",
+                [],
+                |r| r.get(0),
+            )
+            .optional()?
+            .unwrap_or_default())
+    }
+
     pub fn append_event(&self, env: &arw_events::Envelope) -> Result<i64> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare_cached(
-            "INSERT INTO events(time,kind,actor,proj,corr_id,payload) VALUES (?,?,?,?,?,?)",
-        )?;
+        self.append_event_attributed(env, None, None)
+    }
+
+    /// Append with explicit attribution. `actor`/`proj` fall back to the
+    /// payload's `actor`/`proj` string fields when not given, so callers
+    /// that already tag payloads get attribution for free; explicit args
+    /// win when both are present.
+    pub fn append_event_attributed(
+        &self,
+        env: &arw_events::Envelope,
+        actor: Option<&str>,
+        proj: Option<&str>,
+    ) -> Result<i64> {
+        self.ensure_writable()?;
+        let started = Instant::now();
+        let mut conn = self.conn()?;
         let payload = serde_json::to_string(&env.payload).unwrap_or("{}".to_string());
-        stmt.execute(params![
-            env.time,
-            env.kind,
-            None::<String>,
-            None::<String>,
-            env.payload
+        let corr_id = env
+            .payload
+            .get("corr_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let (payload_actor, payload_proj) = Self::envelope_attribution(&env.payload);
+        let actor = actor.map(|s| s.to_string()).or(payload_actor);
+        let proj = proj.map(|s| s.to_string()).or(payload_proj);
+        let id = if Self::hash_chain_enabled() {
+            // The read-prev/insert pair must be atomic against other writers.
+            let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+            let prev = Self::last_chain_hash(&tx)?;
+            let hash = Self::chain_hash(&prev, &env.time, &env.kind, &payload);
+            tx.prepare_cached(
+                "INSERT INTO events(time,kind,actor,proj,corr_id,payload,chain_hash,workspace_id) VALUES (?,?,?,?,?,?,?,?)",
+            )?
+            .execute(params![
+                env.time,
+                env.kind,
+                actor,
+                proj,
+                corr_id,
+                Self::payload_to_sql(payload.clone()),
+                hash,
+                self.workspace,
+            ])?;
+            let id = tx.last_insert_rowid();
+            tx.prepare_cached("INSERT INTO events_fts(id, kind, payload) VALUES (?,?,?)")?
+                .execute(params![id, env.kind, payload])?;
+            tx.commit()?;
+            id
+        } else {
+            let mut stmt = conn.prepare_cached(
+                "INSERT INTO events(time,kind,actor,proj,corr_id,payload,workspace_id) VALUES (?,?,?,?,?,?,?)",
+            )?;
+            stmt.execute(params![
+                env.time,
+                env.kind,
+                actor,
+                proj,
+                corr_id,
+                Self::payload_to_sql(payload.clone()),
+                self.workspace,
+            ])?;
+            let id = conn.last_insert_rowid();
+            let mut fts =
+                conn.prepare_cached("INSERT INTO events_fts(id, kind, payload) VALUES (?,?,?)")?;
+            fts.execute(params![id, env.kind, payload])?;
+            id
+        };
+        self.fanout_event(EventRow {
+            id,
+            time: env.time.clone(),
+            kind: env.kind.clone(),
+            actor,
+            proj,
+            corr_id: env
+                .payload
                 .get("corr_id")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
-            payload,
-        ])?;
-        Ok(conn.last_insert_rowid())
+            payload: env.payload.clone(),
+        });
+        Self::observe_op("append_event", started);
+        Self::observe_op_rows("append_event", 1);
+        Ok(id)
+    }
+
+    /// Compress up to `batch` existing plain-text payload rows in place,
+    /// returning how many were rewritten. Run repeatedly until it returns 0
+    /// to backfill a database created before `compress-events` was enabled.
+    #[cfg(feature = "compress-events")]
+    pub fn compress_existing_events(&self, batch: u64) -> Result<u64> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let min_bytes = Self::compress_min_bytes() as i64;
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare(
+                "SELECT id, payload FROM events WHERE typeof(payload)='text' AND length(payload) >= ? ORDER BY id ASC LIMIT ?",
+            )?;
+            let mapped = stmt.query_map(params![min_bytes, batch as i64], |r| {
+                Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))
+            })?;
+            mapped.collect::<std::result::Result<_, _>>()?
+        };
+        let mut rewritten = 0u64;
+        for (id, payload) in rows {
+            let bytes = Self::compress_payload(&payload)?;
+            conn.execute(
+                "UPDATE events SET payload=? WHERE id=?",
+                params![Value::Blob(bytes), id],
+            )?;
+            rewritten += 1;
+        }
+        Ok(rewritten)
+    }
+
+    #[cfg(feature = "compress-events")]
+    pub async fn compress_existing_events_async(&self, batch: u64) -> Result<u64> {
+        self.run_blocking(move |k| k.compress_existing_events(batch))
+            .await
+    }
+
+    /// Append a batch of envelopes in a single transaction, returning their
+    /// row ids in order. Subscribers see the events only after the commit,
+    /// so a failed batch is invisible.
+    pub fn append_events(&self, envs: &[arw_events::Envelope]) -> Result<Vec<i64>> {
+        self.ensure_writable()?;
+        if envs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let started = Instant::now();
+        let mut conn = self.conn()?;
+        let mut ids = Vec::with_capacity(envs.len());
+        let chained = Self::hash_chain_enabled();
+        {
+            let tx = if chained {
+                conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?
+            } else {
+                conn.transaction()?
+            };
+            let mut prev = if chained {
+                Some(Self::last_chain_hash(&tx)?)
+            } else {
+                None
+            };
+            for env in envs {
+                let payload = serde_json::to_string(&env.payload).unwrap_or("{}".to_string());
+                let hash = prev
+                    .as_ref()
+                    .map(|prev_hash| Self::chain_hash(prev_hash, &env.time, &env.kind, &payload));
+                let (actor, proj) = Self::envelope_attribution(&env.payload);
+                tx.prepare_cached(
+                    "INSERT INTO events(time,kind,actor,proj,corr_id,payload,chain_hash,workspace_id) VALUES (?,?,?,?,?,?,?,?)",
+                )?
+                .execute(params![
+                    env.time,
+                    env.kind,
+                    actor,
+                    proj,
+                    env.payload
+                        .get("corr_id")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string()),
+                    Self::payload_to_sql(payload.clone()),
+                    hash,
+                    self.workspace,
+                ])?;
+                if let Some(hash) = hash {
+                    prev = Some(hash);
+                }
+                let id = tx.last_insert_rowid();
+                tx.prepare_cached("INSERT INTO events_fts(id, kind, payload) VALUES (?,?,?)")?
+                    .execute(params![id, env.kind, payload])?;
+                ids.push(id);
+            }
+            tx.commit()?;
+        }
+        for (env, id) in envs.iter().zip(&ids) {
+            let (actor, proj) = Self::envelope_attribution(&env.payload);
+            self.fanout_event(EventRow {
+                id: *id,
+                time: env.time.clone(),
+                kind: env.kind.clone(),
+                actor,
+                proj,
+                corr_id: env
+                    .payload
+                    .get("corr_id")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                payload: env.payload.clone(),
+            });
+        }
+        Self::observe_op("append_events", started);
+        Self::observe_op_rows("append_events", ids.len() as u64);
+        Ok(ids)
+    }
+
+    pub async fn append_events_async(&self, envs: Vec<arw_events::Envelope>) -> Result<Vec<i64>> {
+        self.run_blocking(move |k| k.append_events(&envs)).await
+    }
+
+    /// Recompute the hash chain over `[since_id, until_id]` (both optional)
+    /// and report what it finds: rewritten rows surface as `first_invalid_id`,
+    /// deleted rows as `gaps`, and rows written before the chain was enabled
+    /// as `unchained` (they reset the chain rather than invalidate it). A
+    /// range is only `valid` when nothing is rewritten and no ids are
+    /// missing, so audit deployments must keep pruning and archival off for
+    /// the window they intend to verify.
+    pub fn verify_event_chain(
+        &self,
+        since_id: Option<i64>,
+        until_id: Option<i64>,
+    ) -> Result<JsonValue> {
+        let conn = self.conn()?;
+        let start = since_id.unwrap_or(0);
+        // Seed with the hash of the row just before the range; the genesis
+        // value (empty string) covers an empty or unchained prefix.
+        let mut prev_hash: String = conn
+            .query_row(
+                "SELECT COALESCE(chain_hash,'') FROM events WHERE id < ? ORDER BY id DESC LIMIT 1",
+                [start],
+                |r| r.get(0),
+            )
+            .optional()?
+            .unwrap_or_default();
+        let mut stmt = conn.prepare(
+            "SELECT id,time,kind,payload,COALESCE(chain_hash,'') FROM events \
+             WHERE id >= ?1 AND (?2 IS NULL OR id <= ?2) ORDER BY id ASC",
+        )?;
+        let mut rows = stmt.query(params![start, until_id])?;
+        let mut checked = 0u64;
+        let mut gaps = 0u64;
+        let mut unchained = 0u64;
+        let mut first_invalid: Option<i64> = None;
+        let mut prev_id: Option<i64> = None;
+        while let Some(r) = rows.next()? {
+            let id: i64 = r.get(0)?;
+            let time: String = r.get(1)?;
+            let kind: String = r.get(2)?;
+            let payload = Self::payload_from_sql(r.get_ref(3)?);
+            let stored: String = r.get(4)?;
+            checked += 1;
+            if let Some(pid) = prev_id {
+                if id != pid + 1 {
+                    gaps += 1;
+                }
+            }
+            prev_id = Some(id);
+            if stored.is_empty() {
+                unchained += 1;
+                prev_hash = stored;
+                continue;
+            }
+            let payload_json = serde_json::to_string(&payload).unwrap_or("{}".to_string());
+            let expect = Self::chain_hash(&prev_hash, &time, &kind, &payload_json);
+            if expect != stored && first_invalid.is_none() {
+                first_invalid = Some(id);
+            }
+            prev_hash = stored;
+        }
+        Ok(json!({
+            "checked": checked,
+            "valid": first_invalid.is_none() && gaps == 0,
+            "first_invalid_id": first_invalid,
+            "gaps": gaps,
+            "unchained": unchained,
+        }))
+    }
+
+    pub async fn verify_event_chain_async(
+        &self,
+        since_id: Option<i64>,
+        until_id: Option<i64>,
+    ) -> Result<JsonValue> {
+        self.run_blocking(move |k| k.verify_event_chain(since_id, until_id))
+            .await
+    }
+
+    /// Subscribe to appended events whose kind starts with one of `prefixes`
+    /// (an empty prefix list matches everything). Dropped receivers are
+    /// pruned lazily on the next append.
+    pub fn subscribe_events_filtered(
+        &self,
+        prefixes: &[String],
+    ) -> tokio::sync::mpsc::UnboundedReceiver<EventRow> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut subs = self
+            .event_subs
+            .lock()
+            .expect("event subscriber mutex poisoned");
+        subs.push(EventSubscriber {
+            prefixes: prefixes.to_vec(),
+            tx,
+        });
+        rx
+    }
+
+    fn fanout_event(&self, row: EventRow) {
+        let mut subs = self
+            .event_subs
+            .lock()
+            .expect("event subscriber mutex poisoned");
+        if subs.is_empty() {
+            return;
+        }
+        subs.retain(|sub| {
+            let interested = sub.prefixes.is_empty()
+                || sub
+                    .prefixes
+                    .iter()
+                    .any(|p| row.kind.starts_with(p.as_str()));
+            if !interested {
+                return !sub.tx.is_closed();
+            }
+            sub.tx.send(row.clone()).is_ok()
+        });
     }
 
     pub fn recent_events(&self, limit: i64, after_id: Option<i64>) -> Result<Vec<EventRow>> {
+        let started = Instant::now();
         let conn = self.conn()?;
+        let ws = self.workspace_clause("workspace_id");
         let mut stmt_after;
         let mut stmt_all;
         let mut rows = if let Some(aid) = after_id {
-            stmt_after = conn.prepare_cached(
-                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE id>? ORDER BY id ASC LIMIT ?",
-            )?;
+            stmt_after = conn.prepare_cached(&format!(
+                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE id>?{ws} ORDER BY id ASC LIMIT ?",
+            ))?;
             stmt_after.query(params![aid, limit])?
         } else {
-            stmt_all = conn.prepare_cached(
-                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events ORDER BY id DESC LIMIT ?",
-            )?;
+            stmt_all = conn.prepare_cached(&format!(
+                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE 1=1{ws} ORDER BY id DESC LIMIT ?",
+            ))?;
             stmt_all.query(params![limit])?
         };
         let mut out = Vec::new();
@@ -1306,32 +2989,289 @@ impl Kernel {
         if after_id.is_none() {
             out.reverse();
         }
+        Self::observe_op("recent_events", started);
+        Self::observe_op_rows("recent_events", out.len() as u64);
         Ok(out)
     }
 
-    pub fn events_by_corr_id(&self, corr_id: &str, limit: Option<i64>) -> Result<Vec<EventRow>> {
-        let conn = self.conn()?;
-        let mut stmt_limit;
-        let mut stmt_all;
-        let mut rows = if let Some(limit) = limit {
-            stmt_limit = conn.prepare_cached(
-                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE corr_id = ? ORDER BY id ASC LIMIT ?",
-            )?;
-            stmt_limit.query(params![corr_id, limit])?
-        } else {
-            stmt_all = conn.prepare_cached(
-                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE corr_id = ? ORDER BY id ASC",
-            )?;
-            stmt_all.query(params![corr_id])?
-        };
-        let mut out = Vec::new();
-        while let Some(row) = rows.next()? {
-            out.push(Self::map_event_row(row)?);
-        }
-        Ok(out)
+    /// Move events older than `before` (RFC3339) out of SQLite into
+    /// gzip-compressed NDJSON segment files under `dest_dir`, named
+    /// `events-{first_id}-{last_id}.ndjson.gz`. Each segment is fully
+    /// written and synced before its rows are deleted, so a crash can
+    /// duplicate a segment but never lose events.
+    pub fn archive_events(&self, before: &str, dest_dir: &Path) -> Result<EventArchiveReport> {
+        let segment_rows = std::env::var("ARW_EVENTS_ARCHIVE_SEGMENT_ROWS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(10_000);
+        self.archive_events_segmented(before, dest_dir, segment_rows)
     }
 
-    pub fn events_by_corr_ids(
+    fn archive_events_segmented(
+        &self,
+        before: &str,
+        dest_dir: &Path,
+        segment_rows: u64,
+    ) -> Result<EventArchiveReport> {
+        use std::io::Write as _;
+        std::fs::create_dir_all(dest_dir)?;
+        let conn = self.conn()?;
+        let mut report = EventArchiveReport {
+            segments: Vec::new(),
+            events: 0,
+            before: before.to_string(),
+        };
+        loop {
+            let batch: Vec<EventRow> = {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE time < ? ORDER BY id ASC LIMIT ?",
+                )?;
+                let mut rows = stmt.query(params![before, segment_rows as i64])?;
+                let mut out = Vec::new();
+                while let Some(row) = rows.next()? {
+                    out.push(Self::map_event_row(row)?);
+                }
+                out
+            };
+            let (Some(first), Some(last)) = (batch.first(), batch.last()) else {
+                break;
+            };
+            let name = format!("events-{}-{}.ndjson.gz", first.id, last.id);
+            let path = dest_dir.join(&name);
+            let file = std::fs::File::create(&path)?;
+            let mut enc = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            for row in &batch {
+                serde_json::to_writer(&mut enc, row)?;
+                enc.write_all(b"\n")?;
+            }
+            enc.finish()?.sync_all()?;
+            let last_id = last.id;
+            conn.execute(
+                "DELETE FROM events WHERE time < ? AND id <= ?",
+                params![before, last_id],
+            )?;
+            conn.execute(
+                "DELETE FROM events_fts WHERE id NOT IN (SELECT id FROM events)",
+                [],
+            )?;
+            report.events += batch.len() as u64;
+            report.segments.push(name);
+            if (batch.len() as u64) < segment_rows {
+                break;
+            }
+        }
+        if report.events > 0 {
+            let _ = conn.execute("PRAGMA wal_checkpoint(TRUNCATE);", []);
+        }
+        Ok(report)
+    }
+
+    pub async fn archive_events_async(
+        &self,
+        before: String,
+        dest_dir: PathBuf,
+    ) -> Result<EventArchiveReport> {
+        self.run_blocking(move |k| k.archive_events(&before, &dest_dir))
+            .await
+    }
+
+    fn parse_event_cursor(cursor: &str) -> Result<(i64, bool)> {
+        let (id, dir) = cursor
+            .split_once(':')
+            .ok_or_else(|| anyhow!("invalid events cursor: {cursor}"))?;
+        let id: i64 = id
+            .parse()
+            .map_err(|_| anyhow!("invalid events cursor: {cursor}"))?;
+        let forward = match dir {
+            "fwd" => true,
+            "back" => false,
+            _ => return Err(anyhow!("invalid events cursor: {cursor}")),
+        };
+        Ok((id, forward))
+    }
+
+    /// Keyset-paginate the event log. Without a cursor the newest `limit`
+    /// events are returned; follow `next_cursor` to walk older history and
+    /// `prev_cursor` to walk back toward the head. Cursors are opaque and
+    /// stay valid across appends and prunes (pruned ids are simply skipped).
+    pub fn events_page(&self, cursor: Option<&str>, limit: i64) -> Result<EventPage> {
+        let limit = limit.max(1);
+        let conn = self.conn()?;
+        let parsed = cursor.map(Self::parse_event_cursor).transpose()?;
+        let mut stmt_fwd;
+        let mut stmt_back;
+        let mut stmt_head;
+        let ws = self.workspace_clause("workspace_id");
+        let mut rows = match parsed {
+            Some((id, true)) => {
+                stmt_fwd = conn.prepare_cached(&format!(
+                    "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE id>?{ws} ORDER BY id ASC LIMIT ?",
+                ))?;
+                stmt_fwd.query(params![id, limit + 1])?
+            }
+            Some((id, false)) => {
+                stmt_back = conn.prepare_cached(&format!(
+                    "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE id<?{ws} ORDER BY id DESC LIMIT ?",
+                ))?;
+                stmt_back.query(params![id, limit + 1])?
+            }
+            None => {
+                stmt_head = conn.prepare_cached(&format!(
+                    "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE 1=1{ws} ORDER BY id DESC LIMIT ?",
+                ))?;
+                stmt_head.query(params![limit + 1])?
+            }
+        };
+        let forward = matches!(parsed, Some((_, true)));
+        let mut items = Vec::new();
+        while let Some(row) = rows.next()? {
+            items.push(Self::map_event_row(row)?);
+        }
+        let has_more = items.len() as i64 > limit;
+        if has_more {
+            items.truncate(limit as usize);
+        }
+        if forward {
+            // Queries ascending; pages are presented newest-first.
+            items.reverse();
+        }
+        let next_cursor = if has_more {
+            if forward {
+                items.first().map(|e| format!("{}:fwd", e.id))
+            } else {
+                items.last().map(|e| format!("{}:back", e.id))
+            }
+        } else {
+            None
+        };
+        let prev_cursor = items.first().map(|e| format!("{}:fwd", e.id));
+        Ok(EventPage {
+            items,
+            next_cursor,
+            prev_cursor,
+            has_more,
+        })
+    }
+
+    /// Events with `start <= time <= end` (RFC3339 bounds, inclusive) in
+    /// chronological order, walking the time index instead of scanning by
+    /// id. `kinds` are kind prefixes OR'ed together; empty means all kinds.
+    /// Returns the page plus an opaque cursor for the next page, `None`
+    /// when the range is exhausted. Pass the cursor back to resume; ties on
+    /// `time` are broken by id so no row is skipped or repeated.
+    pub fn events_in_range(
+        &self,
+        start: &str,
+        end: &str,
+        kinds: &[String],
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<EventRow>, Option<String>)> {
+        let limit = limit.clamp(1, 10_000);
+        let conn = self.conn()?;
+        let mut sql = String::from(
+            "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE time >= ? AND time <= ?",
+        );
+        let mut params: Vec<Value> =
+            vec![Value::Text(start.to_string()), Value::Text(end.to_string())];
+        if let Some(cur) = cursor {
+            let (id_s, time_s) = cur
+                .split_once('@')
+                .ok_or_else(|| anyhow!("invalid events_in_range cursor"))?;
+            let last_id: i64 = id_s
+                .parse()
+                .map_err(|_| anyhow!("invalid events_in_range cursor"))?;
+            sql.push_str(" AND (time > ? OR (time = ? AND id > ?))");
+            params.push(Value::Text(time_s.to_string()));
+            params.push(Value::Text(time_s.to_string()));
+            params.push(Value::Integer(last_id));
+        }
+        let sanitized: Vec<String> = kinds
+            .iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        if !sanitized.is_empty() {
+            let likes = vec!["kind LIKE ?"; sanitized.len()].join(" OR ");
+            sql.push_str(&format!(" AND ({})", likes));
+            for p in &sanitized {
+                params.push(Value::Text(format!("{}%", p)));
+            }
+        }
+        sql.push_str(&self.workspace_clause("workspace_id"));
+        sql.push_str(" ORDER BY time ASC, id ASC LIMIT ?");
+        params.push(Value::Integer(limit + 1));
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(params.iter()))?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_event_row(row)?);
+        }
+        let has_more = out.len() as i64 > limit;
+        if has_more {
+            out.truncate(limit as usize);
+        }
+        let next_cursor = if has_more {
+            out.last().map(|e| format!("{}@{}", e.id, e.time))
+        } else {
+            None
+        };
+        Ok((out, next_cursor))
+    }
+
+    /// Distinct correlation ids with their event counts for a trace
+    /// browser's index view. Null-corr events are excluded; most recently
+    /// active traces come first.
+    pub fn list_corr_ids(&self, since: Option<&str>, limit: i64) -> Result<Vec<(String, i64)>> {
+        let conn = self.conn()?;
+        let mut stmt_since;
+        let mut stmt_all;
+        let mut rows = if let Some(since) = since {
+            stmt_since = conn.prepare_cached(
+                "SELECT corr_id, COUNT(1) AS n FROM events \
+                 WHERE corr_id IS NOT NULL AND time >= ? \
+                 GROUP BY corr_id ORDER BY MAX(id) DESC LIMIT ?",
+            )?;
+            stmt_since.query(params![since, limit])?
+        } else {
+            stmt_all = conn.prepare_cached(
+                "SELECT corr_id, COUNT(1) AS n FROM events \
+                 WHERE corr_id IS NOT NULL \
+                 GROUP BY corr_id ORDER BY MAX(id) DESC LIMIT ?",
+            )?;
+            stmt_all.query(params![limit])?
+        };
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push((row.get(0)?, row.get(1)?));
+        }
+        Ok(out)
+    }
+
+    pub fn events_by_corr_id(&self, corr_id: &str, limit: Option<i64>) -> Result<Vec<EventRow>> {
+        let conn = self.conn()?;
+        let mut stmt_limit;
+        let mut stmt_all;
+        let mut rows = if let Some(limit) = limit {
+            stmt_limit = conn.prepare_cached(
+                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE corr_id = ? ORDER BY id ASC LIMIT ?",
+            )?;
+            stmt_limit.query(params![corr_id, limit])?
+        } else {
+            stmt_all = conn.prepare_cached(
+                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE corr_id = ? ORDER BY id ASC",
+            )?;
+            stmt_all.query(params![corr_id])?
+        };
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_event_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn events_by_corr_ids(
         &self,
         corr_ids: &[String],
         limit: Option<i64>,
@@ -1384,6 +3324,69 @@ impl Kernel {
         Ok(grouped)
     }
 
+    /// Newest events attributed to `actor`, walking the `(actor, id)`
+    /// composite index. Events appended without attribution never match.
+    pub fn events_by_actor(&self, actor: &str, limit: i64) -> Result<Vec<EventRow>> {
+        self.events_by_attribution("actor", actor, limit)
+    }
+
+    /// Newest events attributed to project `proj`, walking the `(proj, id)`
+    /// composite index.
+    pub fn events_by_project(&self, proj: &str, limit: i64) -> Result<Vec<EventRow>> {
+        self.events_by_attribution("proj", proj, limit)
+    }
+
+    fn events_by_attribution(
+        &self,
+        column: &'static str,
+        value: &str,
+        limit: i64,
+    ) -> Result<Vec<EventRow>> {
+        let conn = self.conn()?;
+        let ws = self.workspace_clause("workspace_id");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE {column} = ?{ws} ORDER BY id DESC LIMIT ?",
+        ))?;
+        let mut rows = stmt.query(params![value, limit.max(1)])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_event_row(row)?);
+        }
+        Ok(out)
+    }
+
+    /// Ranges of event ids missing from `[from_id, to_id]` (inclusive), so a
+    /// replay consumer can distinguish pruned history from a broken cursor.
+    pub fn detect_event_id_gaps(&self, from_id: i64, to_id: i64) -> Result<Vec<(i64, i64)>> {
+        if from_id > to_id {
+            return Ok(Vec::new());
+        }
+        let conn = self.conn()?;
+        let bounds: (Option<i64>, Option<i64>) = conn.query_row(
+            "SELECT MIN(id), MAX(id) FROM events WHERE id BETWEEN ?1 AND ?2",
+            params![from_id, to_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let (Some(min_id), Some(max_id)) = bounds else {
+            return Ok(vec![(from_id, to_id)]);
+        };
+        let mut gaps = Vec::new();
+        if min_id > from_id {
+            gaps.push((from_id, min_id - 1));
+        }
+        let mut stmt = conn.prepare(
+            "SELECT prev_id + 1, id - 1 FROM (\n                 SELECT id, LAG(id) OVER (ORDER BY id ASC) AS prev_id\n                 FROM events WHERE id BETWEEN ?1 AND ?2\n             ) WHERE prev_id IS NOT NULL AND id > prev_id + 1\n             ORDER BY id ASC",
+        )?;
+        let mut rows = stmt.query(params![from_id, to_id])?;
+        while let Some(row) = rows.next()? {
+            gaps.push((row.get(0)?, row.get(1)?));
+        }
+        if max_id < to_id {
+            gaps.push((max_id + 1, to_id));
+        }
+        Ok(gaps)
+    }
+
     pub fn tail_events(&self, limit: i64, prefixes: &[String]) -> Result<(Vec<EventRow>, i64)> {
         let conn = self.conn()?;
         let sanitized: Vec<String> = prefixes
@@ -1437,6 +3440,170 @@ impl Kernel {
         Ok((out_desc, total))
     }
 
+    /// Full-text search over event kinds and payloads. `query` uses FTS5
+    /// MATCH syntax; `kinds` (exact matches) and the optional RFC3339 time
+    /// bounds narrow the result. Newest hits come first.
+    pub fn search_events(
+        &self,
+        query: &str,
+        kinds: &[String],
+        since: Option<&str>,
+        until: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<EventRow>> {
+        let conn = self.conn()?;
+        let mut sql = String::from(
+            "SELECT e.id,e.time,e.kind,e.actor,e.proj,e.corr_id,e.payload \
+             FROM events e JOIN events_fts f ON f.id=e.id \
+             WHERE f.events_fts MATCH ?",
+        );
+        let mut args: Vec<&dyn rusqlite::ToSql> = vec![&query];
+        if !kinds.is_empty() {
+            let placeholders = vec!["?"; kinds.len()].join(",");
+            sql.push_str(&format!(" AND e.kind IN ({})", placeholders));
+            for k in kinds {
+                args.push(k);
+            }
+        }
+        if let Some(s) = since.as_ref() {
+            sql.push_str(" AND e.time >= ?");
+            args.push(s);
+        }
+        if let Some(u) = until.as_ref() {
+            sql.push_str(" AND e.time <= ?");
+            args.push(u);
+        }
+        sql.push_str(&self.workspace_clause("e.workspace_id"));
+        sql.push_str(" ORDER BY e.id DESC LIMIT ?");
+        args.push(&limit);
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(args.as_slice())?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_event_row(row)?);
+        }
+        Ok(out)
+    }
+
+    /// Stream events in `range` as NDJSON (one `EventRow` per line), oldest
+    /// first so the output replays in order. Pages with keyset pagination
+    /// over `id` so memory stays bounded regardless of log size. Returns
+    /// the number of rows written.
+    pub fn export_events_ndjson(
+        &self,
+        range: &EventExportRange,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<usize> {
+        let conn = self.conn()?;
+        let page_size: i64 = 500;
+        let cap: Option<usize> = if range.limit > 0 {
+            Some(range.limit as usize)
+        } else {
+            None
+        };
+        let mut cursor: i64 = range.after_id.unwrap_or(0);
+        let mut written = 0usize;
+        loop {
+            let mut sql = String::from(
+                "SELECT id,time,kind,actor,proj,corr_id,payload FROM events WHERE id > ?",
+            );
+            let mut params: Vec<Value> = vec![Value::Integer(cursor)];
+            if let Some(until) = range.until_id {
+                sql.push_str(" AND id <= ?");
+                params.push(Value::Integer(until));
+            }
+            if let Some(prefix) = range
+                .kind_prefix
+                .as_ref()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+            {
+                sql.push_str(" AND kind LIKE ?");
+                params.push(Value::Text(format!("{}%", prefix)));
+            }
+            sql.push_str(&self.workspace_clause("workspace_id"));
+            sql.push_str(" ORDER BY id ASC LIMIT ?");
+            let remaining = cap
+                .map(|c| (c.saturating_sub(written)) as i64)
+                .unwrap_or(page_size);
+            params.push(Value::Integer(remaining.min(page_size)));
+
+            let mut stmt = conn.prepare(&sql)?;
+            let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
+            let mut page_rows = 0usize;
+            while let Some(r) = rows.next()? {
+                let row = Self::map_event_row(r)?;
+                serde_json::to_writer(&mut *writer, &row)?;
+                writer.write_all(b"\n")?;
+                cursor = row.id;
+                page_rows += 1;
+                written += 1;
+            }
+            if page_rows == 0 || cap.map(|c| written >= c).unwrap_or(false) {
+                break;
+            }
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Import an NDJSON event stream produced by [`Self::export_events_ndjson`]
+    /// (or any line-per-`EventRow` source). Rows get fresh local ids; the
+    /// exported `id` is kept only inside the serialized line. Imports are
+    /// batched into transactions, skip blank lines, do not fan out to live
+    /// subscribers, and land unchained (the hash chain only covers locally
+    /// appended events). Returns the number of rows inserted.
+    pub fn import_events_ndjson(&self, reader: &mut dyn std::io::Read) -> Result<usize> {
+        self.ensure_writable()?;
+        use std::io::BufRead as _;
+        let mut conn = self.conn()?;
+        let buf = std::io::BufReader::new(reader);
+        let mut imported = 0usize;
+        let mut batch: Vec<EventRow> = Vec::new();
+        let flush = |conn: &mut Connection, batch: &mut Vec<EventRow>| -> Result<()> {
+            if batch.is_empty() {
+                return Ok(());
+            }
+            let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+            for row in batch.drain(..) {
+                let payload = serde_json::to_string(&row.payload).unwrap_or("{}".to_string());
+                tx.prepare_cached(
+                    "INSERT INTO events(time,kind,actor,proj,corr_id,payload,workspace_id) VALUES (?,?,?,?,?,?,?)",
+                )?
+                .execute(params![
+                    row.time,
+                    row.kind,
+                    row.actor,
+                    row.proj,
+                    row.corr_id,
+                    Self::payload_to_sql(payload.clone()),
+                    self.workspace,
+                ])?;
+                let id = tx.last_insert_rowid();
+                tx.prepare_cached("INSERT INTO events_fts(id, kind, payload) VALUES (?,?,?)")?
+                    .execute(params![id, row.kind, payload])?;
+            }
+            tx.commit()?;
+            Ok(())
+        };
+        for (line_no, line) in buf.lines().enumerate() {
+            let line = line?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let row: EventRow = serde_json::from_str(trimmed)
+                .with_context(|| format!("invalid event on line {}", line_no + 1))?;
+            batch.push(row);
+            imported += 1;
+            if batch.len() >= 500 {
+                flush(&mut conn, &mut batch)?;
+            }
+        }
+        flush(&mut conn, &mut batch)?;
+        Ok(imported)
+    }
+
     pub async fn cas_put(
         bytes: &[u8],
         mime: Option<&str>,
@@ -1461,2679 +3628,10557 @@ impl Kernel {
         Ok(sha)
     }
 
-    pub fn db_path(&self) -> &Path {
-        &self.db_path
+    /// Streaming variant of [`Kernel::cas_put`]: the reader is hashed and
+    /// spooled to a temp file in 64 KiB chunks, then renamed into place once
+    /// the digest is known, so multi-GB artifacts never sit in memory. The
+    /// rename makes the publish atomic; a crash leaves only a stray
+    /// `.tmp-*` file that the next [`Kernel::cas_gc`] run clears out.
+    pub async fn cas_put_stream<R>(
+        mut reader: R,
+        mime: Option<&str>,
+        meta: Option<&serde_json::Value>,
+        dir: &Path,
+    ) -> Result<String>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        use sha2::Digest as _;
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+        let cas_dir = dir.join("blobs");
+        tokio::fs::create_dir_all(&cas_dir).await.ok();
+        let tmp = cas_dir.join(format!(".tmp-{}", Uuid::new_v4()));
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        let mut h = sha2::Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            h.update(&buf[..n]);
+            file.write_all(&buf[..n]).await?;
+        }
+        file.sync_all().await?;
+        drop(file);
+        let sha = format!("{:x}", h.finalize());
+        let path = cas_dir.join(format!("{}.bin", sha));
+        if tokio::fs::metadata(&path).await.is_err() {
+            tokio::fs::rename(&tmp, &path).await?;
+        } else {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        let meta_path = cas_dir.join(format!("{}.json", sha));
+        let meta_obj = serde_json::json!({"mime": mime, "meta": meta});
+        tokio::fs::write(&meta_path, serde_json::to_vec(&meta_obj)?)
+            .await
+            .ok();
+        Ok(sha)
     }
 
-    pub fn insert_action(
-        &self,
-        id: &str,
-        kind: &str,
-        input: &serde_json::Value,
-        policy_ctx: Option<&serde_json::Value>,
-        idem_key: Option<&str>,
-        state: &str,
-    ) -> Result<()> {
-        let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let input_s = serde_json::to_string(input).unwrap_or("{}".to_string());
-        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".to_string()));
-        conn.execute(
-            "INSERT OR REPLACE INTO actions(id,kind,input,policy_ctx,idem_key,state,created,updated) VALUES(?,?,?,?,?,?,?,?)",
-            params![
-                id,
-                kind,
-                input_s,
-                policy_s,
-                idem_key,
-                state,
-                now,
-                now
-            ],
-        )?;
-        Ok(())
+    /// Open a blob for streaming reads; `None` when the digest is not in the
+    /// store (or is not a sha-256 hex string, which doubles as a path guard).
+    pub async fn cas_get_stream(sha: &str, dir: &Path) -> Result<Option<tokio::fs::File>> {
+        if sha.len() != 64 || !sha.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(None);
+        }
+        let path = dir.join("blobs").join(format!("{}.bin", sha));
+        match tokio::fs::File::open(&path).await {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
-    pub fn find_action_by_idem(&self, idem: &str) -> Result<Option<String>> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare("SELECT id FROM actions WHERE idem_key=? LIMIT 1")?;
-        let id_opt: Option<String> = stmt.query_row([idem], |row| row.get(0)).optional()?;
-        Ok(id_opt)
+    /// Mark-and-sweep the CAS blob directory (`{dir}/blobs`, the same `dir`
+    /// passed to [`Kernel::cas_put`]). A blob is live while its digest
+    /// appears in the `artifacts` table or anywhere in a memory record's
+    /// `source` JSON. Blobs younger than `ARW_CAS_GC_MIN_AGE_SEC` (default
+    /// 3600) are always kept so a sweep can't race an in-flight `cas_put`
+    /// whose referencing row hasn't landed yet. With `dry_run` nothing is
+    /// removed and the report only describes what a real run would reclaim.
+    pub fn cas_gc(&self, dir: &Path, dry_run: bool) -> Result<CasGcReport> {
+        let min_age = std::env::var("ARW_CAS_GC_MIN_AGE_SEC")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(3_600);
+        self.cas_gc_sweep(dir, dry_run, Duration::from_secs(min_age))
     }
 
-    pub fn get_action(&self, id: &str) -> Result<Option<ActionRow>> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id,kind,input,policy_ctx,idem_key,state,output,error,created,updated FROM actions WHERE id=? LIMIT 1",
-        )?;
-        let res: Result<ActionRow, _> = stmt.query_row([id], |row| {
-            let input_s: String = row.get(2)?;
-            let policy_s: Option<String> = row.get(3)?;
-            let input_v = serde_json::from_str(&input_s).unwrap_or(serde_json::json!({}));
-            let policy_v =
-                policy_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
-            Ok(ActionRow {
-                id: row.get(0)?,
-                kind: row.get(1)?,
-                input: input_v,
-                policy_ctx: policy_v,
-                idem_key: row.get(4)?,
-                state: row.get(5)?,
-                output: row
-                    .get::<_, Option<String>>(6)?
-                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
-                error: row.get(7)?,
-                created: row.get(8)?,
-                updated: row.get(9)?,
-            })
-        });
-        match res {
-            Ok(a) => Ok(Some(a)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
+    fn cas_gc_sweep(&self, dir: &Path, dry_run: bool, min_age: Duration) -> Result<CasGcReport> {
+        let mut live: HashSet<String> = HashSet::new();
+        {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare("SELECT sha256 FROM artifacts")?;
+            let mut rows = stmt.query([])?;
+            while let Some(r) = rows.next()? {
+                live.insert(r.get::<_, String>(0)?.to_ascii_lowercase());
+            }
+            let mut stmt =
+                conn.prepare("SELECT source FROM memory_records WHERE source IS NOT NULL")?;
+            let mut rows = stmt.query([])?;
+            while let Some(r) = rows.next()? {
+                let s: String = r.get(0)?;
+                if let Ok(v) = serde_json::from_str::<JsonValue>(&s) {
+                    Self::collect_sha256_refs(&v, &mut live);
+                }
+            }
+        }
+        let mut report = CasGcReport {
+            scanned: 0,
+            kept: 0,
+            deleted: 0,
+            deleted_bytes: 0,
+            dry_run,
+        };
+        let entries = match std::fs::read_dir(dir.join("blobs")) {
+            Ok(entries) => entries,
+            // No blob directory yet means nothing to sweep.
+            Err(_) => return Ok(report),
+        };
+        let now = std::time::SystemTime::now();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_spool = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(".tmp-"));
+            if !is_spool && path.extension().and_then(|e| e.to_str()) != Some("bin") {
+                continue;
+            }
+            if is_spool {
+                // Abandoned streaming spool; clear it once it is clearly not
+                // an in-flight upload. Not counted as a blob either way.
+                let old_enough = entry
+                    .metadata()
+                    .ok()
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|m| now.duration_since(m).ok())
+                    .is_some_and(|age| age >= min_age);
+                if old_enough && !dry_run {
+                    let _ = std::fs::remove_file(&path);
+                }
+                continue;
+            }
+            let Some(sha) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            report.scanned += 1;
+            let meta = entry.metadata().ok();
+            let young = meta
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|m| now.duration_since(m).ok())
+                .map(|age| age < min_age)
+                // An unreadable mtime is treated as young: keep, never guess.
+                .unwrap_or(true);
+            if young || live.contains(&sha.to_ascii_lowercase()) {
+                report.kept += 1;
+                continue;
+            }
+            let bytes = meta.map(|m| m.len()).unwrap_or(0);
+            if !dry_run {
+                std::fs::remove_file(&path)?;
+                let _ = std::fs::remove_file(path.with_extension("json"));
+            }
+            report.deleted += 1;
+            report.deleted_bytes += bytes;
+        }
+        #[cfg(feature = "metrics")]
+        if !dry_run {
+            metrics::counter!("arw_kernel_cas_gc_deleted").increment(report.deleted);
+            metrics::counter!("arw_kernel_cas_gc_reclaimed_bytes").increment(report.deleted_bytes);
+        }
+        Ok(report)
+    }
+
+    /// Collect anything that looks like a sha-256 hex digest from a JSON
+    /// tree. Deliberately loose: keeping an extra blob is cheap, deleting a
+    /// referenced one is not.
+    fn collect_sha256_refs(value: &JsonValue, out: &mut HashSet<String>) {
+        match value {
+            JsonValue::String(s) => {
+                let s = s.trim();
+                if s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    out.insert(s.to_ascii_lowercase());
+                }
+            }
+            JsonValue::Array(items) => {
+                for item in items {
+                    Self::collect_sha256_refs(item, out);
+                }
+            }
+            JsonValue::Object(map) => {
+                for item in map.values() {
+                    Self::collect_sha256_refs(item, out);
+                }
+            }
+            _ => {}
         }
     }
 
-    pub fn set_action_state(&self, id: &str, state: &str) -> Result<bool> {
+    /// Upsert an artifact row. Existing columns are only overwritten by
+    /// non-NULL values, so re-registering a digest can add a mime or meta
+    /// without clobbering inline bytes. Returns true when the digest was new.
+    pub fn insert_artifact(&self, row: &ArtifactRow) -> Result<bool> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let n = conn.execute(
-            "UPDATE actions SET state=?, updated=? WHERE id=?",
-            params![state, now, id],
+        let existed = conn
+            .query_row(
+                "SELECT 1 FROM artifacts WHERE sha256 = ?",
+                [row.sha256.as_str()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        let meta_s = row
+            .meta
+            .as_ref()
+            .and_then(|v| serde_json::to_string(v).ok());
+        conn.execute(
+            "INSERT INTO artifacts(sha256,mime,bytes,meta) VALUES(?,?,?,?)
+             ON CONFLICT(sha256) DO UPDATE SET
+               mime = COALESCE(excluded.mime, mime),
+               bytes = COALESCE(excluded.bytes, bytes),
+               meta = COALESCE(excluded.meta, meta)",
+            params![row.sha256, row.mime, row.bytes, meta_s],
         )?;
-        Ok(n > 0)
+        Ok(!existed)
     }
 
-    pub fn delete_actions_by_state(&self, state: &str) -> Result<u64> {
+    pub fn get_artifact(&self, sha256: &str) -> Result<Option<ArtifactRow>> {
         let conn = self.conn()?;
-        let n = conn.execute("DELETE FROM actions WHERE state=?", params![state])?;
-        Ok(n as u64)
+        conn.query_row(
+            "SELECT sha256,mime,bytes,meta FROM artifacts WHERE sha256 = ?",
+            [sha256],
+            Self::map_artifact_row,
+        )
+        .optional()
+        .map_err(Into::into)
     }
 
-    pub async fn delete_actions_by_state_async(&self, state: &str) -> Result<u64> {
-        let state = state.to_string();
-        self.run_blocking(move |k| k.delete_actions_by_state(&state))
-            .await
+    pub fn list_artifacts(&self, limit: i64) -> Result<Vec<ArtifactRow>> {
+        self.search_artifacts(None, None, limit)
     }
 
-    pub fn update_action_result(
+    /// Filter artifacts by mime prefix (e.g. `image/`) and/or exact matches
+    /// on top-level `meta` JSON fields. The meta filter must be an object;
+    /// each entry becomes a `json_extract` equality over scalars.
+    pub fn search_artifacts(
         &self,
-        id: &str,
-        output: Option<&serde_json::Value>,
-        error: Option<&str>,
-    ) -> Result<bool> {
+        mime_prefix: Option<&str>,
+        meta_filter: Option<&JsonValue>,
+        limit: i64,
+    ) -> Result<Vec<ArtifactRow>> {
         let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let out_s = output.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
-        let n = conn.execute(
-            "UPDATE actions SET output=COALESCE(?,output), error=COALESCE(?,error), updated=? WHERE id=?",
-            params![out_s, error, now, id],
-        )?;
+        let mut sql = String::from("SELECT sha256,mime,bytes,meta FROM artifacts WHERE 1=1");
+        let mut binds: Vec<Value> = Vec::new();
+        if let Some(prefix) = mime_prefix {
+            binds.push(Value::Text(prefix.to_string()));
+            sql.push_str(&format!(" AND mime LIKE ?{} || '%'", binds.len()));
+        }
+        if let Some(filter) = meta_filter {
+            let obj = filter
+                .as_object()
+                .ok_or_else(|| anyhow!("artifact meta filter must be a JSON object"))?;
+            for (key, expect) in obj {
+                binds.push(Value::Text(format!("$.{}", key)));
+                let path_idx = binds.len();
+                let bound = match expect {
+                    JsonValue::String(s) => Value::Text(s.clone()),
+                    JsonValue::Bool(b) => Value::Integer(*b as i64),
+                    JsonValue::Number(n) => n
+                        .as_i64()
+                        .map(Value::Integer)
+                        .or_else(|| n.as_f64().map(Value::Real))
+                        .ok_or_else(|| anyhow!("unsupported meta filter number: {}", n))?,
+                    JsonValue::Null => {
+                        sql.push_str(&format!(" AND json_extract(meta, ?{}) IS NULL", path_idx));
+                        continue;
+                    }
+                    other => {
+                        return Err(anyhow!(
+                            "artifact meta filter values must be scalars, got {}",
+                            other
+                        ))
+                    }
+                };
+                binds.push(bound);
+                sql.push_str(&format!(
+                    " AND json_extract(meta, ?{}) = ?{}",
+                    path_idx,
+                    binds.len()
+                ));
+            }
+        }
+        binds.push(Value::Integer(limit.max(1)));
+        sql.push_str(&format!(" ORDER BY sha256 LIMIT ?{}", binds.len()));
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(binds))?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(Self::map_artifact_row(r)?);
+        }
+        Ok(out)
+    }
+
+    /// Remove an artifact row, returning whether it existed. The CAS blob is
+    /// left for [`Kernel::cas_gc`] to reclaim once nothing references it.
+    pub fn delete_artifact(&self, sha256: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let n = conn.execute("DELETE FROM artifacts WHERE sha256 = ?", [sha256])?;
         Ok(n > 0)
     }
 
-    pub fn list_actions(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let opts = ActionListOptions {
-            limit,
-            ..Default::default()
-        };
-        self.list_actions_filtered(&opts)
+    fn map_artifact_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<ArtifactRow> {
+        let meta: Option<String> = r.get(3)?;
+        Ok(ArtifactRow {
+            sha256: r.get(0)?,
+            mime: r.get(1)?,
+            bytes: r.get(2)?,
+            meta: meta.and_then(|s| serde_json::from_str(&s).ok()),
+        })
     }
 
-    pub fn list_actions_filtered(
+    /// Store bytes in the CAS directory and register the digest in the
+    /// artifacts table (without inlining the payload). Returns the digest.
+    pub async fn put_artifact(
         &self,
-        opts: &ActionListOptions,
-    ) -> Result<Vec<serde_json::Value>> {
-        let conn = self.conn()?;
-        let mut sql = String::from("SELECT id,kind,state,created,updated FROM actions");
-        let mut clauses: Vec<&str> = Vec::new();
-        let mut params: Vec<Value> = Vec::new();
-
-        if let Some(state) = opts
-            .state
-            .as_ref()
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-        {
-            clauses.push("state = ?");
-            params.push(Value::Text(state.to_string()))
-        }
+        bytes: &[u8],
+        mime: Option<&str>,
+        meta: Option<&serde_json::Value>,
+        dir: &Path,
+    ) -> Result<String> {
+        let sha = Self::cas_put(bytes, mime, meta, dir).await?;
+        self.insert_artifact(&ArtifactRow {
+            sha256: sha.clone(),
+            mime: mime.map(|s| s.to_string()),
+            bytes: None,
+            meta: meta.cloned(),
+        })?;
+        Ok(sha)
+    }
 
-        if let Some(prefix) = opts
-            .kind_prefix
-            .as_ref()
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-        {
-            clauses.push("kind LIKE ?");
-            params.push(Value::Text(format!("{}%", prefix)));
+    /// Fetch an artifact's payload: inline bytes when present, otherwise the
+    /// CAS blob under `dir`. `None` when the digest is unknown to both.
+    pub async fn load_artifact_bytes(&self, sha256: &str, dir: &Path) -> Result<Option<Vec<u8>>> {
+        if let Some(row) = self.get_artifact(sha256)? {
+            if let Some(bytes) = row.bytes {
+                return Ok(Some(bytes));
+            }
         }
-
-        if let Some(since) = opts
-            .updated_since
-            .as_ref()
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-        {
-            clauses.push("updated >= ?");
-            params.push(Value::Text(since.to_string()));
+        let path = dir.join("blobs").join(format!("{}.bin", sha256));
+        if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(None);
         }
-
-        if !clauses.is_empty() {
-            sql.push_str(" WHERE ");
-            sql.push_str(&clauses.join(" AND "));
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
         }
+    }
 
-        sql.push_str(" ORDER BY updated DESC LIMIT ?");
-        params.push(Value::Integer(opts.clamped_limit()));
+    pub fn db_path(&self) -> &Path {
+        &self.db_path
+    }
 
-        let mut stmt = conn.prepare(&sql)?;
-        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            out.push(serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "kind": r.get::<_, String>(1)?,
-                "state": r.get::<_, String>(2)?,
-                "created": r.get::<_, String>(3)?,
-                "updated": r.get::<_, String>(4)?,
-            }));
+    /// A handle bound to one workspace: its event, action, and lease writes
+    /// are stamped with `workspace_id`, and its list/search and dequeue APIs
+    /// only see rows with that stamp. Handles share the same pool and loops,
+    /// so scoping is free to hand out per tenant. The unscoped handle (the
+    /// default) is the operator view and sees everything, including legacy
+    /// rows with no stamp. Personas already scope through
+    /// `owner_kind='workspace'`/`owner_ref`, and the memory overlay keeps
+    /// its existing agent/project dimensions.
+    pub fn scoped_to_workspace(&self, workspace_id: impl Into<String>) -> Kernel {
+        let mut scoped = self.clone();
+        scoped.workspace = Some(workspace_id.into());
+        scoped
+    }
+
+    pub fn workspace(&self) -> Option<&str> {
+        self.workspace.as_deref()
+    }
+
+    /// SQL fragment enforcing this handle's workspace scope, or empty for
+    /// the unscoped view. Starts with ` AND ` so call sites append it after
+    /// an existing predicate; the id is embedded as an escaped literal to
+    /// leave each site's positional bindings untouched.
+    fn workspace_clause(&self, column: &str) -> String {
+        match self.workspace.as_deref() {
+            Some(ws) => format!(" AND {} = '{}'", column, ws.replace('\'', "''")),
+            None => String::new(),
         }
-        Ok(out)
     }
 
-    pub fn count_actions_by_state(&self, state: &str) -> Result<i64> {
+    /// One-call introspection snapshot: database and WAL file sizes,
+    /// per-table row counts (FTS shadow tables skipped), connection pool
+    /// state, checkout wait stats, and the blocking pool's queue depth.
+    /// Shaped so the server can expose it under `/state/kernel` verbatim.
+    pub fn stats(&self) -> Result<JsonValue> {
+        let mut tables = serde_json::Map::new();
+        {
+            let conn = self.conn()?;
+            let names: Vec<String> = {
+                let mut stmt = conn.prepare(
+                    "SELECT name FROM sqlite_master WHERE type='table' \
+                     AND name NOT LIKE 'sqlite%' AND name NOT GLOB '*_fts_*' ORDER BY name",
+                )?;
+                let rows = stmt.query_map([], |r| r.get(0))?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+            for name in names {
+                let count: i64 = conn
+                    .query_row(&format!("SELECT COUNT(*) FROM \"{}\"", name), [], |r| {
+                        r.get(0)
+                    })
+                    .unwrap_or(-1);
+                tables.insert(name, json!(count));
+            }
+        }
+        let file_len = |path: &Path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let mut wal_path = self.db_path.clone().into_os_string();
+        wal_path.push("-wal");
+        let (pool_idle, pool_created) = {
+            let guard = self.pool.state.lock().expect("pool mutex poisoned");
+            (guard.conns.len(), guard.created)
+        };
+        let (wait_count, wait_total_ms) = {
+            let waits = self
+                .pool
+                .wait_stats
+                .lock()
+                .expect("pool wait stats mutex poisoned");
+            (waits.count, waits.total_ms)
+        };
+        Ok(json!({
+            "db": {
+                "path": self.db_path.display().to_string(),
+                "bytes": file_len(&self.db_path),
+                "wal_bytes": file_len(Path::new(&wal_path)),
+            },
+            "tables": tables,
+            "pool": {
+                "idle": pool_idle,
+                "created": pool_created,
+                "target": self.pool.target_size.load(Ordering::Relaxed),
+                "min": self.pool.min_size,
+                "max": self.pool.max_ceiling,
+            },
+            "waits": {
+                "count": wait_count,
+                "total_ms": wait_total_ms,
+            },
+            "blocking": {
+                "queue_depth": self.blocking.queue_depth(),
+            },
+        }))
+    }
+
+    pub async fn stats_async(&self) -> Result<JsonValue> {
+        self.run_blocking(move |k| k.stats()).await
+    }
+
+    /// Snapshot the live database to `dest` using `VACUUM INTO`, which takes
+    /// a consistent, defragmented copy without stopping writers. The target
+    /// must not already exist; parent directories are created as needed.
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        if dest.exists() {
+            return Err(anyhow!("backup target already exists: {}", dest.display()));
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let dest_s = dest
+            .to_str()
+            .ok_or_else(|| anyhow!("backup target path is not valid UTF-8"))?;
         let conn = self.conn()?;
-        let mut stmt = conn.prepare_cached("SELECT COUNT(1) FROM actions WHERE state=?")?;
-        let n: i64 = stmt.query_row([state], |row| row.get(0))?;
-        Ok(n)
+        conn.execute("VACUUM INTO ?", [dest_s])
+            .with_context(|| format!("backup to {} failed", dest.display()))?;
+        Ok(())
     }
 
-    pub fn dequeue_one_queued(&self) -> Result<Option<(String, String, serde_json::Value)>> {
-        let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let mut stmt = conn.prepare_cached(
-            "UPDATE actions SET state='running', updated=? WHERE id = (
-                 SELECT id FROM actions WHERE state='queued' ORDER BY created LIMIT 1
-             ) RETURNING id, kind, input",
-        )?;
-        let mut rows = stmt.query(params![now])?;
-        if let Some(row) = rows.next()? {
-            let id: String = row.get(0)?;
-            let kind: String = row.get(1)?;
-            let input_s: String = row.get(2)?;
-            let input_v = serde_json::from_str(&input_s).unwrap_or(serde_json::json!({}));
-            return Ok(Some((id, kind, input_v)));
+    pub async fn backup_to_async(&self, dest: PathBuf) -> Result<()> {
+        self.run_blocking(move |k| k.backup_to(&dest)).await
+    }
+
+    /// Replace the database under `dir` with the snapshot at `backup`.
+    /// The snapshot is validated first (`PRAGMA quick_check` plus a probe
+    /// for the events table); any current database is kept alongside as
+    /// `events.sqlite.pre-restore` and stale WAL/SHM files are dropped so
+    /// SQLite doesn't replay them over the snapshot. Restore is an offline
+    /// operation: run it before [`Kernel::open`], never against a state dir
+    /// another process has open.
+    pub fn restore_from(backup: &Path, dir: &Path) -> Result<()> {
+        let src = Connection::open_with_flags(backup, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("open backup {}", backup.display()))?;
+        let check: String = src
+            .query_row("PRAGMA quick_check", [], |r| r.get(0))
+            .with_context(|| format!("integrity check on {}", backup.display()))?;
+        if check != "ok" {
+            return Err(anyhow!("backup failed integrity check: {}", check));
         }
-        Ok(None)
+        let has_events = src
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type='table' AND name='events'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if !has_events {
+            return Err(anyhow!(
+                "backup does not look like a kernel database (no events table): {}",
+                backup.display()
+            ));
+        }
+        drop(src);
+        std::fs::create_dir_all(dir)?;
+        let db_path = dir.join("events.sqlite");
+        if db_path.exists() {
+            std::fs::rename(&db_path, dir.join("events.sqlite.pre-restore"))?;
+        }
+        let _ = std::fs::remove_file(dir.join("events.sqlite-wal"));
+        let _ = std::fs::remove_file(dir.join("events.sqlite-shm"));
+        std::fs::copy(backup, &db_path)?;
+        Ok(())
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn insert_lease(
+    pub fn insert_action(
         &self,
         id: &str,
-        subject: &str,
-        capability: &str,
-        scope: Option<&str>,
-        ttl_until: &str,
-        budget: Option<f64>,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        state: &str,
+    ) -> Result<()> {
+        self.insert_action_with_priority(id, kind, input, policy_ctx, idem_key, state, 0)
+    }
+
+    /// Enqueue an action that only becomes eligible to dequeue at `run_after`
+    /// (RFC3339). Useful for retries and cron-like follow-ups; until then the
+    /// action sits `queued` but is skipped by the workers.
+    pub fn insert_scheduled_action(
+        &self,
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
         policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        run_after: &str,
     ) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        let now = self.now_rfc3339();
+        let input_s = serde_json::to_string(input).unwrap_or("{}".to_string());
+        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".to_string()));
         conn.execute(
-            "INSERT OR REPLACE INTO leases(id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated) VALUES(?,?,?,?,?,?,?,?,?)",
-            params![id, subject, capability, scope, ttl_until, budget, policy_s, now, now],
+            "INSERT OR REPLACE INTO actions(id,kind,input,policy_ctx,idem_key,state,run_after,workspace_id,created,updated) VALUES(?,?,?,?,?,'queued',?,?,?,?)",
+            params![id, kind, input_s, policy_s, idem_key, run_after, self.workspace, now, now],
         )?;
         Ok(())
     }
 
-    pub fn list_leases(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+    /// Queued actions whose `run_after` is still in the future, soonest
+    /// first, so schedulers can show what is parked and when it unblocks.
+    pub fn list_deferred_actions(&self, limit: i64) -> Result<Vec<ActionRow>> {
         let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated FROM leases ORDER BY updated DESC LIMIT ?",
-        )?;
-        let mut rows = stmt.query([limit])?;
+        let now = self.now_rfc3339();
+        let ws = self.workspace_clause("workspace_id");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT id,kind,input,policy_ctx,idem_key,state,output,error,meta,created,updated,priority,run_after,attempts FROM actions
+             WHERE state='queued' AND deleted_at IS NULL AND run_after IS NOT NULL AND run_after > ?{ws}
+             ORDER BY run_after ASC LIMIT ?",
+        ))?;
+        let mut rows = stmt.query(params![now, limit])?;
         let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let policy_s: Option<String> = r.get(6)?;
-            let policy_v = policy_s
-                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                .unwrap_or(serde_json::json!({}));
-            out.push(serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "subject": r.get::<_, String>(1)?,
-                "capability": r.get::<_, String>(2)?,
-                "scope": r.get::<_, Option<String>>(3)?,
-                "ttl_until": r.get::<_, String>(4)?,
-                "budget": r.get::<_, Option<f64>>(5)?,
-                "policy": policy_v,
-                "created": r.get::<_, String>(7)?,
-                "updated": r.get::<_, String>(8)?,
-            }));
+        while let Some(row) = rows.next()? {
+            let input_s: String = row.get(2)?;
+            let policy_s: Option<String> = row.get(3)?;
+            out.push(ActionRow {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                input: serde_json::from_str(&input_s).unwrap_or(serde_json::json!({})),
+                policy_ctx: policy_s
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                idem_key: row.get(4)?,
+                state: row.get(5)?,
+                output: row
+                    .get::<_, Option<String>>(6)?
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                error: row.get(7)?,
+                meta: row
+                    .get::<_, Option<String>>(8)?
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                created: row.get(9)?,
+                updated: row.get(10)?,
+                priority: row.get(11)?,
+                run_after: row.get(12)?,
+                attempts: row.get(13)?,
+            });
         }
         Ok(out)
     }
 
+    /// Enqueue an action that declares prerequisite action ids. The action
+    /// stays `queued` but is skipped by the dequeuer until every
+    /// prerequisite reaches `completed`; a prerequisite that fails (or does
+    /// not exist) blocks it indefinitely.
     #[allow(clippy::too_many_arguments)]
-    pub fn append_contribution(
+    pub fn insert_action_with_deps(
         &self,
-        subject: &str,
+        id: &str,
         kind: &str,
-        qty: f64,
-        unit: &str,
-        corr_id: Option<&str>,
-        proj: Option<&str>,
-        meta: Option<&serde_json::Value>,
-    ) -> Result<i64> {
-        let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let meta_s = meta.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
-        conn.execute(
-            "INSERT INTO contributions(time,subject,kind,qty,unit,corr_id,proj,meta) VALUES(?,?,?,?,?,?,?,?)",
-            params![now, subject, kind, qty, unit, corr_id, proj, meta_s],
-        )?;
-        Ok(conn.last_insert_rowid())
-    }
-
-    pub fn list_contributions(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id,time,subject,kind,qty,unit,corr_id,proj,meta FROM contributions ORDER BY id DESC LIMIT ?",
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        state: &str,
+        deps: &[String],
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let input_s = serde_json::to_string(input).unwrap_or("{}".to_string());
+        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".to_string()));
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO actions(id,kind,input,policy_ctx,idem_key,state,workspace_id,created,updated) VALUES(?,?,?,?,?,?,?,?,?)",
+            params![id, kind, input_s, policy_s, idem_key, state, self.workspace, now, now],
         )?;
-        let mut rows = stmt.query([limit])?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let meta_s: Option<String> = r.get(8)?;
-            let meta_v = meta_s
-                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                .unwrap_or(serde_json::json!({}));
-            out.push(serde_json::json!({
-                "id": r.get::<_, i64>(0)?,
-                "time": r.get::<_, String>(1)?,
-                "subject": r.get::<_, String>(2)?,
-                "kind": r.get::<_, String>(3)?,
-                "qty": r.get::<_, f64>(4)?,
-                "unit": r.get::<_, String>(5)?,
-                "corr_id": r.get::<_, Option<String>>(6)?,
-                "proj": r.get::<_, Option<String>>(7)?,
-                "meta": meta_v,
-            }));
-        }
-        Ok(out)
-    }
-
-    // ---------- Research watcher ----------
-
-    #[allow(clippy::too_many_arguments)]
-    pub fn upsert_research_watcher_item(
-        &self,
-        source: Option<&str>,
-        source_id: Option<&str>,
-        title: Option<&str>,
-        summary: Option<&str>,
-        url: Option<&str>,
-        payload: Option<&serde_json::Value>,
-    ) -> Result<String> {
-        let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let payload_s = payload.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
-        let existing_id: Option<String> = if let Some(src_id) = source_id {
-            conn.query_row(
-                "SELECT id FROM research_watcher_items WHERE source_id = ? LIMIT 1",
-                params![src_id],
-                |r| r.get(0),
-            )
-            .optional()?
-        } else {
-            None
-        };
-        let (id, existed) = if let Some(existing) = existing_id {
-            (existing, true)
-        } else {
-            (uuid::Uuid::new_v4().to_string(), false)
-        };
-        if existed {
-            conn.execute(
-                "UPDATE research_watcher_items SET source=?, title=?, summary=?, url=?, payload=?, updated=? WHERE id=?",
-                params![source, title, summary, url, payload_s, now, id],
-            )?;
-        } else {
-            conn.execute(
-                "INSERT INTO research_watcher_items(id,source,source_id,title,summary,url,payload,status,note,created,updated) VALUES(?,?,?,?,?,?,?,?,?,?,?)",
-                params![
-                    id,
-                    source,
-                    source_id,
-                    title,
-                    summary,
-                    url,
-                    payload_s,
-                    "pending",
-                    Option::<String>::None,
-                    now.clone(),
-                    now
-                ],
+        tx.execute("DELETE FROM action_deps WHERE action_id=?", params![id])?;
+        for dep in deps {
+            if dep == id {
+                return Err(anyhow!("action {id} cannot depend on itself"));
+            }
+            tx.execute(
+                "INSERT OR IGNORE INTO action_deps(action_id, depends_on, created) VALUES(?,?,?)",
+                params![id, dep, now],
             )?;
         }
-        Ok(id)
+        tx.commit()?;
+        Ok(())
     }
 
-    pub fn list_research_watcher_items(
-        &self,
-        status: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
+    /// Dependency edges touching `id` in both directions, with the current
+    /// state of each neighbour, shaped for the UI's DAG view.
+    pub fn action_dependency_graph(&self, id: &str) -> Result<serde_json::Value> {
         let conn = self.conn()?;
-        let limit = limit.clamp(1, 500);
-        let mut out = Vec::new();
-        if let Some(stat) = status {
-            let mut stmt = conn.prepare(
-                "SELECT id,source,source_id,title,summary,url,payload,status,note,created,updated FROM research_watcher_items WHERE status=? ORDER BY updated DESC LIMIT ?",
-            )?;
-            let mut rows = stmt.query(params![stat, limit])?;
-            while let Some(r) = rows.next()? {
-                let payload_s: Option<String> = r.get(6)?;
-                let payload_v = payload_s
-                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                    .unwrap_or(serde_json::json!({}));
-                out.push(serde_json::json!({
-                    "id": r.get::<_, String>(0)?,
-                    "source": r.get::<_, Option<String>>(1)?,
-                    "source_id": r.get::<_, Option<String>>(2)?,
-                    "title": r.get::<_, Option<String>>(3)?,
-                    "summary": r.get::<_, Option<String>>(4)?,
-                    "url": r.get::<_, Option<String>>(5)?,
-                    "payload": payload_v,
-                    "status": r.get::<_, String>(7)?,
-                    "note": r.get::<_, Option<String>>(8)?,
-                    "created": r.get::<_, String>(9)?,
-                    "updated": r.get::<_, String>(10)?
-                }));
-            }
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT id,source,source_id,title,summary,url,payload,status,note,created,updated FROM research_watcher_items ORDER BY updated DESC LIMIT ?",
-            )?;
-            let mut rows = stmt.query([limit])?;
+        let collect = |sql: &str| -> Result<Vec<serde_json::Value>> {
+            let mut stmt = conn.prepare(sql)?;
+            let mut rows = stmt.query(params![id])?;
+            let mut out = Vec::new();
             while let Some(r) = rows.next()? {
-                let payload_s: Option<String> = r.get(6)?;
-                let payload_v = payload_s
-                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                    .unwrap_or(serde_json::json!({}));
                 out.push(serde_json::json!({
                     "id": r.get::<_, String>(0)?,
-                    "source": r.get::<_, Option<String>>(1)?,
-                    "source_id": r.get::<_, Option<String>>(2)?,
-                    "title": r.get::<_, Option<String>>(3)?,
-                    "summary": r.get::<_, Option<String>>(4)?,
-                    "url": r.get::<_, Option<String>>(5)?,
-                    "payload": payload_v,
-                    "status": r.get::<_, String>(7)?,
-                    "note": r.get::<_, Option<String>>(8)?,
-                    "created": r.get::<_, String>(9)?,
-                    "updated": r.get::<_, String>(10)?
+                    "state": r.get::<_, Option<String>>(1)?,
                 }));
             }
-        }
-        Ok(out)
-    }
-
-    pub fn update_research_watcher_status(
-        &self,
-        id: &str,
-        status: &str,
-        note: Option<&str>,
-    ) -> Result<bool> {
-        let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let n = conn.execute(
-            "UPDATE research_watcher_items SET status=?, note=?, updated=? WHERE id=?",
-            params![status, note, now, id],
+            Ok(out)
+        };
+        let depends_on = collect(
+            "SELECT d.depends_on, p.state FROM action_deps d
+             LEFT JOIN actions p ON p.id = d.depends_on
+             WHERE d.action_id = ? ORDER BY d.depends_on",
         )?;
-        Ok(n > 0)
-    }
-
-    pub fn get_research_watcher_item(&self, id: &str) -> Result<Option<ResearchWatcherItem>> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id,source,source_id,title,summary,url,payload,status,note,created,updated FROM research_watcher_items WHERE id=? LIMIT 1",
+        let dependents = collect(
+            "SELECT d.action_id, a.state FROM action_deps d
+             LEFT JOIN actions a ON a.id = d.action_id
+             WHERE d.depends_on = ? ORDER BY d.action_id",
         )?;
-        let mut rows = stmt.query([id])?;
-        if let Some(r) = rows.next()? {
-            let payload_s: Option<String> = r.get(6)?;
-            let payload_v =
-                payload_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
-            Ok(Some(ResearchWatcherItem {
-                id: r.get(0)?,
-                source: r.get(1)?,
-                source_id: r.get(2)?,
-                title: r.get(3)?,
-                summary: r.get(4)?,
-                url: r.get(5)?,
-                payload: payload_v,
-                status: r.get(7)?,
-                note: r.get(8)?,
-                created: r.get(9)?,
-                updated: r.get(10)?,
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(serde_json::json!({
+            "id": id,
+            "depends_on": depends_on,
+            "dependents": dependents,
+        }))
     }
 
-    // ---------- Staging actions ----------
-
+    /// Like [`Self::insert_action`], but with an explicit dequeue priority.
+    /// Higher priorities are picked up first; equal priorities stay FIFO.
     #[allow(clippy::too_many_arguments)]
-    pub fn insert_staging_action(
+    pub fn insert_action_with_priority(
         &self,
-        action_kind: &str,
-        action_input: &serde_json::Value,
-        project: Option<&str>,
-        requested_by: Option<&str>,
-        evidence: Option<&serde_json::Value>,
-    ) -> Result<String> {
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        state: &str,
+        priority: i64,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let started = Instant::now();
         let conn = self.conn()?;
-        let id = uuid::Uuid::new_v4().to_string();
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let input_s = serde_json::to_string(action_input).unwrap_or("{}".into());
-        let evidence_s = evidence.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        let now = self.now_rfc3339();
+        let input_s = serde_json::to_string(input).unwrap_or("{}".to_string());
+        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".to_string()));
         conn.execute(
-            "INSERT INTO staging_actions(id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated) VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?)",
+            "INSERT OR REPLACE INTO actions(id,kind,input,policy_ctx,idem_key,state,priority,workspace_id,created,updated) VALUES(?,?,?,?,?,?,?,?,?,?)",
             params![
                 id,
-                action_kind,
+                kind,
                 input_s,
-                project,
-                requested_by,
-                evidence_s,
-                "pending",
-                Option::<String>::None,
-                Option::<String>::None,
-                Option::<String>::None,
-                Option::<String>::None,
-                now.clone(),
+                policy_s,
+                idem_key,
+                state,
+                priority,
+                self.workspace,
+                now,
                 now
             ],
         )?;
-        Ok(id)
+        Self::observe_op("insert_action", started);
+        Self::observe_op_rows("insert_action", 1);
+        Ok(())
     }
 
-    pub fn list_staging_actions(
-        &self,
-        status: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
+    /// TTL for idempotency keys (`ARW_ACTIONS_IDEM_TTL_SEC`). Unset or `0`
+    /// keeps keys forever, the historical behavior. With a TTL, dedup lookups
+    /// ignore keys on actions older than the window, so a long-retired key
+    /// can be reused without colliding with a months-old submission.
+    fn idem_ttl() -> Option<Duration> {
+        std::env::var("ARW_ACTIONS_IDEM_TTL_SEC")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Oldest `created` an action may have for its idem key to still count.
+    fn idem_cutoff(&self) -> Option<String> {
+        Self::idem_ttl().map(|ttl| {
+            (self.clock.now() - ttl).to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        })
+    }
+
+    /// Clear idem keys on actions older than the TTL so the keyspace can be
+    /// reused; the action rows themselves are untouched. The maintenance
+    /// loop runs this alongside event pruning; it is also callable directly.
+    /// No-op when no TTL is configured.
+    pub fn prune_expired_idem_keys(&self) -> Result<u64> {
+        self.ensure_writable()?;
+        let Some(cutoff) = self.idem_cutoff() else {
+            return Ok(0);
+        };
         let conn = self.conn()?;
-        let limit = limit.clamp(1, 500);
-        let mut out = Vec::new();
-        if let Some(stat) = status {
-            let mut stmt = conn.prepare(
-                "SELECT id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated FROM staging_actions WHERE status=? ORDER BY created ASC LIMIT ?",
-            )?;
-            let mut rows = stmt.query(params![stat, limit])?;
-            while let Some(r) = rows.next()? {
-                let input_s: String = r.get(2)?;
-                let evidence_s: Option<String> = r.get(5)?;
-                let input_v = serde_json::from_str::<serde_json::Value>(&input_s)
-                    .unwrap_or(serde_json::json!({}));
-                let evidence_v = evidence_s
-                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                    .unwrap_or(serde_json::json!({}));
-                out.push(serde_json::json!({
-                    "id": r.get::<_, String>(0)?,
-                    "action_kind": r.get::<_, String>(1)?,
-                    "action_input": input_v,
-                    "project": r.get::<_, Option<String>>(3)?,
-                    "requested_by": r.get::<_, Option<String>>(4)?,
-                    "evidence": evidence_v,
-                    "status": r.get::<_, String>(6)?,
-                    "decision": r.get::<_, Option<String>>(7)?,
-                    "decided_by": r.get::<_, Option<String>>(8)?,
-                    "decided_at": r.get::<_, Option<String>>(9)?,
-                    "action_id": r.get::<_, Option<String>>(10)?,
-                    "created": r.get::<_, String>(11)?,
-                    "updated": r.get::<_, String>(12)?
-                }));
-            }
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated FROM staging_actions ORDER BY created ASC LIMIT ?",
-            )?;
-            let mut rows = stmt.query([limit])?;
-            while let Some(r) = rows.next()? {
-                let input_s: String = r.get(2)?;
-                let evidence_s: Option<String> = r.get(5)?;
-                let input_v = serde_json::from_str::<serde_json::Value>(&input_s)
-                    .unwrap_or(serde_json::json!({}));
-                let evidence_v = evidence_s
-                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                    .unwrap_or(serde_json::json!({}));
-                out.push(serde_json::json!({
-                    "id": r.get::<_, String>(0)?,
-                    "action_kind": r.get::<_, String>(1)?,
-                    "action_input": input_v,
-                    "project": r.get::<_, Option<String>>(3)?,
-                    "requested_by": r.get::<_, Option<String>>(4)?,
-                    "evidence": evidence_v,
-                    "status": r.get::<_, String>(6)?,
-                    "decision": r.get::<_, Option<String>>(7)?,
-                    "decided_by": r.get::<_, Option<String>>(8)?,
-                    "decided_at": r.get::<_, Option<String>>(9)?,
-                    "action_id": r.get::<_, Option<String>>(10)?,
-                    "created": r.get::<_, String>(11)?,
-                    "updated": r.get::<_, String>(12)?
-                }));
-            }
+        let n = conn.execute(
+            "UPDATE actions SET idem_key=NULL WHERE idem_key IS NOT NULL AND created < ?",
+            params![cutoff],
+        )?;
+        Ok(n as u64)
+    }
+
+    pub async fn prune_expired_idem_keys_async(&self) -> Result<u64> {
+        self.run_blocking(move |k| k.prune_expired_idem_keys())
+            .await
+    }
+
+    pub fn find_action_by_idem(&self, idem: &str) -> Result<Option<String>> {
+        let conn = self.conn()?;
+        let mut sql = String::from("SELECT id FROM actions WHERE idem_key=?");
+        let mut params: Vec<Value> = vec![Value::Text(idem.to_string())];
+        if let Some(cutoff) = self.idem_cutoff() {
+            sql.push_str(" AND created >= ?");
+            params.push(Value::Text(cutoff));
         }
-        Ok(out)
+        sql.push_str(" LIMIT 1");
+        let mut stmt = conn.prepare(&sql)?;
+        let id_opt: Option<String> = stmt
+            .query_row(rusqlite::params_from_iter(params.iter()), |row| row.get(0))
+            .optional()?;
+        Ok(id_opt)
     }
 
-    pub fn get_staging_action(&self, id: &str) -> Result<Option<StagingAction>> {
+    /// Like [`Self::find_action_by_idem`], but returns the complete row so
+    /// dedup checks don't need a second `get_action` round-trip.
+    pub fn find_action_by_idem_full(&self, idem: &str) -> Result<Option<ActionRow>> {
+        let conn = self.conn()?;
+        let mut sql = String::from(
+            "SELECT id,kind,input,policy_ctx,idem_key,state,output,error,meta,created,updated,priority,run_after,attempts FROM actions WHERE idem_key=?",
+        );
+        let mut params: Vec<Value> = vec![Value::Text(idem.to_string())];
+        if let Some(cutoff) = self.idem_cutoff() {
+            sql.push_str(" AND created >= ?");
+            params.push(Value::Text(cutoff));
+        }
+        sql.push_str(" LIMIT 1");
+        let mut stmt = conn.prepare(&sql)?;
+        let res: Result<ActionRow, _> =
+            stmt.query_row(rusqlite::params_from_iter(params.iter()), |row| {
+                let input_s: String = row.get(2)?;
+                let policy_s: Option<String> = row.get(3)?;
+                let input_v = serde_json::from_str(&input_s).unwrap_or(serde_json::json!({}));
+                let policy_v =
+                    policy_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+                Ok(ActionRow {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    input: input_v,
+                    policy_ctx: policy_v,
+                    idem_key: row.get(4)?,
+                    state: row.get(5)?,
+                    output: row
+                        .get::<_, Option<String>>(6)?
+                        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                    error: row.get(7)?,
+                    meta: row
+                        .get::<_, Option<String>>(8)?
+                        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                    created: row.get(9)?,
+                    updated: row.get(10)?,
+                    priority: row.get(11)?,
+                    run_after: row.get(12)?,
+                    attempts: row.get(13)?,
+                })
+            });
+        match res {
+            Ok(a) => Ok(Some(a)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get_action(&self, id: &str) -> Result<Option<ActionRow>> {
         let conn = self.conn()?;
         let mut stmt = conn.prepare(
-            "SELECT id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated FROM staging_actions WHERE id=? LIMIT 1",
+            "SELECT id,kind,input,policy_ctx,idem_key,state,output,error,meta,created,updated,priority,run_after,attempts FROM actions WHERE id=? LIMIT 1",
         )?;
-        let mut rows = stmt.query([id])?;
-        if let Some(r) = rows.next()? {
-            let input_s: String = r.get(2)?;
-            let evidence_s: Option<String> = r.get(5)?;
-            let input_v = serde_json::from_str::<serde_json::Value>(&input_s)
-                .unwrap_or(serde_json::json!({}));
-            let evidence_v =
-                evidence_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
-            Ok(Some(StagingAction {
-                id: r.get(0)?,
-                action_kind: r.get(1)?,
-                action_input: input_v,
-                project: r.get(3)?,
-                requested_by: r.get(4)?,
-                evidence: evidence_v,
-                status: r.get(6)?,
-                decision: r.get(7)?,
-                decided_by: r.get(8)?,
-                decided_at: r.get(9)?,
-                action_id: r.get(10)?,
-                created: r.get(11)?,
-                updated: r.get(12)?,
-            }))
-        } else {
-            Ok(None)
+        let res: Result<ActionRow, _> = stmt.query_row([id], |row| {
+            let input_s: String = row.get(2)?;
+            let policy_s: Option<String> = row.get(3)?;
+            let input_v = serde_json::from_str(&input_s).unwrap_or(serde_json::json!({}));
+            let policy_v =
+                policy_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+            Ok(ActionRow {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                input: input_v,
+                policy_ctx: policy_v,
+                idem_key: row.get(4)?,
+                state: row.get(5)?,
+                output: row
+                    .get::<_, Option<String>>(6)?
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                error: row.get(7)?,
+                meta: row
+                    .get::<_, Option<String>>(8)?
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                created: row.get(9)?,
+                updated: row.get(10)?,
+                priority: row.get(11)?,
+                run_after: row.get(12)?,
+                attempts: row.get(13)?,
+            })
+        });
+        match res {
+            Ok(a) => Ok(Some(a)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
         }
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn update_staging_action_status(
+    /// Deep-merge `patch` into the action's `meta` column (worker
+    /// annotations kept apart from `output`/`error`), bumping `updated`.
+    /// Returns whether the action exists.
+    pub fn merge_action_meta(&self, id: &str, patch: &serde_json::Value) -> Result<bool> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let tx = conn.transaction()?;
+        let existing: Option<Option<String>> = tx
+            .query_row(
+                "SELECT meta FROM actions WHERE id=? LIMIT 1",
+                params![id],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let Some(meta_s) = existing else {
+            return Ok(false);
+        };
+        let mut meta = meta_s
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+            .unwrap_or(serde_json::json!({}));
+        merge_json(&mut meta, patch);
+        tx.execute(
+            "UPDATE actions SET meta=?, updated=? WHERE id=?",
+            params![serde_json::to_string(&meta).unwrap_or("{}".into()), now, id],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    pub async fn merge_action_meta_async(
         &self,
-        id: &str,
-        status: &str,
-        decision: Option<&str>,
-        decided_by: Option<&str>,
-        decided_at: Option<&str>,
-        action_id: Option<&str>,
+        id: String,
+        patch: serde_json::Value,
     ) -> Result<bool> {
+        self.run_blocking(move |k| k.merge_action_meta(&id, &patch))
+            .await
+    }
+
+    pub fn set_action_state(&self, id: &str, state: &str) -> Result<bool> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let decided_ts = decided_at.map(|s| s.to_string());
+        let now = self.now_rfc3339();
         let n = conn.execute(
-            "UPDATE staging_actions SET status=?, decision=?, decided_by=?, decided_at=COALESCE(?,decided_at), action_id=?, updated=? WHERE id=?",
-            params![status, decision, decided_by, decided_ts, action_id, now, id],
+            "UPDATE actions SET state=?, updated=? WHERE id=?",
+            params![state, now, id],
         )?;
         Ok(n > 0)
     }
 
-    pub fn find_valid_lease(
-        &self,
-        subject: &str,
-        capability: &str,
-    ) -> Result<Option<serde_json::Value>> {
+    pub fn delete_actions_by_state(&self, state: &str) -> Result<u64> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let mut stmt = conn.prepare(
-            "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated FROM leases \
-             WHERE subject=? AND capability=? AND ttl_until > ? ORDER BY ttl_until DESC LIMIT 1",
-        )?;
-        let mut rows = stmt.query(params![subject, capability, now])?;
-        if let Some(r) = rows.next()? {
-            let policy_s: Option<String> = r.get(6)?;
-            let policy_v = policy_s
-                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                .unwrap_or(serde_json::json!({}));
-            let v = serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "subject": r.get::<_, String>(1)?,
-                "capability": r.get::<_, String>(2)?,
-                "scope": r.get::<_, Option<String>>(3)?,
-                "ttl_until": r.get::<_, String>(4)?,
-                "budget": r.get::<_, Option<f64>>(5)?,
-                "policy": policy_v,
-                "created": r.get::<_, String>(7)?,
-                "updated": r.get::<_, String>(8)?,
-            });
-            Ok(Some(v))
-        } else {
-            Ok(None)
-        }
+        let n = conn.execute("DELETE FROM actions WHERE state=?", params![state])?;
+        Ok(n as u64)
     }
 
-    pub async fn find_valid_lease_async(
-        &self,
-        subject: &str,
-        capability: &str,
-    ) -> Result<Option<serde_json::Value>> {
-        let s = subject.to_string();
-        let c = capability.to_string();
-        self.run_blocking(move |k| k.find_valid_lease(&s, &c)).await
+    pub async fn delete_actions_by_state_async(&self, state: &str) -> Result<u64> {
+        let state = state.to_string();
+        self.run_blocking(move |k| k.delete_actions_by_state(&state))
+            .await
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn append_egress(
-        &self,
-        decision: &str,
-        reason: Option<&str>,
-        dest_host: Option<&str>,
-        dest_port: Option<i64>,
-        protocol: Option<&str>,
-        bytes_in: Option<i64>,
-        bytes_out: Option<i64>,
-        corr_id: Option<&str>,
-        proj: Option<&str>,
-        posture: Option<&str>,
-        meta: Option<&serde_json::Value>,
-    ) -> Result<i64> {
+    /// Soft-delete an action: stamp `deleted_at` so listings and the
+    /// dequeuers skip it, while the row stays behind as an audit tombstone
+    /// that [`Self::restore_action`] can bring back. Returns whether a live
+    /// row was tombstoned.
+    pub fn soft_delete_action(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let meta_s = meta.and_then(|v| serde_json::to_string(v).ok());
-        conn.execute(
-            "INSERT INTO egress_ledger(time,decision,reason,dest_host,dest_port,protocol,bytes_in,bytes_out,corr_id,proj,posture,meta) VALUES(?,?,?,?,?,?,?,?,?,?,?,?)",
-            params![
-                now,
-                decision,
-                reason,
-                dest_host,
-                dest_port,
-                protocol,
-                bytes_in,
-                bytes_out,
-                corr_id,
-                proj,
-                posture,
-                meta_s
-            ],
+        let now = self.now_rfc3339();
+        let n = conn.execute(
+            "UPDATE actions SET deleted_at=?, updated=? WHERE id=? AND deleted_at IS NULL",
+            params![now, now, id],
         )?;
-        Ok(conn.last_insert_rowid())
+        Ok(n > 0)
     }
 
-    pub fn list_egress(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+    /// Clear an action's `deleted_at` tombstone. Returns whether a
+    /// tombstoned row was restored.
+    pub fn restore_action(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id,time,decision,reason,dest_host,dest_port,protocol,bytes_in,bytes_out,corr_id,proj,posture,meta FROM egress_ledger ORDER BY id DESC LIMIT ?",
+        let now = self.now_rfc3339();
+        let n = conn.execute(
+            "UPDATE actions SET deleted_at=NULL, updated=? WHERE id=? AND deleted_at IS NOT NULL",
+            params![now, id],
         )?;
-        let mut rows = stmt.query([limit])?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let meta: Option<String> = r.get(12)?;
-            out.push(serde_json::json!({
-                "id": r.get::<_, i64>(0)?,
-                "time": r.get::<_, String>(1)?,
-                "decision": r.get::<_, String>(2)?,
-                "reason": r.get::<_, Option<String>>(3)?,
-                "dest_host": r.get::<_, Option<String>>(4)?,
-                "dest_port": r.get::<_, Option<i64>>(5)?,
-                "protocol": r.get::<_, Option<String>>(6)?,
-                "bytes_in": r.get::<_, Option<i64>>(7)?,
-                "bytes_out": r.get::<_, Option<i64>>(8)?,
-                "corr_id": r.get::<_, Option<String>>(9)?,
-                "proj": r.get::<_, Option<String>>(10)?,
-                "posture": r.get::<_, Option<String>>(11)?,
-                "meta": meta.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
-            }));
-        }
-        Ok(out)
+        Ok(n > 0)
     }
 
-    pub fn insert_memory(&self, args: &MemoryInsertArgs<'_>) -> Result<String> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.insert_memory(args)
+    pub async fn soft_delete_action_async(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.run_blocking(move |k| k.soft_delete_action(&id)).await
     }
 
-    pub fn insert_memory_with_record(
-        &self,
-        args: &MemoryInsertArgs<'_>,
-    ) -> Result<(String, serde_json::Value)> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.insert_memory_with_record(args)
+    pub async fn restore_action_async(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.run_blocking(move |k| k.restore_action(&id)).await
     }
 
-    pub fn search_memory(
-        &self,
-        q: &str,
-        lane: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
+    /// Soft-delete / restore for staging queue entries, mirroring
+    /// [`Self::soft_delete_action`].
+    pub fn soft_delete_staging_action(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.search_memory(q, lane, limit)
+        let now = self.now_rfc3339();
+        let n = conn.execute(
+            "UPDATE staging_actions SET deleted_at=?, updated=? WHERE id=? AND deleted_at IS NULL",
+            params![now, now, id],
+        )?;
+        Ok(n > 0)
     }
 
-    pub fn fts_search_memory(
-        &self,
-        q: &str,
-        lane: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
+    pub fn restore_staging_action(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.fts_search_memory(q, lane, limit)
+        let now = self.now_rfc3339();
+        let n = conn.execute(
+            "UPDATE staging_actions SET deleted_at=NULL, updated=? WHERE id=? AND deleted_at IS NOT NULL",
+            params![now, id],
+        )?;
+        Ok(n > 0)
     }
 
-    pub fn search_memory_by_embedding(
-        &self,
-        embed: &[f32],
-        lane: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.search_memory_by_embedding(embed, lane, limit)
+    pub async fn soft_delete_staging_action_async(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.run_blocking(move |k| k.soft_delete_staging_action(&id))
+            .await
     }
 
-    pub fn select_memory_hybrid(
-        &self,
-        q: Option<&str>,
-        embed: Option<&[f32]>,
-        lane: Option<&str>,
-        k: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.select_memory_hybrid(q, embed, lane, k)
+    pub async fn restore_staging_action_async(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.run_blocking(move |k| k.restore_staging_action(&id))
+            .await
     }
 
-    pub fn insert_memory_link(
+    pub fn update_action_result(
         &self,
-        src_id: &str,
-        dst_id: &str,
-        rel: Option<&str>,
-        weight: Option<f64>,
-    ) -> Result<()> {
+        id: &str,
+        output: Option<&serde_json::Value>,
+        error: Option<&str>,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.insert_memory_link(src_id, dst_id, rel, weight)
+        let now = self.now_rfc3339();
+        let out_s = output.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        let n = conn.execute(
+            "UPDATE actions SET output=COALESCE(?,output), error=COALESCE(?,error), updated=? WHERE id=?",
+            params![out_s, error, now, id],
+        )?;
+        Ok(n > 0)
     }
 
-    pub fn list_memory_links(&self, src_id: &str, limit: i64) -> Result<Vec<serde_json::Value>> {
+    /// Append one chunk of incremental output for a running action. `seq` is
+    /// the worker's monotonically increasing sequence number; re-sending a
+    /// sequence overwrites it, which makes at-least-once delivery from the
+    /// worker safe. The final result still lands via
+    /// [`Self::update_action_result`]; chunks exist so the server can stream
+    /// partial tool output before that.
+    pub fn append_action_output_chunk(&self, action_id: &str, seq: i64, data: &str) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.list_memory_links(src_id, limit)
+        let now = self.now_rfc3339();
+        conn.execute(
+            "INSERT OR REPLACE INTO action_output_chunks(action_id,seq,data,created) VALUES(?,?,?,?)",
+            params![action_id, seq, data, now],
+        )?;
+        Ok(())
     }
 
-    pub fn list_memory_links_many(
+    /// Page through an action's output chunks in sequence order. Pass the
+    /// last `seq` already seen (or `None` for the start) to fetch the next
+    /// page; an empty page means the reader caught up.
+    pub fn list_action_output_chunks(
         &self,
-        src_ids: &[String],
-        limit_per: i64,
-    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.list_memory_links_many(src_ids, limit_per)
-    }
-
-    pub fn get_memory(&self, id: &str) -> Result<Option<serde_json::Value>> {
+        action_id: &str,
+        after_seq: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
         let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.get_memory(id)
-    }
-
-    pub fn get_memory_many(&self, ids: &[String]) -> Result<HashMap<String, serde_json::Value>> {
-        if ids.is_empty() {
-            return Ok(HashMap::new());
+        let mut stmt = conn.prepare_cached(
+            "SELECT seq,data,created FROM action_output_chunks WHERE action_id=? AND seq > ? ORDER BY seq ASC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![action_id, after_seq.unwrap_or(-1), limit.max(1)])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "seq": r.get::<_, i64>(0)?,
+                "data": r.get::<_, String>(1)?,
+                "created": r.get::<_, String>(2)?,
+            }));
         }
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.get_memory_many(ids)
+        Ok(out)
     }
 
-    pub fn find_memory_by_hash(&self, hash: &str) -> Result<Option<serde_json::Value>> {
+    /// Drop an action's chunks once the final output is stored (or the
+    /// stream is abandoned). Returns the number of chunks removed.
+    pub fn delete_action_output_chunks(&self, action_id: &str) -> Result<u64> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.find_memory_by_hash(hash)
+        let n = conn.execute(
+            "DELETE FROM action_output_chunks WHERE action_id=?",
+            params![action_id],
+        )?;
+        Ok(n as u64)
     }
 
-    pub fn backfill_embed_blobs(&self, batch_limit: usize) -> Result<usize> {
-        if batch_limit == 0 {
-            return Ok(0);
-        }
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.backfill_embed_blobs(batch_limit)
+    pub async fn append_action_output_chunk_async(
+        &self,
+        action_id: &str,
+        seq: i64,
+        data: &str,
+    ) -> Result<()> {
+        let action_id = action_id.to_string();
+        let data = data.to_string();
+        self.run_blocking(move |k| k.append_action_output_chunk(&action_id, seq, &data))
+            .await
     }
 
-    pub fn pending_embed_backfill(&self) -> Result<u64> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.pending_embed_backfill()
+    pub async fn list_action_output_chunks_async(
+        &self,
+        action_id: &str,
+        after_seq: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let action_id = action_id.to_string();
+        self.run_blocking(move |k| k.list_action_output_chunks(&action_id, after_seq, limit))
+            .await
     }
 
-    pub fn expired_memory_candidates(
-        &self,
-        now: DateTime<Utc>,
-        limit: usize,
-    ) -> Result<Vec<MemoryGcCandidate>> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.expired_candidates(now, limit)
+    pub async fn delete_action_output_chunks_async(&self, action_id: &str) -> Result<u64> {
+        let action_id = action_id.to_string();
+        self.run_blocking(move |k| k.delete_action_output_chunks(&action_id))
+            .await
     }
 
-    pub fn lane_overflow_candidates(
+    /// Record a failed attempt and either requeue the action with exponential
+    /// backoff or mark it terminally `failed` once `max_attempts` is reached.
+    /// The backoff delay is `backoff_base_secs * 2^attempts`, capped at one
+    /// day, and lands in `run_after` so the dequeuer re-offers the action
+    /// automatically. Returns the next retry time, or `None` when the action
+    /// is out of attempts (or unknown).
+    pub fn fail_action_with_retry(
         &self,
-        lane: &str,
-        cap: usize,
-        limit: usize,
-    ) -> Result<Vec<MemoryGcCandidate>> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.lane_overflow_candidates(lane, cap, limit)
+        id: &str,
+        error: Option<&str>,
+        max_attempts: i64,
+        backoff_base_secs: i64,
+    ) -> Result<Option<String>> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let tx = conn.transaction()?;
+        let attempts: Option<i64> = tx
+            .query_row(
+                "SELECT attempts FROM actions WHERE id=?",
+                params![id],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let Some(attempts) = attempts else {
+            tx.commit()?;
+            return Ok(None);
+        };
+        let result = if attempts + 1 >= max_attempts {
+            tx.execute(
+                "UPDATE actions SET state='failed', error=COALESCE(?,error), attempts=attempts+1, run_after=NULL, updated=? WHERE id=?",
+                params![error, now, id],
+            )?;
+            None
+        } else {
+            let delay_secs = backoff_base_secs
+                .saturating_mul(1i64 << attempts.clamp(0, 32))
+                .clamp(0, 86_400);
+            let next_retry = (self.clock.now() + chrono::Duration::seconds(delay_secs))
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            tx.execute(
+                "UPDATE actions SET state='queued', error=COALESCE(?,error), attempts=attempts+1, run_after=?, updated=? WHERE id=?",
+                params![error, next_retry, now, id],
+            )?;
+            Some(next_retry)
+        };
+        tx.commit()?;
+        Ok(result)
     }
 
-    pub fn delete_memory_records(&self, ids: &[String]) -> Result<usize> {
-        let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.delete_records(ids)
+    pub fn list_actions(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let opts = ActionListOptions {
+            limit,
+            ..Default::default()
+        };
+        self.list_actions_filtered(&opts)
     }
 
-    pub fn list_recent_memory(
+    pub fn list_actions_filtered(
         &self,
-        lane: Option<&str>,
-        limit: i64,
+        opts: &ActionListOptions,
     ) -> Result<Vec<serde_json::Value>> {
         let conn = self.conn()?;
-        let store = MemoryStore::new(&conn);
-        store.list_recent_memory(lane, limit)
-    }
+        let mut sql = String::from("SELECT id,kind,state,created,updated FROM actions");
+        let mut clauses: Vec<&str> = vec!["deleted_at IS NULL"];
+        let mut params: Vec<Value> = Vec::new();
 
-    pub fn pool_wait_stats(&self) -> (u64, f64) {
-        let stats = self
-            .pool
-            .wait_stats
-            .lock()
-            .expect("pool wait stats mutex poisoned");
-        (stats.count, stats.total_ms)
-    }
+        if let Some(state) = opts
+            .state
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+        {
+            clauses.push("state = ?");
+            params.push(Value::Text(state.to_string()))
+        }
 
-    // ---------- Config snapshots ----------
-    pub fn insert_config_snapshot(&self, config: &serde_json::Value) -> Result<String> {
-        let conn = self.conn()?;
-        let id = uuid::Uuid::new_v4().to_string();
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let cfg = serde_json::to_string(config).unwrap_or("{}".into());
-        conn.execute(
-            "INSERT INTO config_snapshots(id,config,created) VALUES(?,?,?)",
-            params![id, cfg, now],
-        )?;
-        Ok(id)
-    }
+        if let Some(prefix) = opts
+            .kind_prefix
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+        {
+            clauses.push("kind LIKE ?");
+            params.push(Value::Text(format!("{}%", prefix)));
+        }
 
-    pub fn get_config_snapshot(&self, id: &str) -> Result<Option<serde_json::Value>> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare("SELECT config FROM config_snapshots WHERE id=? LIMIT 1")?;
-        let mut rows = stmt.query(params![id])?;
-        if let Some(r) = rows.next()? {
-            let cfg_s: String = r.get(0)?;
-            let v =
-                serde_json::from_str::<serde_json::Value>(&cfg_s).unwrap_or(serde_json::json!({}));
-            Ok(Some(v))
-        } else {
-            Ok(None)
+        if let Some(since) = opts
+            .updated_since
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+        {
+            clauses.push("updated >= ?");
+            params.push(Value::Text(since.to_string()));
         }
-    }
 
-    pub fn list_config_snapshots(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let conn = self.conn()?;
-        let mut stmt =
-            conn.prepare("SELECT id,created FROM config_snapshots ORDER BY created DESC LIMIT ?")?;
-        let mut rows = stmt.query(params![limit])?;
+        sql.push_str(" WHERE ");
+        sql.push_str(&clauses.join(" AND "));
+        sql.push_str(&self.workspace_clause("workspace_id"));
+
+        sql.push_str(" ORDER BY updated DESC LIMIT ?");
+        params.push(Value::Integer(opts.clamped_limit()));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
         let mut out = Vec::new();
         while let Some(r) = rows.next()? {
-            out.push(serde_json::json!({"id": r.get::<_, String>(0)?, "created": r.get::<_, String>(1)?}));
+            out.push(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "kind": r.get::<_, String>(1)?,
+                "state": r.get::<_, String>(2)?,
+                "created": r.get::<_, String>(3)?,
+                "updated": r.get::<_, String>(4)?,
+            }));
         }
         Ok(out)
     }
 
-    // ---------- Orchestrator jobs ----------
-    pub fn insert_orchestrator_job(
+    /// Stream actions matching `opts` as NDJSON (one `ActionRow` per line).
+    ///
+    /// Pages with keyset pagination over `(updated, id)` so memory stays
+    /// bounded regardless of table size. `opts.limit <= 0` exports every
+    /// matching row; a positive limit caps the export. Returns the number
+    /// of rows written.
+    pub fn export_actions_ndjson(
         &self,
-        goal: &str,
-        data: Option<&serde_json::Value>,
-    ) -> Result<String> {
+        opts: &ActionListOptions,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<usize> {
         let conn = self.conn()?;
-        let id = uuid::Uuid::new_v4().to_string();
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let data_s = data.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
-        conn.execute(
-            "INSERT INTO orchestrator_jobs(id,status,goal,data,progress,created,updated) VALUES(?,?,?,?,?,?,?)",
-            params![id, "queued", goal, data_s, 0.0f64, now, now],
-        )?;
-        Ok(id)
-    }
-
-    pub fn update_orchestrator_job(
-        &self,
-        id: &str,
-        status: Option<&str>,
-        progress: Option<f64>,
-        data_patch: Option<&serde_json::Value>,
-    ) -> Result<bool> {
-        let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let mut set_parts: Vec<&str> = Vec::new();
-        if status.is_some() {
-            set_parts.push("status=?");
-        }
-        if progress.is_some() {
-            set_parts.push("progress=?");
-        }
-        let mut merged_data: Option<String> = None;
-        if let Some(patch) = data_patch {
-            if patch.is_object() {
-                let existing: Option<String> = conn.query_row(
-                    "SELECT data FROM orchestrator_jobs WHERE id=? LIMIT 1",
-                    [id],
-                    |row| row.get(0),
-                )?;
-                let mut base = existing
-                    .as_ref()
-                    .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
-                    .unwrap_or_else(|| serde_json::json!({}));
-                if !base.is_object() {
-                    base = serde_json::json!({});
-                }
-                if let serde_json::Value::Object(ref mut base_map) = base {
-                    if let serde_json::Value::Object(ref patch_map) = patch {
-                        for (key, value) in patch_map.iter() {
-                            base_map.insert(key.clone(), value.clone());
-                        }
-                    }
-                }
-                merged_data = Some(serde_json::to_string(&base).unwrap_or_else(|_| "{}".into()));
-            } else if !patch.is_null() {
-                merged_data =
-                    Some(serde_json::to_string(patch).unwrap_or_else(|_| "{}".to_string()));
-            }
-            if merged_data.is_some() {
-                set_parts.push("data=?");
-            }
-        }
-        set_parts.push("updated=?");
-        let sql = format!(
-            "UPDATE orchestrator_jobs SET {} WHERE id=?",
-            set_parts.join(",")
-        );
-        let mut stmt = conn.prepare(&sql)?;
-        let mut params_vec: Vec<rusqlite::types::Value> = Vec::new();
-        if let Some(s) = status {
-            params_vec.push(rusqlite::types::Value::from(s.to_string()));
-        }
-        if let Some(p) = progress {
-            params_vec.push(rusqlite::types::Value::from(p));
-        }
-        if let Some(data) = merged_data {
-            params_vec.push(rusqlite::types::Value::from(data));
-        }
-        params_vec.push(rusqlite::types::Value::from(now.clone()));
-        params_vec.push(rusqlite::types::Value::from(id.to_string()));
-        let n = stmt.execute(rusqlite::params_from_iter(params_vec))?;
-        Ok(n > 0)
-    }
+        let page_size: i64 = 500;
+        let cap: Option<usize> = if opts.limit > 0 {
+            Some(opts.limit as usize)
+        } else {
+            None
+        };
+        let mut cursor: Option<(String, String)> = None;
+        let mut written = 0usize;
+        loop {
+            let mut sql = String::from(
+                "SELECT id,kind,input,policy_ctx,idem_key,state,output,error,meta,created,updated,priority,run_after,attempts FROM actions",
+            );
+            let mut clauses: Vec<&str> = vec!["deleted_at IS NULL"];
+            let mut params: Vec<Value> = Vec::new();
 
-    pub fn list_orchestrator_jobs(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id,status,goal,data,progress,created,updated FROM orchestrator_jobs ORDER BY updated DESC LIMIT ?",
-        )?;
-        let mut rows = stmt.query([limit])?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let status_raw: String = r.get::<_, String>(1)?;
-            let (status_slug, status_label) = Self::normalize_orchestrator_status(&status_raw);
-            let mut payload = serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "status": status_raw,
-                "status_slug": status_slug,
-                "status_label": status_label,
-                "goal": r.get::<_, Option<String>>(2)?,
-                "progress": r.get::<_, Option<f64>>(4)?,
-                "created": r.get::<_, String>(5)?,
-                "updated": r.get::<_, String>(6)?,
-            });
-            let data_raw: Option<String> = r.get(3)?;
-            if let Some(data_raw) = data_raw {
-                if let Ok(val) = serde_json::from_str::<serde_json::Value>(&data_raw) {
-                    let persona = Self::extract_persona_id(&val);
-                    if !val.is_null() {
-                        if let serde_json::Value::Object(ref mut map) = payload {
-                            map.insert("data".into(), val.clone());
-                            if let Some(persona) = persona {
-                                map.insert("persona_id".into(), serde_json::Value::String(persona));
-                            }
-                        }
-                    }
-                }
+            if let Some(state) = opts
+                .state
+                .as_ref()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+            {
+                clauses.push("state = ?");
+                params.push(Value::Text(state.to_string()))
             }
-            out.push(payload);
-        }
-        Ok(out)
-    }
 
-    fn normalize_orchestrator_status(value: &str) -> (&'static str, &'static str) {
-        let normalized = value.trim().to_ascii_lowercase();
-        match normalized.as_str() {
-            "queued" | "pending" | "waiting" => ("queued", "Queued"),
-            "running" | "in_progress" | "in-progress" | "started" | "active" => {
-                ("running", "Running")
-            }
-            "completed" | "complete" | "finished" | "done" | "success" | "succeeded" => {
-                ("completed", "Completed")
+            if let Some(prefix) = opts
+                .kind_prefix
+                .as_ref()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+            {
+                clauses.push("kind LIKE ?");
+                params.push(Value::Text(format!("{}%", prefix)));
             }
-            "failed" | "error" | "errored" | "fail" | "failure" => ("failed", "Failed"),
-            "cancelled" | "canceled" | "aborted" | "stopped" => ("cancelled", "Cancelled"),
-            "unknown" | "" => ("unknown", "Unknown"),
-            other if other.starts_with("run") => ("running", "Running"),
-            other if other.starts_with("queue") => ("queued", "Queued"),
-            other if other.starts_with("wait") => ("queued", "Queued"),
-            other if other.starts_with("fail") => ("failed", "Failed"),
-            other if other.starts_with("cancel") => ("cancelled", "Cancelled"),
-            other if other.starts_with("complete") => ("completed", "Completed"),
-            _ => ("unknown", "Unknown"),
-        }
-    }
 
-    fn extract_persona_id(value: &serde_json::Value) -> Option<String> {
-        if let Some(obj) = value.as_object() {
-            if let Some(pid) = obj
-                .get("persona_id")
-                .and_then(|v| v.as_str())
-                .map(str::trim)
+            if let Some(since) = opts
+                .updated_since
+                .as_ref()
+                .map(|s| s.trim())
                 .filter(|s| !s.is_empty())
             {
-                return Some(pid.to_string());
+                clauses.push("updated >= ?");
+                params.push(Value::Text(since.to_string()));
             }
-            for key in ["training", "submitted"] {
-                if let Some(next) = obj.get(key) {
-                    if let Some(found) = Self::extract_persona_id(next) {
-                        return Some(found);
-                    }
-                }
+
+            if let Some((ref last_updated, ref last_id)) = cursor {
+                clauses.push("(updated < ? OR (updated = ? AND id < ?))");
+                params.push(Value::Text(last_updated.clone()));
+                params.push(Value::Text(last_updated.clone()));
+                params.push(Value::Text(last_id.clone()));
             }
-        }
-        None
-    }
 
-    // ---------- Personas ----------
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
+            sql.push_str(&self.workspace_clause("workspace_id"));
 
-    pub fn upsert_persona_entry(&self, upsert: PersonaEntryUpsert) -> Result<PersonaEntry> {
-        let conn = self.conn()?;
-        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let existing_version: Option<i64> = conn
-            .query_row(
-                "SELECT version FROM persona_entries WHERE id=? LIMIT 1",
-                [&upsert.id],
-                |row| row.get(0),
-            )
-            .optional()?;
-        let version = existing_version.unwrap_or(0).saturating_add(1);
+            sql.push_str(" ORDER BY updated DESC, id DESC LIMIT ?");
+            let remaining = cap
+                .map(|c| (c.saturating_sub(written)) as i64)
+                .unwrap_or(page_size);
+            params.push(Value::Integer(remaining.min(page_size)));
 
-        let traits_s = serde_json::to_string(&upsert.traits).unwrap_or_else(|_| "{}".into());
-        let preferences_s =
-            serde_json::to_string(&upsert.preferences).unwrap_or_else(|_| "{}".into());
-        let worldview_s = serde_json::to_string(&upsert.worldview).unwrap_or_else(|_| "{}".into());
-        let vibe_profile_s =
-            serde_json::to_string(&upsert.vibe_profile).unwrap_or_else(|_| "{}".into());
-        let calibration_s =
-            serde_json::to_string(&upsert.calibration).unwrap_or_else(|_| "{}".into());
+            let mut stmt = conn.prepare(&sql)?;
+            let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
+            let mut page_rows = 0usize;
+            while let Some(r) = rows.next()? {
+                let input_s: String = r.get(2)?;
+                let policy_s: Option<String> = r.get(3)?;
+                let row = ActionRow {
+                    id: r.get(0)?,
+                    kind: r.get(1)?,
+                    input: serde_json::from_str(&input_s).unwrap_or(serde_json::json!({})),
+                    policy_ctx: policy_s
+                        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                    idem_key: r.get(4)?,
+                    state: r.get(5)?,
+                    output: r
+                        .get::<_, Option<String>>(6)?
+                        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                    error: r.get(7)?,
+                    meta: r
+                        .get::<_, Option<String>>(8)?
+                        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                    created: r.get(9)?,
+                    updated: r.get(10)?,
+                    priority: r.get(11)?,
+                    run_after: r.get(12)?,
+                    attempts: r.get(13)?,
+                };
+                serde_json::to_writer(&mut *writer, &row)?;
+                writer.write_all(b"\n")?;
+                cursor = Some((row.updated.clone(), row.id.clone()));
+                page_rows += 1;
+                written += 1;
+            }
+            if page_rows == 0 || cap.map(|c| written >= c).unwrap_or(false) {
+                break;
+            }
+        }
+        writer.flush()?;
+        Ok(written)
+    }
 
-        conn.execute(
-            "INSERT INTO persona_entries \
-                (id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
-             ON CONFLICT(id) DO UPDATE SET \
-                owner_kind=excluded.owner_kind, \
-                owner_ref=excluded.owner_ref, \
-                name=excluded.name, \
-                archetype=excluded.archetype, \
-                traits=excluded.traits, \
-                preferences=excluded.preferences, \
-                worldview=excluded.worldview, \
-                vibe_profile=excluded.vibe_profile, \
-                calibration=excluded.calibration, \
-                updated=excluded.updated, \
-                version=excluded.version",
-            params![
-                upsert.id,
-                upsert.owner_kind,
-                upsert.owner_ref,
-                upsert.name,
-                upsert.archetype,
-                traits_s,
-                preferences_s,
-                worldview_s,
-                vibe_profile_s,
-                calibration_s,
-                now,
-                version
-            ],
+    /// Admission-controlled enqueue: insert a new `queued` action only when
+    /// the current queued count is below `max_queued`, checked and written
+    /// in one transaction so concurrent submitters can't overshoot the cap.
+    /// Returns the new action id, or `None` when the queue is full
+    /// (`max_queued <= 0` disables the cap).
+    pub fn try_enqueue_action(
+        &self,
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        max_queued: i64,
+    ) -> Result<Option<String>> {
+        let mut conn = self.conn()?;
+        self.ensure_writable()?;
+        let now = self.now_rfc3339();
+        let input_s = serde_json::to_string(input).unwrap_or("{}".to_string());
+        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".to_string()));
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        if max_queued > 0 {
+            let ws = self.workspace_clause("workspace_id");
+            let queued: i64 = tx.query_row(
+                &format!(
+                    "SELECT COUNT(1) FROM actions WHERE state='queued' AND deleted_at IS NULL{ws}"
+                ),
+                [],
+                |r| r.get(0),
+            )?;
+            if queued >= max_queued {
+                return Ok(None);
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO actions(id,kind,input,policy_ctx,idem_key,state,workspace_id,created,updated) VALUES(?,?,?,?,?,?,?,?,?)",
+            params![id, kind, input_s, policy_s, idem_key, "queued", self.workspace, now, now],
         )?;
+        tx.commit()?;
+        Ok(Some(id.to_string()))
+    }
 
-        self.get_persona_entry(&upsert.id)?
-            .ok_or_else(|| anyhow!("persona entry not found after upsert"))
+    #[allow(clippy::too_many_arguments)]
+    pub async fn try_enqueue_action_async(
+        &self,
+        id: String,
+        kind: String,
+        input: serde_json::Value,
+        policy_ctx: Option<serde_json::Value>,
+        idem_key: Option<String>,
+        max_queued: i64,
+    ) -> Result<Option<String>> {
+        self.run_blocking(move |k| {
+            k.try_enqueue_action(
+                &id,
+                &kind,
+                &input,
+                policy_ctx.as_ref(),
+                idem_key.as_deref(),
+                max_queued,
+            )
+        })
+        .await
     }
 
-    pub fn get_persona_entry(&self, id: &str) -> Result<Option<PersonaEntry>> {
+    /// Queue-health view: count plus p50/p90/max age (seconds since
+    /// `created`, relative to `now_rfc3339`) for actions in a state,
+    /// computed in SQL so large queues aren't pulled into memory.
+    pub fn action_age_stats(&self, state: &str, now_rfc3339: &str) -> Result<serde_json::Value> {
         let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version \
-             FROM persona_entries WHERE id=? LIMIT 1",
+        let ws = self.workspace_clause("workspace_id");
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(1) FROM actions WHERE state=? AND deleted_at IS NULL{ws}"),
+            params![state],
+            |r| r.get(0),
         )?;
-        let mut rows = stmt.query([id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Self::map_persona_entry_row(row)?))
-        } else {
-            Ok(None)
+        if count == 0 {
+            return Ok(serde_json::json!({
+                "state": state,
+                "count": 0,
+                "p50_s": null,
+                "p90_s": null,
+                "max_s": null,
+            }));
         }
+        let age_at = |percentile: i64| -> Result<i64> {
+            let offset = (count - 1) * percentile / 100;
+            let age: i64 = conn.query_row(
+                &format!(
+                    "SELECT age FROM ( \
+                         SELECT CAST(strftime('%s', ?1) AS INTEGER) - CAST(strftime('%s', created) AS INTEGER) AS age \
+                         FROM actions WHERE state=?2 AND deleted_at IS NULL{ws} ORDER BY age ASC \
+                     ) LIMIT 1 OFFSET ?3"
+                ),
+                params![now_rfc3339, state, offset],
+                |r| r.get(0),
+            )?;
+            Ok(age)
+        };
+        let p50 = age_at(50)?;
+        let p90 = age_at(90)?;
+        let max: i64 = conn.query_row(
+            &format!(
+                "SELECT MAX(CAST(strftime('%s', ?1) AS INTEGER) - CAST(strftime('%s', created) AS INTEGER)) \
+                 FROM actions WHERE state=?2 AND deleted_at IS NULL{ws}"
+            ),
+            params![now_rfc3339, state],
+            |r| r.get(0),
+        )?;
+        Ok(serde_json::json!({
+            "state": state,
+            "count": count,
+            "p50_s": p50,
+            "p90_s": p90,
+            "max_s": max,
+        }))
     }
 
-    pub fn list_persona_entries(
+    pub async fn action_age_stats_async(
         &self,
-        owner_filter: Option<(&str, &str)>,
-        limit: i64,
-    ) -> Result<Vec<PersonaEntry>> {
+        state: String,
+        now_rfc3339: String,
+    ) -> Result<serde_json::Value> {
+        self.run_blocking(move |k| k.action_age_stats(&state, &now_rfc3339))
+            .await
+    }
+
+    pub fn count_actions_by_state(&self, state: &str) -> Result<i64> {
         let conn = self.conn()?;
-        let limit = limit.clamp(1, 500);
-        let mut entries = Vec::new();
-        match owner_filter {
-            Some((owner_kind, owner_ref)) => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version \
-                     FROM persona_entries \
-                     WHERE owner_kind=? AND owner_ref=? \
-                     ORDER BY updated DESC LIMIT ?",
-                )?;
-                let mut rows = stmt.query(params![owner_kind, owner_ref, limit])?;
-                while let Some(row) = rows.next()? {
-                    entries.push(Self::map_persona_entry_row(row)?);
-                }
-            }
-            None => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version \
-                     FROM persona_entries \
-                     ORDER BY updated DESC LIMIT ?",
-                )?;
-                let mut rows = stmt.query([limit])?;
-                while let Some(row) = rows.next()? {
-                    entries.push(Self::map_persona_entry_row(row)?);
-                }
-            }
+        let ws = self.workspace_clause("workspace_id");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT COUNT(1) FROM actions WHERE state=? AND deleted_at IS NULL{ws}"
+        ))?;
+        let n: i64 = stmt.query_row([state], |row| row.get(0))?;
+        Ok(n)
+    }
+
+    pub fn dequeue_one_queued(&self) -> Result<Option<(String, String, serde_json::Value)>> {
+        self.ensure_writable()?;
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let ws = self.workspace_clause("a.workspace_id");
+        let mut stmt = conn.prepare_cached(&format!(
+            "UPDATE actions SET state='running', updated=?1 WHERE id = (
+                 SELECT a.id FROM actions a
+                 WHERE a.state='queued' AND a.deleted_at IS NULL AND (a.run_after IS NULL OR a.run_after <= ?1){ws}
+                   AND NOT EXISTS (
+                     SELECT 1 FROM action_deps d LEFT JOIN actions p ON p.id = d.depends_on
+                     WHERE d.action_id = a.id AND (p.id IS NULL OR p.state <> 'completed')
+                   )
+                 ORDER BY a.priority DESC, a.created LIMIT 1
+             ) RETURNING id, kind, input",
+        ))?;
+        let mut rows = stmt.query(params![now])?;
+        let out = if let Some(row) = rows.next()? {
+            let id: String = row.get(0)?;
+            let kind: String = row.get(1)?;
+            let input_s: String = row.get(2)?;
+            let input_v = serde_json::from_str(&input_s).unwrap_or(serde_json::json!({}));
+            Some((id, kind, input_v))
+        } else {
+            None
         };
-        Ok(entries)
+        Self::observe_op("dequeue_one_queued", started);
+        Self::observe_op_rows("dequeue_one_queued", out.is_some() as u64);
+        Ok(out)
     }
 
-    pub fn insert_persona_proposal(&self, create: PersonaProposalCreate) -> Result<String> {
+    /// Claim up to `n` eligible queued actions in one round-trip, in the
+    /// same priority/FIFO order as [`Self::dequeue_one_queued`]. Returns the
+    /// claimed `(id, kind, input)` tuples; fewer (or none) when the queue
+    /// runs dry.
+    pub fn dequeue_many_queued(&self, n: i64) -> Result<Vec<(String, String, serde_json::Value)>> {
+        if n <= 0 {
+            return Ok(Vec::new());
+        }
+        self.ensure_writable()?;
+        let started = Instant::now();
         let conn = self.conn()?;
-        let proposal_id = Uuid::new_v4().to_string();
-        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let diff_s = serde_json::to_string(&create.diff).unwrap_or_else(|_| "[]".into());
-        let telemetry_scope_s =
-            serde_json::to_string(&create.telemetry_scope).unwrap_or_else(|_| "{}".into());
-        let leases_required_s =
-            serde_json::to_string(&create.leases_required).unwrap_or_else(|_| "[]".into());
+        let now = self.now_rfc3339();
+        let ws = self.workspace_clause("a.workspace_id");
+        let mut stmt = conn.prepare_cached(&format!(
+            "UPDATE actions SET state='running', updated=?1 WHERE id IN (
+                 SELECT a.id FROM actions a
+                 WHERE a.state='queued' AND a.deleted_at IS NULL AND (a.run_after IS NULL OR a.run_after <= ?1){ws}
+                   AND NOT EXISTS (
+                     SELECT 1 FROM action_deps d LEFT JOIN actions p ON p.id = d.depends_on
+                     WHERE d.action_id = a.id AND (p.id IS NULL OR p.state <> 'completed')
+                   )
+                 ORDER BY a.priority DESC, a.created LIMIT ?2
+             ) RETURNING id, kind, input, priority, created",
+        ))?;
+        let mut rows = stmt.query(params![now, n])?;
+        let mut out: Vec<(String, String, serde_json::Value, i64, String)> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let input_s: String = row.get(2)?;
+            out.push((
+                row.get(0)?,
+                row.get(1)?,
+                serde_json::from_str(&input_s).unwrap_or(serde_json::json!({})),
+                row.get(3)?,
+                row.get(4)?,
+            ));
+        }
+        // RETURNING order is unspecified; hand back queue order.
+        out.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.4.cmp(&b.4)));
+        Self::observe_op("dequeue_many_queued", started);
+        Self::observe_op_rows("dequeue_many_queued", out.len() as u64);
+        Ok(out
+            .into_iter()
+            .map(|(id, kind, input, _, _)| (id, kind, input))
+            .collect())
+    }
 
-        conn.execute(
-            "INSERT INTO persona_proposals \
-                (proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            params![
-                proposal_id,
-                create.persona_id,
-                create.submitted_by,
-                diff_s,
-                create.rationale,
-                telemetry_scope_s,
-                leases_required_s,
-                "pending",
-                &now,
-                &now
-            ],
-        )?;
-        Ok(proposal_id)
+    pub async fn dequeue_many_queued_async(
+        &self,
+        n: i64,
+    ) -> Result<Vec<(String, String, serde_json::Value)>> {
+        self.run_blocking(move |k| k.dequeue_many_queued(n)).await
     }
 
-    pub fn get_persona_proposal(&self, proposal_id: &str) -> Result<Option<PersonaProposal>> {
+    /// Like [`Self::dequeue_one_queued`], but only hands out actions whose
+    /// kind starts with one of `kind_prefixes`, so specialized workers don't
+    /// have to dequeue-and-requeue mismatches. An empty prefix list matches
+    /// nothing.
+    pub fn dequeue_one_queued_matching(
+        &self,
+        kind_prefixes: &[String],
+    ) -> Result<Option<(String, String, serde_json::Value)>> {
+        self.ensure_writable()?;
+        if kind_prefixes.is_empty() {
+            return Ok(None);
+        }
         let conn = self.conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated \
-             FROM persona_proposals WHERE proposal_id=? LIMIT 1",
-        )?;
-        let mut rows = stmt.query([proposal_id])?;
+        let now = self.now_rfc3339();
+        let kind_clause = vec!["a.kind LIKE ? || '%'"; kind_prefixes.len()].join(" OR ");
+        let ws = self.workspace_clause("a.workspace_id");
+        let sql = format!(
+            "UPDATE actions SET state='running', updated=?1 WHERE id = (
+                 SELECT a.id FROM actions a
+                 WHERE a.state='queued' AND a.deleted_at IS NULL AND (a.run_after IS NULL OR a.run_after <= ?1){ws}
+                   AND ({kind_clause})
+                   AND NOT EXISTS (
+                     SELECT 1 FROM action_deps d LEFT JOIN actions p ON p.id = d.depends_on
+                     WHERE d.action_id = a.id AND (p.id IS NULL OR p.state <> 'completed')
+                   )
+                 ORDER BY a.priority DESC, a.created LIMIT 1
+             ) RETURNING id, kind, input"
+        );
+        let mut args: Vec<&dyn rusqlite::ToSql> = vec![&now];
+        for p in kind_prefixes {
+            args.push(p);
+        }
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(args.as_slice())?;
         if let Some(row) = rows.next()? {
-            Ok(Some(Self::map_persona_proposal_row(row)?))
-        } else {
-            Ok(None)
+            let id: String = row.get(0)?;
+            let kind: String = row.get(1)?;
+            let input_s: String = row.get(2)?;
+            let input_v = serde_json::from_str(&input_s).unwrap_or(serde_json::json!({}));
+            return Ok(Some((id, kind, input_v)));
         }
+        Ok(None)
     }
 
-    pub fn update_persona_proposal_status(
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_lease(
         &self,
-        proposal_id: &str,
-        update: PersonaProposalStatusUpdate,
-    ) -> Result<bool> {
+        id: &str,
+        subject: &str,
+        capability: &str,
+        scope: Option<&str>,
+        ttl_until: &str,
+        budget: Option<f64>,
+        policy_ctx: Option<&serde_json::Value>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let affected = conn.execute(
-            "UPDATE persona_proposals SET status=?, updated=? WHERE proposal_id=?",
-            params![update.status, now, proposal_id],
+        let now = self.now_rfc3339();
+        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        conn.execute(
+            "INSERT OR REPLACE INTO leases(id,subject,capability,scope,ttl_until,budget,policy_ctx,workspace_id,created,updated) VALUES(?,?,?,?,?,?,?,?,?,?)",
+            params![id, subject, capability, scope, ttl_until, budget, policy_s, self.workspace, now, now],
         )?;
-        Ok(affected > 0)
+        conn.execute(
+            "INSERT INTO lease_events(lease_id,event,subject,capability,time) VALUES(?,'grant',?,?,?)",
+            params![id, subject, capability, now],
+        )?;
+        Ok(())
     }
 
-    pub fn list_persona_proposals(
+    /// Revoke a lease immediately, recording who pulled it and why in the
+    /// audit trail. Returns whether the lease existed.
+    pub fn revoke_lease(
         &self,
-        persona_id: Option<&str>,
-        status: Option<&str>,
+        id: &str,
+        reason: Option<&str>,
+        revoked_by: Option<&str>,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let tx = conn.transaction()?;
+        let row: Option<(String, String)> = tx
+            .query_row(
+                "SELECT subject, capability FROM leases WHERE id=?",
+                params![id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+        let Some((subject, capability)) = row else {
+            return Ok(false);
+        };
+        tx.execute("DELETE FROM leases WHERE id=?", params![id])?;
+        tx.execute(
+            "INSERT INTO lease_events(lease_id,event,subject,capability,reason,actor,time) VALUES(?,'revoke',?,?,?,?,?)",
+            params![id, subject, capability, reason, revoked_by, now],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Extend a lease's `ttl_until` by `extend_ttl_secs` from whichever is
+    /// later of now and the current expiry, logging a `renew` transition.
+    /// Returns the new expiry, or `None` for an unknown lease.
+    pub fn renew_lease(&self, id: &str, extend_ttl_secs: i64) -> Result<Option<String>> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.clock.now();
+        let now_s = self.now_rfc3339();
+        let tx = conn.transaction()?;
+        let row: Option<(String, String, String)> = tx
+            .query_row(
+                "SELECT subject, capability, ttl_until FROM leases WHERE id=?",
+                params![id],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .optional()?;
+        let Some((subject, capability, ttl_until)) = row else {
+            return Ok(None);
+        };
+        let base = chrono::DateTime::parse_from_rfc3339(&ttl_until)
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or(now)
+            .max(now);
+        let new_ttl = (base + chrono::Duration::seconds(extend_ttl_secs))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        tx.execute(
+            "UPDATE leases SET ttl_until=?, updated=? WHERE id=?",
+            params![new_ttl, now_s, id],
+        )?;
+        tx.execute(
+            "INSERT INTO lease_events(lease_id,event,subject,capability,time) VALUES(?,'renew',?,?,?)",
+            params![id, subject, capability, now_s],
+        )?;
+        tx.commit()?;
+        Ok(Some(new_ttl))
+    }
+
+    /// Atomically decrement a lease's budget by `amount`, returning the
+    /// remaining budget. Fails when the lease is unknown or the remaining
+    /// budget does not cover `amount`; a lease without a budget column set
+    /// is treated as unmetered and always returns `None` remaining.
+    pub fn consume_lease_budget(&self, id: &str, amount: f64) -> Result<Option<f64>> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let tx = conn.transaction()?;
+        let budget: Option<Option<f64>> = tx
+            .query_row("SELECT budget FROM leases WHERE id=?", params![id], |r| {
+                r.get(0)
+            })
+            .optional()?;
+        let Some(budget) = budget else {
+            return Err(anyhow!("lease {id} not found"));
+        };
+        let remaining = match budget {
+            None => None,
+            Some(b) if b >= amount => Some(b - amount),
+            Some(b) => {
+                return Err(anyhow!(
+                    "lease {id} budget exhausted: {b} remaining, {amount} requested"
+                ));
+            }
+        };
+        tx.execute(
+            "UPDATE leases SET budget=?, updated=? WHERE id=?",
+            params![remaining, now, id],
+        )?;
+        tx.commit()?;
+        Ok(remaining)
+    }
+
+    /// Lease transition history, newest first, optionally narrowed to one
+    /// subject and/or capability.
+    pub fn list_lease_events(
+        &self,
+        subject: Option<&str>,
+        capability: Option<&str>,
         limit: i64,
-    ) -> Result<Vec<PersonaProposal>> {
+    ) -> Result<Vec<serde_json::Value>> {
         let conn = self.conn()?;
-        let limit = limit.clamp(1, 500);
-        let mut proposals = Vec::new();
-        let mut query = String::from(
-            "SELECT proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated \
-             FROM persona_proposals",
+        let mut sql = String::from(
+            "SELECT id,lease_id,event,subject,capability,reason,actor,time FROM lease_events",
         );
-        let mut conditions: Vec<&str> = Vec::new();
-        if persona_id.is_some() {
-            conditions.push("persona_id=?");
-        }
-        if status.is_some() {
-            conditions.push("status=?");
+        let mut clauses: Vec<&str> = Vec::new();
+        let mut args: Vec<&dyn rusqlite::ToSql> = Vec::new();
+        if let Some(s) = subject.as_ref() {
+            clauses.push("subject = ?");
+            args.push(s);
         }
-        if !conditions.is_empty() {
-            query.push_str(" WHERE ");
-            query.push_str(&conditions.join(" AND "));
+        if let Some(c) = capability.as_ref() {
+            clauses.push("capability = ?");
+            args.push(c);
         }
-        query.push_str(" ORDER BY updated DESC LIMIT ?");
-
-        let mut params_vec: Vec<rusqlite::types::Value> = Vec::new();
-        if let Some(pid) = persona_id {
-            params_vec.push(Value::from(pid.to_string()));
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
         }
-        if let Some(status_val) = status {
-            params_vec.push(Value::from(status_val.to_string()));
+        sql.push_str(" ORDER BY id DESC LIMIT ?");
+        args.push(&limit);
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(args.as_slice())?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "id": r.get::<_, i64>(0)?,
+                "lease_id": r.get::<_, String>(1)?,
+                "event": r.get::<_, String>(2)?,
+                "subject": r.get::<_, Option<String>>(3)?,
+                "capability": r.get::<_, Option<String>>(4)?,
+                "reason": r.get::<_, Option<String>>(5)?,
+                "actor": r.get::<_, Option<String>>(6)?,
+                "time": r.get::<_, String>(7)?,
+            }));
         }
-        params_vec.push(limit.into());
+        Ok(out)
+    }
 
-        let mut stmt = conn.prepare(&query)?;
-        let mut rows = stmt.query(params_from_iter(params_vec))?;
-        while let Some(row) = rows.next()? {
-            proposals.push(Self::map_persona_proposal_row(row)?);
+    pub fn list_leases(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let ws = self.workspace_clause("workspace_id");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated FROM leases WHERE 1=1{ws} ORDER BY updated DESC LIMIT ?",
+        ))?;
+        let mut rows = stmt.query([limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let policy_s: Option<String> = r.get(6)?;
+            let policy_v = policy_s
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .unwrap_or(serde_json::json!({}));
+            out.push(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "subject": r.get::<_, String>(1)?,
+                "capability": r.get::<_, String>(2)?,
+                "scope": r.get::<_, Option<String>>(3)?,
+                "ttl_until": r.get::<_, String>(4)?,
+                "budget": r.get::<_, Option<f64>>(5)?,
+                "policy": policy_v,
+                "created": r.get::<_, String>(7)?,
+                "updated": r.get::<_, String>(8)?,
+            }));
         }
-        Ok(proposals)
+        Ok(out)
     }
 
-    pub fn append_persona_history(&self, append: PersonaHistoryAppend) -> Result<i64> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn append_contribution(
+        &self,
+        subject: &str,
+        kind: &str,
+        qty: f64,
+        unit: &str,
+        corr_id: Option<&str>,
+        proj: Option<&str>,
+        meta: Option<&serde_json::Value>,
+    ) -> Result<i64> {
+        self.ensure_writable()?;
         let conn = self.conn()?;
-        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let diff_s = serde_json::to_string(&append.diff).unwrap_or_else(|_| "[]".into());
+        let now = self.now_rfc3339();
+        let meta_s = meta.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
         conn.execute(
-            "INSERT INTO persona_history (persona_id, proposal_id, diff, applied_by, applied_at) VALUES (?, ?, ?, ?, ?)",
-            params![
-                append.persona_id,
-                append.proposal_id,
-                diff_s,
-                append.applied_by,
-                now
-            ],
+            "INSERT INTO contributions(time,subject,kind,qty,unit,corr_id,proj,meta) VALUES(?,?,?,?,?,?,?,?)",
+            params![now, subject, kind, qty, unit, corr_id, proj, meta_s],
         )?;
         Ok(conn.last_insert_rowid())
     }
 
-    pub fn list_persona_history(
-        &self,
-        persona_id: &str,
-        limit: i64,
-    ) -> Result<Vec<PersonaHistoryEntry>> {
+    pub fn list_contributions(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
         let conn = self.conn()?;
-        let limit = limit.clamp(1, 500);
         let mut stmt = conn.prepare(
-            "SELECT id, persona_id, proposal_id, diff, applied_by, applied_at \
-             FROM persona_history WHERE persona_id=? ORDER BY applied_at DESC LIMIT ?",
+            "SELECT id,time,subject,kind,qty,unit,corr_id,proj,meta FROM contributions ORDER BY id DESC LIMIT ?",
         )?;
-        let mut rows = stmt.query(params![persona_id, limit])?;
+        let mut rows = stmt.query([limit])?;
         let mut out = Vec::new();
-        while let Some(row) = rows.next()? {
-            out.push(Self::map_persona_history_row(row)?);
+        while let Some(r) = rows.next()? {
+            let meta_s: Option<String> = r.get(8)?;
+            let meta_v = meta_s
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .unwrap_or(serde_json::json!({}));
+            out.push(serde_json::json!({
+                "id": r.get::<_, i64>(0)?,
+                "time": r.get::<_, String>(1)?,
+                "subject": r.get::<_, String>(2)?,
+                "kind": r.get::<_, String>(3)?,
+                "qty": r.get::<_, f64>(4)?,
+                "unit": r.get::<_, String>(5)?,
+                "corr_id": r.get::<_, Option<String>>(6)?,
+                "proj": r.get::<_, Option<String>>(7)?,
+                "meta": meta_v,
+            }));
         }
         Ok(out)
     }
 
-    pub fn insert_persona_vibe_sample(
-        &self,
-        create: PersonaVibeSampleCreate,
-        retain: i64,
-    ) -> Result<PersonaVibeSample> {
-        let PersonaVibeSampleCreate {
-            persona_id,
-            kind,
-            signal,
-            strength,
-            note,
-            metadata,
-            recorded_at,
-        } = create;
-        let retain = retain.clamp(1, 500);
-        let conn = self.conn()?;
-        conn.execute(
-            "INSERT INTO persona_vibe_samples (persona_id, kind, signal, strength, note, metadata, recorded_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
-            params![
-                persona_id,
-                kind,
-                signal,
-                strength,
-                note,
-                serialize_optional_json(&metadata),
-                recorded_at
-            ],
+    /// Fold contribution rows appended since the last rollup into daily
+    /// `contribution_rollups` buckets keyed by (subject, kind, unit).
+    /// Incremental via a persisted cursor, mirroring the egress rollups;
+    /// returns the number of raw rows folded in.
+    pub fn rollup_contributions(&self) -> Result<u64> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        Self::fold_contribution_rollups(&mut conn)
+    }
+
+    fn fold_contribution_rollups(conn: &mut Connection) -> Result<u64> {
+        let tx = conn.transaction()?;
+        let last: i64 = tx
+            .query_row(
+                "SELECT last_event_id FROM contribution_rollup_cursor WHERE id = 1",
+                [],
+                |r| r.get(0),
+            )
+            .optional()?
+            .unwrap_or(0);
+        let max_id: Option<i64> = tx.query_row(
+            "SELECT MAX(id) FROM contributions WHERE id > ?",
+            [last],
+            |r| r.get(0),
         )?;
-        let last_id = conn.last_insert_rowid();
-        conn.execute(
-            "DELETE FROM persona_vibe_samples WHERE persona_id=? AND id NOT IN (
-                SELECT id FROM persona_vibe_samples WHERE persona_id=? ORDER BY recorded_at DESC, id DESC LIMIT ?
-            )",
-            params![persona_id, persona_id, retain],
+        let Some(max_id) = max_id else {
+            tx.commit()?;
+            return Ok(0);
+        };
+        let folded: u64 = tx.query_row(
+            "SELECT COUNT(*) FROM contributions WHERE id > ?1 AND id <= ?2",
+            params![last, max_id],
+            |r| r.get(0),
         )?;
-        let mut stmt = conn.prepare(
-            "SELECT id, persona_id, kind, signal, strength, note, metadata, recorded_at FROM persona_vibe_samples WHERE id=? LIMIT 1",
+        tx.execute(
+            "INSERT INTO contribution_rollups(bucket_start,subject,kind,unit,events,qty)
+             SELECT strftime('%Y-%m-%dT00:00:00Z', time), subject, kind, unit,
+                    COUNT(*), COALESCE(SUM(qty),0)
+               FROM contributions WHERE id > ?1 AND id <= ?2
+              GROUP BY 1,2,3,4
+             ON CONFLICT(bucket_start,subject,kind,unit) DO UPDATE SET
+               events = events + excluded.events,
+               qty = qty + excluded.qty",
+            params![last, max_id],
         )?;
-        let mut rows = stmt.query([last_id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Self::map_persona_vibe_sample_row(row)?)
-        } else {
-            Err(anyhow!("persona vibe sample not found after insert"))
-        }
+        tx.execute(
+            "INSERT INTO contribution_rollup_cursor(id,last_event_id) VALUES(1,?1)
+             ON CONFLICT(id) DO UPDATE SET last_event_id = excluded.last_event_id",
+            [max_id],
+        )?;
+        tx.commit()?;
+        Ok(folded)
     }
 
-    pub fn list_persona_vibe_samples(
+    /// Daily rollup buckets, newest first, optionally bounded to buckets at
+    /// or after `since` (RFC3339).
+    pub fn list_contribution_rollups(
         &self,
-        persona_id: &str,
+        since: Option<&str>,
         limit: i64,
-    ) -> Result<Vec<PersonaVibeSample>> {
+    ) -> Result<Vec<serde_json::Value>> {
         let conn = self.conn()?;
-        let limit = limit.clamp(1, 500);
-        let mut stmt = conn.prepare(
-            "SELECT id, persona_id, kind, signal, strength, note, metadata, recorded_at \
-             FROM persona_vibe_samples WHERE persona_id=? \
-             ORDER BY recorded_at DESC, id DESC LIMIT ?",
-        )?;
-        let mut rows = stmt.query(params![persona_id, limit])?;
+        let mut sql = String::from(
+            "SELECT bucket_start,subject,kind,unit,events,qty FROM contribution_rollups",
+        );
+        let mut binds: Vec<Value> = Vec::new();
+        if let Some(since) = since {
+            sql.push_str(" WHERE bucket_start >= ?1");
+            binds.push(Value::Text(since.to_string()));
+        }
+        sql.push_str(" ORDER BY bucket_start DESC LIMIT ?");
+        binds.push(Value::Integer(limit.max(1)));
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(binds))?;
         let mut out = Vec::new();
-        while let Some(row) = rows.next()? {
-            out.push(Self::map_persona_vibe_sample_row(row)?);
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "bucket_start": r.get::<_, String>(0)?,
+                "subject": r.get::<_, String>(1)?,
+                "kind": r.get::<_, String>(2)?,
+                "unit": r.get::<_, String>(3)?,
+                "events": r.get::<_, i64>(4)?,
+                "qty": r.get::<_, f64>(5)?,
+            }));
         }
         Ok(out)
     }
 
-    pub async fn upsert_persona_entry_async(
+    /// Aggregate contributions per unit, optionally narrowed to one subject
+    /// and/or a kind prefix. `time_bucket` controls the grain: `day` keeps
+    /// the daily buckets, `month` folds them into calendar months, and
+    /// `total` collapses everything into one row per (unit, kind, subject
+    /// filter). Folds any new ledger rows into the rollups first, then reads
+    /// only the rollup table.
+    pub fn summarize_contributions(
         &self,
-        upsert: PersonaEntryUpsert,
-    ) -> Result<PersonaEntry> {
-        self.run_blocking(move |kernel| kernel.upsert_persona_entry(upsert))
-            .await
+        subject: Option<&str>,
+        kind_prefix: Option<&str>,
+        time_bucket: &str,
+    ) -> Result<Vec<serde_json::Value>> {
+        let bucket_expr = match time_bucket {
+            "day" => "bucket_start",
+            "month" => "strftime('%Y-%m-01T00:00:00Z', bucket_start)",
+            "total" => "''",
+            other => return Err(anyhow!("unknown contribution time_bucket: {}", other)),
+        };
+        self.rollup_contributions()?;
+        let conn = self.conn()?;
+        let mut sql = format!(
+            "SELECT {bucket_expr} AS bucket, unit, SUM(events), SUM(qty) FROM contribution_rollups WHERE 1=1"
+        );
+        let mut binds: Vec<Value> = Vec::new();
+        if let Some(subject) = subject {
+            sql.push_str(" AND subject = ?");
+            binds.push(Value::Text(subject.to_string()));
+        }
+        if let Some(prefix) = kind_prefix {
+            sql.push_str(" AND kind LIKE ? || '%'");
+            binds.push(Value::Text(prefix.to_string()));
+        }
+        sql.push_str(" GROUP BY bucket, unit ORDER BY bucket DESC, unit ASC");
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(binds))?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "bucket": r.get::<_, String>(0)?,
+                "unit": r.get::<_, String>(1)?,
+                "events": r.get::<_, i64>(2)?,
+                "qty": r.get::<_, f64>(3)?,
+            }));
+        }
+        Ok(out)
     }
 
-    pub async fn get_persona_entry_async(&self, id: String) -> Result<Option<PersonaEntry>> {
-        self.run_blocking(move |kernel| kernel.get_persona_entry(&id))
-            .await
+    pub async fn rollup_contributions_async(&self) -> Result<u64> {
+        self.run_blocking(move |k| k.rollup_contributions()).await
     }
 
-    pub async fn list_persona_entries_async(
+    pub async fn list_contribution_rollups_async(
         &self,
-        owner_kind: Option<String>,
-        owner_ref: Option<String>,
+        since: Option<String>,
         limit: i64,
-    ) -> Result<Vec<PersonaEntry>> {
-        self.run_blocking(move |kernel| {
-            let owner_filter = match (owner_kind.as_deref(), owner_ref.as_deref()) {
-                (Some(kind), Some(r)) => Some((kind, r)),
-                _ => None,
-            };
-            kernel.list_persona_entries(owner_filter, limit)
-        })
-        .await
-    }
-
-    pub async fn insert_persona_proposal_async(
-        &self,
-        create: PersonaProposalCreate,
-    ) -> Result<String> {
-        self.run_blocking(move |kernel| kernel.insert_persona_proposal(create))
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_contribution_rollups(since.as_deref(), limit))
             .await
     }
 
-    pub async fn get_persona_proposal_async(
+    pub async fn summarize_contributions_async(
         &self,
-        proposal_id: String,
-    ) -> Result<Option<PersonaProposal>> {
-        self.run_blocking(move |kernel| kernel.get_persona_proposal(&proposal_id))
-            .await
+        subject: Option<String>,
+        kind_prefix: Option<String>,
+        time_bucket: String,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| {
+            k.summarize_contributions(subject.as_deref(), kind_prefix.as_deref(), &time_bucket)
+        })
+        .await
     }
 
-    pub async fn update_persona_proposal_status_async(
+    /// Create or update the quota for `(subject, kind)`: at most `max_qty`
+    /// of ledger qty inside a sliding `window_secs` window.
+    pub fn set_quota(
         &self,
-        proposal_id: String,
-        status: PersonaProposalStatusUpdate,
-    ) -> Result<bool> {
-        self.run_blocking(move |kernel| kernel.update_persona_proposal_status(&proposal_id, status))
-            .await
+        subject: &str,
+        kind: &str,
+        max_qty: f64,
+        window_secs: i64,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        if max_qty < 0.0 || window_secs <= 0 {
+            return Err(anyhow!(
+                "quota for {subject}/{kind} needs max_qty >= 0 and window_secs > 0"
+            ));
+        }
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        conn.execute(
+            "INSERT INTO quotas(subject,kind,max_qty,window_secs,created,updated) VALUES(?,?,?,?,?,?)
+             ON CONFLICT(subject,kind) DO UPDATE SET
+               max_qty = excluded.max_qty,
+               window_secs = excluded.window_secs,
+               updated = excluded.updated",
+            params![subject, kind, max_qty, window_secs, now, now],
+        )?;
+        Ok(())
     }
 
-    pub async fn list_persona_proposals_async(
-        &self,
-        persona_id: Option<String>,
-        status: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<PersonaProposal>> {
-        self.run_blocking(move |kernel| {
-            kernel.list_persona_proposals(persona_id.as_deref(), status.as_deref(), limit)
-        })
-        .await
+    /// Remove the quota for `(subject, kind)`. Returns whether one existed.
+    pub fn delete_quota(&self, subject: &str, kind: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let n = conn.execute(
+            "DELETE FROM quotas WHERE subject=? AND kind=?",
+            params![subject, kind],
+        )?;
+        Ok(n > 0)
     }
 
-    pub async fn append_persona_history_async(&self, entry: PersonaHistoryAppend) -> Result<i64> {
-        self.run_blocking(move |kernel| kernel.append_persona_history(entry))
-            .await
+    pub fn list_quotas(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT subject,kind,max_qty,window_secs,created,updated FROM quotas ORDER BY subject, kind LIMIT ?",
+        )?;
+        let mut rows = stmt.query([limit.max(1)])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "subject": r.get::<_, String>(0)?,
+                "kind": r.get::<_, String>(1)?,
+                "max_qty": r.get::<_, f64>(2)?,
+                "window_secs": r.get::<_, i64>(3)?,
+                "created": r.get::<_, String>(4)?,
+                "updated": r.get::<_, String>(5)?,
+            }));
+        }
+        Ok(out)
     }
 
-    pub async fn list_persona_history_async(
+    /// Like [`Self::append_contribution`], but enforces any quota configured
+    /// for `(subject, kind)` first: the window's ledger qty plus `qty` must
+    /// stay within `max_qty` or the call fails and nothing is recorded. The
+    /// check and the insert share one transaction, so concurrent workers
+    /// can't both slip under the limit. Without a quota row this degrades to
+    /// a plain append.
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_and_record_contribution(
         &self,
-        persona_id: String,
-        limit: i64,
-    ) -> Result<Vec<PersonaHistoryEntry>> {
-        self.run_blocking(move |kernel| kernel.list_persona_history(&persona_id, limit))
-            .await
+        subject: &str,
+        kind: &str,
+        qty: f64,
+        unit: &str,
+        corr_id: Option<&str>,
+        proj: Option<&str>,
+        meta: Option<&serde_json::Value>,
+    ) -> Result<i64> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let meta_s = meta.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        let quota: Option<(f64, i64)> = tx
+            .query_row(
+                "SELECT max_qty, window_secs FROM quotas WHERE subject=? AND kind=?",
+                params![subject, kind],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+        if let Some((max_qty, window_secs)) = quota {
+            let cutoff = (self.clock.now() - Duration::from_secs(window_secs.max(0) as u64))
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            let used: f64 = tx.query_row(
+                "SELECT COALESCE(SUM(qty),0) FROM contributions WHERE subject=? AND kind=? AND time >= ?",
+                params![subject, kind, cutoff],
+                |r| r.get(0),
+            )?;
+            if used + qty > max_qty {
+                return Err(anyhow!(
+                    "quota exceeded for {subject}/{kind}: {used} used of {max_qty} in {window_secs}s window, {qty} requested"
+                ));
+            }
+        }
+        tx.execute(
+            "INSERT INTO contributions(time,subject,kind,qty,unit,corr_id,proj,meta) VALUES(?,?,?,?,?,?,?,?)",
+            params![now, subject, kind, qty, unit, corr_id, proj, meta_s],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(id)
     }
 
-    pub async fn insert_persona_vibe_sample_async(
+    pub async fn set_quota_async(
         &self,
-        create: PersonaVibeSampleCreate,
-        retain: i64,
-    ) -> Result<PersonaVibeSample> {
-        self.run_blocking(move |kernel| kernel.insert_persona_vibe_sample(create, retain))
+        subject: String,
+        kind: String,
+        max_qty: f64,
+        window_secs: i64,
+    ) -> Result<()> {
+        self.run_blocking(move |k| k.set_quota(&subject, &kind, max_qty, window_secs))
             .await
     }
 
-    pub async fn list_persona_vibe_samples_async(
-        &self,
-        persona_id: String,
-        limit: i64,
-    ) -> Result<Vec<PersonaVibeSample>> {
-        self.run_blocking(move |kernel| kernel.list_persona_vibe_samples(&persona_id, limit))
+    pub async fn delete_quota_async(&self, subject: String, kind: String) -> Result<bool> {
+        self.run_blocking(move |k| k.delete_quota(&subject, &kind))
             .await
     }
 
-    pub async fn apply_persona_diff_async(
-        &self,
-        persona_id: String,
-        diff: JsonValue,
-    ) -> Result<PersonaEntry> {
-        self.run_blocking(move |kernel| kernel.apply_persona_diff(&persona_id, &diff))
-            .await
+    pub async fn list_quotas_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_quotas(limit)).await
     }
 
-    fn map_persona_entry_row(row: &rusqlite::Row<'_>) -> Result<PersonaEntry> {
-        let traits_raw: Option<String> = row.get(5)?;
-        let preferences_raw: Option<String> = row.get(6)?;
-        let worldview_raw: Option<String> = row.get(7)?;
-        let vibe_raw: Option<String> = row.get(8)?;
-        let calibration_raw: Option<String> = row.get(9)?;
-        Ok(PersonaEntry {
-            id: row.get(0)?,
-            owner_kind: row.get(1)?,
-            owner_ref: row.get(2)?,
-            name: row.get(3)?,
-            archetype: row.get(4)?,
-            traits: parse_json_or_default(traits_raw, json!({})),
-            preferences: parse_json_or_default(preferences_raw, json!({})),
-            worldview: parse_json_or_default(worldview_raw, json!({})),
-            vibe_profile: parse_json_or_default(vibe_raw, json!({})),
-            calibration: parse_json_or_default(calibration_raw, json!({})),
-            updated: row.get(10)?,
-            version: row.get(11)?,
+    #[allow(clippy::too_many_arguments)]
+    pub async fn check_and_record_contribution_async(
+        &self,
+        subject: String,
+        kind: String,
+        qty: f64,
+        unit: String,
+        corr_id: Option<String>,
+        proj: Option<String>,
+        meta: Option<serde_json::Value>,
+    ) -> Result<i64> {
+        self.run_blocking(move |k| {
+            k.check_and_record_contribution(
+                &subject,
+                &kind,
+                qty,
+                &unit,
+                corr_id.as_deref(),
+                proj.as_deref(),
+                meta.as_ref(),
+            )
         })
+        .await
     }
 
-    fn map_persona_proposal_row(row: &rusqlite::Row<'_>) -> Result<PersonaProposal> {
-        let diff_raw: Option<String> = row.get(3)?;
-        let telemetry_raw: Option<String> = row.get(5)?;
-        let leases_raw: Option<String> = row.get(6)?;
-        Ok(PersonaProposal {
-            proposal_id: row.get(0)?,
-            persona_id: row.get(1)?,
-            submitted_by: row.get(2)?,
-            diff: parse_json_or_default(diff_raw, json!([])),
-            rationale: row.get(4)?,
-            telemetry_scope: parse_json_or_default(telemetry_raw, json!({})),
+    // ---------- Research watcher ----------
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn upsert_research_watcher_item(
+        &self,
+        source: Option<&str>,
+        source_id: Option<&str>,
+        title: Option<&str>,
+        summary: Option<&str>,
+        url: Option<&str>,
+        payload: Option<&serde_json::Value>,
+    ) -> Result<String> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let payload_s = payload.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        let existing_id: Option<String> = if let Some(src_id) = source_id {
+            conn.query_row(
+                "SELECT id FROM research_watcher_items WHERE source_id = ? LIMIT 1",
+                params![src_id],
+                |r| r.get(0),
+            )
+            .optional()?
+        } else {
+            None
+        };
+        let (id, existed) = if let Some(existing) = existing_id {
+            (existing, true)
+        } else {
+            (uuid::Uuid::new_v4().to_string(), false)
+        };
+        if existed {
+            conn.execute(
+                "UPDATE research_watcher_items SET source=?, title=?, summary=?, url=?, payload=?, updated=? WHERE id=?",
+                params![source, title, summary, url, payload_s, now, id],
+            )?;
+        } else {
+            conn.execute(
+                "INSERT INTO research_watcher_items(id,source,source_id,title,summary,url,payload,status,note,created,updated) VALUES(?,?,?,?,?,?,?,?,?,?,?)",
+                params![
+                    id,
+                    source,
+                    source_id,
+                    title,
+                    summary,
+                    url,
+                    payload_s,
+                    "pending",
+                    Option::<String>::None,
+                    now.clone(),
+                    now
+                ],
+            )?;
+        }
+        Ok(id)
+    }
+
+    pub fn list_research_watcher_items(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let limit = limit.clamp(1, 500);
+        let mut out = Vec::new();
+        if let Some(stat) = status {
+            let mut stmt = conn.prepare(
+                "SELECT id,source,source_id,title,summary,url,payload,status,note,created,updated FROM research_watcher_items WHERE status=? ORDER BY updated DESC LIMIT ?",
+            )?;
+            let mut rows = stmt.query(params![stat, limit])?;
+            while let Some(r) = rows.next()? {
+                let payload_s: Option<String> = r.get(6)?;
+                let payload_v = payload_s
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                    .unwrap_or(serde_json::json!({}));
+                out.push(serde_json::json!({
+                    "id": r.get::<_, String>(0)?,
+                    "source": r.get::<_, Option<String>>(1)?,
+                    "source_id": r.get::<_, Option<String>>(2)?,
+                    "title": r.get::<_, Option<String>>(3)?,
+                    "summary": r.get::<_, Option<String>>(4)?,
+                    "url": r.get::<_, Option<String>>(5)?,
+                    "payload": payload_v,
+                    "status": r.get::<_, String>(7)?,
+                    "note": r.get::<_, Option<String>>(8)?,
+                    "created": r.get::<_, String>(9)?,
+                    "updated": r.get::<_, String>(10)?
+                }));
+            }
+        } else {
+            let mut stmt = conn.prepare(
+                "SELECT id,source,source_id,title,summary,url,payload,status,note,created,updated FROM research_watcher_items ORDER BY updated DESC LIMIT ?",
+            )?;
+            let mut rows = stmt.query([limit])?;
+            while let Some(r) = rows.next()? {
+                let payload_s: Option<String> = r.get(6)?;
+                let payload_v = payload_s
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                    .unwrap_or(serde_json::json!({}));
+                out.push(serde_json::json!({
+                    "id": r.get::<_, String>(0)?,
+                    "source": r.get::<_, Option<String>>(1)?,
+                    "source_id": r.get::<_, Option<String>>(2)?,
+                    "title": r.get::<_, Option<String>>(3)?,
+                    "summary": r.get::<_, Option<String>>(4)?,
+                    "url": r.get::<_, Option<String>>(5)?,
+                    "payload": payload_v,
+                    "status": r.get::<_, String>(7)?,
+                    "note": r.get::<_, Option<String>>(8)?,
+                    "created": r.get::<_, String>(9)?,
+                    "updated": r.get::<_, String>(10)?
+                }));
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn update_research_watcher_status(
+        &self,
+        id: &str,
+        status: &str,
+        note: Option<&str>,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let n = conn.execute(
+            "UPDATE research_watcher_items SET status=?, note=?, updated=? WHERE id=?",
+            params![status, note, now, id],
+        )?;
+        Ok(n > 0)
+    }
+
+    pub fn get_research_watcher_item(&self, id: &str) -> Result<Option<ResearchWatcherItem>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id,source,source_id,title,summary,url,payload,status,note,created,updated FROM research_watcher_items WHERE id=? LIMIT 1",
+        )?;
+        let mut rows = stmt.query([id])?;
+        if let Some(r) = rows.next()? {
+            let payload_s: Option<String> = r.get(6)?;
+            let payload_v =
+                payload_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+            Ok(Some(ResearchWatcherItem {
+                id: r.get(0)?,
+                source: r.get(1)?,
+                source_id: r.get(2)?,
+                title: r.get(3)?,
+                summary: r.get(4)?,
+                url: r.get(5)?,
+                payload: payload_v,
+                status: r.get(7)?,
+                note: r.get(8)?,
+                created: r.get(9)?,
+                updated: r.get(10)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // ---------- Staging actions ----------
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_staging_action(
+        &self,
+        action_kind: &str,
+        action_input: &serde_json::Value,
+        project: Option<&str>,
+        requested_by: Option<&str>,
+        evidence: Option<&serde_json::Value>,
+    ) -> Result<String> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = self.now_rfc3339();
+        let input_s = serde_json::to_string(action_input).unwrap_or("{}".into());
+        let evidence_s = evidence.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        conn.execute(
+            "INSERT INTO staging_actions(id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated) VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?)",
+            params![
+                id,
+                action_kind,
+                input_s,
+                project,
+                requested_by,
+                evidence_s,
+                "pending",
+                Option::<String>::None,
+                Option::<String>::None,
+                Option::<String>::None,
+                Option::<String>::None,
+                now.clone(),
+                now
+            ],
+        )?;
+        Ok(id)
+    }
+
+    pub fn list_staging_actions(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let limit = limit.clamp(1, 500);
+        let mut out = Vec::new();
+        if let Some(stat) = status {
+            let mut stmt = conn.prepare(
+                "SELECT id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated FROM staging_actions WHERE status=? AND deleted_at IS NULL ORDER BY created ASC LIMIT ?",
+            )?;
+            let mut rows = stmt.query(params![stat, limit])?;
+            while let Some(r) = rows.next()? {
+                let input_s: String = r.get(2)?;
+                let evidence_s: Option<String> = r.get(5)?;
+                let input_v = serde_json::from_str::<serde_json::Value>(&input_s)
+                    .unwrap_or(serde_json::json!({}));
+                let evidence_v = evidence_s
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                    .unwrap_or(serde_json::json!({}));
+                out.push(serde_json::json!({
+                    "id": r.get::<_, String>(0)?,
+                    "action_kind": r.get::<_, String>(1)?,
+                    "action_input": input_v,
+                    "project": r.get::<_, Option<String>>(3)?,
+                    "requested_by": r.get::<_, Option<String>>(4)?,
+                    "evidence": evidence_v,
+                    "status": r.get::<_, String>(6)?,
+                    "decision": r.get::<_, Option<String>>(7)?,
+                    "decided_by": r.get::<_, Option<String>>(8)?,
+                    "decided_at": r.get::<_, Option<String>>(9)?,
+                    "action_id": r.get::<_, Option<String>>(10)?,
+                    "created": r.get::<_, String>(11)?,
+                    "updated": r.get::<_, String>(12)?
+                }));
+            }
+        } else {
+            let mut stmt = conn.prepare(
+                "SELECT id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated FROM staging_actions WHERE deleted_at IS NULL ORDER BY created ASC LIMIT ?",
+            )?;
+            let mut rows = stmt.query([limit])?;
+            while let Some(r) = rows.next()? {
+                let input_s: String = r.get(2)?;
+                let evidence_s: Option<String> = r.get(5)?;
+                let input_v = serde_json::from_str::<serde_json::Value>(&input_s)
+                    .unwrap_or(serde_json::json!({}));
+                let evidence_v = evidence_s
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                    .unwrap_or(serde_json::json!({}));
+                out.push(serde_json::json!({
+                    "id": r.get::<_, String>(0)?,
+                    "action_kind": r.get::<_, String>(1)?,
+                    "action_input": input_v,
+                    "project": r.get::<_, Option<String>>(3)?,
+                    "requested_by": r.get::<_, Option<String>>(4)?,
+                    "evidence": evidence_v,
+                    "status": r.get::<_, String>(6)?,
+                    "decision": r.get::<_, Option<String>>(7)?,
+                    "decided_by": r.get::<_, Option<String>>(8)?,
+                    "decided_at": r.get::<_, Option<String>>(9)?,
+                    "action_id": r.get::<_, Option<String>>(10)?,
+                    "created": r.get::<_, String>(11)?,
+                    "updated": r.get::<_, String>(12)?
+                }));
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn get_staging_action(&self, id: &str) -> Result<Option<StagingAction>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id,action_kind,action_input,project,requested_by,evidence,status,decision,decided_by,decided_at,action_id,created,updated FROM staging_actions WHERE id=? LIMIT 1",
+        )?;
+        let mut rows = stmt.query([id])?;
+        if let Some(r) = rows.next()? {
+            let input_s: String = r.get(2)?;
+            let evidence_s: Option<String> = r.get(5)?;
+            let input_v = serde_json::from_str::<serde_json::Value>(&input_s)
+                .unwrap_or(serde_json::json!({}));
+            let evidence_v =
+                evidence_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+            Ok(Some(StagingAction {
+                id: r.get(0)?,
+                action_kind: r.get(1)?,
+                action_input: input_v,
+                project: r.get(3)?,
+                requested_by: r.get(4)?,
+                evidence: evidence_v,
+                status: r.get(6)?,
+                decision: r.get(7)?,
+                decided_by: r.get(8)?,
+                decided_at: r.get(9)?,
+                action_id: r.get(10)?,
+                created: r.get(11)?,
+                updated: r.get(12)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_staging_action_status(
+        &self,
+        id: &str,
+        status: &str,
+        decision: Option<&str>,
+        decided_by: Option<&str>,
+        decided_at: Option<&str>,
+        action_id: Option<&str>,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let decided_ts = decided_at.map(|s| s.to_string());
+        let n = conn.execute(
+            "UPDATE staging_actions SET status=?, decision=?, decided_by=?, decided_at=COALESCE(?,decided_at), action_id=?, updated=? WHERE id=?",
+            params![status, decision, decided_by, decided_ts, action_id, now, id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Decide many staging items at once. Only items currently `pending`
+    /// transition; the rest of the id set is left untouched. Returns the
+    /// number of rows updated.
+    pub fn bulk_decide_staging_actions(
+        &self,
+        ids: &[String],
+        status: &str,
+        decision: Option<&str>,
+        decided_by: Option<&str>,
+    ) -> Result<u64> {
+        self.ensure_writable()?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let tx = conn.transaction()?;
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "UPDATE staging_actions SET status=?, decision=?, decided_by=?, decided_at=?, updated=? \
+             WHERE status='pending' AND id IN ({placeholders})"
+        );
+        let mut params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(ids.len() + 5);
+        params.push(&status);
+        params.push(&decision);
+        params.push(&decided_by);
+        params.push(&now);
+        params.push(&now);
+        for id in ids {
+            params.push(id as &dyn rusqlite::ToSql);
+        }
+        let n = tx.execute(&sql, &params[..])?;
+        tx.commit()?;
+        Ok(n as u64)
+    }
+
+    pub fn find_valid_lease(
+        &self,
+        subject: &str,
+        capability: &str,
+    ) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let ws = self.workspace_clause("workspace_id");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated FROM leases \
+             WHERE subject=? AND capability=? AND ttl_until > ?{ws} ORDER BY ttl_until DESC LIMIT 1",
+        ))?;
+        let mut rows = stmt.query(params![subject, capability, now])?;
+        if let Some(r) = rows.next()? {
+            let policy_s: Option<String> = r.get(6)?;
+            let policy_v = policy_s
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .unwrap_or(serde_json::json!({}));
+            let v = serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "subject": r.get::<_, String>(1)?,
+                "capability": r.get::<_, String>(2)?,
+                "scope": r.get::<_, Option<String>>(3)?,
+                "ttl_until": r.get::<_, String>(4)?,
+                "budget": r.get::<_, Option<f64>>(5)?,
+                "policy": policy_v,
+                "created": r.get::<_, String>(7)?,
+                "updated": r.get::<_, String>(8)?,
+            });
+            Ok(Some(v))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn find_valid_lease_async(
+        &self,
+        subject: &str,
+        capability: &str,
+    ) -> Result<Option<serde_json::Value>> {
+        let s = subject.to_string();
+        let c = capability.to_string();
+        self.run_blocking(move |k| k.find_valid_lease(&s, &c)).await
+    }
+
+    /// Reconstruct the leases that authorized an action from its stored
+    /// `policy_ctx` snapshot, for "why was this allowed?" audit views.
+    ///
+    /// The snapshot may reference leases two ways:
+    /// - `"leases": ["<lease-id>", ...]` — explicit lease ids;
+    /// - `"required_capabilities": ["net:http", ...]` or a single
+    ///   `"capability": "net:http"`, optionally with `"subject"`
+    ///   (defaults to `"local"`) — resolved via the leases table.
+    ///
+    /// Only leases still valid (`ttl_until` in the future) are returned;
+    /// duplicates are collapsed. Unknown actions or actions without a
+    /// policy context yield an empty list.
+    pub fn action_authorizing_leases(&self, action_id: &str) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let policy_s: Option<Option<String>> = conn
+            .query_row(
+                "SELECT policy_ctx FROM actions WHERE id=? LIMIT 1",
+                params![action_id],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let ctx = match policy_s.flatten() {
+            Some(s) => {
+                serde_json::from_str::<serde_json::Value>(&s).unwrap_or(serde_json::json!({}))
+            }
+            None => return Ok(Vec::new()),
+        };
+        let now = self.now_rfc3339();
+        let row_to_lease = |r: &rusqlite::Row<'_>| -> rusqlite::Result<serde_json::Value> {
+            let policy_s: Option<String> = r.get(6)?;
+            let policy_v = policy_s
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .unwrap_or(serde_json::json!({}));
+            Ok(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "subject": r.get::<_, String>(1)?,
+                "capability": r.get::<_, String>(2)?,
+                "scope": r.get::<_, Option<String>>(3)?,
+                "ttl_until": r.get::<_, String>(4)?,
+                "budget": r.get::<_, Option<f64>>(5)?,
+                "policy": policy_v,
+                "created": r.get::<_, String>(7)?,
+                "updated": r.get::<_, String>(8)?,
+            }))
+        };
+        let mut out = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        if let Some(ids) = ctx.get("leases").and_then(|v| v.as_array()) {
+            let mut stmt = conn.prepare(
+                "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated \
+                 FROM leases WHERE id=? AND ttl_until > ? LIMIT 1",
+            )?;
+            for id in ids.iter().filter_map(|v| v.as_str()) {
+                if let Some(lease) = stmt.query_row(params![id, now], row_to_lease).optional()? {
+                    if seen.insert(lease["id"].as_str().unwrap_or_default().to_string()) {
+                        out.push(lease);
+                    }
+                }
+            }
+        }
+        let subject = ctx
+            .get("subject")
+            .and_then(|v| v.as_str())
+            .unwrap_or("local");
+        let mut caps: Vec<&str> = ctx
+            .get("required_capabilities")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        if let Some(cap) = ctx.get("capability").and_then(|v| v.as_str()) {
+            caps.push(cap);
+        }
+        if !caps.is_empty() {
+            let mut stmt = conn.prepare(
+                "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated \
+                 FROM leases WHERE subject=? AND capability=? AND ttl_until > ? \
+                 ORDER BY ttl_until DESC LIMIT 1",
+            )?;
+            for cap in caps {
+                if let Some(lease) = stmt
+                    .query_row(params![subject, cap, now], row_to_lease)
+                    .optional()?
+                {
+                    if seen.insert(lease["id"].as_str().unwrap_or_default().to_string()) {
+                        out.push(lease);
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub async fn action_authorizing_leases_async(
+        &self,
+        action_id: String,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.action_authorizing_leases(&action_id))
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn append_egress(
+        &self,
+        decision: &str,
+        reason: Option<&str>,
+        dest_host: Option<&str>,
+        dest_port: Option<i64>,
+        protocol: Option<&str>,
+        bytes_in: Option<i64>,
+        bytes_out: Option<i64>,
+        corr_id: Option<&str>,
+        proj: Option<&str>,
+        posture: Option<&str>,
+        meta: Option<&serde_json::Value>,
+    ) -> Result<i64> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let meta_s = meta.and_then(|v| serde_json::to_string(v).ok());
+        conn.execute(
+            "INSERT INTO egress_ledger(time,decision,reason,dest_host,dest_port,protocol,bytes_in,bytes_out,corr_id,proj,posture,meta) VALUES(?,?,?,?,?,?,?,?,?,?,?,?)",
+            params![
+                now,
+                decision,
+                reason,
+                dest_host,
+                dest_port,
+                protocol,
+                bytes_in,
+                bytes_out,
+                corr_id,
+                proj,
+                posture,
+                meta_s
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn list_egress(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id,time,decision,reason,dest_host,dest_port,protocol,bytes_in,bytes_out,corr_id,proj,posture,meta FROM egress_ledger ORDER BY id DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query([limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let meta: Option<String> = r.get(12)?;
+            out.push(serde_json::json!({
+                "id": r.get::<_, i64>(0)?,
+                "time": r.get::<_, String>(1)?,
+                "decision": r.get::<_, String>(2)?,
+                "reason": r.get::<_, Option<String>>(3)?,
+                "dest_host": r.get::<_, Option<String>>(4)?,
+                "dest_port": r.get::<_, Option<i64>>(5)?,
+                "protocol": r.get::<_, Option<String>>(6)?,
+                "bytes_in": r.get::<_, Option<i64>>(7)?,
+                "bytes_out": r.get::<_, Option<i64>>(8)?,
+                "corr_id": r.get::<_, Option<String>>(9)?,
+                "proj": r.get::<_, Option<String>>(10)?,
+                "posture": r.get::<_, Option<String>>(11)?,
+                "meta": meta.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Fold ledger rows appended since the last rollup into hourly
+    /// `egress_rollups` buckets keyed by (dest_host, proj, posture, decision).
+    /// Incremental via a persisted cursor so periodic callers only pay for new
+    /// rows; returns the number of raw rows folded in. Safe to call from a
+    /// scheduler or lazily before reading the rollups.
+    pub fn rollup_egress(&self) -> Result<u64> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        Self::fold_egress_rollups(&mut conn)
+    }
+
+    fn fold_egress_rollups(conn: &mut Connection) -> Result<u64> {
+        let tx = conn.transaction()?;
+        let last: i64 = tx
+            .query_row(
+                "SELECT last_event_id FROM egress_rollup_cursor WHERE id = 1",
+                [],
+                |r| r.get(0),
+            )
+            .optional()?
+            .unwrap_or(0);
+        let max_id: Option<i64> = tx.query_row(
+            "SELECT MAX(id) FROM egress_ledger WHERE id > ?",
+            [last],
+            |r| r.get(0),
+        )?;
+        let Some(max_id) = max_id else {
+            tx.commit()?;
+            return Ok(0);
+        };
+        let folded: u64 = tx.query_row(
+            "SELECT COUNT(*) FROM egress_ledger WHERE id > ?1 AND id <= ?2",
+            params![last, max_id],
+            |r| r.get(0),
+        )?;
+        tx.execute(
+            "INSERT INTO egress_rollups(bucket_start,dest_host,proj,posture,decision,events,bytes_in,bytes_out)
+             SELECT strftime('%Y-%m-%dT%H:00:00Z', time),
+                    COALESCE(dest_host,''), COALESCE(proj,''), COALESCE(posture,''), decision,
+                    COUNT(*), COALESCE(SUM(bytes_in),0), COALESCE(SUM(bytes_out),0)
+               FROM egress_ledger WHERE id > ?1 AND id <= ?2
+              GROUP BY 1,2,3,4,5
+             ON CONFLICT(bucket_start,dest_host,proj,posture,decision) DO UPDATE SET
+               events = events + excluded.events,
+               bytes_in = bytes_in + excluded.bytes_in,
+               bytes_out = bytes_out + excluded.bytes_out",
+            params![last, max_id],
+        )?;
+        tx.execute(
+            "INSERT INTO egress_rollup_cursor(id,last_event_id) VALUES(1,?1)
+             ON CONFLICT(id) DO UPDATE SET last_event_id = excluded.last_event_id",
+            [max_id],
+        )?;
+        tx.commit()?;
+        Ok(folded)
+    }
+
+    /// Delete raw ledger rows older than `before` (RFC3339), returning the
+    /// number removed. Pending rows are folded into the rollups first, so
+    /// aggregates are unaffected by the purge.
+    pub fn purge_egress_before(&self, before: &str) -> Result<u64> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        Self::purge_egress_conn(&mut conn, before)
+    }
+
+    fn purge_egress_conn(conn: &mut Connection, before: &str) -> Result<u64> {
+        Self::fold_egress_rollups(conn)?;
+        let purged = conn.execute("DELETE FROM egress_ledger WHERE time < ?", [before])? as u64;
+        Ok(purged)
+    }
+
+    fn egress_segment_rows() -> u64 {
+        std::env::var("ARW_EGRESS_ARCHIVE_SEGMENT_ROWS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(10_000)
+    }
+
+    /// Rotate ledger rows older than `before` into gzip NDJSON segment files
+    /// under `dest_dir`, named `egress-{first_id}-{last_id}.ndjson.gz`, then
+    /// delete them. Rollups are folded first so aggregates stay intact; the
+    /// report's `events` field counts rotated ledger rows. Like event
+    /// archival, each segment is synced before its rows are deleted.
+    pub fn rotate_egress(&self, before: &str, dest_dir: &Path) -> Result<EventArchiveReport> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        Self::rotate_egress_conn(&mut conn, before, dest_dir, Self::egress_segment_rows())
+    }
+
+    fn rotate_egress_conn(
+        conn: &mut Connection,
+        before: &str,
+        dest_dir: &Path,
+        segment_rows: u64,
+    ) -> Result<EventArchiveReport> {
+        use std::io::Write as _;
+        std::fs::create_dir_all(dest_dir)?;
+        Self::fold_egress_rollups(conn)?;
+        let mut report = EventArchiveReport {
+            segments: Vec::new(),
+            events: 0,
+            before: before.to_string(),
+        };
+        loop {
+            let batch: Vec<(i64, serde_json::Value)> = {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT id,time,decision,reason,dest_host,dest_port,protocol,bytes_in,bytes_out,corr_id,proj,posture,meta FROM egress_ledger WHERE time < ? ORDER BY id ASC LIMIT ?",
+                )?;
+                let mut rows = stmt.query(params![before, segment_rows as i64])?;
+                let mut out = Vec::new();
+                while let Some(r) = rows.next()? {
+                    let meta: Option<String> = r.get(12)?;
+                    let id = r.get::<_, i64>(0)?;
+                    out.push((
+                        id,
+                        serde_json::json!({
+                            "id": id,
+                            "time": r.get::<_, String>(1)?,
+                            "decision": r.get::<_, String>(2)?,
+                            "reason": r.get::<_, Option<String>>(3)?,
+                            "dest_host": r.get::<_, Option<String>>(4)?,
+                            "dest_port": r.get::<_, Option<i64>>(5)?,
+                            "protocol": r.get::<_, Option<String>>(6)?,
+                            "bytes_in": r.get::<_, Option<i64>>(7)?,
+                            "bytes_out": r.get::<_, Option<i64>>(8)?,
+                            "corr_id": r.get::<_, Option<String>>(9)?,
+                            "proj": r.get::<_, Option<String>>(10)?,
+                            "posture": r.get::<_, Option<String>>(11)?,
+                            "meta": meta.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                        }),
+                    ));
+                }
+                out
+            };
+            let (Some((first_id, _)), Some((last_id, _))) = (batch.first(), batch.last()) else {
+                break;
+            };
+            let name = format!("egress-{}-{}.ndjson.gz", first_id, last_id);
+            let path = dest_dir.join(&name);
+            let file = std::fs::File::create(&path)?;
+            let mut enc = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            for (_, row) in &batch {
+                serde_json::to_writer(&mut enc, row)?;
+                enc.write_all(b"\n")?;
+            }
+            enc.finish()?.sync_all()?;
+            let last_id = *last_id;
+            conn.execute(
+                "DELETE FROM egress_ledger WHERE time < ? AND id <= ?",
+                params![before, last_id],
+            )?;
+            report.events += batch.len() as u64;
+            report.segments.push(name);
+            if (batch.len() as u64) < segment_rows {
+                break;
+            }
+        }
+        if report.events > 0 {
+            let _ = conn.execute("PRAGMA wal_checkpoint(TRUNCATE);", []);
+        }
+        Ok(report)
+    }
+
+    /// Hourly rollup buckets, newest first, optionally bounded to buckets at
+    /// or after `since` (RFC3339). Group columns use '' for "not recorded".
+    pub fn list_egress_rollups(
+        &self,
+        since: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut sql = String::from(
+            "SELECT bucket_start,dest_host,proj,posture,decision,events,bytes_in,bytes_out FROM egress_rollups",
+        );
+        let mut binds: Vec<Value> = Vec::new();
+        if let Some(since) = since {
+            sql.push_str(" WHERE bucket_start >= ?1");
+            binds.push(Value::Text(since.to_string()));
+        }
+        sql.push_str(" ORDER BY bucket_start DESC LIMIT ?");
+        binds.push(Value::Integer(limit.max(1)));
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(binds))?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "bucket_start": r.get::<_, String>(0)?,
+                "dest_host": r.get::<_, String>(1)?,
+                "proj": r.get::<_, String>(2)?,
+                "posture": r.get::<_, String>(3)?,
+                "decision": r.get::<_, String>(4)?,
+                "events": r.get::<_, i64>(5)?,
+                "bytes_in": r.get::<_, i64>(6)?,
+                "bytes_out": r.get::<_, i64>(7)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Aggregate egress volume grouped by one dimension: `dest_host`, `proj`,
+    /// `posture`, `decision`, or `bucket` (the hourly bucket). Folds any new
+    /// ledger rows into the rollups first, then reads only the rollup table,
+    /// so repeated dashboard refreshes never rescan the raw ledger. Ordered by
+    /// total bytes descending.
+    pub fn summarize_egress(
+        &self,
+        group_by: &str,
+        since: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let col = match group_by {
+            "dest_host" => "dest_host",
+            "proj" => "proj",
+            "posture" => "posture",
+            "decision" => "decision",
+            "bucket" => "bucket_start",
+            other => return Err(anyhow!("unknown egress group_by: {}", other)),
+        };
+        self.rollup_egress()?;
+        let conn = self.conn()?;
+        let mut sql =
+            format!("SELECT {col}, SUM(events), SUM(bytes_in), SUM(bytes_out) FROM egress_rollups");
+        let mut binds: Vec<Value> = Vec::new();
+        if let Some(since) = since {
+            sql.push_str(" WHERE bucket_start >= ?1");
+            binds.push(Value::Text(since.to_string()));
+        }
+        sql.push_str(&format!(
+            " GROUP BY {col} ORDER BY SUM(bytes_in) + SUM(bytes_out) DESC LIMIT ?"
+        ));
+        binds.push(Value::Integer(limit.max(1)));
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(binds))?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                group_by: r.get::<_, String>(0)?,
+                "events": r.get::<_, i64>(1)?,
+                "bytes_in": r.get::<_, i64>(2)?,
+                "bytes_out": r.get::<_, i64>(3)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    pub fn insert_memory(&self, args: &MemoryInsertArgs<'_>) -> Result<String> {
+        self.ensure_writable()?;
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        let id = store.insert_memory(args)?;
+        Self::observe_op("insert_memory", started);
+        Self::observe_op_rows("insert_memory", 1);
+        Ok(id)
+    }
+
+    pub fn insert_memory_with_record(
+        &self,
+        args: &MemoryInsertArgs<'_>,
+    ) -> Result<(String, serde_json::Value)> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.insert_memory_with_record(args)
+    }
+
+    pub fn insert_memory_batch(
+        &self,
+        items: &[MemoryInsertOwned],
+    ) -> Result<Vec<Result<(String, serde_json::Value)>>> {
+        self.ensure_writable()?;
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        let out = store.insert_memory_batch(items)?;
+        Self::observe_op("insert_memory_batch", started);
+        Self::observe_op_rows(
+            "insert_memory_batch",
+            out.iter().filter(|r| r.is_ok()).count() as u64,
+        );
+        Ok(out)
+    }
+
+    pub fn search_memory(
+        &self,
+        q: &str,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.search_memory(q, lane, limit)
+    }
+
+    pub fn search_memory_page(
+        &self,
+        q: &str,
+        lane: Option<&str>,
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<serde_json::Value>, Option<String>)> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.search_memory_page(q, lane, limit, cursor)
+    }
+
+    pub fn search_memory_with_context(
+        &self,
+        q: &str,
+        lane: Option<&str>,
+        limit: i64,
+        ctx: &ReadContext,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.search_memory_with_context(q, lane, limit, ctx)
+    }
+
+    pub fn fts_search_memory(
+        &self,
+        q: &str,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.fts_search_memory(q, lane, limit)
+    }
+
+    pub fn search_memory_by_tags(
+        &self,
+        tags: &[String],
+        mode: TagMatch,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.search_memory_by_tags(tags, mode, lane, limit)
+    }
+
+    pub fn filter_memory(
+        &self,
+        filters: &MemoryFilters,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.filter_memory(filters, lane, limit)
+    }
+
+    pub fn search_memory_by_embedding(
+        &self,
+        embed: &[f32],
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.search_memory_by_embedding(embed, lane, limit)
+    }
+
+    pub fn search_memory_by_embedding_for_model(
+        &self,
+        embed: &[f32],
+        hint: &str,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.search_memory_by_embedding_for_model(embed, hint, lane, limit)
+    }
+
+    pub fn select_memory_hybrid(
+        &self,
+        q: Option<&str>,
+        embed: Option<&[f32]>,
+        lane: Option<&str>,
+        k: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        let out = store.select_memory_hybrid(q, embed, lane, k)?;
+        Self::observe_op("select_memory_hybrid", started);
+        Self::observe_op_rows("select_memory_hybrid", out.len() as u64);
+        Ok(out)
+    }
+
+    pub fn select_memory_hybrid_with_context(
+        &self,
+        q: Option<&str>,
+        embed: Option<&[f32]>,
+        lane: Option<&str>,
+        k: i64,
+        ctx: &ReadContext,
+    ) -> Result<Vec<serde_json::Value>> {
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        let out = store.select_memory_hybrid_with_context(q, embed, lane, k, ctx)?;
+        Self::observe_op("select_memory_hybrid", started);
+        Self::observe_op_rows("select_memory_hybrid", out.len() as u64);
+        Ok(out)
+    }
+
+    /// Hybrid selection with maximal-marginal-relevance diversity; see
+    /// [`MemoryStore::select_memory_hybrid_mmr`] for the `lambda` semantics.
+    pub fn select_memory_hybrid_mmr(
+        &self,
+        q: Option<&str>,
+        embed: Option<&[f32]>,
+        lane: Option<&str>,
+        k: i64,
+        lambda: f64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        let out = store.select_memory_hybrid_mmr(q, embed, lane, k, lambda)?;
+        Self::observe_op("select_memory_hybrid_mmr", started);
+        Self::observe_op_rows("select_memory_hybrid_mmr", out.len() as u64);
+        Ok(out)
+    }
+
+    pub fn insert_memory_link(
+        &self,
+        src_id: &str,
+        dst_id: &str,
+        rel: Option<&str>,
+        weight: Option<f64>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.insert_memory_link(src_id, dst_id, rel, weight)
+    }
+
+    pub fn list_memory_links(&self, src_id: &str, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_memory_links(src_id, limit)
+    }
+
+    pub fn list_memory_backlinks(
+        &self,
+        dst_id: &str,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_memory_backlinks(dst_id, limit)
+    }
+
+    pub fn list_memory_backlinks_many(
+        &self,
+        dst_ids: &[String],
+        limit_per: i64,
+    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_memory_backlinks_many(dst_ids, limit_per)
+    }
+
+    pub fn export_memory(
+        &self,
+        lane: Option<&str>,
+        project: Option<&str>,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<usize> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.export_memory(lane, project, writer)
+    }
+
+    pub fn import_memory(
+        &self,
+        reader: &mut dyn std::io::Read,
+        options: &MemoryImportOptions,
+    ) -> Result<(usize, usize)> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.import_memory(reader, options)
+    }
+
+    pub fn list_memory_links_many(
+        &self,
+        src_ids: &[String],
+        limit_per: i64,
+    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_memory_links_many(src_ids, limit_per)
+    }
+
+    pub fn traverse_memory_graph(
+        &self,
+        start_ids: &[String],
+        max_depth: usize,
+        rel_filter: Option<&str>,
+        limit: usize,
+    ) -> Result<serde_json::Value> {
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        let out = store.traverse_memory_graph(start_ids, max_depth, rel_filter, limit)?;
+        Self::observe_op("traverse_memory_graph", started);
+        Ok(out)
+    }
+
+    pub fn get_memory(&self, id: &str) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.get_memory(id)
+    }
+
+    pub fn get_memory_with_context(
+        &self,
+        id: &str,
+        ctx: &ReadContext,
+    ) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.get_memory_with_context(id, ctx)
+    }
+
+    pub fn list_memory_revisions(&self, id: &str, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_memory_revisions(id, limit)
+    }
+
+    pub fn restore_memory_revision(&self, id: &str, revision: i64) -> Result<serde_json::Value> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.restore_memory_revision(id, revision)
+    }
+
+    pub fn get_memory_many(&self, ids: &[String]) -> Result<HashMap<String, serde_json::Value>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.get_memory_many(ids)
+    }
+
+    pub fn find_memory_by_hash(&self, hash: &str) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.find_memory_by_hash(hash)
+    }
+
+    pub fn backfill_embed_blobs(&self, batch_limit: usize) -> Result<usize> {
+        if batch_limit == 0 {
+            return Ok(0);
+        }
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.backfill_embed_blobs(batch_limit)
+    }
+
+    pub fn pending_embed_backfill(&self) -> Result<u64> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.pending_embed_backfill()
+    }
+
+    pub fn expired_memory_candidates(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.expired_candidates(now, limit)
+    }
+
+    pub fn lane_overflow_candidates(
+        &self,
+        lane: &str,
+        cap: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.lane_overflow_candidates(lane, cap, limit)
+    }
+
+    pub fn configured_lane_overflow_candidates(
+        &self,
+        limit_per_lane: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.configured_lane_overflow_candidates(limit_per_lane)
+    }
+
+    pub fn plan_memory_gc(
+        &self,
+        now: DateTime<Utc>,
+        limit_per_pass: usize,
+    ) -> Result<serde_json::Value> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.plan_memory_gc(now, limit_per_pass)
+    }
+
+    /// Register the lane-overflow compaction callback consulted by
+    /// [`Kernel::run_memory_compaction`]; replaces any previous one. The
+    /// registration is shared by every clone of this handle.
+    pub fn register_memory_compactor(&self, compactor: MemoryCompactor) {
+        let mut slot = self
+            .memory_compactor
+            .lock()
+            .expect("memory compactor mutex poisoned");
+        *slot = Some(compactor);
+    }
+
+    pub fn clear_memory_compactor(&self) {
+        let mut slot = self
+            .memory_compactor
+            .lock()
+            .expect("memory compactor mutex poisoned");
+        *slot = None;
+    }
+
+    /// GC pass over the configured lane caps that summarizes instead of
+    /// deleting outright: each lane's overflow batch is offered to the
+    /// registered compactor, and batches it folds into a summary record
+    /// are removed. Without a registered compactor (or when it declines a
+    /// batch) nothing is deleted, so the pass is non-destructive by
+    /// default. Returns `{"deleted","lanes":{lane: per-batch report}}`.
+    pub fn run_memory_compaction(&self, limit_per_lane: usize) -> Result<serde_json::Value> {
+        self.ensure_writable()?;
+        let compactor = self
+            .memory_compactor
+            .lock()
+            .expect("memory compactor mutex poisoned")
+            .clone();
+        let Some(compactor) = compactor else {
+            return Ok(json!({"deleted": 0, "lanes": {}}));
+        };
+        let started = Instant::now();
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        let mut by_lane: HashMap<String, Vec<MemoryGcCandidate>> = HashMap::new();
+        for candidate in store.configured_lane_overflow_candidates(limit_per_lane)? {
+            by_lane
+                .entry(candidate.lane.clone())
+                .or_default()
+                .push(candidate);
+        }
+        let mut lanes = serde_json::Map::new();
+        let mut total_deleted = 0u64;
+        for (lane, batch) in by_lane {
+            let report =
+                store.compact_memory_candidates(&batch, &|records| compactor(&lane, records))?;
+            total_deleted += report["deleted"].as_u64().unwrap_or(0);
+            lanes.insert(lane, report);
+        }
+        Self::observe_op("run_memory_compaction", started);
+        Self::observe_op_rows("run_memory_compaction", total_deleted);
+        Ok(json!({"deleted": total_deleted, "lanes": lanes}))
+    }
+
+    pub fn set_lane_config(&self, cfg: &MemoryLaneConfig) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.set_lane_config(cfg)
+    }
+
+    pub fn get_lane_config(&self, lane: &str) -> Result<Option<MemoryLaneConfig>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.get_lane_config(lane)
+    }
+
+    pub fn list_lane_configs(&self) -> Result<Vec<MemoryLaneConfig>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_lane_configs()
+    }
+
+    pub fn remove_lane_config(&self, lane: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.remove_lane_config(lane)
+    }
+
+    pub fn register_embed_model(&self, hint: &str, dim: usize) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.register_embed_model(hint, dim)
+    }
+
+    pub fn embed_model_dim(&self, hint: &str) -> Result<Option<i64>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.embed_model_dim(hint)
+    }
+
+    pub fn list_embed_models(&self) -> Result<serde_json::Value> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_embed_models()
+    }
+
+    pub fn remove_embed_model(&self, hint: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.remove_embed_model(hint)
+    }
+
+    pub fn delete_memory_records(&self, ids: &[String]) -> Result<usize> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.delete_records(ids)
+    }
+
+    pub fn list_recent_memory(
+        &self,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_recent_memory(lane, limit)
+    }
+
+    pub fn list_memory_in_window(
+        &self,
+        lane: Option<&str>,
+        since: &str,
+        until: &str,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_memory_in_window(lane, since, until, limit)
+    }
+
+    pub fn list_unembedded_memory(
+        &self,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.list_unembedded(lane, limit)
+    }
+
+    /// Rewrite the memory tables to reclaim space after heavy GC passes.
+    /// Requires quiescence: callers should ensure no concurrent writers.
+    pub fn compact_memory(&self) -> Result<()> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.compact()
+    }
+
+    pub fn top_memory_per_lane(
+        &self,
+        lanes: &[String],
+        k: i64,
+    ) -> Result<std::collections::HashMap<String, Vec<serde_json::Value>>> {
+        let conn = self.conn()?;
+        let store = MemoryStore::new(&conn);
+        store.top_per_lane(lanes, k)
+    }
+
+    pub fn pool_wait_stats(&self) -> (u64, f64) {
+        let stats = self
+            .pool
+            .wait_stats
+            .lock()
+            .expect("pool wait stats mutex poisoned");
+        (stats.count, stats.total_ms)
+    }
+
+    // ---------- Config snapshots ----------
+    pub fn insert_config_snapshot(&self, config: &serde_json::Value) -> Result<String> {
+        self.ensure_writable()?;
+        self.insert_config_snapshot_dedup(config, false)
+    }
+
+    /// Insert a config snapshot, stamping a sha256 digest of the serialized
+    /// config. With `skip_if_latest` set, a snapshot whose digest matches the
+    /// most recent one is not written and the existing id is returned instead.
+    pub fn insert_config_snapshot_dedup(
+        &self,
+        config: &serde_json::Value,
+        skip_if_latest: bool,
+    ) -> Result<String> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let cfg = serde_json::to_string(config).unwrap_or("{}".into());
+        let sha = {
+            use sha2::Digest as _;
+            let mut h = sha2::Sha256::new();
+            h.update(cfg.as_bytes());
+            format!("{:x}", h.finalize())
+        };
+        if skip_if_latest {
+            let latest: Option<(String, Option<String>)> = conn
+                .query_row(
+                    "SELECT id, sha256 FROM config_snapshots ORDER BY created DESC, id DESC LIMIT 1",
+                    [],
+                    |r| Ok((r.get(0)?, r.get(1)?)),
+                )
+                .optional()?;
+            if let Some((id, Some(prev_sha))) = latest {
+                if prev_sha == sha {
+                    return Ok(id);
+                }
+            }
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = self.now_rfc3339();
+        conn.execute(
+            "INSERT INTO config_snapshots(id,config,sha256,created) VALUES(?,?,?,?)",
+            params![id, cfg, sha, now],
+        )?;
+        Ok(id)
+    }
+
+    /// Find the most recent snapshot id carrying the given content digest.
+    pub fn find_config_snapshot_by_digest(&self, sha: &str) -> Result<Option<String>> {
+        let conn = self.conn()?;
+        let id: Option<String> = conn
+            .query_row(
+                "SELECT id FROM config_snapshots WHERE sha256=? ORDER BY created DESC, id DESC LIMIT 1",
+                params![sha],
+                |r| r.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    pub fn get_config_snapshot(&self, id: &str) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT config FROM config_snapshots WHERE id=? LIMIT 1")?;
+        let mut rows = stmt.query(params![id])?;
+        if let Some(r) = rows.next()? {
+            let cfg_s: String = r.get(0)?;
+            let v =
+                serde_json::from_str::<serde_json::Value>(&cfg_s).unwrap_or(serde_json::json!({}));
+            Ok(Some(v))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn list_config_snapshots(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id,created,sha256,label,note,pinned,rolled_back_from FROM config_snapshots ORDER BY created DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "created": r.get::<_, String>(1)?,
+                "sha256": r.get::<_, Option<String>>(2)?,
+                "label": r.get::<_, Option<String>>(3)?,
+                "note": r.get::<_, Option<String>>(4)?,
+                "pinned": r.get::<_, i64>(5)? != 0,
+                "rolled_back_from": r.get::<_, Option<String>>(6)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Attach (or clear, with `None`) a human label and free-form note on a
+    /// snapshot, so the Patch Engine UI can show "pre-rollout" instead of a
+    /// UUID. Returns whether the snapshot exists.
+    pub fn label_config_snapshot(
+        &self,
+        id: &str,
+        label: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let n = conn.execute(
+            "UPDATE config_snapshots SET label=?, note=? WHERE id=?",
+            params![label, note, id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// JSON Patch (RFC 6902) that turns snapshot `a`'s config into snapshot
+    /// `b`'s. An empty patch array means the two snapshots are identical.
+    pub fn diff_config_snapshots(&self, a: &str, b: &str) -> Result<serde_json::Value> {
+        let from = self
+            .get_config_snapshot(a)?
+            .ok_or_else(|| anyhow!("config snapshot {a} not found"))?;
+        let to = self
+            .get_config_snapshot(b)?
+            .ok_or_else(|| anyhow!("config snapshot {b} not found"))?;
+        let patch = json_patch::diff(&from, &to);
+        Ok(serde_json::to_value(patch)?)
+    }
+
+    pub async fn label_config_snapshot_async(
+        &self,
+        id: String,
+        label: Option<String>,
+        note: Option<String>,
+    ) -> Result<bool> {
+        self.run_blocking(move |k| k.label_config_snapshot(&id, label.as_deref(), note.as_deref()))
+            .await
+    }
+
+    pub async fn diff_config_snapshots_async(
+        &self,
+        a: String,
+        b: String,
+    ) -> Result<serde_json::Value> {
+        self.run_blocking(move |k| k.diff_config_snapshots(&a, &b))
+            .await
+    }
+
+    /// Roll back to snapshot `id` by recording its config as a new snapshot
+    /// whose `rolled_back_from` points at the restored one, so history stays
+    /// append-only and the rollback itself is auditable. Returns the new
+    /// snapshot id; applying the config to the live system is the caller's
+    /// job.
+    pub fn rollback_to_snapshot(&self, id: &str) -> Result<String> {
+        self.ensure_writable()?;
+        let config = self
+            .get_config_snapshot(id)?
+            .ok_or_else(|| anyhow!("config snapshot {id} not found"))?;
+        let new_id = self.insert_config_snapshot(&config)?;
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE config_snapshots SET rolled_back_from=? WHERE id=?",
+            params![id, new_id],
+        )?;
+        Ok(new_id)
+    }
+
+    /// Pin (or unpin) a snapshot so retention pruning never removes it.
+    /// Returns whether the snapshot exists.
+    pub fn pin_config_snapshot(&self, id: &str, pinned: bool) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let n = conn.execute(
+            "UPDATE config_snapshots SET pinned=? WHERE id=?",
+            params![pinned as i64, id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Apply retention to the snapshot history: keep the newest `keep_last`
+    /// snapshots plus everything pinned, delete the rest. Returns the number
+    /// removed.
+    pub fn prune_config_snapshots(&self, keep_last: i64) -> Result<u64> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let n = conn.execute(
+            "DELETE FROM config_snapshots WHERE pinned=0 AND id NOT IN (
+                 SELECT id FROM config_snapshots ORDER BY created DESC, id DESC LIMIT ?
+             )",
+            params![keep_last.max(0)],
+        )?;
+        Ok(n as u64)
+    }
+
+    pub async fn rollback_to_snapshot_async(&self, id: String) -> Result<String> {
+        self.run_blocking(move |k| k.rollback_to_snapshot(&id))
+            .await
+    }
+
+    pub async fn pin_config_snapshot_async(&self, id: String, pinned: bool) -> Result<bool> {
+        self.run_blocking(move |k| k.pin_config_snapshot(&id, pinned))
+            .await
+    }
+
+    pub async fn prune_config_snapshots_async(&self, keep_last: i64) -> Result<u64> {
+        self.run_blocking(move |k| k.prune_config_snapshots(keep_last))
+            .await
+    }
+
+    // ---------- Orchestrator jobs ----------
+    pub fn insert_orchestrator_job(
+        &self,
+        goal: &str,
+        data: Option<&serde_json::Value>,
+    ) -> Result<String> {
+        self.insert_orchestrator_job_with_deadline(goal, data, None)
+    }
+
+    /// Like [`Self::insert_orchestrator_job`], with an optional `deadline`
+    /// (RFC3339). Jobs still unfinished at their deadline are moved to
+    /// `failed` with a timeout reason by the overdue sweep.
+    pub fn insert_orchestrator_job_with_deadline(
+        &self,
+        goal: &str,
+        data: Option<&serde_json::Value>,
+        deadline: Option<&str>,
+    ) -> Result<String> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = self.now_rfc3339();
+        let data_s = data.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        conn.execute(
+            "INSERT INTO orchestrator_jobs(id,status,goal,data,progress,deadline,created,updated) VALUES(?,?,?,?,?,?,?,?)",
+            params![id, "queued", goal, data_s, 0.0f64, deadline, now, now],
+        )?;
+        Ok(id)
+    }
+
+    /// Cancel a job that hasn't reached a terminal state, recording who/why
+    /// in `reason`. Returns false when the job is unknown or already
+    /// terminal, so double-cancels and races with completion are harmless.
+    pub fn cancel_orchestrator_job(&self, id: &str, reason: Option<&str>) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let n = conn.execute(
+            "UPDATE orchestrator_jobs SET status='cancelled', reason=?, updated=?
+             WHERE id=? AND status NOT IN ('completed','failed','cancelled')",
+            params![reason, now, id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Fail every non-terminal job whose deadline has passed, stamping a
+    /// timeout reason. The prune loop runs this periodically; callers that
+    /// need fresher timeouts can invoke it explicitly. Returns the number
+    /// of jobs failed.
+    pub fn sweep_overdue_orchestrator_jobs(&self) -> Result<u64> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        Self::sweep_overdue_jobs_conn(&conn, &self.now_rfc3339())
+    }
+
+    fn sweep_overdue_jobs_conn(conn: &Connection, now: &str) -> Result<u64> {
+        let n = conn.execute(
+            "UPDATE orchestrator_jobs SET status='failed', reason=COALESCE(reason,'timeout: deadline exceeded'), updated=?1
+             WHERE deadline IS NOT NULL AND deadline < ?1 AND status NOT IN ('completed','failed','cancelled')",
+            params![now],
+        )?;
+        Ok(n as u64)
+    }
+
+    /// Spawn a sub-job under `parent_id`. Fails when the parent is unknown,
+    /// so orphaned children can't be created by a stale id.
+    pub fn insert_orchestrator_child_job(
+        &self,
+        parent_id: &str,
+        goal: &str,
+        data: Option<&serde_json::Value>,
+    ) -> Result<String> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let exists: Option<i64> = conn
+            .query_row(
+                "SELECT 1 FROM orchestrator_jobs WHERE id=?",
+                params![parent_id],
+                |r| r.get(0),
+            )
+            .optional()?;
+        if exists.is_none() {
+            return Err(anyhow!("orchestrator job {parent_id} not found"));
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = self.now_rfc3339();
+        let data_s = data.map(|v| serde_json::to_string(v).unwrap_or("{}".into()));
+        conn.execute(
+            "INSERT INTO orchestrator_jobs(id,status,goal,data,progress,parent_id,created,updated) VALUES(?,?,?,?,?,?,?,?)",
+            params![id, "queued", goal, data_s, 0.0f64, parent_id, now, now],
+        )?;
+        Ok(id)
+    }
+
+    /// Fold the direct children's progress into the parent as a plain
+    /// average and return it. Childless jobs are left untouched (`None`);
+    /// call after child updates or on a schedule.
+    pub fn rollup_orchestrator_job_progress(&self, parent_id: &str) -> Result<Option<f64>> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let avg: Option<f64> = conn.query_row(
+            "SELECT AVG(COALESCE(progress,0)) FROM orchestrator_jobs WHERE parent_id=?",
+            params![parent_id],
+            |r| r.get(0),
+        )?;
+        let Some(avg) = avg else {
+            return Ok(None);
+        };
+        let now = self.now_rfc3339();
+        conn.execute(
+            "UPDATE orchestrator_jobs SET progress=?, updated=? WHERE id=?",
+            params![avg, now, parent_id],
+        )?;
+        Ok(Some(avg))
+    }
+
+    /// The job and all its descendants as one nested JSON tree (children
+    /// ordered by creation). `None` when the root id is unknown.
+    pub fn get_orchestrator_job_tree(&self, id: &str) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE tree(id) AS (
+                 SELECT id FROM orchestrator_jobs WHERE id=?
+                 UNION ALL
+                 SELECT j.id FROM orchestrator_jobs j JOIN tree t ON j.parent_id = t.id
+             )
+             SELECT j.id,j.status,j.goal,j.progress,j.deadline,j.reason,j.parent_id,j.created,j.updated
+               FROM orchestrator_jobs j JOIN tree t ON j.id = t.id
+              ORDER BY j.created ASC, j.id ASC",
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        let mut root: Option<serde_json::Value> = None;
+        let mut by_parent: HashMap<String, Vec<serde_json::Value>> = HashMap::new();
+        while let Some(r) = rows.next()? {
+            let row_id: String = r.get(0)?;
+            let parent: Option<String> = r.get(6)?;
+            let node = serde_json::json!({
+                "id": row_id,
+                "status": r.get::<_, String>(1)?,
+                "goal": r.get::<_, Option<String>>(2)?,
+                "progress": r.get::<_, Option<f64>>(3)?,
+                "deadline": r.get::<_, Option<String>>(4)?,
+                "reason": r.get::<_, Option<String>>(5)?,
+                "created": r.get::<_, String>(7)?,
+                "updated": r.get::<_, String>(8)?,
+                "children": [],
+            });
+            if node["id"] == serde_json::json!(id) {
+                root = Some(node);
+            } else if let Some(parent) = parent {
+                by_parent.entry(parent).or_default().push(node);
+            }
+        }
+        fn attach(
+            mut node: serde_json::Value,
+            by_parent: &mut HashMap<String, Vec<serde_json::Value>>,
+        ) -> serde_json::Value {
+            let id = node["id"].as_str().unwrap_or_default().to_string();
+            if let Some(children) = by_parent.remove(&id) {
+                node["children"] = serde_json::Value::Array(
+                    children
+                        .into_iter()
+                        .map(|child| attach(child, by_parent))
+                        .collect(),
+                );
+            }
+            node
+        }
+        Ok(root.map(|node| attach(node, &mut by_parent)))
+    }
+
+    /// Claim the oldest queued job for `worker_id` under a lease of
+    /// `lease_secs`. Expired claims are requeued first, so a crashed
+    /// worker's jobs are re-offered automatically; long-running workers
+    /// keep their claim alive with [`Self::renew_orchestrator_job_lease`].
+    /// Returns the claimed job (`id`, `goal`, `data`, `lease_until`) or
+    /// `None` when the queue is empty.
+    pub fn claim_orchestrator_job(
+        &self,
+        worker_id: &str,
+        lease_secs: i64,
+    ) -> Result<Option<serde_json::Value>> {
+        self.ensure_writable()?;
+        if lease_secs <= 0 {
+            return Err(anyhow!("lease_secs must be positive"));
+        }
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        Self::requeue_expired_jobs_conn(&conn, &now)?;
+        let lease_until = (self.clock.now() + chrono::Duration::seconds(lease_secs))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let mut stmt = conn.prepare_cached(
+            "UPDATE orchestrator_jobs SET status='running', worker_id=?2, lease_until=?3, updated=?1
+             WHERE id = (
+                 SELECT id FROM orchestrator_jobs WHERE status='queued' ORDER BY created ASC, id ASC LIMIT 1
+             ) RETURNING id, goal, data",
+        )?;
+        let mut rows = stmt.query(params![now, worker_id, lease_until])?;
+        if let Some(r) = rows.next()? {
+            let data_s: Option<String> = r.get(2)?;
+            return Ok(Some(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "goal": r.get::<_, Option<String>>(1)?,
+                "data": data_s.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()),
+                "lease_until": lease_until,
+            })));
+        }
+        Ok(None)
+    }
+
+    /// Heartbeat: extend the worker's claim by another `lease_secs`.
+    /// Returns false when the claim is gone (expired and reclaimed, or the
+    /// job finished), which tells the worker to abandon its work.
+    pub fn renew_orchestrator_job_lease(
+        &self,
+        id: &str,
+        worker_id: &str,
+        lease_secs: i64,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        if lease_secs <= 0 {
+            return Err(anyhow!("lease_secs must be positive"));
+        }
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let lease_until = (self.clock.now() + chrono::Duration::seconds(lease_secs))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let n = conn.execute(
+            "UPDATE orchestrator_jobs SET lease_until=?, updated=?
+             WHERE id=? AND worker_id=? AND status='running'",
+            params![lease_until, now, id, worker_id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Requeue running jobs whose claim lease has lapsed, clearing the
+    /// worker attribution. Claims run this implicitly; exposed for tests
+    /// and schedulers. Returns the number requeued.
+    pub fn requeue_expired_orchestrator_jobs(&self) -> Result<u64> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        Self::requeue_expired_jobs_conn(&conn, &self.now_rfc3339())
+    }
+
+    fn requeue_expired_jobs_conn(conn: &Connection, now: &str) -> Result<u64> {
+        let n = conn.execute(
+            "UPDATE orchestrator_jobs SET status='queued', worker_id=NULL, lease_until=NULL, updated=?1
+             WHERE status='running' AND lease_until IS NOT NULL AND lease_until < ?1",
+            params![now],
+        )?;
+        Ok(n as u64)
+    }
+
+    pub async fn claim_orchestrator_job_async(
+        &self,
+        worker_id: String,
+        lease_secs: i64,
+    ) -> Result<Option<serde_json::Value>> {
+        self.run_blocking(move |k| k.claim_orchestrator_job(&worker_id, lease_secs))
+            .await
+    }
+
+    pub async fn renew_orchestrator_job_lease_async(
+        &self,
+        id: String,
+        worker_id: String,
+        lease_secs: i64,
+    ) -> Result<bool> {
+        self.run_blocking(move |k| k.renew_orchestrator_job_lease(&id, &worker_id, lease_secs))
+            .await
+    }
+
+    pub async fn requeue_expired_orchestrator_jobs_async(&self) -> Result<u64> {
+        self.run_blocking(move |k| k.requeue_expired_orchestrator_jobs())
+            .await
+    }
+
+    pub async fn insert_orchestrator_child_job_async(
+        &self,
+        parent_id: String,
+        goal: String,
+        data: Option<serde_json::Value>,
+    ) -> Result<String> {
+        self.run_blocking(move |k| {
+            k.insert_orchestrator_child_job(&parent_id, &goal, data.as_ref())
+        })
+        .await
+    }
+
+    pub async fn rollup_orchestrator_job_progress_async(
+        &self,
+        parent_id: String,
+    ) -> Result<Option<f64>> {
+        self.run_blocking(move |k| k.rollup_orchestrator_job_progress(&parent_id))
+            .await
+    }
+
+    pub async fn get_orchestrator_job_tree_async(
+        &self,
+        id: String,
+    ) -> Result<Option<serde_json::Value>> {
+        self.run_blocking(move |k| k.get_orchestrator_job_tree(&id))
+            .await
+    }
+
+    pub fn update_orchestrator_job(
+        &self,
+        id: &str,
+        status: Option<&str>,
+        progress: Option<f64>,
+        data_patch: Option<&serde_json::Value>,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let mut set_parts: Vec<&str> = Vec::new();
+        if status.is_some() {
+            set_parts.push("status=?");
+        }
+        if progress.is_some() {
+            set_parts.push("progress=?");
+        }
+        let mut merged_data: Option<String> = None;
+        let mut history_data: Option<String> = None;
+        if let Some(patch) = data_patch {
+            let existing: Option<(Option<String>, Option<String>)> = conn
+                .query_row(
+                    "SELECT data, data_history FROM orchestrator_jobs WHERE id=? LIMIT 1",
+                    [id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let (existing_data, existing_history) = existing.unwrap_or((None, None));
+            if patch.is_object() {
+                let mut base = existing_data
+                    .as_ref()
+                    .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+                    .unwrap_or_else(|| serde_json::json!({}));
+                if !base.is_object() {
+                    base = serde_json::json!({});
+                }
+                if let serde_json::Value::Object(ref mut base_map) = base {
+                    if let serde_json::Value::Object(ref patch_map) = patch {
+                        for (key, value) in patch_map.iter() {
+                            base_map.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+                merged_data = Some(serde_json::to_string(&base).unwrap_or_else(|_| "{}".into()));
+            } else if !patch.is_null() {
+                merged_data =
+                    Some(serde_json::to_string(patch).unwrap_or_else(|_| "{}".to_string()));
+            }
+            if merged_data.is_some() {
+                set_parts.push("data=?");
+                history_data = Self::push_orchestrator_data_history(existing_history, patch, &now);
+                if history_data.is_some() {
+                    set_parts.push("data_history=?");
+                }
+            }
+        }
+        set_parts.push("updated=?");
+        let sql = format!(
+            "UPDATE orchestrator_jobs SET {} WHERE id=?",
+            set_parts.join(",")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut params_vec: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(s) = status {
+            params_vec.push(rusqlite::types::Value::from(s.to_string()));
+        }
+        if let Some(p) = progress {
+            params_vec.push(rusqlite::types::Value::from(p));
+        }
+        if let Some(data) = merged_data {
+            params_vec.push(rusqlite::types::Value::from(data));
+        }
+        if let Some(history) = history_data {
+            params_vec.push(rusqlite::types::Value::from(history));
+        }
+        params_vec.push(rusqlite::types::Value::from(now.clone()));
+        params_vec.push(rusqlite::types::Value::from(id.to_string()));
+        let n = stmt.execute(rusqlite::params_from_iter(params_vec))?;
+        Ok(n > 0)
+    }
+
+    /// Append a data patch to the job's bounded history ring.
+    ///
+    /// Keeps the last `ARW_ORCH_DATA_HISTORY` patches (default 16; 0 disables)
+    /// and skips patches larger than `ARW_ORCH_DATA_PATCH_MAX_BYTES` (default
+    /// 64 KiB) so a caller posting huge `data` blobs can't balloon the row.
+    fn push_orchestrator_data_history(
+        existing: Option<String>,
+        patch: &serde_json::Value,
+        now: &str,
+    ) -> Option<String> {
+        let keep = std::env::var("ARW_ORCH_DATA_HISTORY")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(16);
+        if keep == 0 {
+            return None;
+        }
+        let max_bytes = std::env::var("ARW_ORCH_DATA_PATCH_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(64 * 1024);
+        let patch_s = serde_json::to_string(patch).ok()?;
+        if patch_s.len() > max_bytes {
+            return None;
+        }
+        let mut ring = existing
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default();
+        ring.push(serde_json::json!({"time": now, "patch": patch}));
+        if ring.len() > keep {
+            let drop = ring.len() - keep;
+            ring.drain(0..drop);
+        }
+        serde_json::to_string(&ring).ok()
+    }
+
+    pub fn orchestrator_job_data_history(&self, id: &str) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let raw: Option<Option<String>> = conn
+            .query_row(
+                "SELECT data_history FROM orchestrator_jobs WHERE id=? LIMIT 1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(raw
+            .flatten()
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default())
+    }
+
+    pub fn list_orchestrator_jobs(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id,status,goal,data,progress,created,updated,deadline,reason,parent_id FROM orchestrator_jobs ORDER BY updated DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query([limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let status_raw: String = r.get::<_, String>(1)?;
+            let (status_slug, status_label) = Self::normalize_orchestrator_status(&status_raw);
+            let mut payload = serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "status": status_raw,
+                "status_slug": status_slug,
+                "status_label": status_label,
+                "goal": r.get::<_, Option<String>>(2)?,
+                "progress": r.get::<_, Option<f64>>(4)?,
+                "created": r.get::<_, String>(5)?,
+                "updated": r.get::<_, String>(6)?,
+                "deadline": r.get::<_, Option<String>>(7)?,
+                "reason": r.get::<_, Option<String>>(8)?,
+                "parent_id": r.get::<_, Option<String>>(9)?,
+            });
+            let data_raw: Option<String> = r.get(3)?;
+            if let Some(data_raw) = data_raw {
+                if let Ok(val) = serde_json::from_str::<serde_json::Value>(&data_raw) {
+                    let persona = Self::extract_persona_id(&val);
+                    if !val.is_null() {
+                        if let serde_json::Value::Object(ref mut map) = payload {
+                            map.insert("data".into(), val.clone());
+                            if let Some(persona) = persona {
+                                map.insert("persona_id".into(), serde_json::Value::String(persona));
+                            }
+                        }
+                    }
+                }
+            }
+            out.push(payload);
+        }
+        Ok(out)
+    }
+
+    fn normalize_orchestrator_status(value: &str) -> (&'static str, &'static str) {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "queued" | "pending" | "waiting" => ("queued", "Queued"),
+            "running" | "in_progress" | "in-progress" | "started" | "active" => {
+                ("running", "Running")
+            }
+            "completed" | "complete" | "finished" | "done" | "success" | "succeeded" => {
+                ("completed", "Completed")
+            }
+            "failed" | "error" | "errored" | "fail" | "failure" => ("failed", "Failed"),
+            "cancelled" | "canceled" | "aborted" | "stopped" => ("cancelled", "Cancelled"),
+            "unknown" | "" => ("unknown", "Unknown"),
+            other if other.starts_with("run") => ("running", "Running"),
+            other if other.starts_with("queue") => ("queued", "Queued"),
+            other if other.starts_with("wait") => ("queued", "Queued"),
+            other if other.starts_with("fail") => ("failed", "Failed"),
+            other if other.starts_with("cancel") => ("cancelled", "Cancelled"),
+            other if other.starts_with("complete") => ("completed", "Completed"),
+            _ => ("unknown", "Unknown"),
+        }
+    }
+
+    fn extract_persona_id(value: &serde_json::Value) -> Option<String> {
+        if let Some(obj) = value.as_object() {
+            if let Some(pid) = obj
+                .get("persona_id")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+            {
+                return Some(pid.to_string());
+            }
+            for key in ["training", "submitted"] {
+                if let Some(next) = obj.get(key) {
+                    if let Some(found) = Self::extract_persona_id(next) {
+                        return Some(found);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // ---------- Personas ----------
+
+    pub fn upsert_persona_entry(&self, upsert: PersonaEntryUpsert) -> Result<PersonaEntry> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let existing_version: Option<i64> = conn
+            .query_row(
+                "SELECT version FROM persona_entries WHERE id=? LIMIT 1",
+                [&upsert.id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let version = existing_version.unwrap_or(0).saturating_add(1);
+
+        let traits_s = serde_json::to_string(&upsert.traits).unwrap_or_else(|_| "{}".into());
+        let preferences_s =
+            serde_json::to_string(&upsert.preferences).unwrap_or_else(|_| "{}".into());
+        let worldview_s = serde_json::to_string(&upsert.worldview).unwrap_or_else(|_| "{}".into());
+        let vibe_profile_s =
+            serde_json::to_string(&upsert.vibe_profile).unwrap_or_else(|_| "{}".into());
+        let calibration_s =
+            serde_json::to_string(&upsert.calibration).unwrap_or_else(|_| "{}".into());
+
+        conn.execute(
+            "INSERT INTO persona_entries \
+                (id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET \
+                owner_kind=excluded.owner_kind, \
+                owner_ref=excluded.owner_ref, \
+                name=excluded.name, \
+                archetype=excluded.archetype, \
+                traits=excluded.traits, \
+                preferences=excluded.preferences, \
+                worldview=excluded.worldview, \
+                vibe_profile=excluded.vibe_profile, \
+                calibration=excluded.calibration, \
+                updated=excluded.updated, \
+                version=excluded.version",
+            params![
+                upsert.id,
+                upsert.owner_kind,
+                upsert.owner_ref,
+                upsert.name,
+                upsert.archetype,
+                traits_s,
+                preferences_s,
+                worldview_s,
+                vibe_profile_s,
+                calibration_s,
+                now,
+                version
+            ],
+        )?;
+
+        self.get_persona_entry(&upsert.id)?
+            .ok_or_else(|| anyhow!("persona entry not found after upsert"))
+    }
+
+    pub fn get_persona_entry(&self, id: &str) -> Result<Option<PersonaEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version \
+             FROM persona_entries WHERE id=? LIMIT 1",
+        )?;
+        let mut rows = stmt.query([id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::map_persona_entry_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn list_persona_entries(
+        &self,
+        owner_filter: Option<(&str, &str)>,
+        limit: i64,
+    ) -> Result<Vec<PersonaEntry>> {
+        let conn = self.conn()?;
+        let limit = limit.clamp(1, 500);
+        let mut entries = Vec::new();
+        match owner_filter {
+            Some((owner_kind, owner_ref)) => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version \
+                     FROM persona_entries \
+                     WHERE owner_kind=? AND owner_ref=? \
+                     ORDER BY updated DESC LIMIT ?",
+                )?;
+                let mut rows = stmt.query(params![owner_kind, owner_ref, limit])?;
+                while let Some(row) = rows.next()? {
+                    entries.push(Self::map_persona_entry_row(row)?);
+                }
+            }
+            None => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version \
+                     FROM persona_entries \
+                     ORDER BY updated DESC LIMIT ?",
+                )?;
+                let mut rows = stmt.query([limit])?;
+                while let Some(row) = rows.next()? {
+                    entries.push(Self::map_persona_entry_row(row)?);
+                }
+            }
+        };
+        Ok(entries)
+    }
+
+    /// Check a proposal diff before it is stored: either a well-formed
+    /// JSON-patch array (op/path syntax validated) or a mergeable object.
+    /// Rejecting here keeps malformed diffs from failing much later at
+    /// apply time.
+    pub fn validate_persona_diff(diff: &JsonValue) -> Result<()> {
+        if diff.is_array() {
+            serde_json::from_value::<json_patch::Patch>(diff.clone())
+                .map_err(|e| anyhow!("invalid persona diff: malformed JSON-patch: {e}"))?;
+            Ok(())
+        } else if diff.is_object() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "invalid persona diff: expected a JSON-patch array or a mergeable object"
+            ))
+        }
+    }
+
+    pub fn insert_persona_proposal(&self, create: PersonaProposalCreate) -> Result<String> {
+        self.ensure_writable()?;
+        Self::validate_persona_diff(&create.diff)?;
+        let conn = self.conn()?;
+        let proposal_id = Uuid::new_v4().to_string();
+        let now = self.now_rfc3339();
+        let diff_s = serde_json::to_string(&create.diff).unwrap_or_else(|_| "[]".into());
+        let telemetry_scope_s =
+            serde_json::to_string(&create.telemetry_scope).unwrap_or_else(|_| "{}".into());
+        let leases_required_s =
+            serde_json::to_string(&create.leases_required).unwrap_or_else(|_| "[]".into());
+
+        conn.execute(
+            "INSERT INTO persona_proposals \
+                (proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                proposal_id,
+                create.persona_id,
+                create.submitted_by,
+                diff_s,
+                create.rationale,
+                telemetry_scope_s,
+                leases_required_s,
+                "pending",
+                &now,
+                &now
+            ],
+        )?;
+        Ok(proposal_id)
+    }
+
+    pub fn get_persona_proposal(&self, proposal_id: &str) -> Result<Option<PersonaProposal>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated, required_approvals \
+             FROM persona_proposals WHERE proposal_id=? LIMIT 1",
+        )?;
+        let mut rows = stmt.query([proposal_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::map_persona_proposal_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn update_persona_proposal_status(
+        &self,
+        proposal_id: &str,
+        update: PersonaProposalStatusUpdate,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let affected = conn.execute(
+            "UPDATE persona_proposals SET status=?, updated=? WHERE proposal_id=?",
+            params![update.status, now, proposal_id],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// Approvals needed before the proposal flips to `approved`. Only
+    /// adjustable while the vote is still undecided.
+    pub fn set_persona_proposal_required_approvals(
+        &self,
+        proposal_id: &str,
+        required: i64,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        if required < 1 {
+            anyhow::bail!("required approvals must be at least 1");
+        }
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let affected = conn.execute(
+            "UPDATE persona_proposals SET required_approvals=?, updated=? \
+             WHERE proposal_id=? AND status='pending'",
+            params![required, now, proposal_id],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// Record (or change) one reviewer's decision and recompute the proposal
+    /// status from the full vote: any reject wins, otherwise it is approved
+    /// once the approval threshold is met, otherwise it stays pending.
+    /// Statuses outside the voting lifecycle (e.g. `applied`) are left alone.
+    pub fn record_persona_proposal_vote(
+        &self,
+        proposal_id: &str,
+        reviewer: &str,
+        decision: &str,
+        note: Option<&str>,
+    ) -> Result<PersonaProposal> {
+        self.ensure_writable()?;
+        if !matches!(decision, "approve" | "reject" | "abstain") {
+            anyhow::bail!("unknown persona proposal decision: {decision}");
+        }
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        let required: i64 = tx
+            .query_row(
+                "SELECT required_approvals FROM persona_proposals WHERE proposal_id=?",
+                params![proposal_id],
+                |r| r.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("persona proposal {proposal_id} not found"))?;
+        tx.execute(
+            "INSERT INTO persona_proposal_votes(proposal_id, reviewer, decision, note, updated) \
+             VALUES (?, ?, ?, ?, ?) \
+             ON CONFLICT(proposal_id, reviewer) DO UPDATE SET decision=excluded.decision, note=excluded.note, updated=excluded.updated",
+            params![proposal_id, reviewer, decision, note, now],
+        )?;
+        let (approvals, rejects): (i64, i64) = tx.query_row(
+            "SELECT COALESCE(SUM(decision='approve'),0), COALESCE(SUM(decision='reject'),0) \
+             FROM persona_proposal_votes WHERE proposal_id=?",
+            params![proposal_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        let status = if rejects > 0 {
+            "rejected"
+        } else if approvals >= required {
+            "approved"
+        } else {
+            "pending"
+        };
+        tx.execute(
+            "UPDATE persona_proposals SET status=?, updated=? \
+             WHERE proposal_id=? AND status IN ('pending','approved','rejected')",
+            params![status, now, proposal_id],
+        )?;
+        tx.commit()?;
+        self.get_persona_proposal(proposal_id)?
+            .ok_or_else(|| anyhow!("persona proposal not found after vote"))
+    }
+
+    pub fn list_persona_proposal_votes(
+        &self,
+        proposal_id: &str,
+    ) -> Result<Vec<PersonaProposalVote>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT proposal_id, reviewer, decision, note, updated \
+             FROM persona_proposal_votes WHERE proposal_id=? ORDER BY updated ASC, reviewer ASC",
+        )?;
+        let mut rows = stmt.query([proposal_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_persona_proposal_vote_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn list_persona_proposals(
+        &self,
+        persona_id: Option<&str>,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<PersonaProposal>> {
+        let conn = self.conn()?;
+        let limit = limit.clamp(1, 500);
+        let mut proposals = Vec::new();
+        let mut query = String::from(
+            "SELECT proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated, required_approvals \
+             FROM persona_proposals",
+        );
+        let mut conditions: Vec<&str> = Vec::new();
+        if persona_id.is_some() {
+            conditions.push("persona_id=?");
+        }
+        if status.is_some() {
+            conditions.push("status=?");
+        }
+        if !conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&conditions.join(" AND "));
+        }
+        query.push_str(" ORDER BY updated DESC LIMIT ?");
+
+        let mut params_vec: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(pid) = persona_id {
+            params_vec.push(Value::from(pid.to_string()));
+        }
+        if let Some(status_val) = status {
+            params_vec.push(Value::from(status_val.to_string()));
+        }
+        params_vec.push(limit.into());
+
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(params_from_iter(params_vec))?;
+        while let Some(row) = rows.next()? {
+            proposals.push(Self::map_persona_proposal_row(row)?);
+        }
+        Ok(proposals)
+    }
+
+    pub fn append_persona_history(&self, append: PersonaHistoryAppend) -> Result<i64> {
+        self.ensure_writable()?;
+        let conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let diff_s = serde_json::to_string(&append.diff).unwrap_or_else(|_| "[]".into());
+        conn.execute(
+            "INSERT INTO persona_history (persona_id, proposal_id, diff, applied_by, applied_at) VALUES (?, ?, ?, ?, ?)",
+            params![
+                append.persona_id,
+                append.proposal_id,
+                diff_s,
+                append.applied_by,
+                now
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn list_persona_history(
+        &self,
+        persona_id: &str,
+        limit: i64,
+    ) -> Result<Vec<PersonaHistoryEntry>> {
+        let conn = self.conn()?;
+        let limit = limit.clamp(1, 500);
+        let mut stmt = conn.prepare(
+            "SELECT id, persona_id, proposal_id, diff, applied_by, applied_at \
+             FROM persona_history WHERE persona_id=? ORDER BY applied_at DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![persona_id, limit])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_persona_history_row(row)?);
+        }
+        Ok(out)
+    }
+
+    fn fold_persona_diff(base: &mut JsonValue, diff: &JsonValue) -> Result<()> {
+        if diff.is_array() {
+            let patch: json_patch::Patch = serde_json::from_value(diff.clone())?;
+            json_patch::patch(base, &patch)?;
+        } else if diff.is_object() {
+            merge_json(base, diff);
+        } else {
+            return Err(anyhow!("persona diff must be a JSON object or array"));
+        }
+        Ok(())
+    }
+
+    /// Squash every history row older than the last `keep_last` into a single
+    /// checkpoint entry that carries their folded net effect, so the table
+    /// stops growing without losing the ability to replay. Returns how many
+    /// rows were squashed (0 when there is nothing worth compacting).
+    pub fn compact_persona_history(&self, persona_id: &str, keep_last: i64) -> Result<i64> {
+        self.ensure_writable()?;
+        if keep_last < 0 {
+            anyhow::bail!("keep_last must be non-negative");
+        }
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        let rows: Vec<PersonaHistoryEntry> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, persona_id, proposal_id, diff, applied_by, applied_at \
+                 FROM persona_history WHERE persona_id=? ORDER BY applied_at ASC, id ASC",
+            )?;
+            let mut raw = stmt.query([persona_id])?;
+            let mut out = Vec::new();
+            while let Some(row) = raw.next()? {
+                out.push(Self::map_persona_history_row(row)?);
+            }
+            out
+        };
+        let squash = rows.len().saturating_sub(keep_last as usize);
+        if squash <= 1 {
+            return Ok(0);
+        }
+        let mut base = json!({});
+        for entry in &rows[..squash] {
+            if let Some(state) = Self::checkpoint_state(entry) {
+                base = state.clone();
+            } else {
+                Self::fold_persona_diff(&mut base, &entry.diff)?;
+            }
+        }
+        // The checkpoint reuses the oldest squashed row's id and the newest
+        // squashed row's timestamp, so (applied_at, id) ordering is stable.
+        let checkpoint_id = rows[0].id;
+        let boundary_at = rows[squash - 1].applied_at.clone();
+        for entry in &rows[..squash] {
+            tx.execute("DELETE FROM persona_history WHERE id=?", params![entry.id])?;
+        }
+        tx.execute(
+            "INSERT INTO persona_history (id, persona_id, proposal_id, diff, applied_by, applied_at) VALUES (?, ?, NULL, ?, 'checkpoint', ?)",
+            params![
+                checkpoint_id,
+                persona_id,
+                serde_json::to_string(&json!({"checkpoint": base})).unwrap_or_else(|_| "{}".into()),
+                boundary_at
+            ],
+        )?;
+        tx.commit()?;
+        Ok(squash as i64)
+    }
+
+    fn checkpoint_state(entry: &PersonaHistoryEntry) -> Option<&JsonValue> {
+        if entry.applied_by.as_deref() == Some("checkpoint") {
+            entry.diff.get("checkpoint")
+        } else {
+            None
+        }
+    }
+
+    /// Replay the history (checkpoints first, then later diffs in order) up
+    /// to and including `up_to_id`, or the whole history when `None`. The
+    /// result is the folded net effect of every applied diff.
+    pub fn reconstruct_persona_history(
+        &self,
+        persona_id: &str,
+        up_to_id: Option<i64>,
+    ) -> Result<JsonValue> {
+        let rows = self.list_persona_history_ascending(persona_id)?;
+        let mut base = json!({});
+        let mut seen = up_to_id.is_none();
+        for entry in &rows {
+            if let Some(state) = Self::checkpoint_state(entry) {
+                base = state.clone();
+            } else {
+                Self::fold_persona_diff(&mut base, &entry.diff)?;
+            }
+            if up_to_id == Some(entry.id) {
+                seen = true;
+                break;
+            }
+        }
+        if !seen {
+            anyhow::bail!("persona history entry {} not found", up_to_id.unwrap_or(0));
+        }
+        Ok(base)
+    }
+
+    fn list_persona_history_ascending(&self, persona_id: &str) -> Result<Vec<PersonaHistoryEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, persona_id, proposal_id, diff, applied_by, applied_at \
+             FROM persona_history WHERE persona_id=? ORDER BY applied_at ASC, id ASC",
+        )?;
+        let mut rows = stmt.query([persona_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_persona_history_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn insert_persona_vibe_sample(
+        &self,
+        create: PersonaVibeSampleCreate,
+        retain: i64,
+    ) -> Result<PersonaVibeSample> {
+        self.ensure_writable()?;
+        let PersonaVibeSampleCreate {
+            persona_id,
+            kind,
+            signal,
+            strength,
+            note,
+            metadata,
+            recorded_at,
+        } = create;
+        let retain = retain.clamp(1, 500);
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO persona_vibe_samples (persona_id, kind, signal, strength, note, metadata, recorded_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                persona_id,
+                kind,
+                signal,
+                strength,
+                note,
+                serialize_optional_json(&metadata),
+                recorded_at
+            ],
+        )?;
+        let last_id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM persona_vibe_samples WHERE persona_id=? AND id NOT IN (
+                SELECT id FROM persona_vibe_samples WHERE persona_id=? ORDER BY recorded_at DESC, id DESC LIMIT ?
+            )",
+            params![persona_id, persona_id, retain],
+        )?;
+        let mut stmt = conn.prepare(
+            "SELECT id, persona_id, kind, signal, strength, note, metadata, recorded_at FROM persona_vibe_samples WHERE id=? LIMIT 1",
+        )?;
+        let mut rows = stmt.query([last_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Self::map_persona_vibe_sample_row(row)?)
+        } else {
+            Err(anyhow!("persona vibe sample not found after insert"))
+        }
+    }
+
+    pub fn list_persona_vibe_samples(
+        &self,
+        persona_id: &str,
+        limit: i64,
+    ) -> Result<Vec<PersonaVibeSample>> {
+        let conn = self.conn()?;
+        let limit = limit.clamp(1, 500);
+        let mut stmt = conn.prepare(
+            "SELECT id, persona_id, kind, signal, strength, note, metadata, recorded_at \
+             FROM persona_vibe_samples WHERE persona_id=? \
+             ORDER BY recorded_at DESC, id DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![persona_id, limit])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Self::map_persona_vibe_sample_row(row)?);
+        }
+        Ok(out)
+    }
+
+    /// Self-contained JSON bundle for one persona: the entry plus its
+    /// proposals and applied history, suitable for backup or moving between
+    /// workspaces independently of the database file.
+    pub fn export_persona(&self, id: &str) -> Result<JsonValue> {
+        let entry = self
+            .get_persona_entry(id)?
+            .ok_or_else(|| anyhow!("persona entry {id} not found"))?;
+        let conn = self.conn()?;
+        let mut proposals = Vec::new();
+        {
+            let mut stmt = conn.prepare(
+                "SELECT proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated, required_approvals \
+                 FROM persona_proposals WHERE persona_id=? ORDER BY created ASC, proposal_id ASC",
+            )?;
+            let mut rows = stmt.query([id])?;
+            while let Some(row) = rows.next()? {
+                proposals.push(Self::map_persona_proposal_row(row)?);
+            }
+        }
+        let mut history = Vec::new();
+        {
+            let mut stmt = conn.prepare(
+                "SELECT id, persona_id, proposal_id, diff, applied_by, applied_at \
+                 FROM persona_history WHERE persona_id=? ORDER BY applied_at ASC, id ASC",
+            )?;
+            let mut rows = stmt.query([id])?;
+            while let Some(row) = rows.next()? {
+                history.push(Self::map_persona_history_row(row)?);
+            }
+        }
+        Ok(json!({
+            "format": "arw.persona.bundle",
+            "version": 1,
+            "exported": self.now_rfc3339(),
+            "entry": entry,
+            "proposals": proposals,
+            "history": history,
+        }))
+    }
+
+    /// Restore a bundle produced by `export_persona`. `mode` is `"replace"`
+    /// (keep the bundled IDs, overwriting any existing rows for the persona)
+    /// or `"new"` (mint fresh persona/proposal IDs so the import cannot
+    /// collide with what is already here). Returns the persona id the bundle
+    /// landed under.
+    pub fn import_persona(&self, bundle: &JsonValue, mode: &str) -> Result<String> {
+        self.ensure_writable()?;
+        if bundle.get("format").and_then(|v| v.as_str()) != Some("arw.persona.bundle") {
+            anyhow::bail!("not a persona bundle");
+        }
+        let entry: PersonaEntry = serde_json::from_value(
+            bundle
+                .get("entry")
+                .cloned()
+                .ok_or_else(|| anyhow!("persona bundle missing entry"))?,
+        )
+        .map_err(|e| anyhow!("persona bundle entry malformed: {e}"))?;
+        let proposals: Vec<PersonaProposal> = match bundle.get("proposals") {
+            Some(v) => serde_json::from_value(v.clone())
+                .map_err(|e| anyhow!("persona bundle proposals malformed: {e}"))?,
+            None => Vec::new(),
+        };
+        let history: Vec<PersonaHistoryEntry> = match bundle.get("history") {
+            Some(v) => serde_json::from_value(v.clone())
+                .map_err(|e| anyhow!("persona bundle history malformed: {e}"))?,
+            None => Vec::new(),
+        };
+        let persona_id = match mode {
+            "replace" => entry.id.clone(),
+            "new" => uuid::Uuid::new_v4().to_string(),
+            other => anyhow::bail!("unknown persona import mode: {other}"),
+        };
+        // Proposal IDs are remapped alongside the persona id so history rows
+        // keep pointing at the right proposal.
+        let mut proposal_ids: HashMap<String, String> = HashMap::new();
+        for p in &proposals {
+            let mapped = if mode == "new" {
+                uuid::Uuid::new_v4().to_string()
+            } else {
+                p.proposal_id.clone()
+            };
+            proposal_ids.insert(p.proposal_id.clone(), mapped);
+        }
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        if mode == "replace" {
+            tx.execute(
+                "DELETE FROM persona_proposals WHERE persona_id=?",
+                params![persona_id],
+            )?;
+            tx.execute(
+                "DELETE FROM persona_history WHERE persona_id=?",
+                params![persona_id],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO persona_entries \
+             (id, owner_kind, owner_ref, name, archetype, traits, preferences, worldview, vibe_profile, calibration, updated, version) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                persona_id,
+                entry.owner_kind,
+                entry.owner_ref,
+                entry.name,
+                entry.archetype,
+                serialize_optional_json(&entry.traits),
+                serialize_optional_json(&entry.preferences),
+                serialize_optional_json(&entry.worldview),
+                serialize_optional_json(&entry.vibe_profile),
+                serialize_optional_json(&entry.calibration),
+                entry.updated,
+                entry.version,
+            ],
+        )?;
+        for p in &proposals {
+            tx.execute(
+                "INSERT OR REPLACE INTO persona_proposals \
+                 (proposal_id, persona_id, submitted_by, diff, rationale, telemetry_scope, leases_required, status, created, updated, required_approvals) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    proposal_ids[&p.proposal_id],
+                    persona_id,
+                    p.submitted_by,
+                    serde_json::to_string(&p.diff).unwrap_or_else(|_| "[]".into()),
+                    p.rationale,
+                    serialize_optional_json(&p.telemetry_scope),
+                    serialize_optional_json(&p.leases_required),
+                    p.status,
+                    p.created,
+                    p.updated,
+                    p.required_approvals,
+                ],
+            )?;
+        }
+        for h in &history {
+            let mapped_proposal = h.proposal_id.as_ref().map(|pid| {
+                proposal_ids
+                    .get(pid)
+                    .cloned()
+                    .unwrap_or_else(|| pid.clone())
+            });
+            tx.execute(
+                "INSERT INTO persona_history (persona_id, proposal_id, diff, applied_by, applied_at) VALUES (?, ?, ?, ?, ?)",
+                params![
+                    persona_id,
+                    mapped_proposal,
+                    serde_json::to_string(&h.diff).unwrap_or_else(|_| "[]".into()),
+                    h.applied_by,
+                    h.applied_at,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(persona_id)
+    }
+
+    pub async fn upsert_persona_entry_async(
+        &self,
+        upsert: PersonaEntryUpsert,
+    ) -> Result<PersonaEntry> {
+        self.run_blocking(move |kernel| kernel.upsert_persona_entry(upsert))
+            .await
+    }
+
+    pub async fn get_persona_entry_async(&self, id: String) -> Result<Option<PersonaEntry>> {
+        self.run_blocking(move |kernel| kernel.get_persona_entry(&id))
+            .await
+    }
+
+    pub async fn list_persona_entries_async(
+        &self,
+        owner_kind: Option<String>,
+        owner_ref: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<PersonaEntry>> {
+        self.run_blocking(move |kernel| {
+            let owner_filter = match (owner_kind.as_deref(), owner_ref.as_deref()) {
+                (Some(kind), Some(r)) => Some((kind, r)),
+                _ => None,
+            };
+            kernel.list_persona_entries(owner_filter, limit)
+        })
+        .await
+    }
+
+    pub async fn insert_persona_proposal_async(
+        &self,
+        create: PersonaProposalCreate,
+    ) -> Result<String> {
+        self.run_blocking(move |kernel| kernel.insert_persona_proposal(create))
+            .await
+    }
+
+    pub async fn get_persona_proposal_async(
+        &self,
+        proposal_id: String,
+    ) -> Result<Option<PersonaProposal>> {
+        self.run_blocking(move |kernel| kernel.get_persona_proposal(&proposal_id))
+            .await
+    }
+
+    pub async fn update_persona_proposal_status_async(
+        &self,
+        proposal_id: String,
+        status: PersonaProposalStatusUpdate,
+    ) -> Result<bool> {
+        self.run_blocking(move |kernel| kernel.update_persona_proposal_status(&proposal_id, status))
+            .await
+    }
+
+    pub async fn set_persona_proposal_required_approvals_async(
+        &self,
+        proposal_id: String,
+        required: i64,
+    ) -> Result<bool> {
+        self.run_blocking(move |kernel| {
+            kernel.set_persona_proposal_required_approvals(&proposal_id, required)
+        })
+        .await
+    }
+
+    pub async fn record_persona_proposal_vote_async(
+        &self,
+        proposal_id: String,
+        reviewer: String,
+        decision: String,
+        note: Option<String>,
+    ) -> Result<PersonaProposal> {
+        self.run_blocking(move |kernel| {
+            kernel.record_persona_proposal_vote(&proposal_id, &reviewer, &decision, note.as_deref())
+        })
+        .await
+    }
+
+    pub async fn list_persona_proposal_votes_async(
+        &self,
+        proposal_id: String,
+    ) -> Result<Vec<PersonaProposalVote>> {
+        self.run_blocking(move |kernel| kernel.list_persona_proposal_votes(&proposal_id))
+            .await
+    }
+
+    pub async fn list_persona_proposals_async(
+        &self,
+        persona_id: Option<String>,
+        status: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<PersonaProposal>> {
+        self.run_blocking(move |kernel| {
+            kernel.list_persona_proposals(persona_id.as_deref(), status.as_deref(), limit)
+        })
+        .await
+    }
+
+    pub async fn append_persona_history_async(&self, entry: PersonaHistoryAppend) -> Result<i64> {
+        self.run_blocking(move |kernel| kernel.append_persona_history(entry))
+            .await
+    }
+
+    pub async fn list_persona_history_async(
+        &self,
+        persona_id: String,
+        limit: i64,
+    ) -> Result<Vec<PersonaHistoryEntry>> {
+        self.run_blocking(move |kernel| kernel.list_persona_history(&persona_id, limit))
+            .await
+    }
+
+    pub async fn compact_persona_history_async(
+        &self,
+        persona_id: String,
+        keep_last: i64,
+    ) -> Result<i64> {
+        self.run_blocking(move |kernel| kernel.compact_persona_history(&persona_id, keep_last))
+            .await
+    }
+
+    pub async fn reconstruct_persona_history_async(
+        &self,
+        persona_id: String,
+        up_to_id: Option<i64>,
+    ) -> Result<JsonValue> {
+        self.run_blocking(move |kernel| kernel.reconstruct_persona_history(&persona_id, up_to_id))
+            .await
+    }
+
+    pub async fn insert_persona_vibe_sample_async(
+        &self,
+        create: PersonaVibeSampleCreate,
+        retain: i64,
+    ) -> Result<PersonaVibeSample> {
+        self.run_blocking(move |kernel| kernel.insert_persona_vibe_sample(create, retain))
+            .await
+    }
+
+    pub async fn list_persona_vibe_samples_async(
+        &self,
+        persona_id: String,
+        limit: i64,
+    ) -> Result<Vec<PersonaVibeSample>> {
+        self.run_blocking(move |kernel| kernel.list_persona_vibe_samples(&persona_id, limit))
+            .await
+    }
+
+    pub async fn apply_persona_diff_async(
+        &self,
+        persona_id: String,
+        diff: JsonValue,
+    ) -> Result<PersonaEntry> {
+        self.run_blocking(move |kernel| kernel.apply_persona_diff(&persona_id, &diff))
+            .await
+    }
+
+    pub async fn export_persona_async(&self, id: String) -> Result<JsonValue> {
+        self.run_blocking(move |kernel| kernel.export_persona(&id))
+            .await
+    }
+
+    pub async fn import_persona_async(&self, bundle: JsonValue, mode: String) -> Result<String> {
+        self.run_blocking(move |kernel| kernel.import_persona(&bundle, &mode))
+            .await
+    }
+
+    fn map_persona_entry_row(row: &rusqlite::Row<'_>) -> Result<PersonaEntry> {
+        let traits_raw: Option<String> = row.get(5)?;
+        let preferences_raw: Option<String> = row.get(6)?;
+        let worldview_raw: Option<String> = row.get(7)?;
+        let vibe_raw: Option<String> = row.get(8)?;
+        let calibration_raw: Option<String> = row.get(9)?;
+        Ok(PersonaEntry {
+            id: row.get(0)?,
+            owner_kind: row.get(1)?,
+            owner_ref: row.get(2)?,
+            name: row.get(3)?,
+            archetype: row.get(4)?,
+            traits: parse_json_or_default(traits_raw, json!({})),
+            preferences: parse_json_or_default(preferences_raw, json!({})),
+            worldview: parse_json_or_default(worldview_raw, json!({})),
+            vibe_profile: parse_json_or_default(vibe_raw, json!({})),
+            calibration: parse_json_or_default(calibration_raw, json!({})),
+            updated: row.get(10)?,
+            version: row.get(11)?,
+        })
+    }
+
+    fn map_persona_proposal_row(row: &rusqlite::Row<'_>) -> Result<PersonaProposal> {
+        let diff_raw: Option<String> = row.get(3)?;
+        let telemetry_raw: Option<String> = row.get(5)?;
+        let leases_raw: Option<String> = row.get(6)?;
+        Ok(PersonaProposal {
+            proposal_id: row.get(0)?,
+            persona_id: row.get(1)?,
+            submitted_by: row.get(2)?,
+            diff: parse_json_or_default(diff_raw, json!([])),
+            rationale: row.get(4)?,
+            telemetry_scope: parse_json_or_default(telemetry_raw, json!({})),
             leases_required: parse_json_or_default(leases_raw, json!([])),
             status: row.get(7)?,
             created: row.get(8)?,
             updated: row.get(9)?,
+            required_approvals: row.get(10)?,
+        })
+    }
+
+    fn map_persona_proposal_vote_row(row: &rusqlite::Row<'_>) -> Result<PersonaProposalVote> {
+        Ok(PersonaProposalVote {
+            proposal_id: row.get(0)?,
+            reviewer: row.get(1)?,
+            decision: row.get(2)?,
+            note: row.get(3)?,
+            updated: row.get(4)?,
+        })
+    }
+
+    fn map_persona_history_row(row: &rusqlite::Row<'_>) -> Result<PersonaHistoryEntry> {
+        let diff_raw: Option<String> = row.get(3)?;
+        Ok(PersonaHistoryEntry {
+            id: row.get(0)?,
+            persona_id: row.get(1)?,
+            proposal_id: row.get(2)?,
+            diff: parse_json_or_default(diff_raw, json!([])),
+            applied_by: row.get(4)?,
+            applied_at: row.get(5)?,
+        })
+    }
+
+    fn map_persona_vibe_sample_row(row: &rusqlite::Row<'_>) -> Result<PersonaVibeSample> {
+        let metadata_raw: Option<String> = row.get(6)?;
+        let metadata = match metadata_raw {
+            Some(raw) => serde_json::from_str(&raw).unwrap_or(JsonValue::Null),
+            None => JsonValue::Null,
+        };
+        Ok(PersonaVibeSample {
+            id: row.get(0)?,
+            persona_id: row.get(1)?,
+            kind: row.get(2)?,
+            signal: row.get(3)?,
+            strength: row.get(4)?,
+            note: row.get(5)?,
+            metadata,
+            recorded_at: row.get(7)?,
+        })
+    }
+
+    pub fn apply_persona_diff(&self, persona_id: &str, diff: &JsonValue) -> Result<PersonaEntry> {
+        let entry = self
+            .get_persona_entry(persona_id)?
+            .ok_or_else(|| anyhow!("persona id not found"))?;
+        let mut entry_value = serde_json::to_value(&entry)?;
+
+        if diff.is_array() {
+            let patch: json_patch::Patch = serde_json::from_value(diff.clone())?;
+            json_patch::patch(&mut entry_value, &patch)?;
+        } else if diff.is_object() {
+            merge_json(&mut entry_value, diff);
+        } else {
+            return Err(anyhow!("persona diff must be a JSON object or array"));
+        }
+
+        let mut updated: PersonaEntry = serde_json::from_value(entry_value)?;
+
+        // preserve immutable fields
+        updated.id = entry.id.clone();
+        updated.owner_kind = entry.owner_kind.clone();
+        updated.owner_ref = entry.owner_ref.clone();
+
+        // ensure required JSON fields remain objects
+        if !updated.traits.is_object() && !updated.traits.is_array() {
+            updated.traits = entry.traits.clone();
+        }
+        if !updated.preferences.is_object() && !updated.preferences.is_array() {
+            updated.preferences = entry.preferences.clone();
+        }
+        if !updated.worldview.is_object() && !updated.worldview.is_array() {
+            updated.worldview = entry.worldview.clone();
+        }
+        if !updated.vibe_profile.is_object() && !updated.vibe_profile.is_array() {
+            updated.vibe_profile = entry.vibe_profile.clone();
+        }
+        if !updated.calibration.is_object() && !updated.calibration.is_array() {
+            updated.calibration = entry.calibration.clone();
+        }
+
+        let upsert = PersonaEntryUpsert {
+            id: updated.id.clone(),
+            owner_kind: updated.owner_kind.clone(),
+            owner_ref: updated.owner_ref.clone(),
+            name: updated.name.clone(),
+            archetype: updated.archetype.clone(),
+            traits: updated.traits.clone(),
+            preferences: updated.preferences.clone(),
+            worldview: updated.worldview.clone(),
+            vibe_profile: updated.vibe_profile.clone(),
+            calibration: updated.calibration.clone(),
+        };
+
+        self.upsert_persona_entry(upsert)
+    }
+
+    // ---------- Logic Units ----------
+    pub fn insert_logic_unit(
+        &self,
+        id: &str,
+        manifest: &serde_json::Value,
+        status: &str,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let mut conn = self.conn()?;
+        let now = self.now_rfc3339();
+        let mf_s = serde_json::to_string(manifest).unwrap_or("{}".into());
+        let tx = conn.transaction()?;
+        // Record every manifest write so a bad update can be rolled back.
+        let version: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(version),0)+1 FROM logic_unit_versions WHERE unit_id = ?",
+            params![id],
+            |r| r.get(0),
+        )?;
+        tx.execute(
+            "INSERT INTO logic_unit_versions(unit_id,version,manifest,status,created) VALUES(?,?,?,?,?)",
+            params![id, version, mf_s, status, now],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO logic_units(id,manifest,status,created,updated) VALUES(?,?,?,?,?)",
+            params![id, mf_s, status, now, now],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Retained manifest history for one logic unit, newest version first.
+    pub fn list_logic_unit_versions(&self, id: &str) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT version,manifest,status,created FROM logic_unit_versions WHERE unit_id = ? ORDER BY version DESC",
+        )?;
+        let mut rows = stmt.query([id])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let mf_s: String = r.get(1)?;
+            let mf_v =
+                serde_json::from_str::<serde_json::Value>(&mf_s).unwrap_or(serde_json::json!({}));
+            out.push(serde_json::json!({
+                "version": r.get::<_, i64>(0)?,
+                "manifest": mf_v,
+                "status": r.get::<_, String>(2)?,
+                "created": r.get::<_, String>(3)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Restore the manifest/status recorded at `version`. The rollback is
+    /// written through `insert_logic_unit`, so it shows up in the history as
+    /// a new version rather than rewriting it.
+    pub fn rollback_logic_unit(&self, id: &str, version: i64) -> Result<()> {
+        self.ensure_writable()?;
+        let (mf_s, status): (String, String) = {
+            let conn = self.conn()?;
+            conn.query_row(
+                "SELECT manifest,status FROM logic_unit_versions WHERE unit_id = ? AND version = ?",
+                params![id, version],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("logic unit {id} has no version {version}"))?
+        };
+        let mf_v =
+            serde_json::from_str::<serde_json::Value>(&mf_s).unwrap_or(serde_json::json!({}));
+        self.insert_logic_unit(id, &mf_v, &status)
+    }
+
+    pub fn list_logic_units(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT id,manifest,status,created,updated FROM logic_units ORDER BY updated DESC LIMIT ?")?;
+        let mut rows = stmt.query([limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let mf_s: String = r.get(1)?;
+            let mf_v =
+                serde_json::from_str::<serde_json::Value>(&mf_s).unwrap_or(serde_json::json!({}));
+            out.push(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "manifest": mf_v,
+                "status": r.get::<_, String>(2)?,
+                "created": r.get::<_, String>(3)?,
+                "updated": r.get::<_, String>(4)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    // ---------------- Async wrappers (blocking pool) ----------------
+    // These helpers offload rusqlite work onto the dedicated blocking pool.
+
+    pub async fn insert_memory_async(&self, owned: MemoryInsertOwned) -> Result<String> {
+        self.run_blocking(move |k| {
+            let args = owned.to_args();
+            k.insert_memory(&args)
+        })
+        .await
+    }
+
+    pub async fn insert_memory_batch_async(
+        &self,
+        items: Vec<MemoryInsertOwned>,
+    ) -> Result<Vec<Result<(String, serde_json::Value)>>> {
+        self.run_blocking(move |k| k.insert_memory_batch(&items))
+            .await
+    }
+
+    pub async fn insert_memory_with_record_async(
+        &self,
+        owned: MemoryInsertOwned,
+    ) -> Result<(String, serde_json::Value)> {
+        self.run_blocking(move |k| {
+            let args = owned.to_args();
+            k.insert_memory_with_record(&args)
+        })
+        .await
+    }
+
+    pub async fn search_memory_async(
+        &self,
+        q: String,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.search_memory(&q, lane.as_deref(), limit))
+            .await
+    }
+
+    pub async fn search_memory_page_async(
+        &self,
+        q: String,
+        lane: Option<String>,
+        limit: i64,
+        cursor: Option<String>,
+    ) -> Result<(Vec<serde_json::Value>, Option<String>)> {
+        self.run_blocking(move |k| {
+            k.search_memory_page(&q, lane.as_deref(), limit, cursor.as_deref())
+        })
+        .await
+    }
+
+    /// Stream search hits incrementally: pages of `page_size` rows are
+    /// fetched on the blocking pool and forwarded one record at a time, so
+    /// large result sets never materialize at once. The channel is bounded,
+    /// so a slow consumer applies backpressure to the pager; dropping the
+    /// receiver stops the scan after the in-flight page. A pagination
+    /// failure is sent as a terminal `Err` item so callers can tell it
+    /// apart from the end of the results. Must be called from within a
+    /// tokio runtime.
+    pub fn stream_memory_search(
+        &self,
+        q: String,
+        lane: Option<String>,
+        page_size: i64,
+    ) -> tokio::sync::mpsc::Receiver<Result<serde_json::Value>> {
+        let page_size = page_size.clamp(1, 1000);
+        let (tx, rx) = tokio::sync::mpsc::channel(page_size as usize);
+        let kernel = self.clone();
+        tokio::spawn(async move {
+            let mut cursor: Option<String> = None;
+            loop {
+                let page = match kernel
+                    .search_memory_page_async(q.clone(), lane.clone(), page_size, cursor.take())
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+                let (items, next) = page;
+                for item in items {
+                    if tx.send(Ok(item)).await.is_err() {
+                        return;
+                    }
+                }
+                match next {
+                    Some(c) => cursor = Some(c),
+                    None => return,
+                }
+            }
+        });
+        rx
+    }
+
+    pub async fn search_memory_with_context_async(
+        &self,
+        q: String,
+        lane: Option<String>,
+        limit: i64,
+        ctx: ReadContext,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.search_memory_with_context(&q, lane.as_deref(), limit, &ctx))
+            .await
+    }
+
+    pub async fn fts_search_memory_async(
+        &self,
+        q: String,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.fts_search_memory(&q, lane.as_deref(), limit))
+            .await
+    }
+
+    pub async fn search_memory_by_tags_async(
+        &self,
+        tags: Vec<String>,
+        mode: TagMatch,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.search_memory_by_tags(&tags, mode, lane.as_deref(), limit))
+            .await
+    }
+
+    pub async fn filter_memory_async(
+        &self,
+        filters: MemoryFilters,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.filter_memory(&filters, lane.as_deref(), limit))
+            .await
+    }
+
+    pub async fn search_memory_by_embedding_async(
+        &self,
+        embed: Vec<f32>,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.search_memory_by_embedding(&embed, lane.as_deref(), limit))
+            .await
+    }
+
+    pub async fn search_memory_by_embedding_for_model_async(
+        &self,
+        embed: Vec<f32>,
+        hint: String,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| {
+            k.search_memory_by_embedding_for_model(&embed, &hint, lane.as_deref(), limit)
+        })
+        .await
+    }
+
+    pub async fn select_memory_hybrid_async(
+        &self,
+        q: Option<String>,
+        embed: Option<Vec<f32>>,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| {
+            k.select_memory_hybrid(q.as_deref(), embed.as_deref(), lane.as_deref(), limit)
+        })
+        .await
+    }
+
+    pub async fn select_memory_hybrid_with_context_async(
+        &self,
+        q: Option<String>,
+        embed: Option<Vec<f32>>,
+        lane: Option<String>,
+        limit: i64,
+        ctx: ReadContext,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| {
+            k.select_memory_hybrid_with_context(
+                q.as_deref(),
+                embed.as_deref(),
+                lane.as_deref(),
+                limit,
+                &ctx,
+            )
+        })
+        .await
+    }
+
+    pub async fn select_memory_hybrid_mmr_async(
+        &self,
+        q: Option<String>,
+        embed: Option<Vec<f32>>,
+        lane: Option<String>,
+        limit: i64,
+        lambda: f64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| {
+            k.select_memory_hybrid_mmr(
+                q.as_deref(),
+                embed.as_deref(),
+                lane.as_deref(),
+                limit,
+                lambda,
+            )
+        })
+        .await
+    }
+
+    pub async fn list_recent_memory_async(
+        &self,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_recent_memory(lane.as_deref(), limit))
+            .await
+    }
+
+    pub async fn list_memory_in_window_async(
+        &self,
+        lane: Option<String>,
+        since: String,
+        until: String,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_memory_in_window(lane.as_deref(), &since, &until, limit))
+            .await
+    }
+
+    pub async fn list_unembedded_memory_async(
+        &self,
+        lane: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        self.run_blocking(move |k| k.list_unembedded_memory(lane.as_deref(), limit))
+            .await
+    }
+
+    pub async fn compact_memory_async(&self) -> Result<()> {
+        self.run_blocking(move |k| k.compact_memory()).await
+    }
+
+    pub async fn top_memory_per_lane_async(
+        &self,
+        lanes: Vec<String>,
+        k: i64,
+    ) -> Result<std::collections::HashMap<String, Vec<serde_json::Value>>> {
+        self.run_blocking(move |k_| k_.top_memory_per_lane(&lanes, k))
+            .await
+    }
+
+    pub async fn find_memory_by_hash_async(
+        &self,
+        hash: String,
+    ) -> Result<Option<serde_json::Value>> {
+        self.run_blocking(move |k| k.find_memory_by_hash(&hash))
+            .await
+    }
+
+    pub async fn expired_memory_candidates_async(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        self.run_blocking(move |k| k.expired_memory_candidates(now, limit))
+            .await
+    }
+
+    pub async fn lane_overflow_candidates_async(
+        &self,
+        lane: String,
+        cap: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        self.run_blocking(move |k| k.lane_overflow_candidates(&lane, cap, limit))
+            .await
+    }
+
+    pub async fn configured_lane_overflow_candidates_async(
+        &self,
+        limit_per_lane: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        self.run_blocking(move |k| k.configured_lane_overflow_candidates(limit_per_lane))
+            .await
+    }
+
+    pub async fn plan_memory_gc_async(
+        &self,
+        now: DateTime<Utc>,
+        limit_per_pass: usize,
+    ) -> Result<serde_json::Value> {
+        self.run_blocking(move |k| k.plan_memory_gc(now, limit_per_pass))
+            .await
+    }
+
+    pub async fn run_memory_compaction_async(
+        &self,
+        limit_per_lane: usize,
+    ) -> Result<serde_json::Value> {
+        self.run_blocking(move |k| k.run_memory_compaction(limit_per_lane))
+            .await
+    }
+
+    pub async fn set_lane_config_async(&self, cfg: MemoryLaneConfig) -> Result<()> {
+        self.run_blocking(move |k| k.set_lane_config(&cfg)).await
+    }
+
+    pub async fn get_lane_config_async(&self, lane: String) -> Result<Option<MemoryLaneConfig>> {
+        self.run_blocking(move |k| k.get_lane_config(&lane)).await
+    }
+
+    pub async fn list_lane_configs_async(&self) -> Result<Vec<MemoryLaneConfig>> {
+        self.run_blocking(move |k| k.list_lane_configs()).await
+    }
+
+    pub async fn remove_lane_config_async(&self, lane: String) -> Result<bool> {
+        self.run_blocking(move |k| k.remove_lane_config(&lane))
+            .await
+    }
+
+    pub async fn register_embed_model_async(&self, hint: String, dim: usize) -> Result<()> {
+        self.run_blocking(move |k| k.register_embed_model(&hint, dim))
+            .await
+    }
+
+    pub async fn embed_model_dim_async(&self, hint: String) -> Result<Option<i64>> {
+        self.run_blocking(move |k| k.embed_model_dim(&hint)).await
+    }
+
+    pub async fn list_embed_models_async(&self) -> Result<serde_json::Value> {
+        self.run_blocking(move |k| k.list_embed_models()).await
+    }
+
+    pub async fn remove_embed_model_async(&self, hint: String) -> Result<bool> {
+        self.run_blocking(move |k| k.remove_embed_model(&hint))
+            .await
+    }
+
+    pub async fn delete_memory_records_async(&self, ids: Vec<String>) -> Result<usize> {
+        self.run_blocking(move |k| k.delete_memory_records(&ids))
+            .await
+    }
+
+    pub async fn insert_memory_link_async(
+        &self,
+        src_id: String,
+        dst_id: String,
+        rel: Option<String>,
+        weight: Option<f64>,
+    ) -> Result<()> {
+        self.run_blocking(move |k| k.insert_memory_link(&src_id, &dst_id, rel.as_deref(), weight))
+            .await
+    }
+
+    pub async fn backfill_embed_blobs_async(&self, batch_limit: usize) -> Result<usize> {
+        if batch_limit == 0 {
+            return Ok(0);
+        }
+        self.run_blocking(move |k| k.backfill_embed_blobs(batch_limit))
+            .await
+    }
+
+    pub async fn pending_embed_backfill_async(&self) -> Result<u64> {
+        self.run_blocking(|k| k.pending_embed_backfill()).await
+    }
+
+    pub async fn list_memory_links_async(
+        &self,
+        src_id: String,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_memory_links(&src_id, limit))
+            .await
+    }
+
+    pub async fn list_memory_links_many_async(
+        &self,
+        src_ids: Vec<String>,
+        limit_per: i64,
+    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
+        self.run_blocking(move |k| k.list_memory_links_many(&src_ids, limit_per))
+            .await
+    }
+
+    pub async fn list_memory_backlinks_async(
+        &self,
+        dst_id: String,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_memory_backlinks(&dst_id, limit))
+            .await
+    }
+
+    pub async fn list_memory_backlinks_many_async(
+        &self,
+        dst_ids: Vec<String>,
+        limit_per: i64,
+    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
+        self.run_blocking(move |k| k.list_memory_backlinks_many(&dst_ids, limit_per))
+            .await
+    }
+
+    pub async fn traverse_memory_graph_async(
+        &self,
+        start_ids: Vec<String>,
+        max_depth: usize,
+        rel_filter: Option<String>,
+        limit: usize,
+    ) -> Result<serde_json::Value> {
+        self.run_blocking(move |k| {
+            k.traverse_memory_graph(&start_ids, max_depth, rel_filter.as_deref(), limit)
+        })
+        .await
+    }
+
+    pub async fn get_memory_async(&self, id: String) -> Result<Option<serde_json::Value>> {
+        self.run_blocking(move |k| k.get_memory(&id)).await
+    }
+
+    pub async fn get_memory_with_context_async(
+        &self,
+        id: String,
+        ctx: ReadContext,
+    ) -> Result<Option<serde_json::Value>> {
+        self.run_blocking(move |k| k.get_memory_with_context(&id, &ctx))
+            .await
+    }
+
+    pub async fn list_memory_revisions_async(
+        &self,
+        id: String,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_memory_revisions(&id, limit))
+            .await
+    }
+
+    pub async fn restore_memory_revision_async(
+        &self,
+        id: String,
+        revision: i64,
+    ) -> Result<serde_json::Value> {
+        self.run_blocking(move |k| k.restore_memory_revision(&id, revision))
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_lease_async(
+        &self,
+        id: String,
+        subject: String,
+        capability: String,
+        scope: Option<String>,
+        ttl_until: String,
+        budget: Option<f64>,
+        policy_ctx: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.run_blocking(move |k| {
+            k.insert_lease(
+                &id,
+                &subject,
+                &capability,
+                scope.as_deref(),
+                &ttl_until,
+                budget,
+                policy_ctx.as_ref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn revoke_lease_async(
+        &self,
+        id: &str,
+        reason: Option<&str>,
+        revoked_by: Option<&str>,
+    ) -> Result<bool> {
+        let id = id.to_string();
+        let reason = reason.map(|s| s.to_string());
+        let revoked_by = revoked_by.map(|s| s.to_string());
+        self.run_blocking(move |k| k.revoke_lease(&id, reason.as_deref(), revoked_by.as_deref()))
+            .await
+    }
+
+    pub async fn renew_lease_async(
+        &self,
+        id: &str,
+        extend_ttl_secs: i64,
+    ) -> Result<Option<String>> {
+        let id = id.to_string();
+        self.run_blocking(move |k| k.renew_lease(&id, extend_ttl_secs))
+            .await
+    }
+
+    pub async fn consume_lease_budget_async(&self, id: &str, amount: f64) -> Result<Option<f64>> {
+        let id = id.to_string();
+        self.run_blocking(move |k| k.consume_lease_budget(&id, amount))
+            .await
+    }
+
+    pub async fn list_lease_events_async(
+        &self,
+        subject: Option<String>,
+        capability: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| {
+            k.list_lease_events(subject.as_deref(), capability.as_deref(), limit)
+        })
+        .await
+    }
+
+    pub async fn list_leases_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_leases(limit)).await
+    }
+
+    pub async fn insert_config_snapshot_async(&self, config: serde_json::Value) -> Result<String> {
+        self.run_blocking(move |k| k.insert_config_snapshot(&config))
+            .await
+    }
+
+    pub async fn insert_config_snapshot_dedup_async(
+        &self,
+        config: serde_json::Value,
+        skip_if_latest: bool,
+    ) -> Result<String> {
+        self.run_blocking(move |k| k.insert_config_snapshot_dedup(&config, skip_if_latest))
+            .await
+    }
+
+    pub async fn find_config_snapshot_by_digest_async(
+        &self,
+        sha: String,
+    ) -> Result<Option<String>> {
+        self.run_blocking(move |k| k.find_config_snapshot_by_digest(&sha))
+            .await
+    }
+
+    pub async fn get_config_snapshot_async(&self, id: String) -> Result<Option<serde_json::Value>> {
+        self.run_blocking(move |k| k.get_config_snapshot(&id)).await
+    }
+
+    pub async fn list_config_snapshots_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_config_snapshots(limit))
+            .await
+    }
+
+    pub async fn insert_logic_unit_async(
+        &self,
+        id: String,
+        manifest: serde_json::Value,
+        status: String,
+    ) -> Result<()> {
+        self.run_blocking(move |k| k.insert_logic_unit(&id, &manifest, &status))
+            .await
+    }
+
+    pub async fn list_logic_units_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_logic_units(limit)).await
+    }
+
+    pub async fn list_logic_unit_versions_async(
+        &self,
+        id: String,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_logic_unit_versions(&id))
+            .await
+    }
+
+    pub async fn rollback_logic_unit_async(&self, id: String, version: i64) -> Result<()> {
+        self.run_blocking(move |k| k.rollback_logic_unit(&id, version))
+            .await
+    }
+
+    pub async fn insert_orchestrator_job_async(
+        &self,
+        goal: &str,
+        data: Option<&serde_json::Value>,
+    ) -> Result<String> {
+        let goal_owned = goal.to_string();
+        let data_clone = data.cloned();
+        self.run_blocking(move |k| k.insert_orchestrator_job(&goal_owned, data_clone.as_ref()))
+            .await
+    }
+
+    pub async fn insert_orchestrator_job_with_deadline_async(
+        &self,
+        goal: &str,
+        data: Option<&serde_json::Value>,
+        deadline: Option<String>,
+    ) -> Result<String> {
+        let goal_owned = goal.to_string();
+        let data_clone = data.cloned();
+        self.run_blocking(move |k| {
+            k.insert_orchestrator_job_with_deadline(
+                &goal_owned,
+                data_clone.as_ref(),
+                deadline.as_deref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn cancel_orchestrator_job_async(
+        &self,
+        id: String,
+        reason: Option<String>,
+    ) -> Result<bool> {
+        self.run_blocking(move |k| k.cancel_orchestrator_job(&id, reason.as_deref()))
+            .await
+    }
+
+    pub async fn sweep_overdue_orchestrator_jobs_async(&self) -> Result<u64> {
+        self.run_blocking(move |k| k.sweep_overdue_orchestrator_jobs())
+            .await
+    }
+
+    pub async fn update_orchestrator_job_async(
+        &self,
+        id: String,
+        status: Option<String>,
+        progress: Option<f64>,
+        data_patch: Option<serde_json::Value>,
+    ) -> Result<bool> {
+        self.run_blocking(move |k| {
+            k.update_orchestrator_job(&id, status.as_deref(), progress, data_patch.as_ref())
+        })
+        .await
+    }
+
+    pub async fn orchestrator_job_data_history_async(
+        &self,
+        id: String,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.orchestrator_job_data_history(&id))
+            .await
+    }
+
+    pub async fn list_orchestrator_jobs_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_orchestrator_jobs(limit))
+            .await
+    }
+
+    pub async fn update_action_result_async(
+        &self,
+        id: String,
+        output: Option<serde_json::Value>,
+        error: Option<String>,
+    ) -> Result<bool> {
+        self.run_blocking(move |k| k.update_action_result(&id, output.as_ref(), error.as_deref()))
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn append_egress_async(
+        &self,
+        decision: String,
+        reason: Option<String>,
+        dest_host: Option<String>,
+        dest_port: Option<i64>,
+        protocol: Option<String>,
+        bytes_in: Option<i64>,
+        bytes_out: Option<i64>,
+        corr_id: Option<String>,
+        proj: Option<String>,
+        posture: Option<String>,
+        meta: Option<serde_json::Value>,
+    ) -> Result<i64> {
+        let meta = meta.map(std::sync::Arc::new);
+        self.run_blocking(move |k| {
+            k.append_egress(
+                &decision,
+                reason.as_deref(),
+                dest_host.as_deref(),
+                dest_port,
+                protocol.as_deref(),
+                bytes_in,
+                bytes_out,
+                corr_id.as_deref(),
+                proj.as_deref(),
+                posture.as_deref(),
+                meta.as_deref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn dequeue_one_queued_async(
+        &self,
+    ) -> Result<Option<(String, String, serde_json::Value)>> {
+        self.run_blocking(|k| k.dequeue_one_queued()).await
+    }
+
+    pub async fn dequeue_one_queued_matching_async(
+        &self,
+        kind_prefixes: Vec<String>,
+    ) -> Result<Option<(String, String, serde_json::Value)>> {
+        self.run_blocking(move |k| k.dequeue_one_queued_matching(&kind_prefixes))
+            .await
+    }
+
+    pub async fn append_event_async(&self, env: &arw_events::Envelope) -> Result<i64> {
+        let env = env.clone();
+        self.run_blocking(move |k| k.append_event(&env)).await
+    }
+
+    pub async fn recent_events_async(
+        &self,
+        limit: i64,
+        after_id: Option<i64>,
+    ) -> Result<Vec<EventRow>> {
+        self.run_blocking(move |k| k.recent_events(limit, after_id))
+            .await
+    }
+
+    pub async fn events_page_async(&self, cursor: Option<String>, limit: i64) -> Result<EventPage> {
+        self.run_blocking(move |k| k.events_page(cursor.as_deref(), limit))
+            .await
+    }
+
+    pub async fn append_event_attributed_async(
+        &self,
+        env: &arw_events::Envelope,
+        actor: Option<String>,
+        proj: Option<String>,
+    ) -> Result<i64> {
+        let env = env.clone();
+        self.run_blocking(move |k| {
+            k.append_event_attributed(&env, actor.as_deref(), proj.as_deref())
+        })
+        .await
+    }
+
+    pub async fn events_by_actor_async(&self, actor: String, limit: i64) -> Result<Vec<EventRow>> {
+        self.run_blocking(move |k| k.events_by_actor(&actor, limit))
+            .await
+    }
+
+    pub async fn events_by_project_async(&self, proj: String, limit: i64) -> Result<Vec<EventRow>> {
+        self.run_blocking(move |k| k.events_by_project(&proj, limit))
+            .await
+    }
+
+    pub async fn events_in_range_async(
+        &self,
+        start: String,
+        end: String,
+        kinds: Vec<String>,
+        limit: i64,
+        cursor: Option<String>,
+    ) -> Result<(Vec<EventRow>, Option<String>)> {
+        self.run_blocking(move |k| {
+            k.events_in_range(&start, &end, &kinds, limit, cursor.as_deref())
+        })
+        .await
+    }
+
+    pub async fn search_events_async(
+        &self,
+        query: String,
+        kinds: Vec<String>,
+        since: Option<String>,
+        until: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<EventRow>> {
+        self.run_blocking(move |k| {
+            k.search_events(&query, &kinds, since.as_deref(), until.as_deref(), limit)
+        })
+        .await
+    }
+
+    pub async fn list_corr_ids_async(
+        &self,
+        since: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>> {
+        self.run_blocking(move |k| k.list_corr_ids(since.as_deref(), limit))
+            .await
+    }
+
+    pub async fn events_by_corr_id_async(
+        &self,
+        corr_id: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<EventRow>> {
+        let cid = corr_id.to_string();
+        self.run_blocking(move |k| k.events_by_corr_id(&cid, limit))
+            .await
+    }
+
+    pub async fn events_by_corr_ids_async(
+        &self,
+        corr_ids: Vec<String>,
+        limit: Option<i64>,
+    ) -> Result<HashMap<String, Vec<EventRow>>> {
+        self.run_blocking(move |k| k.events_by_corr_ids(&corr_ids, limit))
+            .await
+    }
+
+    pub async fn detect_event_id_gaps_async(
+        &self,
+        from_id: i64,
+        to_id: i64,
+    ) -> Result<Vec<(i64, i64)>> {
+        self.run_blocking(move |k| k.detect_event_id_gaps(from_id, to_id))
+            .await
+    }
+
+    pub async fn tail_events_async(
+        &self,
+        limit: i64,
+        prefixes: Vec<String>,
+    ) -> Result<(Vec<EventRow>, i64)> {
+        self.run_blocking(move |k| k.tail_events(limit, &prefixes))
+            .await
+    }
+
+    pub async fn count_actions_by_state_async(&self, state: &str) -> Result<i64> {
+        let s = state.to_string();
+        self.run_blocking(move |k| k.count_actions_by_state(&s))
+            .await
+    }
+
+    pub async fn find_action_by_idem_async(&self, idem: &str) -> Result<Option<String>> {
+        let s = idem.to_string();
+        self.run_blocking(move |k| k.find_action_by_idem(&s)).await
+    }
+
+    pub async fn find_action_by_idem_full_async(&self, idem: &str) -> Result<Option<ActionRow>> {
+        let s = idem.to_string();
+        self.run_blocking(move |k| k.find_action_by_idem_full(&s))
+            .await
+    }
+
+    pub async fn insert_action_async(
+        &self,
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        state: &str,
+    ) -> Result<()> {
+        let id = id.to_string();
+        let kind = kind.to_string();
+        let input = input.clone();
+        let policy_ctx = policy_ctx.cloned();
+        let idem_key = idem_key.map(|s| s.to_string());
+        let state_s = state.to_string();
+        self.run_blocking(move |k| {
+            k.insert_action(
+                &id,
+                &kind,
+                &input,
+                policy_ctx.as_ref(),
+                idem_key.as_deref(),
+                &state_s,
+            )
+        })
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_action_with_priority_async(
+        &self,
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        state: &str,
+        priority: i64,
+    ) -> Result<()> {
+        let id = id.to_string();
+        let kind = kind.to_string();
+        let input = input.clone();
+        let policy_ctx = policy_ctx.cloned();
+        let idem_key = idem_key.map(|s| s.to_string());
+        let state_s = state.to_string();
+        self.run_blocking(move |k| {
+            k.insert_action_with_priority(
+                &id,
+                &kind,
+                &input,
+                policy_ctx.as_ref(),
+                idem_key.as_deref(),
+                &state_s,
+                priority,
+            )
+        })
+        .await
+    }
+
+    pub async fn insert_scheduled_action_async(
+        &self,
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        run_after: &str,
+    ) -> Result<()> {
+        let id = id.to_string();
+        let kind = kind.to_string();
+        let input = input.clone();
+        let policy_ctx = policy_ctx.cloned();
+        let idem_key = idem_key.map(|s| s.to_string());
+        let run_after = run_after.to_string();
+        self.run_blocking(move |k| {
+            k.insert_scheduled_action(
+                &id,
+                &kind,
+                &input,
+                policy_ctx.as_ref(),
+                idem_key.as_deref(),
+                &run_after,
+            )
+        })
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_action_with_deps_async(
+        &self,
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        state: &str,
+        deps: Vec<String>,
+    ) -> Result<()> {
+        let id = id.to_string();
+        let kind = kind.to_string();
+        let input = input.clone();
+        let policy_ctx = policy_ctx.cloned();
+        let idem_key = idem_key.map(|s| s.to_string());
+        let state_s = state.to_string();
+        self.run_blocking(move |k| {
+            k.insert_action_with_deps(
+                &id,
+                &kind,
+                &input,
+                policy_ctx.as_ref(),
+                idem_key.as_deref(),
+                &state_s,
+                &deps,
+            )
+        })
+        .await
+    }
+
+    pub async fn action_dependency_graph_async(&self, id: &str) -> Result<serde_json::Value> {
+        let id = id.to_string();
+        self.run_blocking(move |k| k.action_dependency_graph(&id))
+            .await
+    }
+
+    pub async fn list_deferred_actions_async(&self, limit: i64) -> Result<Vec<ActionRow>> {
+        self.run_blocking(move |k| k.list_deferred_actions(limit))
+            .await
+    }
+
+    pub async fn get_action_async(&self, id: &str) -> Result<Option<ActionRow>> {
+        let s = id.to_string();
+        self.run_blocking(move |k| k.get_action(&s)).await
+    }
+
+    pub async fn fail_action_with_retry_async(
+        &self,
+        id: &str,
+        error: Option<&str>,
+        max_attempts: i64,
+        backoff_base_secs: i64,
+    ) -> Result<Option<String>> {
+        let id = id.to_string();
+        let error = error.map(|s| s.to_string());
+        self.run_blocking(move |k| {
+            k.fail_action_with_retry(&id, error.as_deref(), max_attempts, backoff_base_secs)
+        })
+        .await
+    }
+
+    pub async fn set_action_state_async(&self, id: &str, state: &str) -> Result<bool> {
+        let id_s = id.to_string();
+        let st = state.to_string();
+        self.run_blocking(move |k| k.set_action_state(&id_s, &st))
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn append_contribution_async(
+        &self,
+        subject: &str,
+        kind: &str,
+        qty: f64,
+        unit: &str,
+        corr_id: Option<&str>,
+        proj: Option<&str>,
+        meta: Option<&serde_json::Value>,
+    ) -> Result<i64> {
+        let subject = subject.to_string();
+        let kind = kind.to_string();
+        let unit = unit.to_string();
+        let corr_id = corr_id.map(|s| s.to_string());
+        let proj = proj.map(|s| s.to_string());
+        let meta = meta.cloned();
+        self.run_blocking(move |k| {
+            k.append_contribution(
+                &subject,
+                &kind,
+                qty,
+                &unit,
+                corr_id.as_deref(),
+                proj.as_deref(),
+                meta.as_ref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn upsert_research_watcher_item_async(
+        &self,
+        source: Option<String>,
+        source_id: Option<String>,
+        title: Option<String>,
+        summary: Option<String>,
+        url: Option<String>,
+        payload: Option<serde_json::Value>,
+    ) -> Result<String> {
+        self.run_blocking(move |k| {
+            k.upsert_research_watcher_item(
+                source.as_deref(),
+                source_id.as_deref(),
+                title.as_deref(),
+                summary.as_deref(),
+                url.as_deref(),
+                payload.as_ref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn list_research_watcher_items_async(
+        &self,
+        status: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_research_watcher_items(status.as_deref(), limit))
+            .await
+    }
+
+    pub async fn update_research_watcher_status_async(
+        &self,
+        id: String,
+        status: String,
+        note: Option<String>,
+    ) -> Result<bool> {
+        self.run_blocking(move |k| k.update_research_watcher_status(&id, &status, note.as_deref()))
+            .await
+    }
+
+    pub async fn get_research_watcher_item_async(
+        &self,
+        id: String,
+    ) -> Result<Option<ResearchWatcherItem>> {
+        self.run_blocking(move |k| k.get_research_watcher_item(&id))
+            .await
+    }
+
+    pub async fn insert_staging_action_async(
+        &self,
+        action_kind: String,
+        action_input: serde_json::Value,
+        project: Option<String>,
+        requested_by: Option<String>,
+        evidence: Option<serde_json::Value>,
+    ) -> Result<String> {
+        self.run_blocking(move |k| {
+            k.insert_staging_action(
+                &action_kind,
+                &action_input,
+                project.as_deref(),
+                requested_by.as_deref(),
+                evidence.as_ref(),
+            )
         })
+        .await
+    }
+
+    pub async fn list_staging_actions_async(
+        &self,
+        status: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_staging_actions(status.as_deref(), limit))
+            .await
+    }
+
+    pub async fn get_staging_action_async(&self, id: String) -> Result<Option<StagingAction>> {
+        self.run_blocking(move |k| k.get_staging_action(&id)).await
+    }
+
+    pub async fn bulk_decide_staging_actions_async(
+        &self,
+        ids: Vec<String>,
+        status: String,
+        decision: Option<String>,
+        decided_by: Option<String>,
+    ) -> Result<u64> {
+        self.run_blocking(move |k| {
+            k.bulk_decide_staging_actions(&ids, &status, decision.as_deref(), decided_by.as_deref())
+        })
+        .await
+    }
+
+    pub async fn update_staging_action_status_async(
+        &self,
+        id: String,
+        status: String,
+        decision: Option<String>,
+        decided_by: Option<String>,
+        decided_at: Option<String>,
+        action_id: Option<String>,
+    ) -> Result<bool> {
+        self.run_blocking(move |k| {
+            k.update_staging_action_status(
+                &id,
+                &status,
+                decision.as_deref(),
+                decided_by.as_deref(),
+                decided_at.as_deref(),
+                action_id.as_deref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn list_contributions_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_contributions(limit))
+            .await
+    }
+
+    pub async fn list_actions_async(
+        &self,
+        opts: ActionListOptions,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_actions_filtered(&opts))
+            .await
+    }
+
+    pub async fn list_egress_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_egress(limit)).await
+    }
+
+    pub async fn rollup_egress_async(&self) -> Result<u64> {
+        self.run_blocking(move |k| k.rollup_egress()).await
+    }
+
+    pub async fn purge_egress_before_async(&self, before: String) -> Result<u64> {
+        self.run_blocking(move |k| k.purge_egress_before(&before))
+            .await
+    }
+
+    pub async fn rotate_egress_async(
+        &self,
+        before: String,
+        dest_dir: PathBuf,
+    ) -> Result<EventArchiveReport> {
+        self.run_blocking(move |k| k.rotate_egress(&before, &dest_dir))
+            .await
+    }
+
+    pub async fn cas_gc_async(&self, dir: PathBuf, dry_run: bool) -> Result<CasGcReport> {
+        self.run_blocking(move |k| k.cas_gc(&dir, dry_run)).await
+    }
+
+    pub async fn insert_artifact_async(&self, row: ArtifactRow) -> Result<bool> {
+        self.run_blocking(move |k| k.insert_artifact(&row)).await
+    }
+
+    pub async fn get_artifact_async(&self, sha256: String) -> Result<Option<ArtifactRow>> {
+        self.run_blocking(move |k| k.get_artifact(&sha256)).await
+    }
+
+    pub async fn list_artifacts_async(&self, limit: i64) -> Result<Vec<ArtifactRow>> {
+        self.run_blocking(move |k| k.list_artifacts(limit)).await
+    }
+
+    pub async fn search_artifacts_async(
+        &self,
+        mime_prefix: Option<String>,
+        meta_filter: Option<JsonValue>,
+        limit: i64,
+    ) -> Result<Vec<ArtifactRow>> {
+        self.run_blocking(move |k| {
+            k.search_artifacts(mime_prefix.as_deref(), meta_filter.as_ref(), limit)
+        })
+        .await
+    }
+
+    pub async fn delete_artifact_async(&self, sha256: String) -> Result<bool> {
+        self.run_blocking(move |k| k.delete_artifact(&sha256)).await
+    }
+
+    pub async fn list_egress_rollups_async(
+        &self,
+        since: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.list_egress_rollups(since.as_deref(), limit))
+            .await
+    }
+
+    pub async fn summarize_egress_async(
+        &self,
+        group_by: String,
+        since: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.run_blocking(move |k| k.summarize_egress(&group_by, since.as_deref(), limit))
+            .await
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ActionListOptions {
+    pub limit: i64,
+    pub state: Option<String>,
+    pub kind_prefix: Option<String>,
+    pub updated_since: Option<String>,
+}
+
+impl ActionListOptions {
+    pub fn new(limit: i64) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    pub fn clamped_limit(&self) -> i64 {
+        self.limit.clamp(1, 2000)
+    }
+}
+
+/// Id window for [`Kernel::export_events_ndjson`]. The default exports the
+/// whole log; bounds narrow it without changing the oldest-first order.
+#[derive(Clone, Debug, Default)]
+pub struct EventExportRange {
+    /// Exclusive lower bound, i.e. a replay cursor from a previous export.
+    pub after_id: Option<i64>,
+    /// Inclusive upper bound.
+    pub until_id: Option<i64>,
+    pub kind_prefix: Option<String>,
+    /// `<= 0` exports every matching row; positive caps the export.
+    pub limit: i64,
+}
+
+impl KernelSession {
+    fn workspace_clause(&self, column: &str) -> String {
+        match self.workspace.as_deref() {
+            Some(ws) => format!(" AND {} = '{}'", column, ws.replace('\'', "''")),
+            None => String::new(),
+        }
+    }
+
+    fn store(&self) -> MemoryStore<'_> {
+        MemoryStore::new(&self.conn)
+    }
+
+    pub fn select_memory_hybrid(
+        &self,
+        query: Option<&str>,
+        embed: Option<&[f32]>,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.store().select_memory_hybrid(query, embed, lane, limit)
+    }
+
+    pub fn list_memory_links_many(
+        &self,
+        src_ids: &[String],
+        limit_per: i64,
+    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
+        self.store().list_memory_links_many(src_ids, limit_per)
+    }
+
+    pub fn get_memory_many(&self, ids: &[String]) -> Result<HashMap<String, serde_json::Value>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        self.store().get_memory_many(ids)
+    }
+
+    pub fn expired_memory_candidates(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        self.store().expired_candidates(now, limit)
+    }
+
+    pub fn lane_overflow_candidates(
+        &self,
+        lane: &str,
+        cap: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        self.store().lane_overflow_candidates(lane, cap, limit)
+    }
+
+    pub fn delete_memory_records(&self, ids: &[String]) -> Result<usize> {
+        self.store().delete_records(ids)
+    }
+
+    pub fn list_recent_memory(
+        &self,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.store().list_recent_memory(lane, limit)
+    }
+
+    pub fn list_logic_units(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn: &Connection = &self.conn;
+        let mut stmt = conn.prepare(
+            "SELECT id,manifest,status,created,updated \
+             FROM logic_units ORDER BY updated DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query([limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let mf_s: String = r.get(1)?;
+            let mf_v =
+                serde_json::from_str::<serde_json::Value>(&mf_s).unwrap_or(serde_json::json!({}));
+            out.push(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "manifest": mf_v,
+                "status": r.get::<_, String>(2)?,
+                "created": r.get::<_, String>(3)?,
+                "updated": r.get::<_, String>(4)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    pub fn list_orchestrator_jobs(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn: &Connection = &self.conn;
+        let mut stmt = conn.prepare(
+            "SELECT id,status,goal,data,progress,created,updated,deadline,reason,parent_id \
+             FROM orchestrator_jobs ORDER BY updated DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query([limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let status_raw: String = r.get::<_, String>(1)?;
+            let (status_slug, status_label) = Kernel::normalize_orchestrator_status(&status_raw);
+            let mut payload = serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "status": status_raw,
+                "status_slug": status_slug,
+                "status_label": status_label,
+                "goal": r.get::<_, Option<String>>(2)?,
+                "progress": r.get::<_, Option<f64>>(4)?,
+                "created": r.get::<_, String>(5)?,
+                "updated": r.get::<_, String>(6)?,
+                "deadline": r.get::<_, Option<String>>(7)?,
+                "reason": r.get::<_, Option<String>>(8)?,
+                "parent_id": r.get::<_, Option<String>>(9)?,
+            });
+            let data_raw: Option<String> = r.get(3)?;
+            if let Some(data_raw) = data_raw {
+                if let Ok(val) = serde_json::from_str::<serde_json::Value>(&data_raw) {
+                    let persona = Kernel::extract_persona_id(&val);
+                    if !val.is_null() {
+                        if let serde_json::Value::Object(ref mut map) = payload {
+                            map.insert("data".into(), val.clone());
+                            if let Some(persona) = persona {
+                                map.insert("persona_id".into(), serde_json::Value::String(persona));
+                            }
+                        }
+                    }
+                }
+            }
+            out.push(payload);
+        }
+        Ok(out)
+    }
+
+    pub fn list_leases(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
+        let conn: &Connection = &self.conn;
+        let ws = self.workspace_clause("workspace_id");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated \
+             FROM leases WHERE 1=1{ws} ORDER BY updated DESC LIMIT ?",
+        ))?;
+        let mut rows = stmt.query([limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let policy_s: Option<String> = r.get(6)?;
+            let policy_v = policy_s
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .unwrap_or(serde_json::json!({}));
+            out.push(serde_json::json!({
+                "id": r.get::<_, String>(0)?,
+                "subject": r.get::<_, String>(1)?,
+                "capability": r.get::<_, String>(2)?,
+                "scope": r.get::<_, Option<String>>(3)?,
+                "ttl_until": r.get::<_, String>(4)?,
+                "budget": r.get::<_, Option<f64>>(5)?,
+                "policy": policy_v,
+                "created": r.get::<_, String>(7)?,
+                "updated": r.get::<_, String>(8)?,
+            }));
+        }
+        Ok(out)
     }
 
-    fn map_persona_history_row(row: &rusqlite::Row<'_>) -> Result<PersonaHistoryEntry> {
-        let diff_raw: Option<String> = row.get(3)?;
-        Ok(PersonaHistoryEntry {
-            id: row.get(0)?,
-            persona_id: row.get(1)?,
-            proposal_id: row.get(2)?,
-            diff: parse_json_or_default(diff_raw, json!([])),
-            applied_by: row.get(4)?,
-            applied_at: row.get(5)?,
-        })
+    // ---------- Write transactions ----------
+    // A session pins one connection, so these give callers multi-table
+    // atomicity (insert an action, append its event, write memory) that the
+    // per-call Kernel methods cannot.
+
+    /// Start an immediate write transaction on this session's connection.
+    pub fn begin(&self) -> Result<()> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        Ok(())
     }
 
-    fn map_persona_vibe_sample_row(row: &rusqlite::Row<'_>) -> Result<PersonaVibeSample> {
-        let metadata_raw: Option<String> = row.get(6)?;
-        let metadata = match metadata_raw {
-            Some(raw) => serde_json::from_str(&raw).unwrap_or(JsonValue::Null),
-            None => JsonValue::Null,
-        };
-        Ok(PersonaVibeSample {
-            id: row.get(0)?,
-            persona_id: row.get(1)?,
-            kind: row.get(2)?,
-            signal: row.get(3)?,
-            strength: row.get(4)?,
-            note: row.get(5)?,
-            metadata,
-            recorded_at: row.get(7)?,
-        })
+    pub fn commit(&self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
     }
 
-    pub fn apply_persona_diff(&self, persona_id: &str, diff: &JsonValue) -> Result<PersonaEntry> {
-        let entry = self
-            .get_persona_entry(persona_id)?
-            .ok_or_else(|| anyhow!("persona id not found"))?;
-        let mut entry_value = serde_json::to_value(&entry)?;
+    pub fn rollback(&self) -> Result<()> {
+        self.conn.execute_batch("ROLLBACK")?;
+        Ok(())
+    }
 
-        if diff.is_array() {
-            let patch: json_patch::Patch = serde_json::from_value(diff.clone())?;
-            json_patch::patch(&mut entry_value, &patch)?;
-        } else if diff.is_object() {
-            merge_json(&mut entry_value, diff);
-        } else {
-            return Err(anyhow!("persona diff must be a JSON object or array"));
+    /// Run `f` inside one write transaction: committed when it returns `Ok`,
+    /// rolled back (best effort) when it returns `Err`. Transactions do not
+    /// nest; don't call `begin` inside the closure.
+    pub fn with_tx<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        self.begin()?;
+        match f(self) {
+            Ok(value) => {
+                self.commit()?;
+                Ok(value)
+            }
+            Err(err) => {
+                let _ = self.rollback();
+                Err(err)
+            }
         }
+    }
 
-        let mut updated: PersonaEntry = serde_json::from_value(entry_value)?;
+    fn now_rfc3339(&self) -> String {
+        self.clock
+            .now()
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
 
-        // preserve immutable fields
-        updated.id = entry.id.clone();
-        updated.owner_kind = entry.owner_kind.clone();
-        updated.owner_ref = entry.owner_ref.clone();
+    /// Append one event row through the session connection so it joins the
+    /// caller's transaction. Unlike [`Kernel::append_event`] this does not
+    /// fan out to live subscribers; use the kernel method when listeners
+    /// must see the event.
+    pub fn append_event(&self, env: &arw_events::Envelope) -> Result<i64> {
+        let conn: &Connection = &self.conn;
+        let payload = serde_json::to_string(&env.payload).unwrap_or("{}".to_string());
+        let corr_id = env
+            .payload
+            .get("corr_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let (actor, proj) = Kernel::envelope_attribution(&env.payload);
+        let id = if Kernel::hash_chain_enabled() {
+            let prev = Kernel::last_chain_hash(conn)?;
+            let hash = Kernel::chain_hash(&prev, &env.time, &env.kind, &payload);
+            conn.prepare_cached(
+                "INSERT INTO events(time,kind,actor,proj,corr_id,payload,chain_hash,workspace_id) VALUES (?,?,?,?,?,?,?,?)",
+            )?
+            .execute(params![
+                env.time,
+                env.kind,
+                actor,
+                proj,
+                corr_id,
+                Kernel::payload_to_sql(payload.clone()),
+                hash,
+                self.workspace,
+            ])?;
+            conn.last_insert_rowid()
+        } else {
+            conn.prepare_cached(
+                "INSERT INTO events(time,kind,actor,proj,corr_id,payload,workspace_id) VALUES (?,?,?,?,?,?,?)",
+            )?
+            .execute(params![
+                env.time,
+                env.kind,
+                actor,
+                proj,
+                corr_id,
+                Kernel::payload_to_sql(payload.clone()),
+                self.workspace,
+            ])?;
+            conn.last_insert_rowid()
+        };
+        conn.prepare_cached("INSERT INTO events_fts(id, kind, payload) VALUES (?,?,?)")?
+            .execute(params![id, env.kind, payload])?;
+        Ok(id)
+    }
 
-        // ensure required JSON fields remain objects
-        if !updated.traits.is_object() && !updated.traits.is_array() {
-            updated.traits = entry.traits.clone();
-        }
-        if !updated.preferences.is_object() && !updated.preferences.is_array() {
-            updated.preferences = entry.preferences.clone();
-        }
-        if !updated.worldview.is_object() && !updated.worldview.is_array() {
-            updated.worldview = entry.worldview.clone();
-        }
-        if !updated.vibe_profile.is_object() && !updated.vibe_profile.is_array() {
-            updated.vibe_profile = entry.vibe_profile.clone();
-        }
-        if !updated.calibration.is_object() && !updated.calibration.is_array() {
-            updated.calibration = entry.calibration.clone();
+    /// Insert an action on the session connection (mirrors
+    /// [`Kernel::insert_action`]).
+    pub fn insert_action(
+        &self,
+        id: &str,
+        kind: &str,
+        input: &serde_json::Value,
+        policy_ctx: Option<&serde_json::Value>,
+        idem_key: Option<&str>,
+        state: &str,
+    ) -> Result<()> {
+        let now = self.now_rfc3339();
+        let input_s = serde_json::to_string(input).unwrap_or("{}".to_string());
+        let policy_s = policy_ctx.map(|v| serde_json::to_string(v).unwrap_or("{}".to_string()));
+        self.conn.execute(
+            "INSERT OR REPLACE INTO actions(id,kind,input,policy_ctx,idem_key,state,priority,workspace_id,created,updated) VALUES(?,?,?,?,?,?,0,?,?,?)",
+            params![id, kind, input_s, policy_s, idem_key, state, self.workspace, now, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn insert_memory(&self, args: &MemoryInsertArgs<'_>) -> Result<String> {
+        self.store().insert_memory(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{SecondsFormat, Utc};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn orchestrator_status_normalization() {
+        let cases = vec![
+            ("queued", ("queued", "Queued")),
+            ("Pending", ("queued", "Queued")),
+            ("running", ("running", "Running")),
+            ("IN_PROGRESS", ("running", "Running")),
+            ("completed", ("completed", "Completed")),
+            ("DONE", ("completed", "Completed")),
+            ("failed", ("failed", "Failed")),
+            ("ERROR", ("failed", "Failed")),
+            ("canceled", ("cancelled", "Cancelled")),
+            ("", ("unknown", "Unknown")),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Kernel::normalize_orchestrator_status(input), expected);
         }
+    }
+
+    #[tokio::test]
+    async fn research_watcher_upsert_and_status() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+
+        let id = kernel
+            .upsert_research_watcher_item_async(
+                Some("arxiv".to_string()),
+                Some("arxiv:2309".to_string()),
+                Some("Original title".to_string()),
+                Some("Initial summary".to_string()),
+                Some("https://example.test/paper".to_string()),
+                Some(json!({"authors": ["Ada"]})),
+            )
+            .await
+            .expect("insert research watcher item");
+
+        let pending = kernel
+            .list_research_watcher_items_async(Some("pending".to_string()), 10)
+            .await
+            .expect("list pending");
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0]["id"], id);
+
+        // Upsert with same source_id should update the existing record.
+        let same_id = kernel
+            .upsert_research_watcher_item_async(
+                Some("arxiv".to_string()),
+                Some("arxiv:2309".to_string()),
+                Some("Updated title".to_string()),
+                Some("Refined summary".to_string()),
+                Some("https://example.test/paper".to_string()),
+                None,
+            )
+            .await
+            .expect("update research watcher item");
+        assert_eq!(id, same_id);
+
+        let note = Some("Looks promising".to_string());
+        let changed = kernel
+            .update_research_watcher_status_async(id.clone(), "approved".to_string(), note.clone())
+            .await
+            .expect("update status");
+        assert!(changed);
+
+        let item = kernel
+            .get_research_watcher_item_async(id.clone())
+            .await
+            .expect("fetch item")
+            .expect("item present");
+        assert_eq!(item.status, "approved");
+        assert_eq!(item.note, note);
+
+        let still_pending = kernel
+            .list_research_watcher_items_async(Some("pending".to_string()), 10)
+            .await
+            .expect("list pending after status change");
+        assert!(still_pending.is_empty());
+
+        // Unknown id returns false
+        let changed = kernel
+            .update_research_watcher_status_async(
+                "missing".to_string(),
+                "archived".to_string(),
+                None,
+            )
+            .await
+            .expect("update missing");
+        assert!(!changed);
+    }
+
+    #[tokio::test]
+    async fn orchestrator_jobs_surface_data_payload() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+
+        let data = json!({
+            "persona_id": "persona.test",
+            "preset": "balanced",
+            "diversity": 0.5,
+            "recency": 0.35,
+            "compression": 0.4,
+        });
+        let job_id = kernel
+            .insert_orchestrator_job_async("test goal", Some(&data))
+            .await
+            .expect("insert orchestrator job");
+
+        let jobs = kernel
+            .list_orchestrator_jobs_async(5)
+            .await
+            .expect("list orchestrator jobs");
+        assert!(!jobs.is_empty(), "expected at least one job");
+
+        let job = jobs
+            .into_iter()
+            .find(|job| job["id"] == job_id)
+            .expect("job present");
+        assert_eq!(job["goal"], json!("test goal"));
+        assert_eq!(job["status_slug"], json!("queued"));
+        assert_eq!(job["status_label"], json!("Queued"));
+        assert_eq!(job["progress"], json!(0.0));
+        assert_eq!(job["persona_id"], json!("persona.test"));
+
+        let data_field = job.get("data").cloned().expect("data field surfaced");
+        assert_eq!(data_field["preset"], json!("balanced"));
+        assert_eq!(data_field["diversity"], json!(0.5));
+        assert_eq!(data_field["recency"], json!(0.35));
+        assert_eq!(data_field["compression"], json!(0.4));
+    }
+
+    #[tokio::test]
+    async fn orchestrator_job_data_history_caps_ring() {
+        let mut env = crate::test_support::env::guard();
+        env.set("ARW_ORCH_DATA_HISTORY", "4");
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
 
-        let upsert = PersonaEntryUpsert {
-            id: updated.id.clone(),
-            owner_kind: updated.owner_kind.clone(),
-            owner_ref: updated.owner_ref.clone(),
-            name: updated.name.clone(),
-            archetype: updated.archetype.clone(),
-            traits: updated.traits.clone(),
-            preferences: updated.preferences.clone(),
-            worldview: updated.worldview.clone(),
-            vibe_profile: updated.vibe_profile.clone(),
-            calibration: updated.calibration.clone(),
-        };
+        let job_id = kernel
+            .insert_orchestrator_job_async("history goal", None)
+            .await
+            .expect("insert orchestrator job");
+        for step in 0..6 {
+            let patch = json!({"step": step});
+            let updated = kernel
+                .update_orchestrator_job_async(job_id.clone(), None, None, Some(patch))
+                .await
+                .expect("apply patch");
+            assert!(updated);
+        }
 
-        self.upsert_persona_entry(upsert)
+        let history = kernel
+            .orchestrator_job_data_history_async(job_id.clone())
+            .await
+            .expect("read data history");
+        assert_eq!(history.len(), 4, "ring keeps only the last N patches");
+        assert_eq!(history[0]["patch"], json!({"step": 2}));
+        assert_eq!(history[3]["patch"], json!({"step": 5}));
+        assert!(history.iter().all(|e| e["time"].is_string()));
     }
 
-    // ---------- Logic Units ----------
-    pub fn insert_logic_unit(
-        &self,
-        id: &str,
-        manifest: &serde_json::Value,
-        status: &str,
-    ) -> Result<()> {
-        let conn = self.conn()?;
-        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let mf_s = serde_json::to_string(manifest).unwrap_or("{}".into());
-        conn.execute(
-            "INSERT OR REPLACE INTO logic_units(id,manifest,status,created,updated) VALUES(?,?,?,?,?)",
-            params![id, mf_s, status, now, now],
-        )?;
-        Ok(())
+    #[tokio::test]
+    async fn find_action_by_idem_full_returns_row() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_action(
+                "act-idem",
+                "demo.echo",
+                &json!({"msg": "hi"}),
+                None,
+                Some("idem-123"),
+                "queued",
+            )
+            .expect("insert action");
+
+        let row = kernel
+            .find_action_by_idem_full_async("idem-123")
+            .await
+            .expect("lookup by idem")
+            .expect("row present");
+        assert_eq!(row.id, "act-idem");
+        assert_eq!(row.kind, "demo.echo");
+        assert_eq!(row.input, json!({"msg": "hi"}));
+        assert_eq!(row.idem_key.as_deref(), Some("idem-123"));
+        assert_eq!(row.state, "queued");
+
+        let missing = kernel
+            .find_action_by_idem_full_async("idem-404")
+            .await
+            .expect("lookup missing");
+        assert!(missing.is_none());
     }
 
-    pub fn list_logic_units(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let conn = self.conn()?;
-        let mut stmt = conn.prepare("SELECT id,manifest,status,created,updated FROM logic_units ORDER BY updated DESC LIMIT ?")?;
-        let mut rows = stmt.query([limit])?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let mf_s: String = r.get(1)?;
-            let mf_v =
-                serde_json::from_str::<serde_json::Value>(&mf_s).unwrap_or(serde_json::json!({}));
-            out.push(serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "manifest": mf_v,
-                "status": r.get::<_, String>(2)?,
-                "created": r.get::<_, String>(3)?,
-                "updated": r.get::<_, String>(4)?,
-            }));
+    #[tokio::test]
+    async fn export_actions_ndjson_round_trips_rows() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..5 {
+            let kind = if i % 2 == 0 { "net.fetch" } else { "fs.patch" };
+            kernel
+                .insert_action(
+                    &format!("act-{i}"),
+                    kind,
+                    &json!({"seq": i}),
+                    None,
+                    None,
+                    "queued",
+                )
+                .expect("insert action");
         }
-        Ok(out)
-    }
 
-    // ---------------- Async wrappers (blocking pool) ----------------
-    // These helpers offload rusqlite work onto the dedicated blocking pool.
+        let mut buf: Vec<u8> = Vec::new();
+        let opts = ActionListOptions::default();
+        let written = kernel
+            .export_actions_ndjson(&opts, &mut buf)
+            .expect("export actions");
+        assert_eq!(written, 5);
+        let text = String::from_utf8(buf).expect("utf8 export");
+        let rows: Vec<ActionRow> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("line decodes into ActionRow"))
+            .collect();
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|r| r.state == "queued"));
 
-    pub async fn insert_memory_async(&self, owned: MemoryInsertOwned) -> Result<String> {
-        self.run_blocking(move |k| {
-            let args = owned.to_args();
-            k.insert_memory(&args)
-        })
-        .await
+        // Filters apply the same way as list_actions_filtered.
+        let mut filtered: Vec<u8> = Vec::new();
+        let opts = ActionListOptions {
+            kind_prefix: Some("net.".to_string()),
+            ..Default::default()
+        };
+        let written = kernel
+            .export_actions_ndjson(&opts, &mut filtered)
+            .expect("export filtered");
+        assert_eq!(written, 3);
+
+        // Soft-deleted rows and other workspaces' rows stay out of the export.
+        assert!(kernel.soft_delete_action("act-0").expect("soft delete"));
+        let alpha = kernel.scoped_to_workspace("alpha");
+        alpha
+            .insert_action("act-alpha", "net.fetch", &json!({}), None, None, "queued")
+            .expect("insert scoped action");
+
+        let mut buf: Vec<u8> = Vec::new();
+        let written = kernel
+            .export_actions_ndjson(&ActionListOptions::default(), &mut buf)
+            .expect("export after soft delete");
+        assert_eq!(written, 5);
+        let text = String::from_utf8(buf).expect("utf8 export");
+        assert!(!text.contains("act-0"));
+
+        let mut buf: Vec<u8> = Vec::new();
+        let written = alpha
+            .export_actions_ndjson(&ActionListOptions::default(), &mut buf)
+            .expect("export scoped");
+        assert_eq!(written, 1);
+        let text = String::from_utf8(buf).expect("utf8 export");
+        let row: ActionRow = serde_json::from_str(text.trim()).expect("line decodes");
+        assert_eq!(row.id, "act-alpha");
     }
 
-    pub async fn insert_memory_with_record_async(
-        &self,
-        owned: MemoryInsertOwned,
-    ) -> Result<(String, serde_json::Value)> {
-        self.run_blocking(move |k| {
-            let args = owned.to_args();
-            k.insert_memory_with_record(&args)
-        })
-        .await
+    #[tokio::test]
+    async fn event_attribution_round_trips_through_queries() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let env = arw_events::Envelope {
+            time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            kind: "task.done".into(),
+            payload: json!({ "seq": 0 }),
+            policy: None,
+            ce: None,
+        };
+        // Explicit args attribute the event.
+        kernel
+            .append_event_attributed(&env, Some("agent-a"), Some("proj-x"))
+            .expect("append attributed");
+        // Payload fields attribute it when no args are given; explicit wins.
+        let env = arw_events::Envelope {
+            payload: json!({ "seq": 1, "actor": "agent-b", "proj": "proj-x" }),
+            ..env
+        };
+        kernel.append_event(&env).expect("append from payload");
+        let env = arw_events::Envelope {
+            payload: json!({ "seq": 2, "actor": "agent-b", "proj": "proj-y" }),
+            ..env
+        };
+        kernel
+            .append_event_attributed(&env, Some("agent-a"), None)
+            .expect("append explicit actor");
+
+        let by_proj = kernel
+            .events_by_project("proj-x", 10)
+            .expect("events by project");
+        assert_eq!(by_proj.len(), 2);
+        assert!(by_proj.windows(2).all(|w| w[0].id > w[1].id));
+        let by_actor = kernel.events_by_actor("agent-a", 10).expect("by actor");
+        assert_eq!(by_actor.len(), 2);
+        assert_eq!(by_actor[0].payload["seq"], json!(2));
+        assert_eq!(by_actor[0].actor.as_deref(), Some("agent-a"));
+        assert_eq!(by_actor[0].proj.as_deref(), Some("proj-y"));
+        let by_b = kernel.events_by_actor("agent-b", 10).expect("by actor b");
+        assert_eq!(by_b.len(), 1);
     }
 
-    pub async fn search_memory_async(
-        &self,
-        q: String,
-        lane: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.search_memory(&q, lane.as_deref(), limit))
-            .await
+    #[tokio::test]
+    async fn events_in_range_pages_chronologically() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..8 {
+            let kind = if i % 2 == 0 { "obs.tick" } else { "task.done" };
+            let env = arw_events::Envelope {
+                time: format!("2026-01-01T00:00:0{i}.000Z"),
+                kind: kind.into(),
+                payload: json!({ "seq": i }),
+                policy: None,
+                ce: None,
+            };
+            kernel.append_event(&env).expect("append event");
+        }
+
+        // Inclusive bounds trim both ends of the log.
+        let (rows, cursor) = kernel
+            .events_in_range(
+                "2026-01-01T00:00:02.000Z",
+                "2026-01-01T00:00:06.000Z",
+                &[],
+                100,
+                None,
+            )
+            .expect("range query");
+        assert_eq!(rows.len(), 5);
+        assert!(cursor.is_none());
+        assert!(rows.windows(2).all(|w| w[0].time <= w[1].time));
+
+        // Kind prefixes narrow the range; cursors page without gaps.
+        let kinds = vec!["obs.".to_string()];
+        let (first, cursor) = kernel
+            .events_in_range(
+                "2026-01-01T00:00:00.000Z",
+                "2026-01-01T00:00:07.000Z",
+                &kinds,
+                2,
+                None,
+            )
+            .expect("first page");
+        assert_eq!(first.len(), 2);
+        let cursor = cursor.expect("more pages");
+        let (rest, cursor) = kernel
+            .events_in_range(
+                "2026-01-01T00:00:00.000Z",
+                "2026-01-01T00:00:07.000Z",
+                &kinds,
+                2,
+                Some(&cursor),
+            )
+            .expect("second page");
+        assert_eq!(rest.len(), 2);
+        assert!(cursor.is_none());
+        let seqs: Vec<i64> = first
+            .iter()
+            .chain(rest.iter())
+            .map(|r| r.payload["seq"].as_i64().expect("seq"))
+            .collect();
+        assert_eq!(seqs, vec![0, 2, 4, 6]);
     }
 
-    pub async fn fts_search_memory_async(
-        &self,
-        q: String,
-        lane: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.fts_search_memory(&q, lane.as_deref(), limit))
-            .await
+    #[tokio::test]
+    async fn export_events_ndjson_round_trips_into_fresh_kernel() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..6 {
+            let kind = if i % 2 == 0 { "obs.tick" } else { "task.done" };
+            let env = arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: kind.into(),
+                payload: json!({ "seq": i, "corr_id": format!("corr-{i}") }),
+                policy: None,
+                ce: None,
+            };
+            kernel.append_event(&env).expect("append event");
+        }
+
+        let mut buf: Vec<u8> = Vec::new();
+        let written = kernel
+            .export_events_ndjson(&EventExportRange::default(), &mut buf)
+            .expect("export events");
+        assert_eq!(written, 6);
+        let text = String::from_utf8(buf.clone()).expect("utf8 export");
+        let rows: Vec<EventRow> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("line decodes into EventRow"))
+            .collect();
+        // Oldest first so the stream replays in order.
+        assert!(rows.windows(2).all(|w| w[0].id < w[1].id));
+
+        // Range bounds and kind prefixes narrow the export.
+        let mut narrowed: Vec<u8> = Vec::new();
+        let range = EventExportRange {
+            after_id: Some(rows[1].id),
+            kind_prefix: Some("obs.".to_string()),
+            ..Default::default()
+        };
+        let written = kernel
+            .export_events_ndjson(&range, &mut narrowed)
+            .expect("export narrowed");
+        assert_eq!(written, 2);
+
+        // Importing into a fresh kernel preserves content but mints new ids.
+        let dest_dir = TempDir::new().expect("temp dir");
+        let dest = Kernel::open(dest_dir.path()).expect("kernel open");
+        let imported = dest
+            .import_events_ndjson(&mut std::io::Cursor::new(buf))
+            .expect("import events");
+        assert_eq!(imported, 6);
+        let replayed = dest.recent_events(10, None).expect("recent events");
+        assert_eq!(replayed.len(), 6);
+        assert!(replayed
+            .iter()
+            .any(|r| r.corr_id.as_deref() == Some("corr-3")));
+        let hits = dest
+            .search_events("obs", &[], None, None, 10)
+            .expect("fts search");
+        assert_eq!(hits.len(), 3);
     }
 
-    pub async fn search_memory_by_embedding_async(
-        &self,
-        embed: Vec<f32>,
-        lane: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.search_memory_by_embedding(&embed, lane.as_deref(), limit))
+    #[tokio::test]
+    async fn staging_actions_lifecycle() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let payload = json!({
+            "project": "demo",
+            "evidence": {"link": "https://example.test"}
+        });
+
+        let staging_id = kernel
+            .insert_staging_action_async(
+                "fs.patch".to_string(),
+                payload.clone(),
+                Some("demo".to_string()),
+                Some("alice@example.test".to_string()),
+                payload.get("evidence").cloned(),
+            )
             .await
-    }
+            .expect("insert staging action");
 
-    pub async fn select_memory_hybrid_async(
-        &self,
-        q: Option<String>,
-        embed: Option<Vec<f32>>,
-        lane: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| {
-            k.select_memory_hybrid(q.as_deref(), embed.as_deref(), lane.as_deref(), limit)
-        })
-        .await
-    }
+        let pending = kernel
+            .list_staging_actions_async(Some("pending".to_string()), 10)
+            .await
+            .expect("list pending");
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0]["id"], staging_id);
 
-    pub async fn list_recent_memory_async(
-        &self,
-        lane: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_recent_memory(lane.as_deref(), limit))
+        let review_time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let approved = kernel
+            .update_staging_action_status_async(
+                staging_id.clone(),
+                "approved".to_string(),
+                Some("approved".to_string()),
+                Some("reviewer".to_string()),
+                Some(review_time.clone()),
+                Some("action-1".to_string()),
+            )
             .await
-    }
+            .expect("approve staging");
+        assert!(approved);
 
-    pub async fn find_memory_by_hash_async(
-        &self,
-        hash: String,
-    ) -> Result<Option<serde_json::Value>> {
-        self.run_blocking(move |k| k.find_memory_by_hash(&hash))
+        let record = kernel
+            .get_staging_action_async(staging_id.clone())
             .await
-    }
+            .expect("get staging action")
+            .expect("staging exists");
+        assert_eq!(record.status, "approved");
+        assert_eq!(record.action_id.as_deref(), Some("action-1"));
+        assert_eq!(record.decided_by.as_deref(), Some("reviewer"));
 
-    pub async fn expired_memory_candidates_async(
-        &self,
-        now: DateTime<Utc>,
-        limit: usize,
-    ) -> Result<Vec<MemoryGcCandidate>> {
-        self.run_blocking(move |k| k.expired_memory_candidates(now, limit))
+        let history = kernel
+            .list_staging_actions_async(None, 10)
             .await
+            .expect("list all");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0]["status"], json!("approved"));
     }
 
-    pub async fn lane_overflow_candidates_async(
-        &self,
-        lane: String,
-        cap: usize,
-        limit: usize,
-    ) -> Result<Vec<MemoryGcCandidate>> {
-        self.run_blocking(move |k| k.lane_overflow_candidates(&lane, cap, limit))
+    #[tokio::test]
+    async fn staging_actions_denied() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let payload = json!({"project": "lab"});
+        let id = kernel
+            .insert_staging_action_async(
+                "net.http.get".to_string(),
+                payload.clone(),
+                payload
+                    .get("project")
+                    .and_then(|p| p.as_str())
+                    .map(|s| s.to_string()),
+                None,
+                None,
+            )
             .await
-    }
+            .expect("insert staging");
 
-    pub async fn delete_memory_records_async(&self, ids: Vec<String>) -> Result<usize> {
-        self.run_blocking(move |k| k.delete_memory_records(&ids))
+        let denied = kernel
+            .update_staging_action_status_async(
+                id.clone(),
+                "denied".to_string(),
+                Some("unsupported".to_string()),
+                Some("reviewer".to_string()),
+                Some(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+                None,
+            )
             .await
-    }
+            .expect("deny staging");
+        assert!(denied);
 
-    pub async fn insert_memory_link_async(
-        &self,
-        src_id: String,
-        dst_id: String,
-        rel: Option<String>,
-        weight: Option<f64>,
-    ) -> Result<()> {
-        self.run_blocking(move |k| k.insert_memory_link(&src_id, &dst_id, rel.as_deref(), weight))
+        let record = kernel
+            .get_staging_action_async(id.clone())
             .await
+            .expect("get staging")
+            .expect("staging exists");
+        assert_eq!(record.status, "denied");
+        assert_eq!(record.decision.as_deref(), Some("unsupported"));
+        assert_eq!(record.action_id, None);
     }
 
-    pub async fn backfill_embed_blobs_async(&self, batch_limit: usize) -> Result<usize> {
-        if batch_limit == 0 {
-            return Ok(0);
+    #[tokio::test]
+    async fn detect_event_id_gaps_reports_missing_ranges() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..5 {
+            let env = arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: "gap.test".into(),
+                payload: json!({ "i": i }),
+                policy: None,
+                ce: None,
+            };
+            kernel.append_event(&env).expect("append event");
+        }
+        {
+            let conn = kernel.conn().expect("checkout connection");
+            conn.execute("DELETE FROM events WHERE id=3", [])
+                .expect("delete middle event");
         }
-        self.run_blocking(move |k| k.backfill_embed_blobs(batch_limit))
-            .await
-    }
-
-    pub async fn pending_embed_backfill_async(&self) -> Result<u64> {
-        self.run_blocking(|k| k.pending_embed_backfill()).await
-    }
 
-    pub async fn list_memory_links_async(
-        &self,
-        src_id: String,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_memory_links(&src_id, limit))
+        let gaps = kernel
+            .detect_event_id_gaps_async(1, 5)
             .await
-    }
+            .expect("detect gaps");
+        assert_eq!(gaps, vec![(3, 3)]);
 
-    pub async fn list_memory_links_many_async(
-        &self,
-        src_ids: Vec<String>,
-        limit_per: i64,
-    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
-        self.run_blocking(move |k| k.list_memory_links_many(&src_ids, limit_per))
-            .await
+        // Boundary gaps are reported too, and an empty window is one big gap.
+        assert_eq!(
+            kernel.detect_event_id_gaps(1, 8).expect("detect"),
+            vec![(3, 3), (6, 8)]
+        );
+        assert_eq!(
+            kernel.detect_event_id_gaps(10, 12).expect("detect empty"),
+            vec![(10, 12)]
+        );
+        assert!(kernel
+            .detect_event_id_gaps(4, 5)
+            .expect("contiguous window")
+            .is_empty());
     }
 
-    pub async fn get_memory_async(&self, id: String) -> Result<Option<serde_json::Value>> {
-        self.run_blocking(move |k| k.get_memory(&id)).await
+    #[tokio::test]
+    async fn mock_clock_drives_lease_expiry() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+
+        let ttl_until =
+            (start + chrono::Duration::seconds(60)).to_rfc3339_opts(SecondsFormat::Millis, true);
+        kernel
+            .insert_lease("lease-1", "local", "net:http", None, &ttl_until, None, None)
+            .expect("insert lease");
+
+        let valid = kernel
+            .find_valid_lease("local", "net:http")
+            .expect("find lease");
+        assert!(valid.is_some(), "lease valid before expiry");
+
+        clock.advance(chrono::Duration::seconds(120));
+        let expired = kernel
+            .find_valid_lease("local", "net:http")
+            .expect("find lease after advance");
+        assert!(expired.is_none(), "lease expired once the clock advanced");
+
+        // Row timestamps come from the injected clock as well.
+        kernel
+            .insert_action("act-clock", "demo.echo", &json!({}), None, None, "queued")
+            .expect("insert action");
+        let row = kernel
+            .get_action("act-clock")
+            .expect("get action")
+            .expect("row present");
+        assert_eq!(row.created, "2026-01-01T00:02:00.000Z");
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub async fn insert_lease_async(
-        &self,
-        id: String,
-        subject: String,
-        capability: String,
-        scope: Option<String>,
-        ttl_until: String,
-        budget: Option<f64>,
-        policy_ctx: Option<serde_json::Value>,
-    ) -> Result<()> {
-        self.run_blocking(move |k| {
-            k.insert_lease(
-                &id,
-                &subject,
-                &capability,
-                scope.as_deref(),
-                &ttl_until,
-                budget,
-                policy_ctx.as_ref(),
+    #[tokio::test]
+    async fn truncate_all_clears_tables_and_sequences() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+
+        let env = arw_events::Envelope {
+            time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            kind: "truncate.test".into(),
+            payload: json!({"seed": true}),
+            policy: None,
+            ce: None,
+        };
+        let first_id = kernel.append_event(&env).expect("append event");
+        assert_eq!(first_id, 1);
+        kernel
+            .insert_action("act-1", "demo.echo", &json!({}), None, None, "queued")
+            .expect("insert action");
+        kernel
+            .insert_lease(
+                "lease-1",
+                "local",
+                "net:http",
+                None,
+                "2099-01-01T00:00:00Z",
+                None,
+                None,
             )
-        })
-        .await
-    }
+            .expect("insert lease");
+        let goal_id = kernel
+            .insert_orchestrator_job("seed goal", None)
+            .expect("insert job");
+        // Seed the fold cursors so truncate has rollup state to reset.
+        kernel
+            .append_contribution("alice", "tokens", 1.0, "n", None, None, None)
+            .expect("append contribution");
+        assert_eq!(kernel.rollup_contributions().expect("fold"), 1);
+        kernel
+            .append_egress(
+                "allow", None, None, None, None, None, None, None, None, None, None,
+            )
+            .expect("append egress");
+        assert_eq!(kernel.rollup_egress().expect("fold"), 1);
 
-    pub async fn list_leases_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_leases(limit)).await
-    }
+        kernel.truncate_all().expect("truncate all");
 
-    pub async fn insert_config_snapshot_async(&self, config: serde_json::Value) -> Result<String> {
-        self.run_blocking(move |k| k.insert_config_snapshot(&config))
+        assert!(kernel
+            .recent_events_async(10, None)
             .await
-    }
+            .expect("recent events")
+            .is_empty());
+        assert!(kernel.list_actions(10).expect("list actions").is_empty());
+        assert!(kernel.list_leases(10).expect("list leases").is_empty());
+        assert!(kernel
+            .list_orchestrator_jobs(10)
+            .expect("list jobs")
+            .is_empty());
+        assert!(kernel.get_action("act-1").expect("get action").is_none());
+        assert!(kernel
+            .orchestrator_job_data_history(&goal_id)
+            .expect("history")
+            .is_empty());
+
+        // Rollup tables and their cursors are cleared too; a stale cursor
+        // would make every post-reset ledger row invisible to the fold.
+        {
+            let conn = kernel.conn().expect("checkout connection");
+            for table in [
+                "contribution_rollups",
+                "contribution_rollup_cursor",
+                "egress_rollups",
+                "egress_rollup_cursor",
+                "lease_events",
+                "action_deps",
+                "action_output_chunks",
+                "quotas",
+                "logic_unit_versions",
+                "persona_proposal_votes",
+            ] {
+                let n: i64 = conn
+                    .query_row(&format!("SELECT COUNT(1) FROM {table}"), [], |r| r.get(0))
+                    .expect("count table");
+                assert_eq!(n, 0, "{table} not truncated");
+            }
+        }
+        kernel
+            .append_contribution("bob", "tokens", 2.0, "n", None, None, None)
+            .expect("append after truncate");
+        assert_eq!(kernel.rollup_contributions().expect("fold"), 1);
 
-    pub async fn get_config_snapshot_async(&self, id: String) -> Result<Option<serde_json::Value>> {
-        self.run_blocking(move |k| k.get_config_snapshot(&id)).await
+        // Schema survives and autoincrement ids restart from 1.
+        let next_id = kernel.append_event(&env).expect("append after truncate");
+        assert_eq!(next_id, 1);
     }
 
-    pub async fn list_config_snapshots_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_config_snapshots(limit))
+    #[tokio::test]
+    async fn events_prune_respects_max_rows() {
+        let dir = TempDir::new().expect("temp dir");
+        let prev_max = std::env::var("ARW_EVENTS_MAX_ROWS").ok();
+        let prev_sec = std::env::var("ARW_EVENTS_PRUNE_SEC").ok();
+        std::env::set_var("ARW_EVENTS_MAX_ROWS", "5");
+        std::env::set_var("ARW_EVENTS_PRUNE_SEC", "1");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..12 {
+            let env = arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: "prune.test".into(),
+                payload: json!({ "i": i }),
+                policy: None,
+                ce: None,
+            };
+            kernel.append_event_async(&env).await.expect("append event");
+        }
+        {
+            let conn = kernel.conn().expect("checkout connection for prune");
+            let retention = EventRetention {
+                max_rows: Some(5),
+                ..Default::default()
+            };
+            Kernel::prune_events(&conn, &retention).expect("prune events");
+        }
+        let remaining = kernel
+            .recent_events_async(20, None)
             .await
+            .expect("recent events");
+        assert!(
+            remaining.len() as u64 <= 5,
+            "expected prune to cap events, got {}",
+            remaining.len()
+        );
+        if let Some(prev) = prev_max {
+            std::env::set_var("ARW_EVENTS_MAX_ROWS", prev);
+        } else {
+            std::env::remove_var("ARW_EVENTS_MAX_ROWS");
+        }
+        if let Some(prev) = prev_sec {
+            std::env::set_var("ARW_EVENTS_PRUNE_SEC", prev);
+        } else {
+            std::env::remove_var("ARW_EVENTS_PRUNE_SEC");
+        }
     }
 
-    pub async fn insert_logic_unit_async(
-        &self,
-        id: String,
-        manifest: serde_json::Value,
-        status: String,
-    ) -> Result<()> {
-        self.run_blocking(move |k| k.insert_logic_unit(&id, &manifest, &status))
+    #[tokio::test]
+    async fn bulk_decide_staging_actions_only_touches_pending() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            let id = kernel
+                .insert_staging_action_async(
+                    "fs.patch".to_string(),
+                    json!({"i": i}),
+                    Some("demo".to_string()),
+                    None,
+                    None,
+                )
+                .await
+                .expect("insert staging action");
+            ids.push(id);
+        }
+
+        let updated = kernel
+            .bulk_decide_staging_actions_async(
+                ids[..2].to_vec(),
+                "approved".to_string(),
+                Some("approved".to_string()),
+                Some("reviewer".to_string()),
+            )
             .await
-    }
+            .expect("bulk approve");
+        assert_eq!(updated, 2);
 
-    pub async fn list_logic_units_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_logic_units(limit)).await
-    }
+        // Already-decided items are skipped on a second pass.
+        let repeat = kernel
+            .bulk_decide_staging_actions_async(
+                ids.clone(),
+                "denied".to_string(),
+                Some("denied".to_string()),
+                Some("reviewer".to_string()),
+            )
+            .await
+            .expect("bulk deny remainder");
+        assert_eq!(repeat, 1, "only the still-pending item transitions");
 
-    pub async fn insert_orchestrator_job_async(
-        &self,
-        goal: &str,
-        data: Option<&serde_json::Value>,
-    ) -> Result<String> {
-        let goal_owned = goal.to_string();
-        let data_clone = data.cloned();
-        self.run_blocking(move |k| k.insert_orchestrator_job(&goal_owned, data_clone.as_ref()))
+        let first = kernel
+            .get_staging_action_async(ids[0].clone())
+            .await
+            .expect("get staging")
+            .expect("staging exists");
+        assert_eq!(first.status, "approved");
+        assert!(first.decided_at.is_some());
+        let last = kernel
+            .get_staging_action_async(ids[2].clone())
             .await
+            .expect("get staging")
+            .expect("staging exists");
+        assert_eq!(last.status, "denied");
+        assert_eq!(last.decided_by.as_deref(), Some("reviewer"));
     }
 
-    pub async fn update_orchestrator_job_async(
-        &self,
-        id: String,
-        status: Option<String>,
-        progress: Option<f64>,
-        data_patch: Option<serde_json::Value>,
-    ) -> Result<bool> {
-        self.run_blocking(move |k| {
-            k.update_orchestrator_job(&id, status.as_deref(), progress, data_patch.as_ref())
-        })
-        .await
-    }
+    #[tokio::test]
+    async fn list_corr_ids_groups_counts_and_skips_nulls() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let append = |corr: Option<&str>| {
+            let payload = match corr {
+                Some(c) => json!({"corr_id": c}),
+                None => json!({}),
+            };
+            arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: "trace.step".into(),
+                payload,
+                policy: None,
+                ce: None,
+            }
+        };
+        for corr in [
+            Some("corr-a"),
+            Some("corr-a"),
+            Some("corr-b"),
+            None,
+            Some("corr-a"),
+        ] {
+            kernel
+                .append_event_async(&append(corr))
+                .await
+                .expect("append event");
+        }
 
-    pub async fn list_orchestrator_jobs_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_orchestrator_jobs(limit))
+        let grouped = kernel
+            .list_corr_ids_async(None, 10)
             .await
-    }
+            .expect("list corr ids");
+        assert_eq!(grouped.len(), 2, "null corr_id excluded");
+        assert_eq!(
+            grouped[0],
+            ("corr-a".to_string(), 3),
+            "most recently active trace first"
+        );
+        assert_eq!(grouped[1], ("corr-b".to_string(), 1));
 
-    pub async fn update_action_result_async(
-        &self,
-        id: String,
-        output: Option<serde_json::Value>,
-        error: Option<String>,
-    ) -> Result<bool> {
-        self.run_blocking(move |k| k.update_action_result(&id, output.as_ref(), error.as_deref()))
-            .await
+        let none_since = kernel
+            .list_corr_ids(Some("2099-01-01T00:00:00.000Z"), 10)
+            .expect("future window");
+        assert!(none_since.is_empty());
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub async fn append_egress_async(
-        &self,
-        decision: String,
-        reason: Option<String>,
-        dest_host: Option<String>,
-        dest_port: Option<i64>,
-        protocol: Option<String>,
-        bytes_in: Option<i64>,
-        bytes_out: Option<i64>,
-        corr_id: Option<String>,
-        proj: Option<String>,
-        posture: Option<String>,
-        meta: Option<serde_json::Value>,
-    ) -> Result<i64> {
-        let meta = meta.map(std::sync::Arc::new);
-        self.run_blocking(move |k| {
-            k.append_egress(
-                &decision,
-                reason.as_deref(),
-                dest_host.as_deref(),
-                dest_port,
-                protocol.as_deref(),
-                bytes_in,
-                bytes_out,
-                corr_id.as_deref(),
-                proj.as_deref(),
-                posture.as_deref(),
-                meta.as_deref(),
+    #[tokio::test]
+    async fn merge_action_meta_accumulates_without_clobbering() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_action("act-meta", "demo.work", &json!({}), None, None, "running")
+            .expect("insert action");
+
+        assert!(kernel
+            .merge_action_meta_async(
+                "act-meta".to_string(),
+                json!({"progress": {"pct": 25}, "note": "started"}),
             )
-        })
-        .await
-    }
+            .await
+            .expect("first merge"));
+        assert!(kernel
+            .merge_action_meta_async(
+                "act-meta".to_string(),
+                json!({"progress": {"pct": 80, "stage": "upload"}}),
+            )
+            .await
+            .expect("second merge"));
+
+        let action = kernel
+            .get_action("act-meta")
+            .expect("get action")
+            .expect("action exists");
+        let meta = action.meta.expect("meta surfaced");
+        assert_eq!(meta["progress"]["pct"], 80, "later patch wins per key");
+        assert_eq!(meta["progress"]["stage"], "upload");
+        assert_eq!(meta["note"], "started", "untouched keys survive");
+        assert!(action.output.is_none(), "meta stays separate from output");
 
-    pub async fn dequeue_one_queued_async(
-        &self,
-    ) -> Result<Option<(String, String, serde_json::Value)>> {
-        self.run_blocking(|k| k.dequeue_one_queued()).await
+        assert!(!kernel
+            .merge_action_meta("missing", &json!({"x": 1}))
+            .expect("merge on missing id"));
     }
 
-    pub async fn append_event_async(&self, env: &arw_events::Envelope) -> Result<i64> {
-        let env = env.clone();
-        self.run_blocking(move |k| k.append_event(&env)).await
-    }
+    #[tokio::test]
+    async fn persona_proposal_rejects_malformed_diff_before_insert() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let proposal = |diff: serde_json::Value| PersonaProposalCreate {
+            persona_id: "persona-1".to_string(),
+            submitted_by: "alice".to_string(),
+            diff,
+            rationale: None,
+            telemetry_scope: json!({}),
+            leases_required: json!([]),
+        };
 
-    pub async fn recent_events_async(
-        &self,
-        limit: i64,
-        after_id: Option<i64>,
-    ) -> Result<Vec<EventRow>> {
-        self.run_blocking(move |k| k.recent_events(limit, after_id))
+        // `op` is not a valid JSON-patch operation.
+        let err = kernel
+            .insert_persona_proposal_async(proposal(json!([
+                {"op": "teleport", "path": "/name", "value": "x"}
+            ])))
+            .await
+            .expect_err("malformed op rejected");
+        assert!(err.to_string().contains("invalid persona diff"), "{err}");
+        // Scalar diffs are neither patch arrays nor mergeable objects.
+        assert!(Kernel::validate_persona_diff(&json!("nope")).is_err());
+
+        // Valid shapes still insert.
+        let patch_id = kernel
+            .insert_persona_proposal_async(proposal(json!([
+                {"op": "replace", "path": "/name", "value": "New Name"}
+            ])))
+            .await
+            .expect("patch diff accepted");
+        assert!(!patch_id.is_empty());
+        let merge_id = kernel
+            .insert_persona_proposal_async(proposal(json!({"name": "Merged"})))
             .await
+            .expect("merge diff accepted");
+        assert!(!merge_id.is_empty());
     }
 
-    pub async fn events_by_corr_id_async(
-        &self,
-        corr_id: &str,
-        limit: Option<i64>,
-    ) -> Result<Vec<EventRow>> {
-        let cid = corr_id.to_string();
-        self.run_blocking(move |k| k.events_by_corr_id(&cid, limit))
+    #[tokio::test]
+    async fn action_age_stats_reports_percentiles_in_seconds() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("start time")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        for i in 0..5 {
+            kernel
+                .insert_action(
+                    &format!("age-{i}"),
+                    "demo.work",
+                    &json!({}),
+                    None,
+                    None,
+                    "queued",
+                )
+                .expect("insert action");
+            clock.advance(chrono::Duration::seconds(100));
+        }
+        // Clock now sits 500s past the first insert; ages are 100..=500.
+        let now = start + chrono::Duration::seconds(500);
+        let stats = kernel
+            .action_age_stats_async(
+                "queued".to_string(),
+                now.to_rfc3339_opts(SecondsFormat::Millis, true),
+            )
             .await
+            .expect("age stats");
+        assert_eq!(stats["count"], 5);
+        assert_eq!(stats["p50_s"], 300);
+        assert_eq!(stats["p90_s"], 400);
+        assert_eq!(stats["max_s"], 500);
+
+        let empty = kernel
+            .action_age_stats("running", &now.to_rfc3339_opts(SecondsFormat::Millis, true))
+            .expect("empty stats");
+        assert_eq!(empty["count"], 0);
+        assert!(empty["p50_s"].is_null());
     }
 
-    pub async fn events_by_corr_ids_async(
-        &self,
-        corr_ids: Vec<String>,
-        limit: Option<i64>,
-    ) -> Result<HashMap<String, Vec<EventRow>>> {
-        self.run_blocking(move |k| k.events_by_corr_ids(&corr_ids, limit))
+    #[tokio::test]
+    async fn try_enqueue_action_rejects_when_queue_is_full() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..3 {
+            let accepted = kernel
+                .try_enqueue_action_async(
+                    format!("queued-{i}"),
+                    "demo.work".to_string(),
+                    json!({"i": i}),
+                    None,
+                    None,
+                    3,
+                )
+                .await
+                .expect("enqueue under cap");
+            assert!(accepted.is_some());
+        }
+
+        let rejected = kernel
+            .try_enqueue_action_async(
+                "queued-overflow".to_string(),
+                "demo.work".to_string(),
+                json!({}),
+                None,
+                None,
+                3,
+            )
+            .await
+            .expect("enqueue at cap");
+        assert!(rejected.is_none(), "cap reached, admission must refuse");
+        assert_eq!(
+            kernel.count_actions_by_state("queued").expect("count"),
+            3,
+            "rejected submission leaves the queue untouched"
+        );
+
+        // Draining a slot re-admits work.
+        kernel
+            .set_action_state_async("queued-0", "running")
+            .await
+            .expect("drain one");
+        let readmitted = kernel
+            .try_enqueue_action_async(
+                "queued-3".to_string(),
+                "demo.work".to_string(),
+                json!({}),
+                None,
+                None,
+                3,
+            )
             .await
+            .expect("enqueue after drain");
+        assert!(readmitted.is_some());
     }
 
-    pub async fn tail_events_async(
-        &self,
-        limit: i64,
-        prefixes: Vec<String>,
-    ) -> Result<(Vec<EventRow>, i64)> {
-        self.run_blocking(move |k| k.tail_events(limit, &prefixes))
+    #[tokio::test]
+    async fn subscribe_events_filtered_only_forwards_matching_kinds() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let mut rx = kernel.subscribe_events_filtered(&["egress.".to_string()]);
+        for kind in [
+            "egress.ledger.appended",
+            "action.completed",
+            "egress.preview",
+        ] {
+            let env = arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: kind.into(),
+                payload: json!({"kind": kind}),
+                policy: None,
+                ce: None,
+            };
+            kernel.append_event_async(&env).await.expect("append event");
+        }
+
+        let first = rx.recv().await.expect("first egress event");
+        assert_eq!(first.kind, "egress.ledger.appended");
+        let second = rx.recv().await.expect("second egress event");
+        assert_eq!(second.kind, "egress.preview");
+        assert!(
+            rx.try_recv().is_err(),
+            "action.* kinds must not reach an egress.-filtered subscriber"
+        );
+    }
+
+    #[tokio::test]
+    async fn action_authorizing_leases_resolves_policy_ctx() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_lease(
+                "lease-net",
+                "local",
+                "net:http",
+                None,
+                "2099-01-01T00:00:00.000Z",
+                None,
+                None,
+            )
+            .expect("insert valid lease");
+        kernel
+            .insert_lease(
+                "lease-stale",
+                "local",
+                "io:egress",
+                None,
+                "2000-01-01T00:00:00.000Z",
+                None,
+                None,
+            )
+            .expect("insert expired lease");
+        kernel
+            .insert_action(
+                "act-1",
+                "net.http.get",
+                &json!({"url": "https://example.test"}),
+                Some(&json!({
+                    "required_capabilities": ["net:http", "io:egress"],
+                    "leases": ["lease-net"]
+                })),
+                None,
+                "queued",
+            )
+            .expect("insert action");
+
+        let leases = kernel
+            .action_authorizing_leases_async("act-1".to_string())
             .await
+            .expect("resolve leases");
+        assert_eq!(
+            leases.len(),
+            1,
+            "expired lease skipped, duplicate collapsed"
+        );
+        assert_eq!(leases[0]["id"], "lease-net");
+        assert_eq!(leases[0]["capability"], "net:http");
+
+        // Actions without a policy context yield nothing.
+        kernel
+            .insert_action("act-2", "chat.respond", &json!({}), None, None, "queued")
+            .expect("insert plain action");
+        assert!(kernel
+            .action_authorizing_leases("act-2")
+            .expect("resolve empty")
+            .is_empty());
     }
 
-    pub async fn count_actions_by_state_async(&self, state: &str) -> Result<i64> {
-        let s = state.to_string();
-        self.run_blocking(move |k| k.count_actions_by_state(&s))
+    #[tokio::test]
+    async fn config_snapshot_digests_dedup_identical_configs() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let cfg = json!({"mode": "standard", "port": 8091});
+
+        let first = kernel
+            .insert_config_snapshot_dedup_async(cfg.clone(), false)
+            .await
+            .expect("first snapshot");
+        let deduped = kernel
+            .insert_config_snapshot_dedup_async(cfg.clone(), true)
+            .await
+            .expect("dedup snapshot");
+        assert_eq!(
+            first, deduped,
+            "identical latest config should not re-insert"
+        );
+
+        let listed = kernel
+            .list_config_snapshots_async(10)
+            .await
+            .expect("list snapshots");
+        assert_eq!(listed.len(), 1);
+        let sha = listed[0]["sha256"]
+            .as_str()
+            .expect("sha present")
+            .to_string();
+        assert_eq!(sha.len(), 64);
+        let found = kernel
+            .find_config_snapshot_by_digest_async(sha)
+            .await
+            .expect("digest lookup");
+        assert_eq!(found.as_deref(), Some(first.as_str()));
+
+        // A different config inserts fresh even with dedup requested.
+        let second = kernel
+            .insert_config_snapshot_dedup_async(json!({"mode": "trial"}), true)
             .await
+            .expect("second snapshot");
+        assert_ne!(first, second);
+        assert_eq!(
+            kernel
+                .list_config_snapshots_async(10)
+                .await
+                .expect("list snapshots")
+                .len(),
+            2
+        );
     }
 
-    pub async fn find_action_by_idem_async(&self, idem: &str) -> Result<Option<String>> {
-        let s = idem.to_string();
-        self.run_blocking(move |k| k.find_action_by_idem(&s)).await
+    #[tokio::test]
+    async fn fresh_database_is_stamped_with_all_schema_versions() {
+        let dir = TempDir::new().expect("temp dir");
+        let k = Kernel::open(dir.path()).expect("kernel open");
+        let latest = SCHEMA_MIGRATIONS.last().expect("migrations listed").version;
+        assert_eq!(k.schema_version().expect("schema version"), latest);
+        assert!(k
+            .pending_schema_migrations()
+            .expect("pending migrations")
+            .is_empty());
     }
 
-    pub async fn insert_action_async(
-        &self,
-        id: &str,
-        kind: &str,
-        input: &serde_json::Value,
-        policy_ctx: Option<&serde_json::Value>,
-        idem_key: Option<&str>,
-        state: &str,
-    ) -> Result<()> {
-        let id = id.to_string();
-        let kind = kind.to_string();
-        let input = input.clone();
-        let policy_ctx = policy_ctx.cloned();
-        let idem_key = idem_key.map(|s| s.to_string());
-        let state_s = state.to_string();
-        self.run_blocking(move |k| {
-            k.insert_action(
-                &id,
-                &kind,
-                &input,
-                policy_ctx.as_ref(),
-                idem_key.as_deref(),
-                &state_s,
+    #[tokio::test]
+    async fn legacy_database_without_version_table_is_migrated_on_open() {
+        let dir = TempDir::new().expect("temp dir");
+        // Simulate an install that predates both the version table and the
+        // columns the migrations add.
+        {
+            let conn =
+                rusqlite::Connection::open(dir.path().join("events.sqlite")).expect("open raw");
+            conn.execute_batch(
+                r#"
+                CREATE TABLE events (id INTEGER PRIMARY KEY AUTOINCREMENT, time TEXT NOT NULL, kind TEXT NOT NULL, actor TEXT, proj TEXT, corr_id TEXT, payload TEXT NOT NULL);
+                CREATE TABLE egress_ledger (id INTEGER PRIMARY KEY AUTOINCREMENT, time TEXT NOT NULL);
+                CREATE TABLE orchestrator_jobs (id TEXT PRIMARY KEY, status TEXT NOT NULL);
+                CREATE TABLE config_snapshots (id TEXT PRIMARY KEY, config TEXT NOT NULL);
+                CREATE TABLE actions (id TEXT PRIMARY KEY, kind TEXT NOT NULL, state TEXT, created TEXT NOT NULL DEFAULT '');
+                CREATE TABLE leases (id TEXT PRIMARY KEY, subject TEXT NOT NULL, capability TEXT NOT NULL);
+                CREATE TABLE staging_actions (id TEXT PRIMARY KEY, action_kind TEXT NOT NULL, action_input TEXT NOT NULL, status TEXT NOT NULL);
+                CREATE TABLE persona_proposals (proposal_id TEXT PRIMARY KEY, persona_id TEXT NOT NULL, submitted_by TEXT NOT NULL, diff TEXT NOT NULL, status TEXT NOT NULL, created TEXT NOT NULL, updated TEXT NOT NULL);
+                "#,
             )
-        })
-        .await
+            .expect("legacy schema");
+        }
+        let k = Kernel::open(dir.path()).expect("kernel open");
+        let latest = SCHEMA_MIGRATIONS.last().expect("migrations listed").version;
+        assert_eq!(k.schema_version().expect("schema version"), latest);
+        let conn = rusqlite::Connection::open(dir.path().join("events.sqlite")).expect("reopen");
+        for m in SCHEMA_MIGRATIONS {
+            let (table, column) = m.column;
+            // Index-only migrations declare no column; their DDL is idempotent.
+            if column.is_empty() {
+                continue;
+            }
+            assert!(
+                Kernel::column_exists(&conn, table, column).expect("column check"),
+                "migration {} did not add {}.{}",
+                m.version,
+                table,
+                column
+            );
+        }
     }
 
-    pub async fn get_action_async(&self, id: &str) -> Result<Option<ActionRow>> {
-        let s = id.to_string();
-        self.run_blocking(move |k| k.get_action(&s)).await
+    #[tokio::test]
+    async fn dry_run_lists_pending_migrations_without_applying() {
+        let dir = TempDir::new().expect("temp dir");
+        let conn = rusqlite::Connection::open(dir.path().join("events.sqlite")).expect("open raw");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE events (id INTEGER PRIMARY KEY AUTOINCREMENT, time TEXT NOT NULL, kind TEXT NOT NULL, actor TEXT, proj TEXT, corr_id TEXT, payload TEXT NOT NULL);
+            CREATE TABLE egress_ledger (id INTEGER PRIMARY KEY AUTOINCREMENT, time TEXT NOT NULL);
+            CREATE TABLE orchestrator_jobs (id TEXT PRIMARY KEY, status TEXT NOT NULL);
+            CREATE TABLE config_snapshots (id TEXT PRIMARY KEY, config TEXT NOT NULL);
+            CREATE TABLE actions (id TEXT PRIMARY KEY, kind TEXT NOT NULL, state TEXT, created TEXT NOT NULL DEFAULT '');
+            CREATE TABLE leases (id TEXT PRIMARY KEY, subject TEXT NOT NULL, capability TEXT NOT NULL);
+            CREATE TABLE staging_actions (id TEXT PRIMARY KEY, action_kind TEXT NOT NULL, action_input TEXT NOT NULL, status TEXT NOT NULL);
+            CREATE TABLE persona_proposals (proposal_id TEXT PRIMARY KEY, persona_id TEXT NOT NULL, submitted_by TEXT NOT NULL, diff TEXT NOT NULL, status TEXT NOT NULL, created TEXT NOT NULL, updated TEXT NOT NULL);
+            "#,
+        )
+        .expect("legacy schema");
+        let pending = Kernel::run_schema_migrations(&conn, true).expect("dry run");
+        assert_eq!(
+            pending,
+            SCHEMA_MIGRATIONS
+                .iter()
+                .map(|m| m.version)
+                .collect::<Vec<_>>()
+        );
+        // Nothing was applied or stamped.
+        assert!(!Kernel::column_exists(&conn, "actions", "meta").expect("column check"));
+        let stamped: i64 = conn
+            .query_row("SELECT COUNT(1) FROM schema_version", [], |r| r.get(0))
+            .expect("count");
+        assert_eq!(stamped, 0);
+        // A real run applies everything the dry run reported.
+        let applied = Kernel::run_schema_migrations(&conn, false).expect("apply");
+        assert_eq!(applied, pending);
+        assert!(Kernel::column_exists(&conn, "actions", "meta").expect("column check"));
+    }
+
+    fn insert_event_at(conn: &rusqlite::Connection, time: &str, kind: &str) {
+        conn.execute(
+            "INSERT INTO events(time, kind, payload) VALUES(?, ?, '{}')",
+            params![time, kind],
+        )
+        .expect("insert event");
     }
 
-    pub async fn set_action_state_async(&self, id: &str, state: &str) -> Result<bool> {
-        let id_s = id.to_string();
-        let st = state.to_string();
-        self.run_blocking(move |k| k.set_action_state(&id_s, &st))
-            .await
+    #[tokio::test]
+    async fn prune_exempt_prefixes_survive_age_and_row_caps() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let conn = kernel.conn().expect("checkout connection");
+        let old = (chrono::Utc::now() - Duration::from_secs(30 * 86_400))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        insert_event_at(&conn, &old, "egress.ledger.appended");
+        insert_event_at(&conn, &old, "probe.hw");
+        let retention = EventRetention {
+            max_age: Some(Duration::from_secs(86_400)),
+            exempt_prefixes: vec!["egress.".into()],
+            ..Default::default()
+        };
+        let pruned = Kernel::prune_events(&conn, &retention).expect("prune");
+        assert_eq!(pruned, 1);
+        let kinds: Vec<String> = {
+            let mut stmt = conn.prepare("SELECT kind FROM events").expect("stmt");
+            stmt.query_map([], |r| r.get(0))
+                .expect("rows")
+                .collect::<std::result::Result<_, _>>()
+                .expect("kinds")
+        };
+        assert_eq!(kinds, vec!["egress.ledger.appended".to_string()]);
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub async fn append_contribution_async(
-        &self,
-        subject: &str,
-        kind: &str,
-        qty: f64,
-        unit: &str,
-        corr_id: Option<&str>,
-        proj: Option<&str>,
-        meta: Option<&serde_json::Value>,
-    ) -> Result<i64> {
-        let subject = subject.to_string();
-        let kind = kind.to_string();
-        let unit = unit.to_string();
-        let corr_id = corr_id.map(|s| s.to_string());
-        let proj = proj.map(|s| s.to_string());
-        let meta = meta.cloned();
-        self.run_blocking(move |k| {
-            k.append_contribution(
-                &subject,
-                &kind,
-                qty,
-                &unit,
-                corr_id.as_deref(),
-                proj.as_deref(),
-                meta.as_ref(),
+    #[tokio::test]
+    async fn prune_per_kind_overrides_win_over_global_age() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let conn = kernel.conn().expect("checkout connection");
+        let two_days_ago = (chrono::Utc::now() - Duration::from_secs(2 * 86_400))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        insert_event_at(&conn, &two_days_ago, "models.download.progress");
+        insert_event_at(&conn, &two_days_ago, "chat.message");
+        let retention = EventRetention {
+            max_age: Some(Duration::from_secs(7 * 86_400)),
+            kind_max_age: vec![(
+                "models.download.progress".into(),
+                Duration::from_secs(86_400),
+            )],
+            ..Default::default()
+        };
+        let pruned = Kernel::prune_events(&conn, &retention).expect("prune");
+        assert_eq!(pruned, 1, "only the overridden kind should age out");
+        let remaining: i64 = conn
+            .query_row(
+                "SELECT COUNT(1) FROM events WHERE kind = 'chat.message'",
+                [],
+                |r| r.get(0),
             )
-        })
-        .await
+            .expect("count");
+        assert_eq!(remaining, 1);
     }
 
-    pub async fn upsert_research_watcher_item_async(
-        &self,
-        source: Option<String>,
-        source_id: Option<String>,
-        title: Option<String>,
-        summary: Option<String>,
-        url: Option<String>,
-        payload: Option<serde_json::Value>,
-    ) -> Result<String> {
-        self.run_blocking(move |k| {
-            k.upsert_research_watcher_item(
-                source.as_deref(),
-                source_id.as_deref(),
-                title.as_deref(),
-                summary.as_deref(),
-                url.as_deref(),
-                payload.as_ref(),
-            )
-        })
-        .await
+    #[cfg(feature = "compress-events")]
+    #[tokio::test]
+    async fn compressed_payloads_round_trip_transparently() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let big_note = "x".repeat(4096);
+        let env = arw_events::Envelope {
+            time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            kind: "compress.test".into(),
+            payload: json!({ "note": big_note }),
+            policy: None,
+            ce: None,
+        };
+        kernel.append_event_async(&env).await.expect("append event");
+        let conn = kernel.conn().expect("checkout connection");
+        let stored_type: String = conn
+            .query_row("SELECT typeof(payload) FROM events", [], |r| r.get(0))
+            .expect("typeof");
+        assert_eq!(stored_type, "blob", "large payload should be compressed");
+        let rows = kernel
+            .recent_events_async(10, None)
+            .await
+            .expect("recent events");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].payload, env.payload);
     }
 
-    pub async fn list_research_watcher_items_async(
-        &self,
-        status: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_research_watcher_items(status.as_deref(), limit))
+    #[cfg(feature = "compress-events")]
+    #[tokio::test]
+    async fn compress_backfill_rewrites_existing_text_rows() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let payload = json!({ "note": "y".repeat(2048) });
+        {
+            let conn = kernel.conn().expect("checkout connection");
+            // Simulate rows written before the feature was enabled.
+            for _ in 0..3 {
+                conn.execute(
+                    "INSERT INTO events(time, kind, payload) VALUES(?, 'compress.backfill', ?)",
+                    params![
+                        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                        serde_json::to_string(&payload).expect("serialize"),
+                    ],
+                )
+                .expect("insert event");
+            }
+        }
+        let rewritten = kernel
+            .compress_existing_events_async(10)
+            .await
+            .expect("backfill");
+        assert_eq!(rewritten, 3);
+        assert_eq!(
+            kernel
+                .compress_existing_events_async(10)
+                .await
+                .expect("backfill again"),
+            0
+        );
+        let rows = kernel
+            .recent_events_async(10, None)
+            .await
+            .expect("recent events");
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|r| r.payload == payload));
+    }
+
+    #[tokio::test]
+    async fn search_events_matches_payloads_with_kind_and_time_filters() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let append = |kind: &str, note: &str| {
+            let env = arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: kind.into(),
+                payload: json!({ "note": note }),
+                policy: None,
+                ce: None,
+            };
+            kernel.append_event(&env).expect("append event")
+        };
+        append("chat.message", "quarterly forecast draft");
+        append("chat.message", "grocery list");
+        append("tool.ran", "quarterly numbers crunched");
+        let hits = kernel
+            .search_events_async("quarterly".into(), vec![], None, None, 10)
+            .await
+            .expect("search");
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].id > hits[1].id, "newest hits first");
+        let hits = kernel
+            .search_events_async(
+                "quarterly".into(),
+                vec!["chat.message".into()],
+                None,
+                None,
+                10,
+            )
+            .await
+            .expect("search with kind filter");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, "chat.message");
+        let hits = kernel
+            .search_events_async(
+                "quarterly".into(),
+                vec![],
+                Some("2099-01-01T00:00:00Z".into()),
+                None,
+                10,
+            )
             .await
+            .expect("search with time filter");
+        assert!(hits.is_empty());
     }
 
-    pub async fn update_research_watcher_status_async(
-        &self,
-        id: String,
-        status: String,
-        note: Option<String>,
-    ) -> Result<bool> {
-        self.run_blocking(move |k| k.update_research_watcher_status(&id, &status, note.as_deref()))
+    #[tokio::test]
+    async fn events_page_walks_both_directions_with_cursors() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..25 {
+            let env = arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: "page.test".into(),
+                payload: json!({ "i": i }),
+                policy: None,
+                ce: None,
+            };
+            kernel.append_event_async(&env).await.expect("append event");
+        }
+        let first = kernel.events_page_async(None, 10).await.expect("head page");
+        assert_eq!(first.items.len(), 10);
+        assert!(first.has_more);
+        assert_eq!(first.items[0].payload["i"], json!(24));
+        let second = kernel
+            .events_page_async(first.next_cursor.clone(), 10)
             .await
-    }
-
-    pub async fn get_research_watcher_item_async(
-        &self,
-        id: String,
-    ) -> Result<Option<ResearchWatcherItem>> {
-        self.run_blocking(move |k| k.get_research_watcher_item(&id))
+            .expect("older page");
+        assert_eq!(second.items[0].payload["i"], json!(14));
+        assert!(second.has_more);
+        let third = kernel
+            .events_page_async(second.next_cursor.clone(), 10)
+            .await
+            .expect("oldest page");
+        assert_eq!(third.items.len(), 5);
+        assert!(!third.has_more);
+        assert!(third.next_cursor.is_none());
+        // Walk back toward the head from the oldest page.
+        let newer = kernel
+            .events_page_async(third.prev_cursor.clone(), 10)
             .await
+            .expect("newer page");
+        assert_eq!(newer.items.len(), 10);
+        assert_eq!(newer.items[0].payload["i"], json!(14));
+        assert!(newer.has_more);
+        assert!(kernel
+            .events_page_async(Some("not-a-cursor".into()), 10)
+            .await
+            .is_err());
     }
 
-    pub async fn insert_staging_action_async(
-        &self,
-        action_kind: String,
-        action_input: serde_json::Value,
-        project: Option<String>,
-        requested_by: Option<String>,
-        evidence: Option<serde_json::Value>,
-    ) -> Result<String> {
-        self.run_blocking(move |k| {
-            k.insert_staging_action(
-                &action_kind,
-                &action_input,
-                project.as_deref(),
-                requested_by.as_deref(),
-                evidence.as_ref(),
-            )
-        })
-        .await
+    #[tokio::test]
+    async fn archive_events_writes_segments_and_truncates_live_table() {
+        use std::io::BufRead as _;
+        let dir = TempDir::new().expect("temp dir");
+        let dest = TempDir::new().expect("dest dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let old = (chrono::Utc::now() - Duration::from_secs(30 * 86_400))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        {
+            let conn = kernel.conn().expect("checkout connection");
+            for i in 0..12 {
+                conn.execute(
+                    "INSERT INTO events(time, kind, payload) VALUES(?, 'archive.test', ?)",
+                    params![old, format!("{{\"i\":{i}}}")],
+                )
+                .expect("insert event");
+            }
+        }
+        let env = arw_events::Envelope {
+            time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            kind: "archive.keep".into(),
+            payload: json!({}),
+            policy: None,
+            ce: None,
+        };
+        kernel.append_event_async(&env).await.expect("append event");
+        let cutoff = (chrono::Utc::now() - Duration::from_secs(86_400))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let report = kernel
+            .archive_events_segmented(&cutoff, dest.path(), 5)
+            .expect("archive");
+        assert_eq!(report.events, 12);
+        assert_eq!(report.segments.len(), 3, "12 rows in segments of 5");
+        let remaining = kernel
+            .recent_events_async(50, None)
+            .await
+            .expect("recent events");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].kind, "archive.keep");
+        // Every archived event is readable back out of the segments.
+        let mut restored = 0usize;
+        for name in &report.segments {
+            let file = std::fs::File::open(dest.path().join(name)).expect("open segment");
+            let reader = std::io::BufReader::new(flate2::read::GzDecoder::new(file));
+            for line in reader.lines() {
+                let row: EventRow =
+                    serde_json::from_str(&line.expect("segment line")).expect("parse row");
+                assert_eq!(row.kind, "archive.test");
+                restored += 1;
+            }
+        }
+        assert_eq!(restored, 12);
     }
 
-    pub async fn list_staging_actions_async(
-        &self,
-        status: Option<String>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_staging_actions(status.as_deref(), limit))
+    #[tokio::test]
+    async fn append_events_commits_batch_atomically_and_fans_out() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let mut rx = kernel.subscribe_events_filtered(&["batch.".to_string()]);
+        let envs: Vec<arw_events::Envelope> = (0..3)
+            .map(|i| arw_events::Envelope {
+                time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                kind: format!("batch.item{i}"),
+                payload: json!({ "i": i }),
+                policy: None,
+                ce: None,
+            })
+            .collect();
+        let ids = kernel
+            .append_events_async(envs)
+            .await
+            .expect("append batch");
+        assert_eq!(ids.len(), 3);
+        assert!(ids.windows(2).all(|w| w[1] > w[0]), "ids are monotonic");
+        for i in 0..3 {
+            let row = rx.recv().await.expect("fanout event");
+            assert_eq!(row.payload["i"], json!(i));
+        }
+        let rows = kernel
+            .recent_events_async(10, None)
             .await
+            .expect("recent events");
+        assert_eq!(rows.len(), 3);
+        assert!(kernel
+            .append_events_async(Vec::new())
+            .await
+            .expect("empty batch")
+            .is_empty());
     }
 
-    pub async fn get_staging_action_async(&self, id: String) -> Result<Option<StagingAction>> {
-        self.run_blocking(move |k| k.get_staging_action(&id)).await
+    #[tokio::test]
+    async fn dequeue_prefers_higher_priority_then_fifo() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_action_async("bulk-1", "bulk.export", &json!({}), None, None, "queued")
+            .await
+            .expect("insert bulk-1");
+        kernel
+            .insert_action_async("bulk-2", "bulk.export", &json!({}), None, None, "queued")
+            .await
+            .expect("insert bulk-2");
+        kernel
+            .insert_action_with_priority_async(
+                "interactive",
+                "chat.respond",
+                &json!({}),
+                None,
+                None,
+                "queued",
+                10,
+            )
+            .await
+            .expect("insert interactive");
+        let order: Vec<String> = {
+            let mut out = Vec::new();
+            while let Some((id, _, _)) = kernel.dequeue_one_queued().expect("dequeue") {
+                out.push(id);
+            }
+            out
+        };
+        assert_eq!(order, vec!["interactive", "bulk-1", "bulk-2"]);
+        let row = kernel
+            .get_action_async("interactive")
+            .await
+            .expect("get action")
+            .expect("row exists");
+        assert_eq!(row.priority, 10);
     }
 
-    pub async fn update_staging_action_status_async(
-        &self,
-        id: String,
-        status: String,
-        decision: Option<String>,
-        decided_by: Option<String>,
-        decided_at: Option<String>,
-        action_id: Option<String>,
-    ) -> Result<bool> {
-        self.run_blocking(move |k| {
-            k.update_staging_action_status(
-                &id,
-                &status,
-                decision.as_deref(),
-                decided_by.as_deref(),
-                decided_at.as_deref(),
-                action_id.as_deref(),
+    #[tokio::test]
+    async fn scheduled_actions_stay_parked_until_run_after() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("timestamp")
+            .with_timezone(&chrono::Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        kernel
+            .insert_scheduled_action_async(
+                "retry-1",
+                "tool.retry",
+                &json!({}),
+                None,
+                None,
+                "2026-01-01T00:05:00Z",
             )
-        })
-        .await
+            .await
+            .expect("schedule action");
+        assert!(
+            kernel.dequeue_one_queued().expect("dequeue").is_none(),
+            "not due yet"
+        );
+        let deferred = kernel
+            .list_deferred_actions_async(10)
+            .await
+            .expect("list deferred");
+        assert_eq!(deferred.len(), 1);
+        assert_eq!(deferred[0].id, "retry-1");
+        assert_eq!(
+            deferred[0].run_after.as_deref(),
+            Some("2026-01-01T00:05:00Z")
+        );
+        clock.advance(chrono::Duration::minutes(10));
+        let picked = kernel
+            .dequeue_one_queued()
+            .expect("dequeue")
+            .expect("due action");
+        assert_eq!(picked.0, "retry-1");
+        assert!(kernel
+            .list_deferred_actions_async(10)
+            .await
+            .expect("list deferred")
+            .is_empty());
     }
 
-    pub async fn list_contributions_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_contributions(limit))
+    #[tokio::test]
+    async fn failed_actions_retry_with_backoff_until_attempts_run_out() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("timestamp")
+            .with_timezone(&chrono::Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        kernel
+            .insert_action_async("flaky", "tool.flaky", &json!({}), None, None, "running")
+            .await
+            .expect("insert action");
+        // First failure: requeued 30s out.
+        let next = kernel
+            .fail_action_with_retry_async("flaky", Some("boom"), 3, 30)
+            .await
+            .expect("retry")
+            .expect("has next retry");
+        assert_eq!(next, "2026-01-01T00:00:30.000Z");
+        assert!(kernel.dequeue_one_queued().expect("dequeue").is_none());
+        clock.advance(chrono::Duration::seconds(31));
+        assert!(kernel.dequeue_one_queued().expect("dequeue").is_some());
+        // Second failure: backoff doubles.
+        let next = kernel
+            .fail_action_with_retry_async("flaky", Some("boom again"), 3, 30)
+            .await
+            .expect("retry")
+            .expect("has next retry");
+        assert_eq!(next, "2026-01-01T00:01:31.000Z");
+        clock.advance(chrono::Duration::seconds(61));
+        assert!(kernel.dequeue_one_queued().expect("dequeue").is_some());
+        // Third failure exhausts the budget.
+        assert!(kernel
+            .fail_action_with_retry_async("flaky", Some("gave up"), 3, 30)
             .await
+            .expect("retry")
+            .is_none());
+        let row = kernel
+            .get_action_async("flaky")
+            .await
+            .expect("get action")
+            .expect("row exists");
+        assert_eq!(row.state, "failed");
+        assert_eq!(row.attempts, 3);
+        assert_eq!(row.error.as_deref(), Some("gave up"));
+        assert!(row.run_after.is_none());
+        assert!(kernel
+            .fail_action_with_retry_async("missing", None, 3, 30)
+            .await
+            .expect("retry missing")
+            .is_none());
     }
 
-    pub async fn list_actions_async(
-        &self,
-        opts: ActionListOptions,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_actions_filtered(&opts))
+    #[tokio::test]
+    async fn dependent_actions_wait_for_prerequisites_to_complete() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_action_async("fetch", "data.fetch", &json!({}), None, None, "queued")
+            .await
+            .expect("insert fetch");
+        kernel
+            .insert_action_with_deps_async(
+                "transform",
+                "data.transform",
+                &json!({}),
+                None,
+                None,
+                "queued",
+                vec!["fetch".into()],
+            )
+            .await
+            .expect("insert transform");
+        let (id, _, _) = kernel
+            .dequeue_one_queued()
+            .expect("dequeue")
+            .expect("prerequisite first");
+        assert_eq!(id, "fetch");
+        assert!(
+            kernel.dequeue_one_queued().expect("dequeue").is_none(),
+            "dependent must wait for fetch to complete"
+        );
+        kernel
+            .set_action_state_async("fetch", "completed")
+            .await
+            .expect("complete fetch");
+        let (id, _, _) = kernel
+            .dequeue_one_queued()
+            .expect("dequeue")
+            .expect("dependent now eligible");
+        assert_eq!(id, "transform");
+        let graph = kernel
+            .action_dependency_graph_async("transform")
+            .await
+            .expect("graph");
+        assert_eq!(graph["depends_on"][0]["id"], json!("fetch"));
+        assert_eq!(graph["depends_on"][0]["state"], json!("completed"));
+        let graph = kernel
+            .action_dependency_graph_async("fetch")
+            .await
+            .expect("graph");
+        assert_eq!(graph["dependents"][0]["id"], json!("transform"));
+        assert!(kernel
+            .insert_action_with_deps_async(
+                "loop",
+                "x",
+                &json!({}),
+                None,
+                None,
+                "queued",
+                vec!["loop".into()],
+            )
             .await
+            .is_err());
     }
 
-    pub async fn list_egress_async(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        self.run_blocking(move |k| k.list_egress(limit)).await
+    #[tokio::test]
+    async fn kind_filtered_dequeue_only_hands_out_matching_actions() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_action_async("gpu-1", "model.infer", &json!({}), None, None, "queued")
+            .await
+            .expect("insert gpu-1");
+        kernel
+            .insert_action_async("cpu-1", "fs.scan", &json!({}), None, None, "queued")
+            .await
+            .expect("insert cpu-1");
+        assert!(kernel
+            .dequeue_one_queued_matching_async(vec![])
+            .await
+            .expect("empty prefixes")
+            .is_none());
+        let (id, kind, _) = kernel
+            .dequeue_one_queued_matching_async(vec!["model.".into()])
+            .await
+            .expect("dequeue matching")
+            .expect("gpu action available");
+        assert_eq!(id, "gpu-1");
+        assert_eq!(kind, "model.infer");
+        assert!(
+            kernel
+                .dequeue_one_queued_matching_async(vec!["model.".into()])
+                .await
+                .expect("dequeue matching")
+                .is_none(),
+            "only the cpu action remains"
+        );
+        let row = kernel
+            .get_action_async("cpu-1")
+            .await
+            .expect("get action")
+            .expect("row exists");
+        assert_eq!(row.state, "queued", "non-matching action untouched");
     }
-}
-
-#[derive(Clone, Debug, Default)]
-pub struct ActionListOptions {
-    pub limit: i64,
-    pub state: Option<String>,
-    pub kind_prefix: Option<String>,
-    pub updated_since: Option<String>,
-}
 
-impl ActionListOptions {
-    pub fn new(limit: i64) -> Self {
-        Self {
-            limit,
-            ..Default::default()
+    #[tokio::test]
+    async fn batch_dequeue_claims_up_to_n_in_queue_order() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..4 {
+            kernel
+                .insert_action_async(
+                    &format!("a{i}"),
+                    "demo.echo",
+                    &json!({}),
+                    None,
+                    None,
+                    "queued",
+                )
+                .await
+                .expect("insert action");
         }
+        kernel
+            .insert_action_with_priority_async(
+                "urgent",
+                "chat.respond",
+                &json!({}),
+                None,
+                None,
+                "queued",
+                5,
+            )
+            .await
+            .expect("insert urgent");
+        let batch = kernel
+            .dequeue_many_queued_async(3)
+            .await
+            .expect("batch dequeue");
+        let ids: Vec<&str> = batch.iter().map(|(id, _, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["urgent", "a0", "a1"]);
+        let rest = kernel
+            .dequeue_many_queued_async(10)
+            .await
+            .expect("batch dequeue rest");
+        assert_eq!(rest.len(), 2);
+        assert!(kernel
+            .dequeue_many_queued_async(10)
+            .await
+            .expect("drained")
+            .is_empty());
+        assert!(kernel
+            .dequeue_many_queued_async(0)
+            .await
+            .expect("zero")
+            .is_empty());
     }
 
-    pub fn clamped_limit(&self) -> i64 {
-        self.limit.clamp(1, 2000)
-    }
-}
-
-impl KernelSession {
-    fn store(&self) -> MemoryStore<'_> {
-        MemoryStore::new(&self.conn)
-    }
-
-    pub fn select_memory_hybrid(
-        &self,
-        query: Option<&str>,
-        embed: Option<&[f32]>,
-        lane: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.store().select_memory_hybrid(query, embed, lane, limit)
+    #[tokio::test]
+    async fn revoked_leases_disappear_and_leave_an_audit_trail() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let ttl = (chrono::Utc::now() + chrono::Duration::hours(1))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        kernel
+            .insert_lease_async(
+                "lease-1".into(),
+                "local".into(),
+                "net:http".into(),
+                None,
+                ttl.clone(),
+                None,
+                None,
+            )
+            .await
+            .expect("insert lease");
+        assert!(kernel
+            .revoke_lease_async("lease-1", Some("incident response"), Some("ops"))
+            .await
+            .expect("revoke"));
+        assert!(
+            !kernel
+                .revoke_lease_async("lease-1", None, None)
+                .await
+                .expect("revoke again"),
+            "second revoke is a no-op"
+        );
+        assert!(kernel
+            .list_leases_async(10)
+            .await
+            .expect("list leases")
+            .is_empty());
+        let events = kernel
+            .list_lease_events_async(Some("local".into()), Some("net:http".into()), 10)
+            .await
+            .expect("lease events");
+        assert_eq!(events.len(), 2, "grant then revoke");
+        assert_eq!(events[0]["event"], json!("revoke"));
+        assert_eq!(events[0]["reason"], json!("incident response"));
+        assert_eq!(events[0]["actor"], json!("ops"));
+        assert_eq!(events[1]["event"], json!("grant"));
+        assert!(kernel
+            .list_lease_events_async(Some("other".into()), None, 10)
+            .await
+            .expect("filtered events")
+            .is_empty());
     }
 
-    pub fn list_memory_links_many(
-        &self,
-        src_ids: &[String],
-        limit_per: i64,
-    ) -> Result<HashMap<String, Vec<serde_json::Value>>> {
-        self.store().list_memory_links_many(src_ids, limit_per)
+    #[tokio::test]
+    async fn lease_renewal_extends_ttl_and_budget_consumption_is_atomic() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("timestamp")
+            .with_timezone(&chrono::Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        kernel
+            .insert_lease_async(
+                "lease-m".into(),
+                "local".into(),
+                "net:http".into(),
+                None,
+                "2026-01-01T01:00:00.000Z".into(),
+                Some(10.0),
+                None,
+            )
+            .await
+            .expect("insert lease");
+        // Renewal extends from the current (future) expiry.
+        let new_ttl = kernel
+            .renew_lease_async("lease-m", 1800)
+            .await
+            .expect("renew")
+            .expect("lease exists");
+        assert_eq!(new_ttl, "2026-01-01T01:30:00.000Z");
+        // A lease already expired renews from now instead.
+        clock.set(
+            chrono::DateTime::parse_from_rfc3339("2026-01-01T02:00:00Z")
+                .expect("timestamp")
+                .with_timezone(&chrono::Utc),
+        );
+        let new_ttl = kernel
+            .renew_lease_async("lease-m", 600)
+            .await
+            .expect("renew")
+            .expect("lease exists");
+        assert_eq!(new_ttl, "2026-01-01T02:10:00.000Z");
+        assert!(kernel
+            .renew_lease_async("missing", 600)
+            .await
+            .expect("renew missing")
+            .is_none());
+        // Budget accounting.
+        assert_eq!(
+            kernel
+                .consume_lease_budget_async("lease-m", 4.0)
+                .await
+                .expect("consume"),
+            Some(6.0)
+        );
+        assert!(
+            kernel
+                .consume_lease_budget_async("lease-m", 7.0)
+                .await
+                .is_err(),
+            "overdraw is rejected"
+        );
+        assert_eq!(
+            kernel
+                .consume_lease_budget_async("lease-m", 6.0)
+                .await
+                .expect("consume rest"),
+            Some(0.0)
+        );
+        let events = kernel
+            .list_lease_events_async(None, None, 10)
+            .await
+            .expect("events");
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e["event"] == json!("renew"))
+                .count(),
+            2
+        );
     }
 
-    pub fn get_memory_many(&self, ids: &[String]) -> Result<HashMap<String, serde_json::Value>> {
-        if ids.is_empty() {
-            return Ok(HashMap::new());
-        }
-        self.store().get_memory_many(ids)
+    #[tokio::test]
+    async fn egress_rollups_bucket_by_hour_and_fold_incrementally() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T10:05:00Z")
+            .expect("timestamp")
+            .with_timezone(&chrono::Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        let log = |host: &str, proj: &str, bin: i64, bout: i64| {
+            kernel
+                .append_egress(
+                    "allow",
+                    None,
+                    Some(host),
+                    Some(443),
+                    Some("https"),
+                    Some(bin),
+                    Some(bout),
+                    None,
+                    Some(proj),
+                    Some("standard"),
+                    None,
+                )
+                .expect("append egress");
+        };
+        log("api.example.com", "proj-a", 100, 10);
+        log("api.example.com", "proj-a", 200, 20);
+        log("cdn.example.com", "proj-b", 50, 5);
+        // Next hour, same host/project lands in a fresh bucket.
+        clock.set(
+            chrono::DateTime::parse_from_rfc3339("2026-01-01T11:30:00Z")
+                .expect("timestamp")
+                .with_timezone(&chrono::Utc),
+        );
+        log("api.example.com", "proj-a", 400, 40);
+        assert_eq!(kernel.rollup_egress().expect("rollup"), 4);
+        assert_eq!(kernel.rollup_egress().expect("rollup again"), 0);
+        let buckets = kernel
+            .list_egress_rollups_async(None, 10)
+            .await
+            .expect("rollups");
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0]["bucket_start"], json!("2026-01-01T11:00:00Z"));
+        let first_hour: Vec<_> = buckets
+            .iter()
+            .filter(|b| b["bucket_start"] == json!("2026-01-01T10:00:00Z"))
+            .collect();
+        assert_eq!(first_hour.len(), 2);
+        let api = first_hour
+            .iter()
+            .find(|b| b["dest_host"] == json!("api.example.com"))
+            .expect("api bucket");
+        assert_eq!(api["events"], json!(2));
+        assert_eq!(api["bytes_in"], json!(300));
+        assert_eq!(api["bytes_out"], json!(30));
+        // Summaries fold new ledger rows in lazily before reading.
+        log("cdn.example.com", "proj-b", 1000, 100);
+        let by_host = kernel
+            .summarize_egress_async("dest_host".into(), None, 10)
+            .await
+            .expect("summary");
+        assert_eq!(by_host.len(), 2);
+        assert_eq!(by_host[0]["dest_host"], json!("cdn.example.com"));
+        assert_eq!(by_host[0]["bytes_in"], json!(1050));
+        assert_eq!(by_host[1]["dest_host"], json!("api.example.com"));
+        assert_eq!(by_host[1]["events"], json!(3));
+        // `since` bounds buckets; unknown dimensions are rejected.
+        let recent = kernel
+            .summarize_egress("proj", Some("2026-01-01T11:00:00Z"), 10)
+            .expect("recent summary");
+        assert_eq!(recent.len(), 2);
+        assert!(kernel.summarize_egress("reason", None, 10).is_err());
     }
 
-    pub fn expired_memory_candidates(
-        &self,
-        now: DateTime<Utc>,
-        limit: usize,
-    ) -> Result<Vec<MemoryGcCandidate>> {
-        self.store().expired_candidates(now, limit)
+    #[tokio::test]
+    async fn egress_rotation_and_purge_leave_rollups_intact() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:10:00Z")
+            .expect("timestamp")
+            .with_timezone(&chrono::Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        for i in 0..3 {
+            kernel
+                .append_egress(
+                    "allow",
+                    None,
+                    Some("old.example.com"),
+                    Some(443),
+                    Some("https"),
+                    Some(10 + i),
+                    Some(1),
+                    None,
+                    Some("proj-a"),
+                    None,
+                    None,
+                )
+                .expect("append egress");
+        }
+        clock.set(
+            chrono::DateTime::parse_from_rfc3339("2026-01-02T00:10:00Z")
+                .expect("timestamp")
+                .with_timezone(&chrono::Utc),
+        );
+        kernel
+            .append_egress(
+                "deny",
+                Some("policy"),
+                Some("new.example.com"),
+                Some(443),
+                Some("https"),
+                Some(5),
+                Some(5),
+                None,
+                Some("proj-a"),
+                None,
+                None,
+            )
+            .expect("append egress");
+        // Rotate day one out into a compressed segment.
+        let archive = dir.path().join("egress-archive");
+        let report = kernel
+            .rotate_egress_async("2026-01-02T00:00:00.000Z".into(), archive.clone())
+            .await
+            .expect("rotate");
+        assert_eq!(report.events, 3);
+        assert_eq!(report.segments, vec!["egress-1-3.ndjson.gz".to_string()]);
+        let file = std::fs::File::open(archive.join(&report.segments[0])).expect("segment");
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(file), &mut text)
+            .expect("gunzip");
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("segment row"))
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["dest_host"], json!("old.example.com"));
+        // Only the recent row remains live, but rollups still cover both days.
+        assert_eq!(kernel.list_egress(10).expect("ledger").len(), 1);
+        let by_host = kernel
+            .summarize_egress("dest_host", None, 10)
+            .expect("summary");
+        assert_eq!(by_host.len(), 2);
+        // Purging the rest empties the ledger without touching rollups.
+        assert_eq!(
+            kernel
+                .purge_egress_before_async("2026-01-03T00:00:00.000Z".into())
+                .await
+                .expect("purge"),
+            1
+        );
+        assert!(kernel.list_egress(10).expect("ledger").is_empty());
+        let by_host = kernel
+            .summarize_egress("dest_host", None, 10)
+            .expect("summary");
+        assert_eq!(by_host.len(), 2);
+        assert_eq!(
+            by_host
+                .iter()
+                .map(|b| b["events"].as_i64().unwrap())
+                .sum::<i64>(),
+            4
+        );
     }
 
-    pub fn lane_overflow_candidates(
-        &self,
-        lane: &str,
-        cap: usize,
-        limit: usize,
-    ) -> Result<Vec<MemoryGcCandidate>> {
-        self.store().lane_overflow_candidates(lane, cap, limit)
+    #[tokio::test]
+    async fn cas_gc_sweeps_only_unreferenced_blobs() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let kept_artifact = Kernel::cas_put(b"artifact blob", Some("text/plain"), None, dir.path())
+            .await
+            .expect("cas put");
+        let kept_memory = Kernel::cas_put(b"memory blob", None, None, dir.path())
+            .await
+            .expect("cas put");
+        let orphan = Kernel::cas_put(b"orphan blob", None, None, dir.path())
+            .await
+            .expect("cas put");
+        {
+            let conn = kernel.conn().expect("checkout connection");
+            conn.execute(
+                "INSERT INTO artifacts(sha256, mime) VALUES(?, 'text/plain')",
+                [kept_artifact.as_str()],
+            )
+            .expect("artifact row");
+        }
+        let value = json!({"note": "blob holder"});
+        let source = json!({"kind": "blob", "sha256": kept_memory});
+        kernel
+            .insert_memory(&MemoryInsertArgs {
+                id: None,
+                lane: "episodic",
+                kind: None,
+                key: None,
+                value: &value,
+                embed: None,
+                embed_hint: None,
+                normalize_on_insert: false,
+                tags: None,
+                score: None,
+                prob: None,
+                agent_id: None,
+                project_id: None,
+                persona_id: None,
+                text: None,
+                durability: None,
+                trust: None,
+                privacy: None,
+                ttl_s: None,
+                keywords: None,
+                entities: None,
+                source: Some(&source),
+                links: None,
+                extra: None,
+                hash: None,
+                on_conflict: Default::default(),
+            })
+            .expect("insert memory");
+        // Freshly written blobs are inside the grace window: nothing goes.
+        let report = kernel.cas_gc(dir.path(), false).expect("gc");
+        assert_eq!((report.kept, report.deleted), (3, 0));
+        // Dry run flags the orphan but leaves it on disk.
+        let report = kernel
+            .cas_gc_sweep(dir.path(), true, Duration::ZERO)
+            .expect("gc dry run");
+        assert_eq!((report.scanned, report.kept, report.deleted), (3, 2, 1));
+        assert!(report.dry_run);
+        assert!(report.deleted_bytes > 0);
+        let blob_path =
+            |sha: &str, ext: &str| dir.path().join("blobs").join(format!("{sha}.{ext}"));
+        assert!(blob_path(&orphan, "bin").exists());
+        // A real sweep removes the blob and its sidecar metadata only.
+        let report = kernel
+            .cas_gc_sweep(dir.path(), false, Duration::ZERO)
+            .expect("gc");
+        assert_eq!(report.deleted, 1);
+        assert!(!blob_path(&orphan, "bin").exists());
+        assert!(!blob_path(&orphan, "json").exists());
+        assert!(blob_path(&kept_artifact, "bin").exists());
+        assert!(blob_path(&kept_memory, "bin").exists());
     }
 
-    pub fn delete_memory_records(&self, ids: &[String]) -> Result<usize> {
-        self.store().delete_records(ids)
+    #[tokio::test]
+    async fn streaming_cas_matches_buffered_digests_and_reads_back() {
+        use tokio::io::AsyncReadExt as _;
+        let dir = TempDir::new().expect("temp dir");
+        let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let sha = Kernel::cas_put_stream(
+            payload.as_slice(),
+            Some("application/octet-stream"),
+            None,
+            dir.path(),
+        )
+        .await
+        .expect("streaming put");
+        // Same bytes through the buffered path land on the same digest.
+        let buffered = Kernel::cas_put(&payload, None, None, dir.path())
+            .await
+            .expect("buffered put");
+        assert_eq!(sha, buffered);
+        let mut reader = Kernel::cas_get_stream(&sha, dir.path())
+            .await
+            .expect("open stream")
+            .expect("blob exists");
+        let mut round_trip = Vec::new();
+        reader
+            .read_to_end(&mut round_trip)
+            .await
+            .expect("read blob");
+        assert_eq!(round_trip, payload);
+        // Unknown digests and malformed identifiers return None, not errors.
+        let missing = format!("{:064}", 0);
+        assert!(Kernel::cas_get_stream(&missing, dir.path())
+            .await
+            .expect("lookup")
+            .is_none());
+        assert!(Kernel::cas_get_stream("../../etc/passwd", dir.path())
+            .await
+            .expect("lookup")
+            .is_none());
+        // No spool files are left behind after a completed put.
+        let stray: Vec<_> = std::fs::read_dir(dir.path().join("blobs"))
+            .expect("blobs dir")
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with(".tmp-"))
+            .collect();
+        assert!(stray.is_empty());
     }
 
-    pub fn list_recent_memory(
-        &self,
-        lane: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<serde_json::Value>> {
-        self.store().list_recent_memory(lane, limit)
+    #[tokio::test]
+    async fn artifact_rows_round_trip_and_filter_by_mime_and_meta() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        // CAS-backed artifact: digest registered, payload on disk only.
+        let report_meta = json!({"task": "report", "rev": 2});
+        let sha = kernel
+            .put_artifact(
+                b"rendered report",
+                Some("text/html"),
+                Some(&report_meta),
+                dir.path(),
+            )
+            .await
+            .expect("put artifact");
+        let row = kernel
+            .get_artifact_async(sha.clone())
+            .await
+            .expect("get")
+            .expect("artifact exists");
+        assert_eq!(row.mime.as_deref(), Some("text/html"));
+        assert!(row.bytes.is_none());
+        assert_eq!(row.meta, Some(report_meta));
+        assert_eq!(
+            kernel
+                .load_artifact_bytes(&sha, dir.path())
+                .await
+                .expect("load")
+                .as_deref(),
+            Some(&b"rendered report"[..])
+        );
+        // Inline artifact plus a re-register that only fills in missing columns.
+        assert!(kernel
+            .insert_artifact(&ArtifactRow {
+                sha256: "aa".repeat(32),
+                mime: Some("image/png".into()),
+                bytes: Some(b"tiny png".to_vec()),
+                meta: Some(json!({"task": "thumbnail"})),
+            })
+            .expect("insert inline"));
+        assert!(!kernel
+            .insert_artifact(&ArtifactRow {
+                sha256: "aa".repeat(32),
+                mime: None,
+                bytes: None,
+                meta: None,
+            })
+            .expect("re-register"));
+        let inline = kernel
+            .get_artifact(&"aa".repeat(32))
+            .expect("get inline")
+            .expect("inline exists");
+        assert_eq!(inline.mime.as_deref(), Some("image/png"));
+        assert_eq!(inline.bytes.as_deref(), Some(&b"tiny png"[..]));
+        assert_eq!(
+            kernel
+                .load_artifact_bytes(&"aa".repeat(32), dir.path())
+                .await
+                .expect("load inline")
+                .as_deref(),
+            Some(&b"tiny png"[..])
+        );
+        // Search by mime prefix and meta fields.
+        assert_eq!(kernel.list_artifacts(10).expect("list").len(), 2);
+        let images = kernel
+            .search_artifacts_async(Some("image/".into()), None, 10)
+            .await
+            .expect("search mime");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].sha256, "aa".repeat(32));
+        let reports = kernel
+            .search_artifacts(None, Some(&json!({"task": "report", "rev": 2})), 10)
+            .expect("search meta");
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].sha256, sha);
+        assert!(kernel
+            .search_artifacts(None, Some(&json!({"task": "missing"})), 10)
+            .expect("search meta miss")
+            .is_empty());
+        assert!(kernel
+            .search_artifacts(None, Some(&json!(["not", "an", "object"])), 10)
+            .is_err());
+        // Delete removes the row; the CAS blob waits for GC.
+        assert!(kernel
+            .delete_artifact_async(sha.clone())
+            .await
+            .expect("delete"));
+        assert!(!kernel.delete_artifact(&sha).expect("delete again"));
+        assert!(dir.path().join("blobs").join(format!("{sha}.bin")).exists());
     }
 
-    pub fn list_logic_units(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let conn: &Connection = &self.conn;
-        let mut stmt = conn.prepare(
-            "SELECT id,manifest,status,created,updated \
-             FROM logic_units ORDER BY updated DESC LIMIT ?",
-        )?;
-        let mut rows = stmt.query([limit])?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let mf_s: String = r.get(1)?;
-            let mf_v =
-                serde_json::from_str::<serde_json::Value>(&mf_s).unwrap_or(serde_json::json!({}));
-            out.push(serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "manifest": mf_v,
-                "status": r.get::<_, String>(2)?,
-                "created": r.get::<_, String>(3)?,
-                "updated": r.get::<_, String>(4)?,
-            }));
+    #[tokio::test]
+    async fn backup_and_restore_round_trip_a_live_database() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..3 {
+            kernel
+                .append_event(&arw_events::Envelope {
+                    time: String::new(),
+                    kind: "backup.test".into(),
+                    payload: json!({"i": i}),
+                    policy: None,
+                    ce: None,
+                })
+                .expect("append event");
         }
-        Ok(out)
-    }
-
-    pub fn list_orchestrator_jobs(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let conn: &Connection = &self.conn;
-        let mut stmt = conn.prepare(
-            "SELECT id,status,goal,data,progress,created,updated \
-             FROM orchestrator_jobs ORDER BY updated DESC LIMIT ?",
-        )?;
-        let mut rows = stmt.query([limit])?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let status_raw: String = r.get::<_, String>(1)?;
-            let (status_slug, status_label) = Kernel::normalize_orchestrator_status(&status_raw);
-            let mut payload = serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "status": status_raw,
-                "status_slug": status_slug,
-                "status_label": status_label,
-                "goal": r.get::<_, Option<String>>(2)?,
-                "progress": r.get::<_, Option<f64>>(4)?,
-                "created": r.get::<_, String>(5)?,
-                "updated": r.get::<_, String>(6)?,
-            });
-            let data_raw: Option<String> = r.get(3)?;
-            if let Some(data_raw) = data_raw {
-                if let Ok(val) = serde_json::from_str::<serde_json::Value>(&data_raw) {
-                    let persona = Kernel::extract_persona_id(&val);
-                    if !val.is_null() {
-                        if let serde_json::Value::Object(ref mut map) = payload {
-                            map.insert("data".into(), val.clone());
-                            if let Some(persona) = persona {
-                                map.insert("persona_id".into(), serde_json::Value::String(persona));
-                            }
-                        }
-                    }
-                }
-            }
-            out.push(payload);
+        let backup = dir.path().join("backups").join("snapshot.sqlite");
+        kernel
+            .backup_to_async(backup.clone())
+            .await
+            .expect("backup");
+        // Refuses to clobber an existing snapshot.
+        assert!(kernel.backup_to(&backup).is_err());
+        // The snapshot restores into a fresh state dir and opens cleanly.
+        let restored = TempDir::new().expect("temp dir");
+        Kernel::restore_from(&backup, restored.path()).expect("restore");
+        let replica = Kernel::open(restored.path()).expect("open restored");
+        assert_eq!(replica.recent_events(10, None).expect("events").len(), 3);
+        drop(replica);
+        // Restoring over an existing database keeps it as a fallback copy.
+        Kernel::restore_from(&backup, restored.path()).expect("restore again");
+        assert!(restored.path().join("events.sqlite.pre-restore").exists());
+        // Garbage input fails validation before anything is touched.
+        let bogus = dir.path().join("bogus.sqlite");
+        std::fs::write(&bogus, b"not a database").expect("write bogus");
+        assert!(Kernel::restore_from(&bogus, restored.path()).is_err());
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[tokio::test]
+    async fn sqlcipher_keyed_database_rejects_unkeyed_opens_and_rekeys() {
+        let mut env = crate::test_support::env::guard();
+        env.set("ARW_SQLCIPHER_KEY", "first-key");
+        let dir = TempDir::new().expect("temp dir");
+        {
+            let kernel = Kernel::open(dir.path()).expect("kernel open");
+            kernel
+                .append_event(&arw_events::Envelope {
+                    time: String::new(),
+                    kind: "cipher.test".into(),
+                    payload: json!({}),
+                    policy: None,
+                    ce: None,
+                })
+                .expect("append event");
         }
-        Ok(out)
+        // Without the key the file is unreadable ciphertext.
+        {
+            let raw =
+                rusqlite::Connection::open(dir.path().join("events.sqlite")).expect("open handle");
+            assert!(raw
+                .query_row("SELECT COUNT(*) FROM events", [], |r| r.get::<_, i64>(0))
+                .is_err());
+        }
+        // Rotation re-encrypts in place; the new key must come from env next open.
+        {
+            let kernel = Kernel::open(dir.path()).expect("kernel reopen");
+            assert!(kernel.rotate_sqlcipher_key("  ").is_err());
+            kernel
+                .rotate_sqlcipher_key_async("second-key".into())
+                .await
+                .expect("rekey");
+        }
+        env.set("ARW_SQLCIPHER_KEY", "second-key");
+        let kernel = Kernel::open(dir.path()).expect("open with rotated key");
+        assert_eq!(kernel.recent_events(10, None).expect("events").len(), 1);
     }
 
-    pub fn list_leases(&self, limit: i64) -> Result<Vec<serde_json::Value>> {
-        let conn: &Connection = &self.conn;
-        let mut stmt = conn.prepare(
-            "SELECT id,subject,capability,scope,ttl_until,budget,policy_ctx,created,updated \
-             FROM leases ORDER BY updated DESC LIMIT ?",
-        )?;
-        let mut rows = stmt.query([limit])?;
-        let mut out = Vec::new();
-        while let Some(r) = rows.next()? {
-            let policy_s: Option<String> = r.get(6)?;
-            let policy_v = policy_s
-                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                .unwrap_or(serde_json::json!({}));
-            out.push(serde_json::json!({
-                "id": r.get::<_, String>(0)?,
-                "subject": r.get::<_, String>(1)?,
-                "capability": r.get::<_, String>(2)?,
-                "scope": r.get::<_, Option<String>>(3)?,
-                "ttl_until": r.get::<_, String>(4)?,
-                "budget": r.get::<_, Option<f64>>(5)?,
-                "policy": policy_v,
-                "created": r.get::<_, String>(7)?,
-                "updated": r.get::<_, String>(8)?,
-            }));
+    #[tokio::test]
+    async fn stats_snapshot_reports_tables_pool_and_files() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..5 {
+            kernel
+                .append_event(&arw_events::Envelope {
+                    time: String::new(),
+                    kind: "stats.test".into(),
+                    payload: json!({"i": i}),
+                    policy: None,
+                    ce: None,
+                })
+                .expect("append event");
         }
-        Ok(out)
+        let stats = kernel.stats_async().await.expect("stats");
+        assert_eq!(stats["tables"]["events"], json!(5));
+        assert_eq!(stats["tables"]["actions"], json!(0));
+        // FTS shadow tables are folded out of the per-table listing.
+        assert!(stats["tables"]["events_fts_data"].is_null());
+        assert!(stats["db"]["bytes"].as_u64().unwrap() > 0);
+        assert!(stats["pool"]["created"].as_u64().unwrap() >= 1);
+        assert!(stats["pool"]["max"].as_u64().unwrap() >= stats["pool"]["min"].as_u64().unwrap());
+        assert!(stats["waits"]["count"].as_u64().is_some());
+        assert!(stats["blocking"]["queue_depth"].as_u64().is_some());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::{SecondsFormat, Utc};
-    use serde_json::json;
-    use tempfile::TempDir;
 
-    #[test]
-    fn orchestrator_status_normalization() {
-        let cases = vec![
-            ("queued", ("queued", "Queued")),
-            ("Pending", ("queued", "Queued")),
-            ("running", ("running", "Running")),
-            ("IN_PROGRESS", ("running", "Running")),
-            ("completed", ("completed", "Completed")),
-            ("DONE", ("completed", "Completed")),
-            ("failed", ("failed", "Failed")),
-            ("ERROR", ("failed", "Failed")),
-            ("canceled", ("cancelled", "Cancelled")),
-            ("", ("unknown", "Unknown")),
-        ];
-        for (input, expected) in cases {
-            assert_eq!(Kernel::normalize_orchestrator_status(input), expected);
+    #[tokio::test]
+    async fn hash_chained_events_detect_rewrites_and_deletions() {
+        let mut env = crate::test_support::env::guard();
+        env.set("ARW_EVENTS_HASH_CHAIN", "1");
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let envelope = |i: i64| arw_events::Envelope {
+            time: format!("2026-01-01T00:00:0{}.000Z", i),
+            kind: "audit.test".into(),
+            payload: json!({"i": i}),
+            policy: None,
+            ce: None,
+        };
+        kernel.append_event(&envelope(0)).expect("append");
+        kernel
+            .append_events(&[envelope(1), envelope(2), envelope(3)])
+            .expect("append batch");
+        kernel.append_event(&envelope(4)).expect("append");
+        let report = kernel
+            .verify_event_chain_async(None, None)
+            .await
+            .expect("verify");
+        assert_eq!(report["checked"], json!(5));
+        assert_eq!(report["valid"], json!(true));
+        assert_eq!(report["unchained"], json!(0));
+        // Partial ranges verify against the hash of the preceding row.
+        let partial = kernel.verify_event_chain(Some(3), None).expect("verify");
+        assert_eq!(partial["checked"], json!(3));
+        assert_eq!(partial["valid"], json!(true));
+        // A rewritten payload breaks the chain at that row.
+        {
+            let conn = kernel.conn().expect("checkout connection");
+            conn.execute("UPDATE events SET payload='{\"i\":99}' WHERE id=2", [])
+                .expect("tamper");
+        }
+        let report = kernel.verify_event_chain(None, None).expect("verify");
+        assert_eq!(report["valid"], json!(false));
+        assert_eq!(report["first_invalid_id"], json!(2));
+        // A deleted row shows up as a gap even though hashes line up again.
+        {
+            let conn = kernel.conn().expect("checkout connection");
+            conn.execute("UPDATE events SET payload='{\"i\":1}' WHERE id=2", [])
+                .expect("restore");
+            conn.execute("DELETE FROM events WHERE id=3", [])
+                .expect("delete row");
         }
+        let report = kernel.verify_event_chain(None, None).expect("verify");
+        assert_eq!(report["valid"], json!(false));
+        assert_eq!(report["gaps"], json!(1));
     }
 
     #[tokio::test]
-    async fn research_watcher_upsert_and_status() {
+    async fn workspace_scoped_handles_do_not_leak_rows() {
         let dir = TempDir::new().expect("temp dir");
         let kernel = Kernel::open(dir.path()).expect("kernel open");
-
-        let id = kernel
-            .upsert_research_watcher_item_async(
-                Some("arxiv".to_string()),
-                Some("arxiv:2309".to_string()),
-                Some("Original title".to_string()),
-                Some("Initial summary".to_string()),
-                Some("https://example.test/paper".to_string()),
-                Some(json!({"authors": ["Ada"]})),
+        let alpha = kernel.scoped_to_workspace("alpha");
+        let beta = kernel.scoped_to_workspace("beta");
+        let envelope = |kind: &str| arw_events::Envelope {
+            time: "2026-01-01T00:00:00.000Z".into(),
+            kind: kind.into(),
+            payload: json!({}),
+            policy: None,
+            ce: None,
+        };
+        alpha.append_event(&envelope("ws.alpha")).expect("append");
+        beta.append_event(&envelope("ws.beta")).expect("append");
+        kernel.append_event(&envelope("ws.none")).expect("append");
+
+        // Scoped reads see only their own stamp; the unscoped handle sees all.
+        let seen: Vec<String> = alpha
+            .recent_events(10, None)
+            .expect("recent")
+            .into_iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(seen, vec!["ws.alpha".to_string()]);
+        assert_eq!(kernel.recent_events(10, None).expect("recent").len(), 3);
+
+        alpha
+            .insert_action("a-alpha", "demo.run", &json!({}), None, None, "queued")
+            .expect("insert action");
+        beta.insert_action("a-beta", "demo.run", &json!({}), None, None, "queued")
+            .expect("insert action");
+        let claimed = alpha.dequeue_one_queued().expect("dequeue");
+        assert_eq!(claimed.map(|(id, _, _)| id), Some("a-alpha".to_string()));
+        assert!(alpha.dequeue_one_queued().expect("dequeue").is_none());
+        let opts = ActionListOptions {
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(beta.list_actions_filtered(&opts).expect("list").len(), 1);
+        assert_eq!(kernel.list_actions_filtered(&opts).expect("list").len(), 2);
+
+        alpha
+            .insert_lease(
+                "l-alpha",
+                "local",
+                "net:http",
+                None,
+                "2099-01-01T00:00:00Z",
+                None,
+                None,
             )
-            .await
-            .expect("insert research watcher item");
+            .expect("insert lease");
+        assert!(alpha
+            .find_valid_lease("local", "net:http")
+            .expect("find")
+            .is_some());
+        assert!(beta
+            .find_valid_lease("local", "net:http")
+            .expect("find")
+            .is_none());
+        assert_eq!(beta.list_leases(10).expect("list").len(), 0);
+        assert_eq!(kernel.list_leases(10).expect("list").len(), 1);
+
+        // Scoped sessions apply the same lease filter as the kernel handle.
+        assert_eq!(
+            alpha
+                .session()
+                .expect("session")
+                .list_leases(10)
+                .expect("list")
+                .len(),
+            1
+        );
+        assert_eq!(
+            beta.session()
+                .expect("session")
+                .list_leases(10)
+                .expect("list")
+                .len(),
+            0
+        );
 
-        let pending = kernel
-            .list_research_watcher_items_async(Some("pending".to_string()), 10)
-            .await
-            .expect("list pending");
-        assert_eq!(pending.len(), 1);
-        assert_eq!(pending[0]["id"], id);
+        // State counts are scoped too: a-alpha was dequeued, a-beta is still queued.
+        assert_eq!(alpha.count_actions_by_state("queued").expect("count"), 0);
+        assert_eq!(beta.count_actions_by_state("queued").expect("count"), 1);
+        assert_eq!(kernel.count_actions_by_state("queued").expect("count"), 1);
+    }
 
-        // Upsert with same source_id should update the existing record.
-        let same_id = kernel
-            .upsert_research_watcher_item_async(
-                Some("arxiv".to_string()),
-                Some("arxiv:2309".to_string()),
-                Some("Updated title".to_string()),
-                Some("Refined summary".to_string()),
-                Some("https://example.test/paper".to_string()),
-                None,
-            )
+    #[tokio::test]
+    async fn soft_deleted_actions_are_hidden_until_restored() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_action("a1", "demo.run", &json!({}), None, None, "queued")
+            .expect("insert action");
+        assert!(kernel.soft_delete_action("a1").expect("soft delete"));
+        // Already-deleted rows are not tombstoned twice.
+        assert!(!kernel.soft_delete_action("a1").expect("soft delete"));
+
+        // Hidden from listings, counts, and the dequeuers…
+        let opts = ActionListOptions::new(10);
+        assert!(kernel
+            .list_actions_filtered(&opts)
+            .expect("list")
+            .is_empty());
+        assert_eq!(kernel.count_actions_by_state("queued").expect("count"), 0);
+        assert!(kernel.dequeue_one_queued().expect("dequeue").is_none());
+        // …but the tombstone row itself survives for audits.
+        assert!(kernel.get_action("a1").expect("get").is_some());
+
+        assert!(kernel.restore_action_async("a1").await.expect("restore"));
+        let claimed = kernel.dequeue_one_queued().expect("dequeue");
+        assert_eq!(claimed.map(|(id, _, _)| id), Some("a1".to_string()));
+
+        let staged = kernel
+            .insert_staging_action("demo.run", &json!({}), None, None, None)
+            .expect("insert staging");
+        assert!(kernel
+            .soft_delete_staging_action_async(&staged)
             .await
-            .expect("update research watcher item");
-        assert_eq!(id, same_id);
+            .expect("soft delete"));
+        assert!(kernel
+            .list_staging_actions(None, 10)
+            .expect("list")
+            .is_empty());
+        assert!(kernel.restore_staging_action(&staged).expect("restore"));
+        assert_eq!(
+            kernel.list_staging_actions(None, 10).expect("list").len(),
+            1
+        );
+    }
 
-        let note = Some("Looks promising".to_string());
-        let changed = kernel
-            .update_research_watcher_status_async(id.clone(), "approved".to_string(), note.clone())
-            .await
-            .expect("update status");
-        assert!(changed);
+    #[tokio::test]
+    async fn idem_keys_expire_after_configured_ttl() {
+        let mut env = crate::test_support::env::guard();
+        env.set("ARW_ACTIONS_IDEM_TTL_SEC", "3600");
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        kernel
+            .insert_action("a1", "demo.run", &json!({}), None, Some("key-1"), "queued")
+            .expect("insert action");
+        assert_eq!(
+            kernel.find_action_by_idem("key-1").expect("find"),
+            Some("a1".to_string())
+        );
 
-        let item = kernel
-            .get_research_watcher_item_async(id.clone())
+        // Inside the window the key still dedups; past it, lookups miss.
+        clock.advance(chrono::Duration::seconds(3599));
+        assert!(kernel.find_action_by_idem("key-1").expect("find").is_some());
+        clock.advance(chrono::Duration::seconds(2));
+        assert!(kernel.find_action_by_idem("key-1").expect("find").is_none());
+        assert!(kernel
+            .find_action_by_idem_full("key-1")
+            .expect("find")
+            .is_none());
+
+        // Cleanup clears the expired key so the action row shows it gone too.
+        assert_eq!(
+            kernel.prune_expired_idem_keys_async().await.expect("prune"),
+            1
+        );
+        let row = kernel.get_action("a1").expect("get").expect("row");
+        assert!(row.idem_key.is_none());
+        assert_eq!(kernel.prune_expired_idem_keys().expect("prune"), 0);
+    }
+
+    #[tokio::test]
+    async fn action_output_chunks_page_in_sequence_order() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        kernel
+            .insert_action("a1", "demo.run", &json!({}), None, None, "running")
+            .expect("insert action");
+        for seq in 0..5 {
+            kernel
+                .append_action_output_chunk_async("a1", seq, &format!("line {seq}\n"))
+                .await
+                .expect("append chunk");
+        }
+        // Worker retries overwrite, not duplicate.
+        kernel
+            .append_action_output_chunk("a1", 4, "line 4 (redelivered)\n")
+            .expect("append chunk");
+
+        let first = kernel
+            .list_action_output_chunks("a1", None, 3)
+            .expect("page");
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0]["seq"], json!(0));
+        let last_seq = first.last().and_then(|c| c["seq"].as_i64());
+        let rest = kernel
+            .list_action_output_chunks_async("a1", last_seq, 10)
             .await
-            .expect("fetch item")
-            .expect("item present");
-        assert_eq!(item.status, "approved");
-        assert_eq!(item.note, note);
+            .expect("page");
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[1]["data"], json!("line 4 (redelivered)\n"));
+        assert!(kernel
+            .list_action_output_chunks("a1", Some(4), 10)
+            .expect("page")
+            .is_empty());
+
+        assert_eq!(
+            kernel
+                .delete_action_output_chunks_async("a1")
+                .await
+                .expect("delete"),
+            5
+        );
+        assert!(kernel
+            .list_action_output_chunks("a1", None, 10)
+            .expect("page")
+            .is_empty());
+    }
 
-        let still_pending = kernel
-            .list_research_watcher_items_async(Some("pending".to_string()), 10)
+    #[tokio::test]
+    async fn contribution_rollups_fold_daily_buckets() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T06:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        kernel
+            .append_contribution("alice", "compute.cpu", 100.0, "ms", None, None, None)
+            .expect("append");
+        kernel
+            .append_contribution("alice", "compute.cpu", 50.0, "ms", None, None, None)
+            .expect("append");
+        kernel
+            .append_contribution("bob", "task.submit", 1.0, "task", None, None, None)
+            .expect("append");
+        clock.advance(chrono::Duration::days(1));
+        kernel
+            .append_contribution("alice", "compute.cpu", 25.0, "ms", None, None, None)
+            .expect("append");
+
+        assert_eq!(kernel.rollup_contributions_async().await.expect("fold"), 4);
+        // Re-folding with no new rows is free.
+        assert_eq!(kernel.rollup_contributions().expect("fold"), 0);
+
+        let buckets = kernel
+            .list_contribution_rollups_async(None, 10)
             .await
-            .expect("list pending after status change");
-        assert!(still_pending.is_empty());
+            .expect("list");
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0]["bucket_start"], json!("2026-01-02T00:00:00Z"));
+        assert_eq!(buckets[0]["qty"], json!(25.0));
+
+        let daily = kernel
+            .summarize_contributions(Some("alice"), Some("compute."), "day")
+            .expect("summarize");
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[1]["qty"], json!(150.0));
+        assert_eq!(daily[1]["events"], json!(2));
+
+        let total = kernel
+            .summarize_contributions_async(Some("alice".into()), None, "total".into())
+            .await
+            .expect("summarize");
+        assert_eq!(total.len(), 1);
+        assert_eq!(total[0]["unit"], json!("ms"));
+        assert_eq!(total[0]["qty"], json!(175.0));
 
-        // Unknown id returns false
-        let changed = kernel
-            .update_research_watcher_status_async(
-                "missing".to_string(),
-                "archived".to_string(),
+        assert!(kernel
+            .summarize_contributions(None, None, "fortnight")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn quotas_reject_contributions_over_the_window_limit() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        kernel
+            .set_quota("alice", "compute.cpu", 100.0, 3600)
+            .expect("set quota");
+        assert_eq!(kernel.list_quotas(10).expect("list").len(), 1);
+
+        kernel
+            .check_and_record_contribution("alice", "compute.cpu", 60.0, "ms", None, None, None)
+            .expect("record");
+        kernel
+            .check_and_record_contribution_async(
+                "alice".into(),
+                "compute.cpu".into(),
+                40.0,
+                "ms".into(),
+                None,
+                None,
                 None,
             )
             .await
-            .expect("update missing");
-        assert!(!changed);
+            .expect("record");
+        // The window is full; the next unit is rejected and not recorded.
+        let err = kernel
+            .check_and_record_contribution("alice", "compute.cpu", 1.0, "ms", None, None, None)
+            .expect_err("over quota");
+        assert!(err.to_string().contains("quota exceeded"));
+        assert_eq!(kernel.list_contributions(10).expect("list").len(), 2);
+        // Other subjects and unquota'd kinds are unaffected.
+        kernel
+            .check_and_record_contribution("bob", "compute.cpu", 500.0, "ms", None, None, None)
+            .expect("record");
+
+        // Once the window slides past the earlier spend, capacity returns.
+        clock.advance(chrono::Duration::seconds(3601));
+        kernel
+            .check_and_record_contribution("alice", "compute.cpu", 100.0, "ms", None, None, None)
+            .expect("record");
+
+        assert!(kernel
+            .delete_quota_async("alice".into(), "compute.cpu".into())
+            .await
+            .expect("delete"));
+        assert!(kernel.list_quotas(10).expect("list").is_empty());
+        assert!(kernel.set_quota("alice", "compute.cpu", 1.0, 0).is_err());
     }
 
     #[tokio::test]
-    async fn orchestrator_jobs_surface_data_payload() {
+    async fn config_snapshots_diff_and_carry_labels() {
         let dir = TempDir::new().expect("temp dir");
         let kernel = Kernel::open(dir.path()).expect("kernel open");
+        let a = kernel
+            .insert_config_snapshot(&json!({"port": 8080, "log": {"level": "info"}}))
+            .expect("insert");
+        let b = kernel
+            .insert_config_snapshot(&json!({"port": 9090, "log": {"level": "info"}, "tls": true}))
+            .expect("insert");
+
+        let patch = kernel
+            .diff_config_snapshots_async(a.clone(), b.clone())
+            .await
+            .expect("diff");
+        let ops = patch.as_array().expect("patch array");
+        assert_eq!(ops.len(), 2);
+        assert!(ops.iter().any(|op| op["path"] == json!("/port")));
+        assert!(ops.iter().any(|op| op["op"] == json!("add")));
+        // Identical snapshots diff to an empty patch.
+        assert_eq!(
+            kernel.diff_config_snapshots(&a, &a).expect("diff"),
+            json!([])
+        );
+        assert!(kernel.diff_config_snapshots(&a, "missing").is_err());
 
-        let data = json!({
-            "persona_id": "persona.test",
-            "preset": "balanced",
-            "diversity": 0.5,
-            "recency": 0.35,
-            "compression": 0.4,
-        });
-        let job_id = kernel
-            .insert_orchestrator_job_async("test goal", Some(&data))
+        assert!(kernel
+            .label_config_snapshot_async(
+                a.clone(),
+                Some("pre-rollout".into()),
+                Some("before enabling TLS".into()),
+            )
             .await
-            .expect("insert orchestrator job");
+            .expect("label"));
+        let listed = kernel.list_config_snapshots(10).expect("list");
+        let labeled = listed
+            .iter()
+            .find(|s| s["id"] == json!(a.clone()))
+            .expect("snapshot listed");
+        assert_eq!(labeled["label"], json!("pre-rollout"));
+        assert_eq!(labeled["note"], json!("before enabling TLS"));
+        assert!(!kernel
+            .label_config_snapshot("missing", None, None)
+            .expect("label"));
+    }
 
-        let jobs = kernel
-            .list_orchestrator_jobs_async(5)
+    #[tokio::test]
+    async fn snapshot_rollback_records_provenance_and_pruning_keeps_pins() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        let mut ids = Vec::new();
+        for port in [8080, 8081, 8082] {
+            ids.push(
+                kernel
+                    .insert_config_snapshot(&json!({ "port": port }))
+                    .expect("insert"),
+            );
+            clock.advance(chrono::Duration::seconds(1));
+        }
+
+        let restored = kernel
+            .rollback_to_snapshot_async(ids[0].clone())
             .await
-            .expect("list orchestrator jobs");
-        assert!(!jobs.is_empty(), "expected at least one job");
+            .expect("rollback");
+        assert_eq!(
+            kernel.get_config_snapshot(&restored).expect("get"),
+            Some(json!({ "port": 8080 }))
+        );
+        let listed = kernel.list_config_snapshots(10).expect("list");
+        assert_eq!(listed[0]["id"], json!(restored.clone()));
+        assert_eq!(listed[0]["rolled_back_from"], json!(ids[0].clone()));
+        assert!(kernel.rollback_to_snapshot("missing").is_err());
+
+        // Pinned snapshots survive retention; unpinned history outside the
+        // keep window does not.
+        assert!(kernel
+            .pin_config_snapshot_async(ids[1].clone(), true)
+            .await
+            .expect("pin"));
+        clock.advance(chrono::Duration::seconds(1));
+        assert_eq!(
+            kernel.prune_config_snapshots_async(1).await.expect("prune"),
+            2
+        );
+        let remaining = kernel.list_config_snapshots(10).expect("list");
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(remaining[0]["id"], json!(restored));
+        assert_eq!(remaining[1]["id"], json!(ids[1].clone()));
+        assert_eq!(remaining[1]["pinned"], json!(true));
+    }
 
-        let job = jobs
-            .into_iter()
-            .find(|job| job["id"] == job_id)
-            .expect("job present");
-        assert_eq!(job["goal"], json!("test goal"));
-        assert_eq!(job["status_slug"], json!("queued"));
-        assert_eq!(job["status_label"], json!("Queued"));
-        assert_eq!(job["progress"], json!(0.0));
-        assert_eq!(job["persona_id"], json!("persona.test"));
+    #[tokio::test]
+    async fn orchestrator_jobs_cancel_and_time_out() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+
+        let cancelled = kernel
+            .insert_orchestrator_job("train mini-agent", None)
+            .expect("insert");
+        assert!(kernel
+            .cancel_orchestrator_job_async(cancelled.clone(), Some("operator abort".into()))
+            .await
+            .expect("cancel"));
+        // Terminal jobs are not re-cancelled.
+        assert!(!kernel
+            .cancel_orchestrator_job(&cancelled, None)
+            .expect("cancel"));
+
+        let deadline =
+            (start + chrono::Duration::seconds(60)).to_rfc3339_opts(SecondsFormat::Millis, true);
+        let overdue = kernel
+            .insert_orchestrator_job_with_deadline_async(
+                "summarize corpus",
+                None,
+                Some(deadline.clone()),
+            )
+            .await
+            .expect("insert");
+        assert_eq!(kernel.sweep_overdue_orchestrator_jobs().expect("sweep"), 0);
+        clock.advance(chrono::Duration::seconds(61));
+        assert_eq!(
+            kernel
+                .sweep_overdue_orchestrator_jobs_async()
+                .await
+                .expect("sweep"),
+            1
+        );
 
-        let data_field = job.get("data").cloned().expect("data field surfaced");
-        assert_eq!(data_field["preset"], json!("balanced"));
-        assert_eq!(data_field["diversity"], json!(0.5));
-        assert_eq!(data_field["recency"], json!(0.35));
-        assert_eq!(data_field["compression"], json!(0.4));
+        let jobs = kernel.list_orchestrator_jobs(10).expect("list");
+        let timed_out = jobs
+            .iter()
+            .find(|j| j["id"] == json!(overdue.clone()))
+            .expect("job listed");
+        assert_eq!(timed_out["status_slug"], json!("failed"));
+        assert_eq!(timed_out["reason"], json!("timeout: deadline exceeded"));
+        assert_eq!(timed_out["deadline"], json!(deadline));
+        let aborted = jobs
+            .iter()
+            .find(|j| j["id"] == json!(cancelled.clone()))
+            .expect("job listed");
+        assert_eq!(aborted["status_slug"], json!("cancelled"));
+        assert_eq!(aborted["reason"], json!("operator abort"));
     }
 
     #[tokio::test]
-    async fn staging_actions_lifecycle() {
+    async fn orchestrator_job_trees_nest_and_roll_up_progress() {
         let dir = TempDir::new().expect("temp dir");
         let kernel = Kernel::open(dir.path()).expect("kernel open");
-        let payload = json!({
-            "project": "demo",
-            "evidence": {"link": "https://example.test"}
-        });
+        let root = kernel
+            .insert_orchestrator_job("train ensemble", None)
+            .expect("insert");
+        let child_a = kernel
+            .insert_orchestrator_child_job(&root, "train shard a", None)
+            .expect("child");
+        let child_b = kernel
+            .insert_orchestrator_child_job_async(root.clone(), "train shard b".into(), None)
+            .await
+            .expect("child");
+        let grandchild = kernel
+            .insert_orchestrator_child_job(&child_b, "evaluate shard b", None)
+            .expect("grandchild");
+        assert!(kernel
+            .insert_orchestrator_child_job("missing", "orphan", None)
+            .is_err());
+
+        kernel
+            .update_orchestrator_job(&child_a, Some("completed"), Some(1.0), None)
+            .expect("update");
+        kernel
+            .update_orchestrator_job(&child_b, Some("running"), Some(0.5), None)
+            .expect("update");
+        assert_eq!(
+            kernel
+                .rollup_orchestrator_job_progress_async(root.clone())
+                .await
+                .expect("rollup"),
+            Some(0.75)
+        );
+        // Childless jobs are left alone.
+        assert_eq!(
+            kernel
+                .rollup_orchestrator_job_progress(&child_a)
+                .expect("rollup"),
+            None
+        );
 
-        let staging_id = kernel
-            .insert_staging_action_async(
-                "fs.patch".to_string(),
-                payload.clone(),
-                Some("demo".to_string()),
-                Some("alice@example.test".to_string()),
-                payload.get("evidence").cloned(),
-            )
+        let tree = kernel
+            .get_orchestrator_job_tree_async(root.clone())
             .await
-            .expect("insert staging action");
+            .expect("tree")
+            .expect("root exists");
+        assert_eq!(tree["id"], json!(root));
+        assert_eq!(tree["progress"], json!(0.75));
+        let children = tree["children"].as_array().expect("children");
+        assert_eq!(children.len(), 2);
+        let shard_b = children
+            .iter()
+            .find(|c| c["id"] == json!(child_b.clone()))
+            .expect("child b");
+        assert_eq!(shard_b["children"][0]["id"], json!(grandchild));
+        assert!(kernel
+            .get_orchestrator_job_tree("missing")
+            .expect("tree")
+            .is_none());
+    }
 
-        let pending = kernel
-            .list_staging_actions_async(Some("pending".to_string()), 10)
+    #[tokio::test]
+    async fn orchestrator_claims_lease_and_requeue_on_expiry() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("kernel open");
+        let job = kernel
+            .insert_orchestrator_job("train mini-agent", Some(&json!({"shard": 1})))
+            .expect("insert");
+
+        let claimed = kernel
+            .claim_orchestrator_job_async("worker-a".into(), 30)
             .await
-            .expect("list pending");
-        assert_eq!(pending.len(), 1);
-        assert_eq!(pending[0]["id"], staging_id);
+            .expect("claim")
+            .expect("job available");
+        assert_eq!(claimed["id"], json!(job.clone()));
+        assert_eq!(claimed["data"], json!({"shard": 1}));
+        // The queue is drained; a second worker gets nothing.
+        assert!(kernel
+            .claim_orchestrator_job("worker-b", 30)
+            .expect("claim")
+            .is_none());
+
+        // Heartbeats keep the claim alive across the original lease window.
+        clock.advance(chrono::Duration::seconds(20));
+        assert!(kernel
+            .renew_orchestrator_job_lease_async(job.clone(), "worker-a".into(), 30)
+            .await
+            .expect("renew"));
+        clock.advance(chrono::Duration::seconds(20));
+        assert!(kernel
+            .claim_orchestrator_job("worker-b", 30)
+            .expect("claim")
+            .is_none());
+
+        // Once the lease lapses the job is requeued and reclaimable.
+        clock.advance(chrono::Duration::seconds(31));
+        let reclaimed = kernel
+            .claim_orchestrator_job("worker-b", 30)
+            .expect("claim")
+            .expect("requeued job");
+        assert_eq!(reclaimed["id"], json!(job.clone()));
+        // The old worker's heartbeat now fails, telling it to stand down.
+        assert!(!kernel
+            .renew_orchestrator_job_lease(&job, "worker-a", 30)
+            .expect("renew"));
+        assert_eq!(
+            kernel
+                .requeue_expired_orchestrator_jobs_async()
+                .await
+                .expect("requeue"),
+            0
+        );
+        assert!(kernel.claim_orchestrator_job("worker-a", 0).is_err());
+    }
 
-        let review_time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
-        let approved = kernel
-            .update_staging_action_status_async(
-                staging_id.clone(),
-                "approved".to_string(),
-                Some("approved".to_string()),
-                Some("reviewer".to_string()),
-                Some(review_time.clone()),
-                Some("action-1".to_string()),
+    #[tokio::test]
+    async fn logic_unit_versions_record_history_and_roll_back() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("open kernel");
+
+        kernel
+            .insert_logic_unit("lu-1", &json!({"rev": 1}), "installed")
+            .expect("insert v1");
+        kernel
+            .insert_logic_unit_async(
+                "lu-1".into(),
+                json!({"rev": 2, "bad": true}),
+                "installed".into(),
             )
             .await
-            .expect("approve staging");
-        assert!(approved);
+            .expect("insert v2");
 
-        let record = kernel
-            .get_staging_action_async(staging_id.clone())
+        let versions = kernel
+            .list_logic_unit_versions_async("lu-1".into())
             .await
-            .expect("get staging action")
-            .expect("staging exists");
-        assert_eq!(record.status, "approved");
-        assert_eq!(record.action_id.as_deref(), Some("action-1"));
-        assert_eq!(record.decided_by.as_deref(), Some("reviewer"));
-
-        let history = kernel
-            .list_staging_actions_async(None, 10)
+            .expect("versions");
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0]["version"], json!(2));
+        assert_eq!(versions[0]["manifest"]["bad"], json!(true));
+        assert_eq!(versions[1]["version"], json!(1));
+
+        // Rolling back restores the old manifest and records the rollback
+        // itself as a new version.
+        kernel
+            .rollback_logic_unit_async("lu-1".into(), 1)
             .await
-            .expect("list all");
-        assert_eq!(history.len(), 1);
-        assert_eq!(history[0]["status"], json!("approved"));
+            .expect("rollback");
+        let units = kernel.list_logic_units(10).expect("list units");
+        assert_eq!(units[0]["manifest"], json!({"rev": 1}));
+        let versions = kernel.list_logic_unit_versions("lu-1").expect("versions");
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[0]["version"], json!(3));
+        assert_eq!(versions[0]["manifest"], json!({"rev": 1}));
+
+        // Unknown versions are rejected; other units are untouched.
+        assert!(kernel.rollback_logic_unit("lu-1", 9).is_err());
+        assert!(kernel
+            .list_logic_unit_versions("lu-2")
+            .expect("versions")
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn staging_actions_denied() {
+    async fn persona_bundles_export_and_import_with_remapping() {
         let dir = TempDir::new().expect("temp dir");
-        let kernel = Kernel::open(dir.path()).expect("kernel open");
-        let payload = json!({"project": "lab"});
-        let id = kernel
-            .insert_staging_action_async(
-                "net.http.get".to_string(),
-                payload.clone(),
-                payload
-                    .get("project")
-                    .and_then(|p| p.as_str())
-                    .map(|s| s.to_string()),
-                None,
-                None,
-            )
+        let kernel = Kernel::open(dir.path()).expect("open kernel");
+
+        kernel
+            .upsert_persona_entry(PersonaEntryUpsert {
+                id: "persona-1".into(),
+                owner_kind: "workspace".into(),
+                owner_ref: "ws-a".into(),
+                name: Some("Scout".into()),
+                archetype: None,
+                traits: json!({"curious": true}),
+                preferences: json!({}),
+                worldview: json!({}),
+                vibe_profile: json!({}),
+                calibration: json!({}),
+            })
+            .expect("upsert persona");
+        let proposal_id = kernel
+            .insert_persona_proposal(PersonaProposalCreate {
+                persona_id: "persona-1".into(),
+                submitted_by: "alice".into(),
+                diff: json!({"name": "Scout II"}),
+                rationale: None,
+                telemetry_scope: json!({}),
+                leases_required: json!([]),
+            })
+            .expect("insert proposal");
+        kernel
+            .append_persona_history(PersonaHistoryAppend {
+                persona_id: "persona-1".into(),
+                proposal_id: Some(proposal_id.clone()),
+                diff: json!({"name": "Scout II"}),
+                applied_by: Some("alice".into()),
+            })
+            .expect("append history");
+
+        let bundle = kernel
+            .export_persona_async("persona-1".into())
             .await
-            .expect("insert staging");
+            .expect("export");
+        assert_eq!(bundle["format"], json!("arw.persona.bundle"));
+        assert_eq!(bundle["entry"]["name"], json!("Scout"));
+        assert_eq!(bundle["proposals"].as_array().map(Vec::len), Some(1));
+        assert_eq!(bundle["history"].as_array().map(Vec::len), Some(1));
+
+        // "new" mints fresh IDs, so the original persona is untouched and the
+        // copy's history points at the remapped proposal.
+        let new_id = kernel
+            .import_persona_async(bundle.clone(), "new".into())
+            .await
+            .expect("import new");
+        assert_ne!(new_id, "persona-1");
+        let copy = kernel
+            .get_persona_entry(&new_id)
+            .expect("get copy")
+            .expect("copy exists");
+        assert_eq!(copy.name.as_deref(), Some("Scout"));
+        let copied_history = kernel.list_persona_history(&new_id, 10).expect("history");
+        assert_eq!(copied_history.len(), 1);
+        let copied_proposal_id = copied_history[0]
+            .proposal_id
+            .clone()
+            .expect("proposal ref kept");
+        assert_ne!(copied_proposal_id, proposal_id);
+        assert!(kernel
+            .get_persona_proposal(&copied_proposal_id)
+            .expect("get proposal")
+            .is_some());
+
+        // "replace" keeps the bundled IDs and overwrites in place without
+        // duplicating proposals or history.
+        kernel
+            .import_persona(&bundle, "replace")
+            .expect("import replace");
+        assert_eq!(
+            kernel
+                .list_persona_history("persona-1", 10)
+                .expect("history")
+                .len(),
+            1
+        );
+        assert!(kernel.import_persona(&bundle, "merge").is_err());
+        assert!(kernel.import_persona(&json!({"entry": {}}), "new").is_err());
+    }
 
-        let denied = kernel
-            .update_staging_action_status_async(
-                id.clone(),
-                "denied".to_string(),
-                Some("unsupported".to_string()),
-                Some("reviewer".to_string()),
-                Some(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+    #[tokio::test]
+    async fn persona_proposal_votes_compute_status_from_threshold() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("open kernel");
+        let proposal_id = kernel
+            .insert_persona_proposal(PersonaProposalCreate {
+                persona_id: "persona-1".into(),
+                submitted_by: "alice".into(),
+                diff: json!({"name": "Scout II"}),
+                rationale: None,
+                telemetry_scope: json!({}),
+                leases_required: json!([]),
+            })
+            .expect("insert proposal");
+        assert!(kernel
+            .set_persona_proposal_required_approvals_async(proposal_id.clone(), 2)
+            .await
+            .expect("set threshold"));
+
+        // One approval is short of the threshold; an abstention doesn't count.
+        let p = kernel
+            .record_persona_proposal_vote(&proposal_id, "alice", "approve", None)
+            .expect("vote");
+        assert_eq!(p.status, "pending");
+        assert_eq!(p.required_approvals, 2);
+        let p = kernel
+            .record_persona_proposal_vote(&proposal_id, "carol", "abstain", Some("not my area"))
+            .expect("vote");
+        assert_eq!(p.status, "pending");
+        let p = kernel
+            .record_persona_proposal_vote_async(
+                proposal_id.clone(),
+                "bob".into(),
+                "approve".into(),
                 None,
             )
             .await
-            .expect("deny staging");
-        assert!(denied);
+            .expect("vote");
+        assert_eq!(p.status, "approved");
+
+        // A reviewer changing their mind re-derives the status; any reject
+        // wins over approvals.
+        let p = kernel
+            .record_persona_proposal_vote(&proposal_id, "bob", "reject", Some("regression"))
+            .expect("vote");
+        assert_eq!(p.status, "rejected");
+        let votes = kernel
+            .list_persona_proposal_votes_async(proposal_id.clone())
+            .await
+            .expect("votes");
+        assert_eq!(votes.len(), 3);
+
+        // Threshold edits are locked once the vote has settled.
+        assert!(!kernel
+            .set_persona_proposal_required_approvals(&proposal_id, 3)
+            .expect("set threshold"));
+        assert!(kernel
+            .record_persona_proposal_vote(&proposal_id, "dave", "maybe", None)
+            .is_err());
+        assert!(kernel
+            .record_persona_proposal_vote("missing", "dave", "approve", None)
+            .is_err());
+        assert!(kernel
+            .set_persona_proposal_required_approvals(&proposal_id, 0)
+            .is_err());
+    }
 
-        let record = kernel
-            .get_staging_action_async(id.clone())
+    #[tokio::test]
+    async fn persona_history_compaction_preserves_replay() {
+        let dir = TempDir::new().expect("temp dir");
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z")
+            .expect("parse start")
+            .with_timezone(&Utc);
+        let clock = Arc::new(MockClock::new(start));
+        let kernel = Kernel::open_with_clock(dir.path(), clock.clone()).expect("open kernel");
+        let append = |diff: serde_json::Value| PersonaHistoryAppend {
+            persona_id: "persona-1".into(),
+            proposal_id: None,
+            diff,
+            applied_by: Some("alice".into()),
+        };
+        let mut ids = Vec::new();
+        for diff in [
+            json!({"tone": "curt"}),
+            json!([{"op": "add", "path": "/tags", "value": ["ops"]}]),
+            json!({"tone": "warm"}),
+            json!({"focus": "triage"}),
+        ] {
+            ids.push(kernel.append_persona_history(append(diff)).expect("append"));
+            clock.advance(chrono::Duration::seconds(1));
+        }
+        let full = kernel
+            .reconstruct_persona_history("persona-1", None)
+            .expect("replay");
+        assert_eq!(
+            full,
+            json!({"tone": "warm", "tags": ["ops"], "focus": "triage"})
+        );
+
+        // Squash the two oldest rows into a checkpoint; replay is unchanged.
+        let squashed = kernel
+            .compact_persona_history_async("persona-1".into(), 2)
             .await
-            .expect("get staging")
-            .expect("staging exists");
-        assert_eq!(record.status, "denied");
-        assert_eq!(record.decision.as_deref(), Some("unsupported"));
-        assert_eq!(record.action_id, None);
+            .expect("compact");
+        assert_eq!(squashed, 2);
+        let history = kernel
+            .list_persona_history("persona-1", 10)
+            .expect("history");
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            kernel
+                .reconstruct_persona_history_async("persona-1".into(), None)
+                .await
+                .expect("replay"),
+            full
+        );
+        // Intermediate versions after the checkpoint are still addressable.
+        assert_eq!(
+            kernel
+                .reconstruct_persona_history("persona-1", Some(ids[2]))
+                .expect("replay"),
+            json!({"tone": "warm", "tags": ["ops"]})
+        );
+
+        // Nothing left worth squashing, and bad arguments are rejected.
+        assert_eq!(
+            kernel
+                .compact_persona_history("persona-1", 2)
+                .expect("compact"),
+            0
+        );
+        assert!(kernel.compact_persona_history("persona-1", -1).is_err());
+        assert!(kernel
+            .reconstruct_persona_history("persona-1", Some(9999))
+            .is_err());
     }
 
     #[tokio::test]
-    async fn events_prune_respects_max_rows() {
+    async fn session_write_transactions_are_atomic() {
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("open kernel");
+        let session = kernel.session().expect("session");
+        let envelope = |kind: &str| arw_events::Envelope {
+            time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            kind: kind.into(),
+            payload: json!({"seed": true}),
+            policy: None,
+            ce: None,
+        };
+        let memory_value = json!({"note": "tx"});
+        let memory_args = MemoryInsertArgs {
+            id: None,
+            lane: "episodic",
+            kind: None,
+            key: None,
+            value: &memory_value,
+            embed: None,
+            embed_hint: None,
+            normalize_on_insert: false,
+            tags: None,
+            score: None,
+            prob: None,
+            agent_id: None,
+            project_id: None,
+            persona_id: None,
+            text: None,
+            durability: None,
+            trust: None,
+            privacy: None,
+            ttl_s: None,
+            keywords: None,
+            entities: None,
+            source: None,
+            links: None,
+            extra: None,
+            hash: None,
+            on_conflict: Default::default(),
+        };
+
+        // A successful closure commits the whole batch at once.
+        let memory_id = session
+            .with_tx(|s| {
+                s.insert_action(
+                    "act-1",
+                    "demo.echo",
+                    &json!({"msg": "hi"}),
+                    None,
+                    None,
+                    "queued",
+                )?;
+                s.append_event(&envelope("actions.submitted"))?;
+                s.insert_memory(&memory_args)
+            })
+            .expect("commit tx");
+        assert!(kernel.get_action("act-1").expect("get").is_some());
+        assert_eq!(
+            kernel
+                .recent_events(10, None)
+                .expect("events")
+                .iter()
+                .filter(|e| e.kind == "actions.submitted")
+                .count(),
+            1
+        );
+        assert!(!memory_id.is_empty());
+
+        // A failing closure rolls everything back.
+        let err = session
+            .with_tx(|s| -> Result<()> {
+                s.insert_action("act-2", "demo.echo", &json!({}), None, None, "queued")?;
+                anyhow::bail!("boom")
+            })
+            .expect_err("tx fails");
+        assert!(err.to_string().contains("boom"), "{err}");
+        assert!(kernel.get_action("act-2").expect("get").is_none());
+
+        // Manual begin/rollback works the same way.
+        session.begin().expect("begin");
+        session
+            .insert_action("act-3", "demo.echo", &json!({}), None, None, "queued")
+            .expect("insert");
+        session.rollback().expect("rollback");
+        assert!(kernel.get_action("act-3").expect("get").is_none());
+        session.begin().expect("begin");
+        session
+            .insert_action("act-4", "demo.echo", &json!({}), None, None, "queued")
+            .expect("insert");
+        session.commit().expect("commit");
+        assert!(kernel.get_action("act-4").expect("get").is_some());
+    }
+
+    #[test]
+    fn checkpoint_wal_threshold_honors_env() {
+        let mut env = crate::test_support::env::guard();
+        env.remove("ARW_SQLITE_CHECKPOINT_WAL_MB");
+        assert_eq!(Kernel::checkpoint_wal_threshold_bytes(), 4 * 1024 * 1024);
+        env.set("ARW_SQLITE_CHECKPOINT_WAL_MB", "16");
+        assert_eq!(Kernel::checkpoint_wal_threshold_bytes(), 16 * 1024 * 1024);
+        // 0 disables the size gate, i.e. checkpoint on every tick.
+        env.set("ARW_SQLITE_CHECKPOINT_WAL_MB", "0");
+        assert_eq!(Kernel::checkpoint_wal_threshold_bytes(), 0);
+        env.set("ARW_SQLITE_CHECKPOINT_WAL_MB", "not-a-number");
+        assert_eq!(Kernel::checkpoint_wal_threshold_bytes(), 4 * 1024 * 1024);
+    }
+
+    #[tokio::test]
+    async fn maintenance_loop_respects_env_cadence() {
+        let mut env = crate::test_support::env::guard();
+        // 0 disables maintenance entirely; the default open starts it.
+        env.set("ARW_SQLITE_MAINTENANCE_SEC", "0");
         let dir = TempDir::new().expect("temp dir");
-        let prev_max = std::env::var("ARW_EVENTS_MAX_ROWS").ok();
-        let prev_sec = std::env::var("ARW_EVENTS_PRUNE_SEC").ok();
-        std::env::set_var("ARW_EVENTS_MAX_ROWS", "5");
-        std::env::set_var("ARW_EVENTS_PRUNE_SEC", "1");
         let kernel = Kernel::open(dir.path()).expect("kernel open");
-        for i in 0..12 {
-            let env = arw_events::Envelope {
+        assert!(kernel.maintenance.is_none());
+        drop(kernel);
+        env.remove("ARW_SQLITE_MAINTENANCE_SEC");
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        assert!(kernel.maintenance.is_some());
+    }
+
+    #[tokio::test]
+    async fn pool_recycles_aged_out_connections_transparently() {
+        let mut env = crate::test_support::env::guard();
+        // Every checkout retires the connection, so each call below forces a
+        // recycle-and-replace pass through the pool.
+        env.set("ARW_SQLITE_POOL_MAX_USES", "1");
+        let dir = TempDir::new().expect("temp dir");
+        let kernel = Kernel::open(dir.path()).expect("kernel open");
+        for i in 0..5 {
+            let id = format!("act-{i}");
+            kernel
+                .insert_action(&id, "demo.echo", &json!({"i": i}), None, None, "queued")
+                .expect("insert");
+            assert!(kernel.get_action(&id).expect("get").is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn read_only_kernel_serves_reads_and_rejects_writes() {
+        let dir = TempDir::new().expect("temp dir");
+        let writer = Kernel::open(dir.path()).expect("open writer");
+        writer
+            .insert_action("act-1", "demo.echo", &json!({}), None, None, "queued")
+            .expect("insert");
+
+        let ro = Kernel::open_read_only(dir.path()).expect("open read-only");
+        assert!(ro.get_action("act-1").expect("get").is_some());
+        assert!(ro.list_leases(10).expect("leases").is_empty());
+
+        // Mutations fail fast with the typed error, not a SQLite I/O error.
+        let err = ro
+            .insert_action("act-2", "demo.echo", &json!({}), None, None, "queued")
+            .expect_err("write rejected");
+        assert!(err.downcast_ref::<ReadOnlyKernelError>().is_some(), "{err}");
+        assert!(ro
+            .append_event(&arw_events::Envelope {
                 time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-                kind: "prune.test".into(),
-                payload: json!({ "i": i }),
+                kind: "nope".into(),
+                payload: json!({}),
                 policy: None,
                 ce: None,
-            };
-            kernel.append_event_async(&env).await.expect("append event");
-        }
-        {
-            let conn = kernel.conn().expect("checkout connection for prune");
-            Kernel::prune_events(&conn, Some(5), None).expect("prune events");
-        }
-        let remaining = kernel
-            .recent_events_async(20, None)
-            .await
-            .expect("recent events");
-        assert!(
-            remaining.len() as u64 <= 5,
-            "expected prune to cap events, got {}",
-            remaining.len()
-        );
-        if let Some(prev) = prev_max {
-            std::env::set_var("ARW_EVENTS_MAX_ROWS", prev);
-        } else {
-            std::env::remove_var("ARW_EVENTS_MAX_ROWS");
-        }
-        if let Some(prev) = prev_sec {
-            std::env::set_var("ARW_EVENTS_PRUNE_SEC", prev);
-        } else {
-            std::env::remove_var("ARW_EVENTS_PRUNE_SEC");
+            })
+            .expect_err("write rejected")
+            .downcast_ref::<ReadOnlyKernelError>()
+            .is_some());
+        assert!(ro.dequeue_one_queued().is_err());
+        // The guard covers mutators across table groups, not just the
+        // action queue.
+        for err in [
+            ro.set_action_state("act-1", "running")
+                .expect_err("write rejected"),
+            ro.try_enqueue_action("act-3", "demo.echo", &json!({}), None, None, 10)
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.cancel_orchestrator_job("job-1", None)
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.sweep_overdue_orchestrator_jobs()
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.prune_config_snapshots(1)
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.pin_config_snapshot("cfg-1", true)
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.compact_persona_history("persona-1", 1)
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.rollback_logic_unit("unit-1", 1)
+                .expect_err("write rejected"),
+            ro.rollup_contributions()
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.rollup_egress().map(|_| ()).expect_err("write rejected"),
+            ro.purge_egress_before("2099-01-01T00:00:00Z")
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.rotate_egress("2099-01-01T00:00:00Z", dir.path())
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.insert_memory_link("m-src", "m-dst", None, None)
+                .expect_err("write rejected"),
+            ro.delete_memory_records(&["m-src".to_string()])
+                .map(|_| ())
+                .expect_err("write rejected"),
+            ro.compact_memory().expect_err("write rejected"),
+            ro.requeue_expired_orchestrator_jobs()
+                .map(|_| ())
+                .expect_err("write rejected"),
+        ] {
+            assert!(err.downcast_ref::<ReadOnlyKernelError>().is_some(), "{err}");
         }
+
+        // insert_memory_with_record is guarded too; it takes a full args struct.
+        let memory_value = json!({"note": "ro"});
+        let memory_args = MemoryInsertArgs {
+            id: None,
+            lane: "episodic",
+            kind: None,
+            key: None,
+            value: &memory_value,
+            embed: None,
+            embed_hint: None,
+            normalize_on_insert: false,
+            tags: None,
+            score: None,
+            prob: None,
+            agent_id: None,
+            project_id: None,
+            persona_id: None,
+            text: None,
+            durability: None,
+            trust: None,
+            privacy: None,
+            ttl_s: None,
+            keywords: None,
+            entities: None,
+            source: None,
+            links: None,
+            extra: None,
+            hash: None,
+            on_conflict: Default::default(),
+        };
+        assert!(ro
+            .insert_memory_with_record(&memory_args)
+            .map(|_| ())
+            .expect_err("write rejected")
+            .downcast_ref::<ReadOnlyKernelError>()
+            .is_some());
+
+        // Read-only never creates a database.
+        let empty = TempDir::new().expect("temp dir");
+        assert!(Kernel::open_read_only(empty.path()).is_err());
     }
 }
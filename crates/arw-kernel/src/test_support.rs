@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(dead_code)]
 pub mod env {
     use std::sync::{Mutex, MutexGuard, OnceLock};
 
@@ -12,9 +13,7 @@ pub mod env {
     pub fn guard() -> EnvGuard {
         let lk = ENV_LOCK.get_or_init(|| Mutex::new(()));
         EnvGuard {
-            _lock: lk
-                .lock()
-                .unwrap_or_else(|poison| poison.into_inner()),
+            _lock: lk.lock().unwrap_or_else(|poison| poison.into_inner()),
             saved: Vec::new(),
         }
     }
@@ -3,7 +3,7 @@
 
 use anyhow::Result;
 use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
-use rusqlite::{params, params_from_iter, Connection};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
 use serde::Serialize;
 use serde_json::{json, Map, Value};
 use sha2::{Digest, Sha256};
@@ -39,8 +39,14 @@ const SELECT_COLUMN_LIST: &[&str] = &[
     "source",
     "links",
     "extra",
+    "last_accessed",
+    "access_count",
 ];
 
+/// Recency half-life applied when a lane declares no override
+/// ([`MemoryLaneConfig::half_life_s`]): six hours, the historical constant.
+pub const DEFAULT_HALF_LIFE_S: f64 = 6.0 * 3600.0;
+
 /// Summary of a memory record removed by the hygiene pass.
 #[derive(Debug, Clone, Serialize)]
 pub struct MemoryGcCandidate {
@@ -86,6 +92,26 @@ pub struct MemoryStore<'c> {
     conn: &'c Connection,
 }
 
+/// Dedup policy applied when an insert's content hash already matches an
+/// existing record (see [`MemoryInsertArgs::compute_hash`]). An explicit-id
+/// overwrite of the matching row is a correction, not a duplicate, and always
+/// proceeds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Insert unconditionally; dedup is the caller's job (historical
+    /// behavior).
+    #[default]
+    Insert,
+    /// Leave the existing record untouched and return it.
+    Skip,
+    /// Keep the existing content but union the incoming tags, keywords, and
+    /// links into it.
+    MergeTags,
+    /// Keep the existing content but raise its score/prob to the higher of
+    /// the stored and incoming values.
+    BumpScore,
+}
+
 pub struct MemoryInsertArgs<'a> {
     pub id: Option<&'a str>,
     pub lane: &'a str,
@@ -94,6 +120,9 @@ pub struct MemoryInsertArgs<'a> {
     pub value: &'a Value,
     pub embed: Option<&'a [f32]>,
     pub embed_hint: Option<&'a str>,
+    /// L2-normalize the embedding before storage, recording the original
+    /// norm under `extra.embed_norm`. Zero/non-finite vectors are stored raw.
+    pub normalize_on_insert: bool,
     pub tags: Option<&'a [String]>,
     pub score: Option<f64>,
     pub prob: Option<f64>,
@@ -111,6 +140,7 @@ pub struct MemoryInsertArgs<'a> {
     pub links: Option<&'a Value>,
     pub extra: Option<&'a Value>,
     pub hash: Option<String>,
+    pub on_conflict: OnConflict,
 }
 
 impl<'a> MemoryInsertArgs<'a> {
@@ -151,6 +181,7 @@ pub struct MemoryInsertOwned {
     pub value: Value,
     pub embed: Option<Vec<f32>>,
     pub embed_hint: Option<String>,
+    pub normalize_on_insert: bool,
     pub tags: Option<Vec<String>>,
     pub score: Option<f64>,
     pub prob: Option<f64>,
@@ -168,6 +199,7 @@ pub struct MemoryInsertOwned {
     pub links: Option<Value>,
     pub extra: Option<Value>,
     pub hash: Option<String>,
+    pub on_conflict: OnConflict,
 }
 
 impl MemoryInsertOwned {
@@ -180,6 +212,7 @@ impl MemoryInsertOwned {
             value: &self.value,
             embed: self.embed.as_deref(),
             embed_hint: self.embed_hint.as_deref(),
+            normalize_on_insert: self.normalize_on_insert,
             tags: self.tags.as_deref(),
             score: self.score,
             prob: self.prob,
@@ -197,6 +230,7 @@ impl MemoryInsertOwned {
             links: self.links.as_ref(),
             extra: self.extra.as_ref(),
             hash: self.hash.clone(),
+            on_conflict: self.on_conflict,
         }
     }
 
@@ -205,6 +239,147 @@ impl MemoryInsertOwned {
     }
 }
 
+/// Caller clearance applied before rows leave the store: records above
+/// `max_privacy` are dropped and `redact_fields` are masked on the rest.
+/// The privacy order is `public` < `internal` < `private` < `secret`;
+/// unknown labels rank above `secret` so unrecognized markings never leak,
+/// and unlabeled records count as `private` (the insert-side default).
+#[derive(Debug, Clone, Default)]
+pub struct ReadContext {
+    /// Highest privacy label the caller may see; `None` disables filtering
+    /// (trusted in-process callers).
+    pub max_privacy: Option<String>,
+    /// Top-level fields replaced with `"[redacted]"` on surviving records.
+    pub redact_fields: Vec<String>,
+}
+
+impl ReadContext {
+    /// Drop or mask a single record in place; returns whether it survives.
+    pub fn admit(&self, record: &mut Value) -> bool {
+        if let Some(ref max) = self.max_privacy {
+            let allowed = privacy_rank(Some(max.as_str()));
+            let actual = privacy_rank(record.get("privacy").and_then(|v| v.as_str()));
+            if actual > allowed {
+                return false;
+            }
+        }
+        if !self.redact_fields.is_empty() {
+            if let Some(obj) = record.as_object_mut() {
+                for field in &self.redact_fields {
+                    if obj.contains_key(field.as_str()) {
+                        obj.insert(field.clone(), Value::String("[redacted]".into()));
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn apply_all(&self, records: Vec<Value>) -> Vec<Value> {
+        records
+            .into_iter()
+            .filter_map(|mut r| self.admit(&mut r).then_some(r))
+            .collect()
+    }
+}
+
+fn privacy_rank(label: Option<&str>) -> u8 {
+    match label.map(str::trim) {
+        Some("public") => 0,
+        Some("internal") => 1,
+        None | Some("private") => 2,
+        Some("secret") => 3,
+        Some(_) => 4,
+    }
+}
+
+/// Match mode for [`MemoryStore::search_memory_by_tags`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagMatch {
+    /// A record must carry every queried tag.
+    All,
+    /// Any one queried tag suffices.
+    Any,
+}
+
+/// Structural filters for [`MemoryStore::filter_memory`], pushed into SQL
+/// so candidates are constrained before any ranking work. Filters compose
+/// with AND.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFilters {
+    /// Every listed keyword must appear in the record's keyword list
+    /// (exact token match, not substring).
+    pub keywords: Vec<String>,
+    /// Match records carrying an entity whose `kind` equals this.
+    pub entity_kind: Option<String>,
+    /// Match records carrying this entity: a bare string entity, or an
+    /// object entity's `name` (falling back to `value`). Combined with
+    /// `entity_kind`, both must hold for the same entity.
+    pub entity_value: Option<String>,
+}
+
+/// Per-lane defaults and ranking overrides from the `memory_lanes`
+/// registry. Unset fields fall through to the caller's values (or the
+/// built-in constants for ranking).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MemoryLaneConfig {
+    pub lane: String,
+    /// TTL applied to inserts that do not set one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_ttl_s: Option<i64>,
+    /// Record cap consulted by [`MemoryStore::configured_lane_overflow_candidates`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cap: Option<i64>,
+    /// Durability applied to inserts that do not set one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_durability: Option<String>,
+    /// Recency half-life (seconds) used when ranking this lane's
+    /// candidates; overrides the built-in six hours.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub half_life_s: Option<i64>,
+    /// Sliding-window mode: reads reset the TTL clock by stamping
+    /// `ttl_anchor`, so only records idle for a full TTL expire.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub refresh_ttl_on_access: bool,
+    /// Set by the store on upsert; ignored on input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+/// Options for [`MemoryStore::import_memory`].
+#[derive(Debug, Clone, Default)]
+pub struct MemoryImportOptions {
+    /// Reassign every imported record into this lane instead of the
+    /// exported one. Content hashes are recomputed, since they cover the
+    /// lane.
+    pub lane_override: Option<String>,
+    /// Dedup policy applied per record; defaults to plain insert.
+    pub on_conflict: OnConflict,
+}
+
+/// Similarity metric applied when comparing embedding vectors.
+///
+/// `Cosine` is the default and matches the historical behavior. `Dot` suits
+/// models tuned for inner-product retrieval; `L2` ranks by Euclidean
+/// distance, surfaced as a negated distance so larger is still better.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Metric {
+    #[default]
+    Cosine,
+    Dot,
+    L2,
+}
+
+impl Metric {
+    fn score(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => cosine_sim(a, b),
+            Metric::Dot => dot_product(a, b),
+            Metric::L2 => -l2_distance(a, b),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct RankedCandidate {
     id: String,
@@ -221,11 +396,18 @@ struct CandidateRow {
     embed_blob: Option<Vec<u8>>,
 }
 
+/// `fts` is `None` for rows that did not come from an FTS match, otherwise
+/// the match's relevance normalized to `0..=1` (see
+/// [`normalize_bm25_ranks`]); a plain hit with no rank is `Some(1.0)`.
+/// `half_life_s` overrides the built-in six-hour recency half-life (see
+/// [`MemoryLaneConfig::half_life_s`]).
 fn build_ranked_candidate(
     row: CandidateRow,
     embed: Option<&[f32]>,
+    metric: Metric,
     now: &DateTime<Utc>,
-    fts_hit: bool,
+    fts: Option<f32>,
+    half_life_s: Option<f64>,
 ) -> RankedCandidate {
     let embed_vec = match row.embed_blob {
         Some(blob) => decode_embed_blob(&blob),
@@ -237,7 +419,7 @@ fn build_ranked_candidate(
     let mut sim = 0f32;
     if let (Some(candidate_embed), Some(target_embed)) = (embed_vec.as_ref(), embed) {
         if candidate_embed.len() == target_embed.len() && !target_embed.is_empty() {
-            sim = cosine_sim(target_embed, candidate_embed);
+            sim = metric.score(target_embed, candidate_embed);
         }
     }
     let recency = row
@@ -246,7 +428,7 @@ fn build_ranked_candidate(
         .and_then(parse_timestamp)
         .map(|t| {
             let age = now.signed_duration_since(t).num_seconds().max(0) as f64;
-            let hl = 6.0f64 * 3600.0f64;
+            let hl = effective_half_life(half_life_s);
             ((-age / hl).exp()) as f32
         })
         .unwrap_or(0.5);
@@ -255,14 +437,74 @@ fn build_ranked_candidate(
     let w_fts = 0.2f32;
     let w_rec = 0.2f32;
     let w_util = 0.1f32;
-    let fts_score = if fts_hit { 1.0 } else { 0.0 };
+    let fts_score = fts.unwrap_or(0.0).clamp(0.0, 1.0);
     let cscore = w_sim * sim + w_fts * fts_score + w_rec * recency + w_util * util;
     RankedCandidate {
         id: row.id,
         cscore,
         sim,
-        fts_hit,
+        fts_hit: fts.is_some(),
+    }
+}
+
+/// Greedy maximal-marginal-relevance selection over hydrated hybrid
+/// results. Relevance is the record's composite score; redundancy is the
+/// highest embedding similarity against anything already selected.
+fn mmr_rerank(pool: Vec<Value>, lambda: f64, limit: usize) -> Vec<Value> {
+    let lambda = lambda.clamp(0.0, 1.0);
+    let mut candidates: Vec<(Value, Option<Vec<f32>>, f64)> = pool
+        .into_iter()
+        .map(|value| {
+            let embed = value["embed"].as_array().map(|arr| {
+                arr.iter()
+                    .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+                    .collect::<Vec<f32>>()
+            });
+            let cscore = value["cscore"].as_f64().unwrap_or(0.0);
+            (value, embed, cscore)
+        })
+        .collect();
+    let mut selected: Vec<(Value, Option<Vec<f32>>)> =
+        Vec::with_capacity(limit.min(candidates.len()));
+    while selected.len() < limit && !candidates.is_empty() {
+        let mut best_idx = 0usize;
+        let mut best_score = f64::NEG_INFINITY;
+        for (idx, (_, embed, cscore)) in candidates.iter().enumerate() {
+            let redundancy = embed
+                .as_ref()
+                .map(|cand| {
+                    selected
+                        .iter()
+                        .filter_map(|(_, sel)| sel.as_ref())
+                        .filter(|sel| sel.len() == cand.len() && !cand.is_empty())
+                        .map(|sel| cosine_sim(cand, sel) as f64)
+                        .fold(0f64, f64::max)
+                })
+                .unwrap_or(0.0);
+            let mmr = lambda * cscore - (1.0 - lambda) * redundancy;
+            if mmr > best_score {
+                best_score = mmr;
+                best_idx = idx;
+            }
+        }
+        let (value, embed, _) = candidates.remove(best_idx);
+        selected.push((value, embed));
+    }
+    selected.into_iter().map(|(value, _)| value).collect()
+}
+
+/// Map raw FTS5 `bm25()` ranks (negative; more negative = better match) to
+/// relevance scores in `0..=1`, normalized against the best rank in the
+/// candidate set so the blend weight is comparable across queries.
+fn normalize_bm25_ranks(ranks: &[f64]) -> Vec<f32> {
+    let best = ranks.iter().fold(0f64, |acc, r| acc.min(*r));
+    if best >= 0.0 {
+        return vec![1.0; ranks.len()];
     }
+    ranks
+        .iter()
+        .map(|r| (r.min(0.0) / best).clamp(0.0, 1.0) as f32)
+        .collect()
 }
 
 impl<'c> MemoryStore<'c> {
@@ -294,11 +536,14 @@ impl<'c> MemoryStore<'c> {
               trust REAL,
               privacy TEXT,
               ttl_s INTEGER,
+              ttl_anchor TEXT,
               keywords TEXT,
               entities TEXT,
               source TEXT,
               links TEXT,
               extra TEXT,
+              last_accessed TEXT,
+              access_count INTEGER NOT NULL DEFAULT 0,
               created TEXT NOT NULL,
               updated TEXT NOT NULL
             );
@@ -328,6 +573,56 @@ impl<'c> MemoryStore<'c> {
               PRIMARY KEY (src_id,dst_id,rel)
             );
             CREATE INDEX IF NOT EXISTS idx_mem_links_src ON memory_links(src_id);
+            CREATE INDEX IF NOT EXISTS idx_mem_links_dst ON memory_links(dst_id);
+
+            -- Approximate-nearest-neighbor side table: each embedded record
+            -- hashes to a sign-bit LSH bucket; searches probe a handful of
+            -- buckets instead of brute-forcing every row.
+            CREATE TABLE IF NOT EXISTS memory_ann (
+              id TEXT PRIMARY KEY,
+              dim INTEGER NOT NULL,
+              bucket INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_mem_ann_bucket ON memory_ann(dim, bucket);
+
+            -- Normalized (trimmed, lowercased) tag index; the comma-joined
+            -- `memory_records.tags` column stays the display form.
+            CREATE TABLE IF NOT EXISTS memory_tags (
+              id TEXT NOT NULL,
+              tag TEXT NOT NULL,
+              PRIMARY KEY (id, tag)
+            );
+            CREATE INDEX IF NOT EXISTS idx_mem_tags_tag ON memory_tags(tag);
+
+            -- Per-lane defaults (TTL, cap, durability) and ranking overrides
+            -- that the store applies automatically.
+            CREATE TABLE IF NOT EXISTS memory_lanes (
+              lane TEXT PRIMARY KEY,
+              default_ttl_s INTEGER,
+              cap INTEGER,
+              default_durability TEXT,
+              half_life_s INTEGER,
+              refresh_ttl_on_access INTEGER NOT NULL DEFAULT 0,
+              updated TEXT NOT NULL
+            );
+
+            -- Embedding-model registry: pins the vector dimension for each
+            -- `embed_hint` so vectors from different models are never mixed.
+            CREATE TABLE IF NOT EXISTS memory_embed_models (
+              hint TEXT PRIMARY KEY,
+              dim INTEGER NOT NULL,
+              updated TEXT NOT NULL
+            );
+
+            -- Prior contents of overwritten records, snapshotted as hydrated
+            -- JSON so corrections stay auditable and reversible.
+            CREATE TABLE IF NOT EXISTS memory_revisions (
+              id TEXT NOT NULL,
+              revision INTEGER NOT NULL,
+              record TEXT NOT NULL,
+              captured TEXT NOT NULL,
+              PRIMARY KEY (id, revision)
+            );
             "#,
         )?;
         for ddl in [
@@ -341,11 +636,15 @@ impl<'c> MemoryStore<'c> {
             "ALTER TABLE memory_records ADD COLUMN trust REAL",
             "ALTER TABLE memory_records ADD COLUMN privacy TEXT",
             "ALTER TABLE memory_records ADD COLUMN ttl_s INTEGER",
+            "ALTER TABLE memory_records ADD COLUMN ttl_anchor TEXT",
             "ALTER TABLE memory_records ADD COLUMN keywords TEXT",
             "ALTER TABLE memory_records ADD COLUMN entities TEXT",
             "ALTER TABLE memory_records ADD COLUMN source TEXT",
             "ALTER TABLE memory_records ADD COLUMN links TEXT",
             "ALTER TABLE memory_records ADD COLUMN extra TEXT",
+            "ALTER TABLE memory_records ADD COLUMN last_accessed TEXT",
+            "ALTER TABLE memory_records ADD COLUMN access_count INTEGER NOT NULL DEFAULT 0",
+            "ALTER TABLE memory_lanes ADD COLUMN refresh_ttl_on_access INTEGER NOT NULL DEFAULT 0",
             "CREATE INDEX IF NOT EXISTS idx_mem_updated ON memory_records(updated DESC)",
             "CREATE INDEX IF NOT EXISTS idx_mem_lane_updated ON memory_records(lane, updated DESC)",
             "CREATE INDEX IF NOT EXISTS idx_mem_persona_updated ON memory_records(persona_id, updated DESC)",
@@ -360,33 +659,107 @@ impl<'c> MemoryStore<'c> {
         Ok(id)
     }
 
+    pub fn insert_memory_at(
+        &self,
+        args: &MemoryInsertArgs<'_>,
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        let (id, _) = self.insert_memory_with_record_at(args, now)?;
+        Ok(id)
+    }
+
     pub fn insert_memory_with_record(
         &self,
         args: &MemoryInsertArgs<'_>,
     ) -> Result<(String, Value)> {
-        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let value_s = serde_json::to_string(args.value).unwrap_or_else(|_| "{}".to_string());
-        let (embed_s, embed_blob) = if let Some(values) = args.embed {
-            let arr: Vec<String> = values.iter().map(|f| f.to_string()).collect();
-            (
-                Some(format!("[{}]", arr.join(","))),
-                Some(encode_embed_blob(values)),
-            )
+        self.insert_memory_with_record_at(args, Utc::now())
+    }
+
+    /// Insert with an explicit `now` so TTL expiry and recency are
+    /// deterministic in tests; pairs with the `now` parameter already
+    /// accepted by [`Self::expired_candidates`].
+    pub fn insert_memory_with_record_at(
+        &self,
+        args: &MemoryInsertArgs<'_>,
+        now: DateTime<Utc>,
+    ) -> Result<(String, Value)> {
+        let now = now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        // Lane-registry defaults fill in whatever the caller left unset.
+        let lane_cfg = if args.ttl_s.is_none() || args.durability.is_none() {
+            self.get_lane_config(args.lane)?
         } else {
-            (None, None)
+            None
+        };
+        let ttl_s = args
+            .ttl_s
+            .or_else(|| lane_cfg.as_ref().and_then(|c| c.default_ttl_s));
+        let durability: Option<String> = args
+            .durability
+            .map(|s| s.to_string())
+            .or_else(|| lane_cfg.as_ref().and_then(|c| c.default_durability.clone()));
+        let value_s = serde_json::to_string(args.value).unwrap_or_else(|_| "{}".to_string());
+        let (stored_embed, embed_norm) = match args.embed {
+            Some(values) if args.normalize_on_insert => match l2_normalize(values) {
+                Some((unit, norm)) => (Some(unit), Some(norm)),
+                None => (Some(values.to_vec()), None),
+            },
+            Some(values) => (Some(values.to_vec()), None),
+            None => (None, None),
+        };
+        // A registered `embed_hint` pins the vector dimension; the first
+        // insert under an unregistered hint pins it implicitly.
+        if let (Some(hint), Some(values)) = (args.embed_hint, stored_embed.as_ref()) {
+            self.check_embed_model(hint, values.len(), &now)?;
+        }
+        // New rows store only the packed little-endian f32 blob; the legacy
+        // `embed` text column stays readable but is no longer written.
+        let embed_blob = stored_embed
+            .as_ref()
+            .map(|values| encode_embed_blob(values));
+        let extra_merged: Option<Value> = match (args.extra, embed_norm) {
+            (extra, Some(norm)) => {
+                let mut extra_map = extra
+                    .and_then(|v| v.as_object().cloned())
+                    .unwrap_or_default();
+                extra_map.insert("embed_norm".into(), json!(norm));
+                Some(Value::Object(extra_map))
+            }
+            (Some(v), None) => Some(v.clone()),
+            (None, None) => None,
         };
         let hash = args.hash.clone().unwrap_or_else(|| args.compute_hash());
+        if args.on_conflict != OnConflict::Insert {
+            if let Some(resolved) = self.resolve_insert_conflict(args, &hash, &now)? {
+                return Ok(resolved);
+            }
+        }
         let id = args
             .id
             .map(|s| s.to_string())
             .unwrap_or_else(|| Uuid::new_v4().to_string());
+        // Reusing an id overwrites the row; snapshot the outgoing record
+        // first so the change is auditable and reversible via
+        // [`Self::restore_memory_revision`].
+        if args.id.is_some() {
+            if let Some(previous) = self.get_memory_untracked(&id)? {
+                self.conn.execute(
+                    "INSERT INTO memory_revisions(id,revision,record,captured) \
+                     VALUES(?1,(SELECT COALESCE(MAX(revision),0)+1 FROM memory_revisions WHERE id=?1),?2,?3)",
+                    params![
+                        &id,
+                        serde_json::to_string(&previous).unwrap_or_else(|_| "{}".to_string()),
+                        &now
+                    ],
+                )?;
+            }
+        }
         let tags_joined = args.tags.map(|ts| ts.join(","));
         let keywords_joined = args.keywords.map(|kw| kw.join(","));
         self.conn.execute(
             "INSERT OR REPLACE INTO memory_records(
-                id,lane,kind,key,value,tags,hash,embed,embed_blob,embed_hint,score,prob,
+                id,lane,kind,key,value,tags,hash,embed_blob,embed_hint,score,prob,
                 agent_id,project_id,persona_id,text,durability,trust,privacy,ttl_s,keywords,entities,source,links,extra,created,updated
-            ) VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)",
+            ) VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)",
             params![
                 id,
                 args.lane,
@@ -395,7 +768,6 @@ impl<'c> MemoryStore<'c> {
                 value_s.clone(),
                 tags_joined.clone(),
                 hash.clone(),
-                embed_s,
                 embed_blob,
                 args.embed_hint,
                 args.score,
@@ -404,15 +776,17 @@ impl<'c> MemoryStore<'c> {
                 args.project_id,
                 args.persona_id,
                 args.text,
-                args.durability,
+                durability,
                 args.trust,
                 args.privacy,
-                args.ttl_s,
+                ttl_s,
                 keywords_joined.clone(),
                 args.entities.and_then(|v| serde_json::to_string(v).ok()),
                 args.source.and_then(|v| serde_json::to_string(v).ok()),
                 args.links.and_then(|v| serde_json::to_string(v).ok()),
-                args.extra.and_then(|v| serde_json::to_string(v).ok()),
+                extra_merged
+                    .as_ref()
+                    .and_then(|v| serde_json::to_string(v).ok()),
                 now.clone(),
                 now.clone(),
             ],
@@ -430,6 +804,19 @@ impl<'c> MemoryStore<'c> {
                 tags_joined.clone().unwrap_or_default(),
             ],
         );
+        let _ = self.rewrite_tag_index(&id, tags_joined.as_deref());
+        // Keep the ANN side table in step with the stored embedding.
+        let _ = self
+            .conn
+            .execute("DELETE FROM memory_ann WHERE id=?", params![id.as_str()]);
+        if let Some(ref values) = stored_embed {
+            if !values.is_empty() {
+                let _ = self.conn.execute(
+                    "INSERT OR REPLACE INTO memory_ann(id,dim,bucket) VALUES(?,?,?)",
+                    params![&id, values.len() as i64, ann_bucket(values)],
+                );
+            }
+        }
 
         let mut map = Map::new();
         map.insert("id".into(), json!(id.clone()));
@@ -449,9 +836,9 @@ impl<'c> MemoryStore<'c> {
         map.insert("tags".into(), Value::Array(tags_array));
         map.insert("hash".into(), json!(hash));
 
-        if let Some(embed) = args.embed {
+        if let Some(ref embed) = stored_embed {
             if !embed.is_empty() {
-                map.insert("embed".into(), json!(embed.to_vec()));
+                map.insert("embed".into(), json!(embed));
             }
         }
         if let Some(hint) = args.embed_hint {
@@ -477,7 +864,7 @@ impl<'c> MemoryStore<'c> {
         if let Some(text) = args.text {
             map.insert("text".into(), json!(text));
         }
-        if let Some(durability) = args.durability {
+        if let Some(ref durability) = durability {
             map.insert("durability".into(), json!(durability));
         }
         if let Some(trust) = args.trust {
@@ -486,7 +873,7 @@ impl<'c> MemoryStore<'c> {
         if let Some(privacy) = args.privacy {
             map.insert("privacy".into(), json!(privacy));
         }
-        if let Some(ttl) = args.ttl_s {
+        if let Some(ttl) = ttl_s {
             map.insert("ttl_s".into(), json!(ttl));
         }
         if let Some(keywords) = keywords_joined {
@@ -504,13 +891,259 @@ impl<'c> MemoryStore<'c> {
         if let Some(links) = args.links.cloned() {
             map.insert("links".into(), links);
         }
-        if let Some(extra) = args.extra.cloned() {
+        if let Some(extra) = extra_merged {
             map.insert("extra".into(), extra);
         }
 
         Ok((id, Value::Object(map)))
     }
 
+    /// Insert a batch of records inside one transaction instead of paying a
+    /// commit per row. Items are applied in order and return per-item
+    /// results — an item that fails (e.g. a malformed embedding) records its
+    /// error without aborting the rest of the batch.
+    pub fn insert_memory_batch(
+        &self,
+        items: &[MemoryInsertOwned],
+    ) -> Result<Vec<Result<(String, Value)>>> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        let mut out = Vec::with_capacity(items.len());
+        for item in items {
+            out.push(self.insert_memory_with_record(&item.to_args()));
+        }
+        tx.commit()?;
+        Ok(out)
+    }
+
+    /// Apply the insert's [`OnConflict`] policy against an existing record
+    /// with the same content hash. Returns the surviving record when the
+    /// insert should be short-circuited, `None` when it should proceed.
+    fn resolve_insert_conflict(
+        &self,
+        args: &MemoryInsertArgs<'_>,
+        hash: &str,
+        now: &str,
+    ) -> Result<Option<(String, Value)>> {
+        let Some(existing) = self.find_memory_by_hash(hash)? else {
+            return Ok(None);
+        };
+        let existing_id = existing
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        // Rewriting the matching row under its own id is a correction, not a
+        // duplicate; let the normal overwrite (and its revision snapshot) run.
+        if args.id == Some(existing_id.as_str()) {
+            return Ok(None);
+        }
+        match args.on_conflict {
+            OnConflict::Insert => return Ok(None),
+            OnConflict::Skip => {}
+            OnConflict::MergeTags => {
+                let (old_tags, old_kw): (Option<String>, Option<String>) = self.conn.query_row(
+                    "SELECT tags, keywords FROM memory_records WHERE id=?",
+                    params![&existing_id],
+                    |r| Ok((r.get(0)?, r.get(1)?)),
+                )?;
+                let merged_tags = merge_joined_lists(old_tags, args.tags.map(|ts| ts.join(",")));
+                let merged_kw = merge_joined_lists(old_kw, args.keywords.map(|kw| kw.join(",")));
+                let merged_links = merge_links_json(existing.get("links"), args.links);
+                self.conn.execute(
+                    "UPDATE memory_records SET tags=?, keywords=?, links=?, updated=? WHERE id=?",
+                    params![
+                        merged_tags,
+                        merged_kw,
+                        merged_links
+                            .as_ref()
+                            .and_then(|v| serde_json::to_string(v).ok()),
+                        now,
+                        &existing_id
+                    ],
+                )?;
+                let _ = self.conn.execute(
+                    "UPDATE memory_fts SET tags=? WHERE id=?",
+                    params![merged_tags.clone().unwrap_or_default(), &existing_id],
+                );
+                let _ = self.rewrite_tag_index(&existing_id, merged_tags.as_deref());
+            }
+            OnConflict::BumpScore => {
+                let bump = |stored: Option<f64>, incoming: Option<f64>| match (stored, incoming) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    (a, b) => a.or(b),
+                };
+                let score = bump(existing.get("score").and_then(|v| v.as_f64()), args.score);
+                let prob = bump(existing.get("prob").and_then(|v| v.as_f64()), args.prob);
+                self.conn.execute(
+                    "UPDATE memory_records SET score=?, prob=?, updated=? WHERE id=?",
+                    params![score, prob, now, &existing_id],
+                )?;
+            }
+        }
+        let record = self.get_memory_untracked(&existing_id)?.unwrap_or(existing);
+        Ok(Some((existing_id, record)))
+    }
+
+    /// Rebuild a record's rows in the normalized tag index from its
+    /// comma-joined display tags.
+    fn rewrite_tag_index(&self, id: &str, tags_joined: Option<&str>) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM memory_tags WHERE id=?", params![id])?;
+        let Some(tags) = tags_joined else {
+            return Ok(());
+        };
+        let mut stmt = self
+            .conn
+            .prepare("INSERT OR IGNORE INTO memory_tags(id,tag) VALUES(?,?)")?;
+        for tag in tags.split(',').filter_map(normalize_tag) {
+            stmt.execute(params![id, tag])?;
+        }
+        Ok(())
+    }
+
+    /// Exact, index-backed tag search over the normalized junction table.
+    /// Input tags are normalized the same way stored ones are; `mode`
+    /// decides whether a record must carry all of them or any one.
+    pub fn search_memory_by_tags(
+        &self,
+        tags: &[String],
+        mode: TagMatch,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Value>> {
+        let mut wanted: Vec<String> = tags
+            .iter()
+            .filter_map(|t| normalize_tag(t.as_str()))
+            .collect();
+        wanted.sort();
+        wanted.dedup();
+        if wanted.is_empty() || limit <= 0 {
+            return Ok(Vec::new());
+        }
+        let placeholders = wanted.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let needed: i64 = match mode {
+            TagMatch::All => wanted.len() as i64,
+            TagMatch::Any => 1,
+        };
+        let sql = format!(
+            "SELECT {cols} FROM memory_records WHERE id IN ( \
+                 SELECT id FROM memory_tags WHERE tag IN ({placeholders}) \
+                 GROUP BY id HAVING COUNT(DISTINCT tag) >= ? \
+             ){lane_clause} ORDER BY updated DESC LIMIT ?",
+            cols = select_columns(None),
+            lane_clause = if lane.is_some() { " AND lane=?" } else { "" }
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut params: Vec<&dyn rusqlite::ToSql> =
+            wanted.iter().map(|t| t as &dyn rusqlite::ToSql).collect();
+        params.push(&needed);
+        if let Some(ref l) = lane {
+            params.push(l as &dyn rusqlite::ToSql);
+        }
+        params.push(&limit);
+        let mut rows = stmt.query(&params[..])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(row_to_value_full(r)?);
+        }
+        let hit_ids: Vec<String> = out
+            .iter()
+            .filter_map(|v| v.get("id").and_then(|id| id.as_str()).map(String::from))
+            .collect();
+        self.record_access(&hit_ids);
+        Ok(out)
+    }
+
+    /// Structural pre-filter over the `keywords` and `entities` columns,
+    /// pushed into SQL so retrieval pipelines can constrain candidates
+    /// before any ranking work. Filters compose with AND; an empty filter
+    /// set degenerates to a recency listing.
+    pub fn filter_memory(
+        &self,
+        filters: &MemoryFilters,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Value>> {
+        if limit <= 0 {
+            return Ok(Vec::new());
+        }
+        let mut sql = format!(
+            "SELECT {cols} FROM memory_records WHERE 1=1",
+            cols = select_columns(None)
+        );
+        let mut args: Vec<rusqlite::types::Value> = Vec::new();
+        // Must-have keywords: exact token match against the comma-joined
+        // column, so "rust" never matches "trust".
+        for kw in &filters.keywords {
+            let kw = kw.trim();
+            if kw.is_empty() {
+                continue;
+            }
+            sql.push_str(" AND (',' || COALESCE(keywords,'') || ',') LIKE ?");
+            args.push(format!("%,{kw},%").into());
+        }
+        // Entity matches walk the JSON column: a bare string entity
+        // matches on value; object entities match `$.kind` / `$.name`
+        // (falling back to `$.value`). Kind and value set together must
+        // hold for the same entity.
+        let entity_kind = filters.entity_kind.as_deref().filter(|s| !s.is_empty());
+        let entity_value = filters.entity_value.as_deref().filter(|s| !s.is_empty());
+        match (entity_kind, entity_value) {
+            (Some(kind), Some(value)) => {
+                sql.push_str(
+                    " AND entities IS NOT NULL AND EXISTS ( \
+                     SELECT 1 FROM json_each(memory_records.entities) je \
+                     WHERE je.type = 'object' \
+                       AND json_extract(je.value,'$.kind') = ? \
+                       AND COALESCE(json_extract(je.value,'$.name'), json_extract(je.value,'$.value')) = ?)",
+                );
+                args.push(kind.to_string().into());
+                args.push(value.to_string().into());
+            }
+            (Some(kind), None) => {
+                sql.push_str(
+                    " AND entities IS NOT NULL AND EXISTS ( \
+                     SELECT 1 FROM json_each(memory_records.entities) je \
+                     WHERE je.type = 'object' AND json_extract(je.value,'$.kind') = ?)",
+                );
+                args.push(kind.to_string().into());
+            }
+            (None, Some(value)) => {
+                sql.push_str(
+                    " AND entities IS NOT NULL AND EXISTS ( \
+                     SELECT 1 FROM json_each(memory_records.entities) je \
+                     WHERE (je.type = 'text' AND je.value = ?) \
+                        OR (je.type = 'object' \
+                            AND COALESCE(json_extract(je.value,'$.name'), json_extract(je.value,'$.value')) = ?))",
+                );
+                args.push(value.to_string().into());
+                args.push(value.to_string().into());
+            }
+            (None, None) => {}
+        }
+        if let Some(l) = lane {
+            sql.push_str(" AND lane=?");
+            args.push(l.to_string().into());
+        }
+        sql.push_str(" ORDER BY updated DESC LIMIT ?");
+        args.push(limit.into());
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(args.iter()))?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(row_to_value(r)?);
+        }
+        let hit_ids: Vec<String> = out
+            .iter()
+            .filter_map(|v| v["id"].as_str().map(|s| s.to_string()))
+            .collect();
+        self.record_access(&hit_ids);
+        Ok(out)
+    }
+
     pub fn search_memory(&self, query: &str, lane: Option<&str>, limit: i64) -> Result<Vec<Value>> {
         let mut out = Vec::new();
         let like_q = format!("%{}%", query);
@@ -539,9 +1172,78 @@ impl<'c> MemoryStore<'c> {
                 out.push(row_to_value(r)?);
             }
         }
+        let hit_ids: Vec<String> = out
+            .iter()
+            .filter_map(|v| v["id"].as_str().map(|s| s.to_string()))
+            .collect();
+        self.record_access(&hit_ids);
         Ok(out)
     }
 
+    /// Cursor-paged variant of [`Self::search_memory`]. Pages walk newest-
+    /// first on an `(updated, id)` keyset, so rows inserted between pages
+    /// are never skipped or repeated. Returns the page plus an opaque
+    /// cursor for the next one, `None` when the result set is exhausted.
+    pub fn search_memory_page(
+        &self,
+        query: &str,
+        lane: Option<&str>,
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<Value>, Option<String>)> {
+        let limit = limit.clamp(1, 1000);
+        let like_q = format!("%{}%", query);
+        let mut sql = format!(
+            "SELECT {cols} FROM memory_records \
+             WHERE (COALESCE(key,'') LIKE ? OR COALESCE(value,'') LIKE ? OR COALESCE(tags,'') LIKE ?)",
+            cols = select_columns(None)
+        );
+        let mut args: Vec<rusqlite::types::Value> =
+            vec![like_q.clone().into(), like_q.clone().into(), like_q.into()];
+        if let Some(cur) = cursor {
+            let (id_s, updated_s) = cur
+                .split_once('@')
+                .ok_or_else(|| anyhow::anyhow!("invalid search_memory_page cursor"))?;
+            sql.push_str(" AND (updated < ? OR (updated = ? AND id < ?))");
+            args.push(updated_s.to_string().into());
+            args.push(updated_s.to_string().into());
+            args.push(id_s.to_string().into());
+        }
+        if let Some(l) = lane {
+            sql.push_str(" AND lane=?");
+            args.push(l.to_string().into());
+        }
+        sql.push_str(" ORDER BY updated DESC, id DESC LIMIT ?");
+        args.push((limit + 1).into());
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(args.iter()))?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(row_to_value(r)?);
+        }
+        let has_more = out.len() as i64 > limit;
+        if has_more {
+            out.truncate(limit as usize);
+        }
+        let next_cursor = if has_more {
+            out.last().map(|r| {
+                format!(
+                    "{}@{}",
+                    r["id"].as_str().unwrap_or_default(),
+                    r["updated"].as_str().unwrap_or_default()
+                )
+            })
+        } else {
+            None
+        };
+        let hit_ids: Vec<String> = out
+            .iter()
+            .filter_map(|v| v["id"].as_str().map(|s| s.to_string()))
+            .collect();
+        self.record_access(&hit_ids);
+        Ok((out, next_cursor))
+    }
+
     pub fn fts_search_memory(
         &self,
         query: &str,
@@ -576,10 +1278,44 @@ impl<'c> MemoryStore<'c> {
                 out.push(row_to_value(r)?);
             }
         }
+        let hit_ids: Vec<String> = out
+            .iter()
+            .filter_map(|v| v["id"].as_str().map(|s| s.to_string()))
+            .collect();
+        self.record_access(&hit_ids);
         Ok(out)
     }
 
-    fn hydrate_ranked(&self, ranked: Vec<RankedCandidate>) -> Result<Vec<Value>> {
+    /// Batched access bump for read hits: stamps `last_accessed` and
+    /// increments `access_count` in a single statement. Best-effort — a
+    /// failed bump (e.g. a read-only connection) never fails the read.
+    pub fn record_access(&self, ids: &[String]) {
+        if ids.is_empty() {
+            return;
+        }
+        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let placeholders = vec!["?"; ids.len()].join(",");
+        let sql = format!(
+            "UPDATE memory_records SET last_accessed=?, access_count=access_count+1 \
+             WHERE id IN ({placeholders})"
+        );
+        let mut args: Vec<rusqlite::types::Value> = Vec::with_capacity(ids.len() + 1);
+        args.push(now.into());
+        for id in ids {
+            args.push(id.clone().into());
+        }
+        let _ = self.conn.execute(&sql, params_from_iter(args.iter()));
+        // Sliding-window lanes also reset the TTL clock on read; best-effort
+        // like the access bump above.
+        let sql = format!(
+            "UPDATE memory_records SET ttl_anchor=? \
+             WHERE id IN ({placeholders}) \
+               AND lane IN (SELECT lane FROM memory_lanes WHERE refresh_ttl_on_access=1)"
+        );
+        let _ = self.conn.execute(&sql, params_from_iter(args.iter()));
+    }
+
+    fn hydrate_ranked(&self, ranked: Vec<RankedCandidate>, half_life_s: f64) -> Result<Vec<Value>> {
         if ranked.is_empty() {
             return Ok(Vec::new());
         }
@@ -592,10 +1328,16 @@ impl<'c> MemoryStore<'c> {
                     obj.insert("cscore".into(), json!(candidate.cscore));
                     obj.insert("sim".into(), json!(candidate.sim));
                     obj.insert("_fts_hit".into(), Value::Bool(candidate.fts_hit));
+                    obj.insert("_half_life_s".into(), json!(half_life_s));
                 }
                 ordered.push(value);
             }
         }
+        let hit_ids: Vec<String> = ordered
+            .iter()
+            .filter_map(|v| v["id"].as_str().map(|s| s.to_string()))
+            .collect();
+        self.record_access(&hit_ids);
         Ok(ordered)
     }
 
@@ -604,46 +1346,84 @@ impl<'c> MemoryStore<'c> {
         embed: &[f32],
         lane: Option<&str>,
         limit: i64,
+    ) -> Result<Vec<Value>> {
+        self.search_memory_by_embedding_with_metric(embed, lane, limit, Metric::Cosine)
+    }
+
+    /// Fast similarity path for stores populated with `normalize_on_insert`:
+    /// normalizes the query vector once and compares with plain dot products,
+    /// which equals cosine similarity when the stored vectors are unit-length.
+    /// Falls back to the cosine metric for a zero/non-finite query vector.
+    pub fn search_memory_by_embedding_normalized(
+        &self,
+        embed: &[f32],
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Value>> {
+        match l2_normalize(embed) {
+            Some((unit, _norm)) => {
+                self.search_memory_by_embedding_with_metric(&unit, lane, limit, Metric::Dot)
+            }
+            None => self.search_memory_by_embedding_with_metric(embed, lane, limit, Metric::Cosine),
+        }
+    }
+
+    pub fn search_memory_by_embedding_with_metric(
+        &self,
+        embed: &[f32],
+        lane: Option<&str>,
+        limit: i64,
+        metric: Metric,
+    ) -> Result<Vec<Value>> {
+        self.search_memory_by_embedding_inner(embed, None, lane, limit, metric)
+    }
+
+    /// Similarity search restricted to vectors stored under `hint`, so
+    /// embeddings from different models are never compared. A query vector
+    /// whose dimension contradicts the registry is rejected outright.
+    pub fn search_memory_by_embedding_for_model(
+        &self,
+        embed: &[f32],
+        hint: &str,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Value>> {
+        if let Some(expected) = self.embed_model_dim(hint)? {
+            if expected != embed.len() as i64 {
+                return Err(anyhow::anyhow!(
+                    "embed_hint '{hint}' expects {expected}-dim vectors, query has {}",
+                    embed.len()
+                ));
+            }
+        }
+        self.search_memory_by_embedding_inner(embed, Some(hint), lane, limit, Metric::Cosine)
+    }
+
+    fn search_memory_by_embedding_inner(
+        &self,
+        embed: &[f32],
+        hint: Option<&str>,
+        lane: Option<&str>,
+        limit: i64,
+        metric: Metric,
     ) -> Result<Vec<Value>> {
         if embed.is_empty() || limit <= 0 {
             return Ok(Vec::new());
         }
         let limit_usize = limit as usize;
-        let sql = if lane.is_some() {
-            "SELECT id,updated,score,embed,embed_blob \
-             FROM memory_records \
-             WHERE lane=? ORDER BY updated DESC LIMIT 1000"
-        } else {
-            "SELECT id,updated,score,embed,embed_blob \
-             FROM memory_records ORDER BY updated DESC LIMIT 1000"
-        };
-        let mut stmt = self.conn.prepare(sql)?;
-        let mut rows = if let Some(l) = lane {
-            stmt.query(params![l])?
-        } else {
-            stmt.query([])?
+        // Probe the ANN index first; a probe that cannot fill the page (or
+        // an index that does not yet cover every embedded row) falls back
+        // to the brute-force scan.
+        let candidates = match self.ann_candidates(embed, hint, lane)? {
+            Some(rows) if rows.len() >= limit_usize => rows,
+            _ => self.brute_force_candidates(hint, lane)?,
         };
-        let mut ranked: Vec<RankedCandidate> = Vec::new();
         let now = Utc::now();
-        while let Some(row) = rows.next()? {
-            let id: String = row.get(0)?;
-            let updated: Option<String> = row.get(1)?;
-            let score: Option<f64> = row.get(2)?;
-            let embed_text: Option<String> = row.get(3)?;
-            let embed_blob: Option<Vec<u8>> = row.get(4)?;
-            ranked.push(build_ranked_candidate(
-                CandidateRow {
-                    id,
-                    updated,
-                    score,
-                    embed_text,
-                    embed_blob,
-                },
-                Some(embed),
-                &now,
-                false,
-            ));
-        }
+        let half_life = self.lane_half_life_s(lane)?;
+        let mut ranked: Vec<RankedCandidate> = candidates
+            .into_iter()
+            .map(|row| build_ranked_candidate(row, Some(embed), metric, &now, None, half_life))
+            .collect();
         if ranked.len() > limit_usize {
             ranked.select_nth_unstable_by(limit_usize.saturating_sub(1), |a, b| {
                 b.cscore.partial_cmp(&a.cscore).unwrap_or(Ordering::Equal)
@@ -651,36 +1431,146 @@ impl<'c> MemoryStore<'c> {
             ranked.truncate(limit_usize);
         }
         ranked.sort_by(|a, b| b.cscore.partial_cmp(&a.cscore).unwrap_or(Ordering::Equal));
-        self.hydrate_ranked(ranked)
+        self.hydrate_ranked(ranked, effective_half_life(half_life))
     }
 
-    pub fn select_memory_hybrid(
+    /// Candidate rows from the ANN bucket probe, or `None` when the index
+    /// cannot serve the query because rows predating it are still
+    /// unindexed (see [`Self::backfill_ann_index`]).
+    fn ann_candidates(
         &self,
-        query: Option<&str>,
-        embed: Option<&[f32]>,
+        embed: &[f32],
+        hint: Option<&str>,
         lane: Option<&str>,
-        limit: i64,
-    ) -> Result<Vec<Value>> {
-        if limit <= 0 {
-            return Ok(Vec::new());
+    ) -> Result<Option<Vec<CandidateRow>>> {
+        let embedded: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM memory_records \
+             WHERE embed_blob IS NOT NULL OR (embed IS NOT NULL AND embed NOT IN ('','[]'))",
+            [],
+            |r| r.get(0),
+        )?;
+        let indexed: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM memory_ann", [], |r| r.get(0))?;
+        if indexed < embedded {
+            return Ok(None);
         }
-        let limit_usize = limit as usize;
-        let fetch_cap = limit.max(1);
-        let mut ranked: Vec<RankedCandidate> = Vec::new();
-        let now = Utc::now();
+        let probes = ann_probe_buckets(ann_bucket(embed));
+        let placeholders = vec!["?"; probes.len()].join(",");
+        let hint_clause = if hint.is_some() {
+            " AND r.embed_hint=?"
+        } else {
+            ""
+        };
+        let lane_clause = if lane.is_some() { " AND r.lane=?" } else { "" };
+        let sql = format!(
+            "SELECT r.id,r.updated,r.score,r.embed,r.embed_blob \
+             FROM memory_records r JOIN memory_ann a ON a.id=r.id \
+             WHERE a.dim=? AND a.bucket IN ({placeholders}){hint_clause}{lane_clause} \
+             ORDER BY r.updated DESC LIMIT 1000",
+        );
+        let mut args: Vec<rusqlite::types::Value> = Vec::with_capacity(probes.len() + 3);
+        args.push((embed.len() as i64).into());
+        for probe in probes {
+            args.push(probe.into());
+        }
+        if let Some(h) = hint {
+            args.push(h.to_string().into());
+        }
+        if let Some(l) = lane {
+            args.push(l.to_string().into());
+        }
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(args.iter()))?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(CandidateRow {
+                id: row.get(0)?,
+                updated: row.get(1)?,
+                score: row.get(2)?,
+                embed_text: row.get(3)?,
+                embed_blob: row.get(4)?,
+            });
+        }
+        Ok(Some(out))
+    }
+
+    /// The historical scan path: newest 1000 rows regardless of embedding.
+    fn brute_force_candidates(
+        &self,
+        hint: Option<&str>,
+        lane: Option<&str>,
+    ) -> Result<Vec<CandidateRow>> {
+        let mut clauses: Vec<&str> = Vec::new();
+        if hint.is_some() {
+            clauses.push("embed_hint=?");
+        }
+        if lane.is_some() {
+            clauses.push("lane=?");
+        }
+        let where_clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", clauses.join(" AND "))
+        };
+        let sql = format!(
+            "SELECT id,updated,score,embed,embed_blob \
+             FROM memory_records{where_clause} ORDER BY updated DESC LIMIT 1000"
+        );
+        let mut args: Vec<rusqlite::types::Value> = Vec::with_capacity(2);
+        if let Some(h) = hint {
+            args.push(h.to_string().into());
+        }
+        if let Some(l) = lane {
+            args.push(l.to_string().into());
+        }
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(args.iter()))?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(CandidateRow {
+                id: row.get(0)?,
+                updated: row.get(1)?,
+                score: row.get(2)?,
+                embed_text: row.get(3)?,
+                embed_blob: row.get(4)?,
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn select_memory_hybrid(
+        &self,
+        query: Option<&str>,
+        embed: Option<&[f32]>,
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Value>> {
+        if limit <= 0 {
+            return Ok(Vec::new());
+        }
+        let limit_usize = limit as usize;
+        let fetch_cap = limit.max(1);
+        let mut ranked: Vec<RankedCandidate> = Vec::new();
+        let now = Utc::now();
+        let half_life = self.lane_half_life_s(lane)?;
 
         if let Some(qs) = query {
             if !qs.is_empty() {
+                // FTS5's `rank` column is bm25(): best matches first (most
+                // negative). Fetch in rank order so the cap keeps the most
+                // relevant rows, then normalize the ranks into the
+                // composite-score blend.
                 let sql = if lane.is_some() {
-                    "SELECT r.id,r.updated,r.score,r.embed,r.embed_blob \
+                    "SELECT r.id,r.updated,r.score,r.embed,r.embed_blob,f.rank \
                      FROM memory_records r JOIN memory_fts f ON f.id=r.id \
                      WHERE f.memory_fts MATCH ? AND f.lane=? \
-                     ORDER BY r.updated DESC LIMIT ?"
+                     ORDER BY f.rank ASC LIMIT ?"
                 } else {
-                    "SELECT r.id,r.updated,r.score,r.embed,r.embed_blob \
+                    "SELECT r.id,r.updated,r.score,r.embed,r.embed_blob,f.rank \
                      FROM memory_records r JOIN memory_fts f ON f.id=r.id \
                      WHERE f.memory_fts MATCH ? \
-                     ORDER BY r.updated DESC LIMIT ?"
+                     ORDER BY f.rank ASC LIMIT ?"
                 };
                 let mut stmt = self.conn.prepare(sql)?;
                 let mut rows = if let Some(lane_name) = lane {
@@ -688,23 +1578,27 @@ impl<'c> MemoryStore<'c> {
                 } else {
                     stmt.query(params![qs, fetch_cap])?
                 };
+                let mut hits: Vec<CandidateRow> = Vec::new();
+                let mut ranks: Vec<f64> = Vec::new();
                 while let Some(row) = rows.next()? {
-                    let id: String = row.get(0)?;
-                    let updated: Option<String> = row.get(1)?;
-                    let score: Option<f64> = row.get(2)?;
-                    let embed_text: Option<String> = row.get(3)?;
-                    let embed_blob: Option<Vec<u8>> = row.get(4)?;
+                    hits.push(CandidateRow {
+                        id: row.get(0)?,
+                        updated: row.get(1)?,
+                        score: row.get(2)?,
+                        embed_text: row.get(3)?,
+                        embed_blob: row.get(4)?,
+                    });
+                    ranks.push(row.get::<_, f64>(5)?);
+                }
+                let fts_scores = normalize_bm25_ranks(&ranks);
+                for (row, fts_score) in hits.into_iter().zip(fts_scores) {
                     ranked.push(build_ranked_candidate(
-                        CandidateRow {
-                            id,
-                            updated,
-                            score,
-                            embed_text,
-                            embed_blob,
-                        },
+                        row,
                         embed,
+                        Metric::Cosine,
                         &now,
-                        true,
+                        Some(fts_score),
+                        half_life,
                     ));
                 }
             }
@@ -740,8 +1634,10 @@ impl<'c> MemoryStore<'c> {
                         embed_blob,
                     },
                     embed,
+                    Metric::Cosine,
                     &now,
-                    false,
+                    None,
+                    half_life,
                 ));
             }
         }
@@ -753,7 +1649,29 @@ impl<'c> MemoryStore<'c> {
             ranked.truncate(limit_usize);
         }
         ranked.sort_by(|a, b| b.cscore.partial_cmp(&a.cscore).unwrap_or(Ordering::Equal));
-        self.hydrate_ranked(ranked)
+        self.hydrate_ranked(ranked, effective_half_life(half_life))
+    }
+
+    /// Hybrid selection followed by a maximal-marginal-relevance pass so the
+    /// top-K is not dominated by near-duplicate records. `lambda` in
+    /// `0..=1` trades relevance (1.0 = plain hybrid order) against
+    /// diversity (0.0 = maximally spread). The diversity term compares
+    /// stored embeddings; records without one neither receive nor impose a
+    /// redundancy penalty. Draws from a pool of `4 * limit` hybrid
+    /// candidates before re-ranking down to `limit`.
+    pub fn select_memory_hybrid_mmr(
+        &self,
+        query: Option<&str>,
+        embed: Option<&[f32]>,
+        lane: Option<&str>,
+        limit: i64,
+        lambda: f64,
+    ) -> Result<Vec<Value>> {
+        if limit <= 0 {
+            return Ok(Vec::new());
+        }
+        let pool = self.select_memory_hybrid(query, embed, lane, limit.saturating_mul(4))?;
+        Ok(mmr_rerank(pool, lambda, limit as usize))
     }
 
     pub fn expired_candidates(
@@ -765,10 +1683,11 @@ impl<'c> MemoryStore<'c> {
             return Ok(Vec::new());
         }
         let mut stmt = self.conn.prepare(
-            "SELECT id,lane,kind,project_id,agent_id,durability,ttl_s,created,updated \
+            "SELECT id,lane,kind,project_id,agent_id,durability,ttl_s,created,updated,\
+                    COALESCE(ttl_anchor, created) AS anchor \
              FROM memory_records \
              WHERE ttl_s IS NOT NULL AND ttl_s > 0 \
-               AND (strftime('%s', created) + ttl_s) <= ?1 \
+               AND (strftime('%s', COALESCE(ttl_anchor, created)) + ttl_s) <= ?1 \
              ORDER BY updated ASC, id ASC \
              LIMIT ?2",
         )?;
@@ -776,8 +1695,8 @@ impl<'c> MemoryStore<'c> {
         let mut out = Vec::new();
         while let Some(row) = rows.next()? {
             let ttl = row.get::<_, Option<i64>>(6)?.unwrap_or(0);
-            let created: String = row.get(7)?;
-            let expired_at = parse_timestamp(&created)
+            let anchor: String = row.get(9)?;
+            let expired_at = parse_timestamp(&anchor)
                 .unwrap_or(now)
                 .checked_add_signed(Duration::seconds(ttl))
                 .unwrap_or(now)
@@ -793,6 +1712,280 @@ impl<'c> MemoryStore<'c> {
         Ok(out)
     }
 
+    /// Dry-run hygiene report combining the TTL-expiry pass with the
+    /// registry-driven lane-cap pass. Nothing is deleted; the report lists
+    /// every candidate plus counts per lane and per reason and the
+    /// estimated bytes reclaimed (payload column lengths, not page-level
+    /// accounting). A record caught by both passes is counted once, under
+    /// the reason that found it first.
+    pub fn plan_memory_gc(&self, now: DateTime<Utc>, limit_per_pass: usize) -> Result<Value> {
+        let mut candidates = self.expired_candidates(now, limit_per_pass)?;
+        let mut seen: std::collections::HashSet<String> =
+            candidates.iter().map(|c| c.id.clone()).collect();
+        for cand in self.configured_lane_overflow_candidates(limit_per_pass)? {
+            if seen.insert(cand.id.clone()) {
+                candidates.push(cand);
+            }
+        }
+        let ids: Vec<String> = candidates.iter().map(|c| c.id.clone()).collect();
+        let bytes_by_id = self.estimate_record_bytes(&ids)?;
+        let mut by_lane: Map<String, Value> = Map::new();
+        let mut by_reason: HashMap<&'static str, usize> = HashMap::new();
+        let mut total_bytes: i64 = 0;
+        for cand in &candidates {
+            let bytes = bytes_by_id.get(&cand.id).copied().unwrap_or(0);
+            total_bytes += bytes;
+            let entry = by_lane
+                .entry(cand.lane.clone())
+                .or_insert_with(|| json!({"count": 0, "estimated_bytes": 0}));
+            if let Some(obj) = entry.as_object_mut() {
+                let count = obj["count"].as_i64().unwrap_or(0) + 1;
+                let lane_bytes = obj["estimated_bytes"].as_i64().unwrap_or(0) + bytes;
+                obj.insert("count".into(), json!(count));
+                obj.insert("estimated_bytes".into(), json!(lane_bytes));
+            }
+            let reason = match cand.reason {
+                MemoryGcReason::TtlExpired { .. } => "ttl_expired",
+                MemoryGcReason::LaneCap { .. } => "lane_cap",
+            };
+            *by_reason.entry(reason).or_insert(0) += 1;
+        }
+        Ok(json!({
+            "generated": now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "total": candidates.len(),
+            "estimated_bytes": total_bytes,
+            "by_lane": Value::Object(by_lane),
+            "by_reason": by_reason,
+            "candidates": candidates,
+        }))
+    }
+
+    /// Sum of the payload column lengths per record, the store's estimate
+    /// of what deleting each would reclaim.
+    fn estimate_record_bytes(&self, ids: &[String]) -> Result<HashMap<String, i64>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "SELECT id, LENGTH(value) \
+                 + COALESCE(LENGTH(text),0) + COALESCE(LENGTH(embed_blob),0) \
+                 + COALESCE(LENGTH(embed),0) + COALESCE(LENGTH(tags),0) \
+                 + COALESCE(LENGTH(keywords),0) + COALESCE(LENGTH(entities),0) \
+                 + COALESCE(LENGTH(source),0) + COALESCE(LENGTH(links),0) \
+                 + COALESCE(LENGTH(extra),0) \
+             FROM memory_records WHERE id IN ({placeholders})"
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let params = params_from_iter(ids.iter().map(|s| s.as_str()));
+        let mut rows = stmt.query(params)?;
+        let mut out = HashMap::with_capacity(ids.len());
+        while let Some(row) = rows.next()? {
+            out.insert(row.get::<_, String>(0)?, row.get::<_, i64>(1)?);
+        }
+        Ok(out)
+    }
+
+    /// Fold a batch of GC candidates into one summary record: the callback
+    /// sees the hydrated records and may return a summary, which is
+    /// inserted with a `links.summarizes` list naming every original (the
+    /// provenance lives on the summary itself, since `delete_records`
+    /// purges link rows touching the deleted ids) before the originals are
+    /// removed. A callback returning `None` vetoes the batch and nothing
+    /// is deleted. Returns `{"deleted","summary_id"}`.
+    pub fn compact_memory_candidates(
+        &self,
+        candidates: &[MemoryGcCandidate],
+        summarize: &dyn Fn(&[Value]) -> Result<Option<MemoryInsertOwned>>,
+    ) -> Result<Value> {
+        let ids: Vec<String> = candidates.iter().map(|c| c.id.clone()).collect();
+        let by_id = self.get_memory_many(&ids)?;
+        let records: Vec<Value> = ids.iter().filter_map(|id| by_id.get(id).cloned()).collect();
+        if records.is_empty() {
+            return Ok(json!({"deleted": 0, "summary_id": Value::Null}));
+        }
+        let Some(mut summary) = summarize(&records)? else {
+            return Ok(json!({"deleted": 0, "summary_id": Value::Null}));
+        };
+        let kept: Vec<&str> = records
+            .iter()
+            .filter_map(|r| r.get("id").and_then(|v| v.as_str()))
+            .collect();
+        let mut links = summary
+            .links
+            .take()
+            .and_then(|v| v.as_object().cloned())
+            .unwrap_or_default();
+        links.insert("summarizes".into(), json!(kept));
+        summary.links = Some(Value::Object(links));
+        let (summary_id, _) = self.insert_memory_with_record(&summary.to_args())?;
+        let deleted = self.delete_records(&ids)?;
+        Ok(json!({"deleted": deleted, "summary_id": summary_id}))
+    }
+
+    /// Upsert a lane's registry entry; the stored `updated` stamp is set
+    /// here, whatever the input carries.
+    pub fn set_lane_config(&self, cfg: &MemoryLaneConfig) -> Result<()> {
+        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        self.conn.execute(
+            "INSERT OR REPLACE INTO memory_lanes(lane,default_ttl_s,cap,default_durability,half_life_s,refresh_ttl_on_access,updated) \
+             VALUES(?,?,?,?,?,?,?)",
+            params![
+                cfg.lane,
+                cfg.default_ttl_s,
+                cfg.cap,
+                cfg.default_durability,
+                cfg.half_life_s,
+                cfg.refresh_ttl_on_access,
+                now
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_lane_config(&self, lane: &str) -> Result<Option<MemoryLaneConfig>> {
+        self.conn
+            .query_row(
+                "SELECT lane,default_ttl_s,cap,default_durability,half_life_s,refresh_ttl_on_access,updated \
+                 FROM memory_lanes WHERE lane=?",
+                params![lane],
+                |r| {
+                    Ok(MemoryLaneConfig {
+                        lane: r.get(0)?,
+                        default_ttl_s: r.get(1)?,
+                        cap: r.get(2)?,
+                        default_durability: r.get(3)?,
+                        half_life_s: r.get(4)?,
+                        refresh_ttl_on_access: r.get(5)?,
+                        updated: r.get(6)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    pub fn list_lane_configs(&self) -> Result<Vec<MemoryLaneConfig>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT lane,default_ttl_s,cap,default_durability,half_life_s,refresh_ttl_on_access,updated \
+             FROM memory_lanes ORDER BY lane ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(MemoryLaneConfig {
+                lane: r.get(0)?,
+                default_ttl_s: r.get(1)?,
+                cap: r.get(2)?,
+                default_durability: r.get(3)?,
+                half_life_s: r.get(4)?,
+                refresh_ttl_on_access: r.get(5)?,
+                updated: r.get(6)?,
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn remove_lane_config(&self, lane: &str) -> Result<bool> {
+        let n = self
+            .conn
+            .execute("DELETE FROM memory_lanes WHERE lane=?", params![lane])?;
+        Ok(n > 0)
+    }
+
+    /// Pin the vector dimension for an `embed_hint` ahead of any inserts;
+    /// an insert under an unregistered hint pins it implicitly from its
+    /// first vector instead.
+    pub fn register_embed_model(&self, hint: &str, dim: usize) -> Result<()> {
+        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        self.conn.execute(
+            "INSERT OR REPLACE INTO memory_embed_models(hint,dim,updated) VALUES(?,?,?)",
+            params![hint, dim as i64, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn embed_model_dim(&self, hint: &str) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT dim FROM memory_embed_models WHERE hint=?",
+                params![hint],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    pub fn list_embed_models(&self) -> Result<Value> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT hint,dim,updated FROM memory_embed_models ORDER BY hint ASC")?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(json!({
+                "hint": r.get::<_, String>(0)?,
+                "dim": r.get::<_, i64>(1)?,
+                "updated": r.get::<_, String>(2)?,
+            }));
+        }
+        Ok(Value::Array(out))
+    }
+
+    pub fn remove_embed_model(&self, hint: &str) -> Result<bool> {
+        let n = self.conn.execute(
+            "DELETE FROM memory_embed_models WHERE hint=?",
+            params![hint],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Insert-side guard: reject a vector whose dimension contradicts the
+    /// registered hint, registering first-seen hints as a side effect.
+    fn check_embed_model(&self, hint: &str, dim: usize, now: &str) -> Result<()> {
+        match self.embed_model_dim(hint)? {
+            Some(expected) if expected != dim as i64 => Err(anyhow::anyhow!(
+                "embed_hint '{hint}' expects {expected}-dim vectors, got {dim}"
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO memory_embed_models(hint,dim,updated) VALUES(?,?,?)",
+                    params![hint, dim as i64, now],
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Overflow candidates for every lane whose registry entry declares a
+    /// cap, so hygiene passes need no caller-side cap tables.
+    pub fn configured_lane_overflow_candidates(
+        &self,
+        limit_per_lane: usize,
+    ) -> Result<Vec<MemoryGcCandidate>> {
+        let mut out = Vec::new();
+        for cfg in self.list_lane_configs()? {
+            let Some(cap) = cfg.cap.filter(|c| *c >= 0) else {
+                continue;
+            };
+            out.extend(self.lane_overflow_candidates(&cfg.lane, cap as usize, limit_per_lane)?);
+        }
+        Ok(out)
+    }
+
+    /// Ranking override from the lane registry: the recency half-life in
+    /// seconds, when the searched lane declares one.
+    fn lane_half_life_s(&self, lane: Option<&str>) -> Result<Option<f64>> {
+        let Some(lane) = lane else {
+            return Ok(None);
+        };
+        Ok(self
+            .get_lane_config(lane)?
+            .and_then(|c| c.half_life_s)
+            .map(|s| s as f64))
+    }
+
     pub fn lane_overflow_candidates(
         &self,
         lane: &str,
@@ -818,11 +2011,14 @@ impl<'c> MemoryStore<'c> {
         if fetch == 0 {
             return Ok(Vec::new());
         }
+        // Usage-aware eviction order: least-recently-read first (falling
+        // back to `updated` for never-read rows), ties broken by read
+        // frequency so a frequently-hit record outlives a one-off peer.
         let mut stmt = self.conn.prepare(
             "SELECT id,lane,kind,project_id,agent_id,durability,ttl_s,created,updated \
              FROM memory_records \
              WHERE lane = ?1 \
-             ORDER BY updated ASC, id ASC \
+             ORDER BY COALESCE(last_accessed, updated) ASC, access_count ASC, id ASC \
              LIMIT ?2",
         )?;
         let mut rows = stmt.query(params![lane, fetch as i64])?;
@@ -865,10 +2061,44 @@ impl<'c> MemoryStore<'c> {
             }
         }
 
+        {
+            let mut stmt = tx.prepare("DELETE FROM memory_ann WHERE id = ?1")?;
+            for id in ids {
+                let _ = stmt.execute(params![id])?;
+            }
+        }
+
+        {
+            let mut stmt = tx.prepare("DELETE FROM memory_revisions WHERE id = ?1")?;
+            for id in ids {
+                let _ = stmt.execute(params![id])?;
+            }
+        }
+
+        {
+            let mut stmt = tx.prepare("DELETE FROM memory_tags WHERE id = ?1")?;
+            for id in ids {
+                let _ = stmt.execute(params![id])?;
+            }
+        }
+
         tx.commit()?;
         Ok(total_deleted)
     }
 
+    /// Rewrite the database to reclaim pages after heavy deletes, then
+    /// rebuild the FTS index (which fragments independently of the main
+    /// tables). Runs `VACUUM`, so the caller must hold exclusive access:
+    /// no open transactions on this connection and no concurrent writers.
+    pub fn compact(&self) -> Result<()> {
+        self.conn
+            .execute("INSERT INTO memory_fts(memory_fts) VALUES('rebuild')", [])?;
+        self.conn
+            .execute("INSERT INTO memory_fts(memory_fts) VALUES('optimize')", [])?;
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
     pub fn backfill_embed_blobs(&self, batch_limit: usize) -> Result<usize> {
         let limit = batch_limit.clamp(1, 1024);
         let mut to_update: Vec<(String, Vec<u8>)> = Vec::new();
@@ -907,6 +2137,107 @@ impl<'c> MemoryStore<'c> {
         Ok(count)
     }
 
+    /// Index up to `batch_limit` embedded rows missing from `memory_ann`,
+    /// returning how many were added. Run repeatedly until it returns 0 to
+    /// backfill a store created before the ANN index existed; searches fall
+    /// back to brute force until the backlog drains.
+    pub fn backfill_ann_index(&self, batch_limit: usize) -> Result<usize> {
+        let limit = batch_limit.clamp(1, 1024);
+        let mut to_index: Vec<(String, Vec<f32>)> = Vec::new();
+        {
+            let mut stmt = self.conn.prepare(
+                "SELECT r.id, r.embed, r.embed_blob \
+                 FROM memory_records r LEFT JOIN memory_ann a ON a.id = r.id \
+                 WHERE a.id IS NULL \
+                   AND (r.embed_blob IS NOT NULL OR (r.embed IS NOT NULL AND r.embed NOT IN ('','[]'))) \
+                 ORDER BY r.updated ASC, r.id ASC \
+                 LIMIT ?1",
+            )?;
+            let mut rows = stmt.query(params![limit as i64])?;
+            while let Some(row) = rows.next()? {
+                let id: String = row.get(0)?;
+                let embed_s: Option<String> = row.get(1)?;
+                let blob: Option<Vec<u8>> = row.get(2)?;
+                let vec = match blob {
+                    Some(b) => decode_embed_blob(&b),
+                    None => embed_s.as_deref().and_then(|s| parse_embedding(s).ok()),
+                };
+                match vec {
+                    Some(v) if !v.is_empty() => to_index.push((id, v)),
+                    _ => {}
+                }
+            }
+        }
+        if to_index.is_empty() {
+            return Ok(0);
+        }
+        let count = to_index.len();
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR REPLACE INTO memory_ann(id,dim,bucket) VALUES(?1,?2,?3)")?;
+            for (id, vec) in to_index.into_iter() {
+                let _ = stmt.execute(params![id, vec.len() as i64, ann_bucket(&vec)])?;
+            }
+        }
+        tx.commit()?;
+        Ok(count)
+    }
+
+    /// Populate the normalized tag index for legacy rows written before it
+    /// existed; new writes maintain it inline.
+    pub fn backfill_tag_index(&self, batch_limit: usize) -> Result<usize> {
+        let limit = batch_limit.clamp(1, 1024);
+        let mut to_index: Vec<(String, String)> = Vec::new();
+        {
+            let mut stmt = self.conn.prepare(
+                "SELECT r.id, r.tags \
+                 FROM memory_records r LEFT JOIN memory_tags t ON t.id = r.id \
+                 WHERE t.id IS NULL AND r.tags IS NOT NULL AND r.tags != '' \
+                 GROUP BY r.id \
+                 ORDER BY r.updated ASC, r.id ASC \
+                 LIMIT ?1",
+            )?;
+            let mut rows = stmt.query(params![limit as i64])?;
+            while let Some(row) = rows.next()? {
+                to_index.push((row.get(0)?, row.get(1)?));
+            }
+        }
+        if to_index.is_empty() {
+            return Ok(0);
+        }
+        let count = to_index.len();
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, tags) in to_index.iter() {
+            self.rewrite_tag_index(id, Some(tags))?;
+        }
+        tx.commit()?;
+        Ok(count)
+    }
+
+    /// Tagged rows the normalized tag index does not cover yet.
+    pub fn pending_tag_backfill(&self) -> Result<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM memory_records r LEFT JOIN memory_tags t ON t.id = r.id \
+             WHERE t.id IS NULL AND r.tags IS NOT NULL AND r.tags != ''",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count.max(0) as u64)
+    }
+
+    /// Embedded rows the ANN index does not cover yet.
+    pub fn pending_ann_backfill(&self) -> Result<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM memory_records r LEFT JOIN memory_ann a ON a.id = r.id \
+             WHERE a.id IS NULL \
+               AND (r.embed_blob IS NOT NULL OR (r.embed IS NOT NULL AND r.embed NOT IN ('','[]')))",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count.max(0) as u64)
+    }
+
     pub fn pending_embed_backfill(&self) -> Result<u64> {
         let mut stmt = self.conn.prepare(
             "SELECT COUNT(*) FROM memory_records WHERE embed_blob IS NULL AND embed IS NOT NULL",
@@ -920,26 +2251,184 @@ impl<'c> MemoryStore<'c> {
         }
     }
 
-    pub fn insert_memory_link(
+    /// Records with no embedding yet (null or empty `embed`), oldest-first
+    /// so an incremental embedding worker drains the backlog in insert
+    /// order. Returns `(id, text)` pairs; `text` is whatever the caller
+    /// stored to embed from.
+    pub fn list_unembedded(
         &self,
-        src_id: &str,
-        dst_id: &str,
-        rel: Option<&str>,
-        weight: Option<f64>,
-    ) -> Result<()> {
-        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let rel = rel.unwrap_or("");
-        self.conn.execute(
-            "INSERT OR REPLACE INTO memory_links(src_id,dst_id,rel,weight,created,updated) VALUES(?,?,?,?,?,?)",
-            params![src_id, dst_id, rel, weight, now, now],
-        )?;
-        Ok(())
+        lane: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        let mut out = Vec::new();
+        if let Some(l) = lane {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, text FROM memory_records \
+                 WHERE embed_blob IS NULL AND (embed IS NULL OR embed='' OR embed='[]') AND lane=? \
+                 ORDER BY updated ASC, id ASC LIMIT ?",
+            )?;
+            let mut rows = stmt.query(params![l, limit])?;
+            while let Some(r) = rows.next()? {
+                out.push((r.get(0)?, r.get(1)?));
+            }
+        } else {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, text FROM memory_records \
+                 WHERE embed_blob IS NULL AND (embed IS NULL OR embed='' OR embed='[]') \
+                 ORDER BY updated ASC, id ASC LIMIT ?",
+            )?;
+            let mut rows = stmt.query(params![limit])?;
+            while let Some(r) = rows.next()? {
+                out.push((r.get(0)?, r.get(1)?));
+            }
+        }
+        Ok(out)
     }
 
-    pub fn list_memory_links(&self, src_id: &str, limit: i64) -> Result<Vec<Value>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT dst_id,rel,weight,updated FROM memory_links WHERE src_id=? ORDER BY updated DESC LIMIT ?",
-        )?;
+    /// Fold `drop_id` into `keep_id`: links referencing the dropped record
+    /// are repointed at the kept one (duplicate edges collapse onto the
+    /// existing row), tags and keywords are unioned, and the dropped record
+    /// plus its FTS row are removed — all in one transaction.
+    pub fn merge_records(&self, keep_id: &str, drop_id: &str) -> Result<()> {
+        if keep_id == drop_id {
+            return Err(anyhow::anyhow!("cannot merge a record into itself"));
+        }
+        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let tx = self.conn.unchecked_transaction()?;
+        for (label, id) in [("keep", keep_id), ("drop", drop_id)] {
+            let found: Option<i64> = tx
+                .query_row(
+                    "SELECT 1 FROM memory_records WHERE id=? LIMIT 1",
+                    params![id],
+                    |r| r.get(0),
+                )
+                .optional()?;
+            if found.is_none() {
+                return Err(anyhow::anyhow!("{label} record {id} not found"));
+            }
+        }
+
+        let (keep_tags, keep_kw): (Option<String>, Option<String>) = tx.query_row(
+            "SELECT tags, keywords FROM memory_records WHERE id=?",
+            params![keep_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        let (drop_tags, drop_kw): (Option<String>, Option<String>) = tx.query_row(
+            "SELECT tags, keywords FROM memory_records WHERE id=?",
+            params![drop_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        tx.execute(
+            "UPDATE memory_records SET tags=?, keywords=?, updated=? WHERE id=?",
+            params![
+                merge_joined_lists(keep_tags, drop_tags),
+                merge_joined_lists(keep_kw, drop_kw),
+                now,
+                keep_id
+            ],
+        )?;
+
+        // Repoint links; edges that would duplicate an existing (src,dst,rel)
+        // key are left behind and swept with the remaining drop references.
+        tx.execute(
+            "UPDATE OR IGNORE memory_links SET src_id=?1, updated=?2 WHERE src_id=?3",
+            params![keep_id, now, drop_id],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE memory_links SET dst_id=?1, updated=?2 WHERE dst_id=?3",
+            params![keep_id, now, drop_id],
+        )?;
+        tx.execute(
+            "DELETE FROM memory_links WHERE src_id=?1 OR dst_id=?1",
+            params![drop_id],
+        )?;
+        // A keep<->drop edge collapses into a self-loop; drop it.
+        tx.execute(
+            "DELETE FROM memory_links WHERE src_id=?1 AND dst_id=?1",
+            params![keep_id],
+        )?;
+
+        tx.execute("DELETE FROM memory_records WHERE id=?", params![drop_id])?;
+        tx.execute("DELETE FROM memory_fts WHERE id=?", params![drop_id])?;
+        tx.execute("DELETE FROM memory_ann WHERE id=?", params![drop_id])?;
+        tx.execute("DELETE FROM memory_revisions WHERE id=?", params![drop_id])?;
+        tx.execute("DELETE FROM memory_tags WHERE id=?", params![drop_id])?;
+        let keep_tags: Option<String> = tx.query_row(
+            "SELECT tags FROM memory_records WHERE id=?",
+            params![keep_id],
+            |r| r.get(0),
+        )?;
+        self.rewrite_tag_index(keep_id, keep_tags.as_deref())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Prior contents of an overwritten record, newest first. Each entry is
+    /// `{revision, captured, record}` where `record` is the hydrated value
+    /// the row held before that overwrite.
+    pub fn list_memory_revisions(&self, id: &str, limit: i64) -> Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT revision, record, captured FROM memory_revisions \
+             WHERE id=? ORDER BY revision DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![id, limit])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let record_s: String = row.get(1)?;
+            let record = serde_json::from_str::<Value>(&record_s)
+                .unwrap_or_else(|_| Value::Object(Map::new()));
+            out.push(json!({
+                "revision": row.get::<_, i64>(0)?,
+                "captured": row.get::<_, String>(2)?,
+                "record": record,
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Re-insert the snapshot stored under `revision` through the normal
+    /// insert path, so the state being replaced is itself captured as a new
+    /// revision and FTS/ANN stay in step. Returns the restored record.
+    pub fn restore_memory_revision(&self, id: &str, revision: i64) -> Result<Value> {
+        let record_s: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT record FROM memory_revisions WHERE id=? AND revision=?",
+                params![id, revision],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let Some(record_s) = record_s else {
+            return Err(anyhow::anyhow!("memory {id} has no revision {revision}"));
+        };
+        let record: Value = serde_json::from_str(&record_s).map_err(|e| {
+            anyhow::anyhow!("memory {id} revision {revision} snapshot is not valid JSON: {e}")
+        })?;
+        let owned = record_to_insert_owned(&record);
+        let (_, value) = self.insert_memory_with_record(&owned.to_args())?;
+        Ok(value)
+    }
+
+    pub fn insert_memory_link(
+        &self,
+        src_id: &str,
+        dst_id: &str,
+        rel: Option<&str>,
+        weight: Option<f64>,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let rel = rel.unwrap_or("");
+        self.conn.execute(
+            "INSERT OR REPLACE INTO memory_links(src_id,dst_id,rel,weight,created,updated) VALUES(?,?,?,?,?,?)",
+            params![src_id, dst_id, rel, weight, now, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_memory_links(&self, src_id: &str, limit: i64) -> Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT dst_id,rel,weight,updated FROM memory_links WHERE src_id=? ORDER BY updated DESC LIMIT ?",
+        )?;
         let mut rows = stmt.query(params![src_id, limit])?;
         let mut out = Vec::new();
         while let Some(r) = rows.next()? {
@@ -1017,7 +2506,360 @@ impl<'c> MemoryStore<'c> {
         Ok(out)
     }
 
+    /// Stream records (and the link edges between them) as JSONL, one
+    /// `{"type":"record",...}` or `{"type":"link",...}` object per line.
+    /// Records carry their full hydrated shape — embeddings, tags, keywords —
+    /// so the stream round-trips through [`Self::import_memory`]. `lane` and
+    /// `project` narrow the export; links are included when their source
+    /// record is. Returns the number of lines written.
+    pub fn export_memory(
+        &self,
+        lane: Option<&str>,
+        project: Option<&str>,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<usize> {
+        let mut filter = String::new();
+        let mut params: Vec<&dyn rusqlite::ToSql> = Vec::new();
+        if let Some(ref l) = lane {
+            filter.push_str(" AND lane=?");
+            params.push(l as &dyn rusqlite::ToSql);
+        }
+        if let Some(ref p) = project {
+            filter.push_str(" AND project_id=?");
+            params.push(p as &dyn rusqlite::ToSql);
+        }
+        let mut written = 0usize;
+        {
+            let sql = format!(
+                "SELECT {cols} FROM memory_records WHERE 1=1{filter} ORDER BY id ASC",
+                cols = select_columns(None)
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut rows = stmt.query(&params[..])?;
+            while let Some(r) = rows.next()? {
+                let record = row_to_value_full(r)?;
+                serde_json::to_writer(&mut *writer, &json!({"type": "record", "record": record}))?;
+                writer.write_all(b"\n")?;
+                written += 1;
+            }
+        }
+        {
+            let sql = format!(
+                "SELECT l.src_id, l.dst_id, l.rel, l.weight \
+                 FROM memory_links l JOIN memory_records r ON r.id = l.src_id \
+                 WHERE 1=1{filter} ORDER BY l.src_id ASC, l.dst_id ASC, l.rel ASC"
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut rows = stmt.query(&params[..])?;
+            while let Some(r) = rows.next()? {
+                let link = json!({
+                    "type": "link",
+                    "src_id": r.get::<_, String>(0)?,
+                    "dst_id": r.get::<_, String>(1)?,
+                    "rel": r.get::<_, String>(2)?,
+                    "weight": r.get::<_, Option<f64>>(3)?,
+                });
+                serde_json::to_writer(&mut *writer, &link)?;
+                writer.write_all(b"\n")?;
+                written += 1;
+            }
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Import a JSONL stream produced by [`Self::export_memory`]. Records
+    /// keep their exported ids so link edges stay valid; each goes through
+    /// the normal insert path, so FTS/ANN and revision snapshots apply.
+    /// Returns `(records, links)` imported.
+    pub fn import_memory(
+        &self,
+        reader: &mut dyn std::io::Read,
+        options: &MemoryImportOptions,
+    ) -> Result<(usize, usize)> {
+        use std::io::BufRead as _;
+        let buf = std::io::BufReader::new(reader);
+        let tx = self.conn.unchecked_transaction()?;
+        let mut records = 0usize;
+        let mut links = 0usize;
+        for (line_no, line) in buf.lines().enumerate() {
+            let line = line?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let entry: Value = serde_json::from_str(trimmed).map_err(|e| {
+                anyhow::anyhow!("invalid memory entry on line {}: {e}", line_no + 1)
+            })?;
+            match entry.get("type").and_then(|v| v.as_str()) {
+                Some("record") => {
+                    let record = entry.get("record").ok_or_else(|| {
+                        anyhow::anyhow!("record entry on line {} has no record", line_no + 1)
+                    })?;
+                    let mut owned = record_to_insert_owned(record);
+                    if let Some(ref lane) = options.lane_override {
+                        owned.lane = lane.clone();
+                        // The stored hash covered the exported lane.
+                        owned.hash = None;
+                    }
+                    owned.on_conflict = options.on_conflict;
+                    self.insert_memory(&owned.to_args())?;
+                    records += 1;
+                }
+                Some("link") => {
+                    let src = entry.get("src_id").and_then(|v| v.as_str());
+                    let dst = entry.get("dst_id").and_then(|v| v.as_str());
+                    let (Some(src), Some(dst)) = (src, dst) else {
+                        return Err(anyhow::anyhow!(
+                            "link entry on line {} is missing src_id/dst_id",
+                            line_no + 1
+                        ));
+                    };
+                    self.insert_memory_link(
+                        src,
+                        dst,
+                        entry.get("rel").and_then(|v| v.as_str()),
+                        entry.get("weight").and_then(|v| v.as_f64()),
+                    )?;
+                    links += 1;
+                }
+                other => {
+                    return Err(anyhow::anyhow!(
+                        "unknown memory entry type {:?} on line {}",
+                        other,
+                        line_no + 1
+                    ));
+                }
+            }
+        }
+        tx.commit()?;
+        Ok((records, links))
+    }
+
+    /// Incoming edges for a record: who links TO `dst_id`.
+    pub fn list_memory_backlinks(&self, dst_id: &str, limit: i64) -> Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT src_id,rel,weight,updated FROM memory_links WHERE dst_id=? ORDER BY updated DESC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![dst_id, limit])?;
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            out.push(serde_json::json!({
+                "src_id": r.get::<_, String>(0)?,
+                "rel": r.get::<_, String>(1)?,
+                "weight": r.get::<_, Option<f64>>(2)?,
+                "updated": r.get::<_, String>(3)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    pub fn list_memory_backlinks_many(
+        &self,
+        dst_ids: &[String],
+        limit_per: i64,
+    ) -> Result<HashMap<String, Vec<Value>>> {
+        if dst_ids.is_empty() || limit_per == 0 {
+            return Ok(HashMap::new());
+        }
+        let placeholders = dst_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        if limit_per < 0 {
+            let sql = format!(
+                "SELECT dst_id,src_id,rel,weight,updated \
+                 FROM memory_links \
+                 WHERE dst_id IN ({placeholders}) \
+                 ORDER BY dst_id ASC, updated DESC"
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let params = params_from_iter(dst_ids.iter().map(|s| s.as_str()));
+            let mut rows = stmt.query(params)?;
+            let mut out: HashMap<String, Vec<Value>> = HashMap::new();
+            while let Some(row) = rows.next()? {
+                let dst_id: String = row.get(0)?;
+                out.entry(dst_id.clone()).or_default().push(json!({
+                    "src_id": row.get::<_, String>(1)?,
+                    "rel": row.get::<_, String>(2)?,
+                    "weight": row.get::<_, Option<f64>>(3)?,
+                    "updated": row.get::<_, String>(4)?,
+                }));
+            }
+            return Ok(out);
+        }
+
+        let sql = format!(
+            "SELECT dst_id,src_id,rel,weight,updated \
+             FROM ( \
+                 SELECT dst_id,src_id,rel,weight,updated, \
+                        ROW_NUMBER() OVER (PARTITION BY dst_id ORDER BY updated DESC) AS rn \
+                 FROM memory_links \
+                 WHERE dst_id IN ({placeholders}) \
+             ) \
+             WHERE rn <= ? \
+             ORDER BY dst_id ASC, updated DESC"
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(dst_ids.len() + 1);
+        for id in dst_ids {
+            params.push(id as &dyn rusqlite::ToSql);
+        }
+        let limit_param = limit_per;
+        params.push(&limit_param);
+        let mut rows = stmt.query(&params[..])?;
+        let mut out: HashMap<String, Vec<Value>> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let dst_id: String = row.get(0)?;
+            out.entry(dst_id.clone()).or_default().push(json!({
+                "src_id": row.get::<_, String>(1)?,
+                "rel": row.get::<_, String>(2)?,
+                "weight": row.get::<_, Option<f64>>(3)?,
+                "updated": row.get::<_, String>(4)?,
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Bounded breadth-first walk over outgoing `memory_links` edges.
+    /// `max_depth` is capped at 8 and `limit` (clamped to `1..=1024`) bounds
+    /// the total nodes returned, starting records included; each node is
+    /// expanded at most once, so cycles terminate. Returns
+    /// `{"nodes": [...], "edges": [...]}` where nodes carry the depth they
+    /// were first reached at and edges the depth of their traversal step.
+    pub fn traverse_memory_graph(
+        &self,
+        start_ids: &[String],
+        max_depth: usize,
+        rel_filter: Option<&str>,
+        limit: usize,
+    ) -> Result<Value> {
+        let limit = limit.clamp(1, 1024);
+        let max_depth = max_depth.min(8);
+        let rel_owned = rel_filter.map(|s| s.to_string());
+        let mut depths: HashMap<String, usize> = HashMap::new();
+        let mut order: Vec<String> = Vec::new();
+        let mut frontier: Vec<String> = Vec::new();
+        for id in start_ids {
+            if depths.len() >= limit {
+                break;
+            }
+            if !depths.contains_key(id) {
+                depths.insert(id.clone(), 0);
+                order.push(id.clone());
+                frontier.push(id.clone());
+            }
+        }
+        let mut edges: Vec<Value> = Vec::new();
+        let mut depth = 0usize;
+        while !frontier.is_empty() && depth < max_depth && depths.len() < limit {
+            depth += 1;
+            let placeholders = frontier.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+            let sql = format!(
+                "SELECT src_id, dst_id, rel, weight FROM memory_links \
+                 WHERE src_id IN ({placeholders}){rel_clause} \
+                 ORDER BY src_id ASC, updated DESC",
+                rel_clause = if rel_owned.is_some() {
+                    " AND rel=?"
+                } else {
+                    ""
+                }
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut params: Vec<&dyn rusqlite::ToSql> = frontier
+                .iter()
+                .map(|id| id as &dyn rusqlite::ToSql)
+                .collect();
+            if let Some(ref rel) = rel_owned {
+                params.push(rel as &dyn rusqlite::ToSql);
+            }
+            let mut rows = stmt.query(&params[..])?;
+            let mut next: Vec<String> = Vec::new();
+            while let Some(row) = rows.next()? {
+                let dst: String = row.get(1)?;
+                edges.push(json!({
+                    "src_id": row.get::<_, String>(0)?,
+                    "dst_id": dst.clone(),
+                    "rel": row.get::<_, String>(2)?,
+                    "weight": row.get::<_, Option<f64>>(3)?,
+                    "depth": depth,
+                }));
+                if !depths.contains_key(&dst) && depths.len() < limit {
+                    depths.insert(dst.clone(), depth);
+                    order.push(dst.clone());
+                    next.push(dst);
+                }
+            }
+            frontier = next;
+        }
+        let mut records = self.get_memory_many(&order)?;
+        let nodes: Vec<Value> = order
+            .iter()
+            .filter_map(|id| {
+                records.remove(id).map(|mut rec| {
+                    if let Some(map) = rec.as_object_mut() {
+                        map.insert("depth".into(), json!(depths.get(id).copied().unwrap_or(0)));
+                    }
+                    rec
+                })
+            })
+            .collect();
+        Ok(json!({"nodes": nodes, "edges": edges}))
+    }
+
     pub fn get_memory(&self, id: &str) -> Result<Option<Value>> {
+        let value = self.get_memory_untracked(id)?;
+        if value.is_some() {
+            self.record_access(&[id.to_string()]);
+        }
+        Ok(value)
+    }
+
+    /// [`Self::get_memory`] filtered through the caller's [`ReadContext`];
+    /// a record above the clearance reads as absent.
+    pub fn get_memory_with_context(&self, id: &str, ctx: &ReadContext) -> Result<Option<Value>> {
+        Ok(self
+            .get_memory(id)?
+            .and_then(|mut rec| ctx.admit(&mut rec).then_some(rec)))
+    }
+
+    /// [`Self::search_memory`] with clearance filtering applied after the
+    /// fetch; pages can come back shorter than `limit` when records are
+    /// withheld.
+    pub fn search_memory_with_context(
+        &self,
+        query: &str,
+        lane: Option<&str>,
+        limit: i64,
+        ctx: &ReadContext,
+    ) -> Result<Vec<Value>> {
+        Ok(ctx.apply_all(self.search_memory(query, lane, limit)?))
+    }
+
+    /// [`Self::fts_search_memory`] filtered through a [`ReadContext`].
+    pub fn fts_search_memory_with_context(
+        &self,
+        query: &str,
+        lane: Option<&str>,
+        limit: i64,
+        ctx: &ReadContext,
+    ) -> Result<Vec<Value>> {
+        Ok(ctx.apply_all(self.fts_search_memory(query, lane, limit)?))
+    }
+
+    /// [`Self::select_memory_hybrid`] filtered through a [`ReadContext`].
+    pub fn select_memory_hybrid_with_context(
+        &self,
+        query: Option<&str>,
+        embed: Option<&[f32]>,
+        lane: Option<&str>,
+        limit: i64,
+        ctx: &ReadContext,
+    ) -> Result<Vec<Value>> {
+        Ok(ctx.apply_all(self.select_memory_hybrid(query, embed, lane, limit)?))
+    }
+
+    /// Fetch a record without bumping its access stats; used internally
+    /// where a read is bookkeeping rather than retrieval (e.g. revision
+    /// snapshots).
+    fn get_memory_untracked(&self, id: &str) -> Result<Option<Value>> {
         let sql = format!(
             "SELECT {cols} FROM memory_records WHERE id=? LIMIT 1",
             cols = select_columns(None)
@@ -1079,6 +2921,80 @@ impl<'c> MemoryStore<'c> {
         Ok(out)
     }
 
+    /// Top-K most recently updated records per lane, fetched in one
+    /// windowed query instead of one query per lane.
+    pub fn top_per_lane(&self, lanes: &[String], k: i64) -> Result<HashMap<String, Vec<Value>>> {
+        if lanes.is_empty() || k <= 0 {
+            return Ok(HashMap::new());
+        }
+        let placeholders = lanes.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "SELECT {cols} FROM ( \
+                 SELECT {cols}, \
+                        ROW_NUMBER() OVER (PARTITION BY lane ORDER BY updated DESC, id DESC) AS rn \
+                 FROM memory_records \
+                 WHERE lane IN ({placeholders}) \
+             ) \
+             WHERE rn <= ? \
+             ORDER BY lane ASC, updated DESC",
+            cols = select_columns(None)
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(lanes.len() + 1);
+        for lane in lanes {
+            params.push(lane as &dyn rusqlite::ToSql);
+        }
+        let k_param = k;
+        params.push(&k_param);
+        let mut rows = stmt.query(&params[..])?;
+        let mut out: HashMap<String, Vec<Value>> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let record = row_to_value_full(row)?;
+            let lane = record["lane"].as_str().unwrap_or_default().to_string();
+            out.entry(lane).or_default().push(record);
+        }
+        Ok(out)
+    }
+
+    /// Records whose `updated` timestamp falls within `[since, until]`,
+    /// ordered ascending for timeline/journal style consumers.
+    pub fn list_memory_in_window(
+        &self,
+        lane: Option<&str>,
+        since: &str,
+        until: &str,
+        limit: i64,
+    ) -> Result<Vec<Value>> {
+        if since > until {
+            return Err(anyhow::anyhow!(
+                "invalid window: since {since} is after until {until}"
+            ));
+        }
+        let mut out = Vec::new();
+        if let Some(l) = lane {
+            let sql = format!(
+                "SELECT {cols} FROM memory_records WHERE lane=? AND updated BETWEEN ? AND ? ORDER BY updated ASC LIMIT ?",
+                cols = select_columns(None)
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut rows = stmt.query(params![l, since, until, limit])?;
+            while let Some(r) = rows.next()? {
+                out.push(row_to_value_full(r)?);
+            }
+        } else {
+            let sql = format!(
+                "SELECT {cols} FROM memory_records WHERE updated BETWEEN ? AND ? ORDER BY updated ASC LIMIT ?",
+                cols = select_columns(None)
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut rows = stmt.query(params![since, until, limit])?;
+            while let Some(r) = rows.next()? {
+                out.push(row_to_value_full(r)?);
+            }
+        }
+        Ok(out)
+    }
+
     pub fn find_memory_by_hash(&self, hash: &str) -> Result<Option<Value>> {
         let sql = format!(
             "SELECT {cols} FROM memory_records WHERE hash=? LIMIT 1",
@@ -1286,17 +3202,130 @@ fn row_to_value_common(row: &rusqlite::Row<'_>) -> Result<Value> {
     if let Some(extra) = parse_json_string(row.get::<_, Option<String>>(26)?) {
         map.insert("extra".into(), extra);
     }
+    if let Some(last_accessed) = row.get::<_, Option<String>>(27)? {
+        map.insert("last_accessed".into(), json!(last_accessed));
+    }
+    let access_count = row.get::<_, Option<i64>>(28)?.unwrap_or(0);
+    if access_count > 0 {
+        map.insert("access_count".into(), json!(access_count));
+    }
 
     Ok(Value::Object(map))
 }
 
-fn split_list(input: &str) -> Vec<Value> {
-    input
-        .split(',')
-        .map(|part| part.trim())
-        .filter(|part| !part.is_empty())
-        .map(|part| Value::String(part.to_string()))
-        .collect()
+/// Rebuild insert arguments from a hydrated record (the shape produced by
+/// [`row_to_value_common`]); used to replay revision snapshots through the
+/// normal insert path.
+fn record_to_insert_owned(record: &Value) -> MemoryInsertOwned {
+    let get_str = |k: &str| {
+        record
+            .get(k)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+    let get_f64 = |k: &str| record.get(k).and_then(|v| v.as_f64());
+    let get_list = |k: &str| {
+        record.get(k).and_then(|v| v.as_array()).map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect::<Vec<_>>()
+        })
+    };
+    MemoryInsertOwned {
+        id: get_str("id"),
+        lane: get_str("lane").unwrap_or_default(),
+        kind: get_str("kind"),
+        key: get_str("key"),
+        value: record
+            .get("value")
+            .cloned()
+            .unwrap_or_else(|| Value::Object(Map::new())),
+        embed: record.get("embed").and_then(|v| v.as_array()).map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_f64().map(|f| f as f32))
+                .collect()
+        }),
+        embed_hint: get_str("embed_hint"),
+        // Snapshots hold the stored (already normalized) vector.
+        normalize_on_insert: false,
+        tags: get_list("tags"),
+        score: get_f64("score"),
+        prob: get_f64("prob"),
+        agent_id: get_str("agent_id"),
+        project_id: get_str("project_id"),
+        persona_id: get_str("persona_id"),
+        text: get_str("text"),
+        durability: get_str("durability"),
+        trust: get_f64("trust"),
+        privacy: get_str("privacy"),
+        ttl_s: record.get("ttl_s").and_then(|v| v.as_i64()),
+        keywords: get_list("keywords"),
+        entities: record.get("entities").cloned(),
+        source: record.get("source").cloned(),
+        links: record.get("links").cloned(),
+        extra: record.get("extra").cloned(),
+        hash: get_str("hash"),
+        on_conflict: OnConflict::default(),
+    }
+}
+
+/// The half-life ranking actually used: a positive lane override, else the
+/// built-in default. Surfaced on results as `_half_life_s`.
+fn effective_half_life(half_life_s: Option<f64>) -> f64 {
+    half_life_s
+        .filter(|h| *h > 0.0)
+        .unwrap_or(DEFAULT_HALF_LIFE_S)
+}
+
+/// Canonical form a tag takes in the `memory_tags` index: trimmed and
+/// lowercased; empty tokens drop out.
+fn normalize_tag(raw: &str) -> Option<String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    Some(trimmed.to_lowercase())
+}
+
+/// Union comma-joined tag/keyword lists, keeping the first list's order.
+fn merge_joined_lists(a: Option<String>, b: Option<String>) -> Option<String> {
+    let mut merged: Vec<String> = Vec::new();
+    for list in [a, b].into_iter().flatten() {
+        for item in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if !merged.iter().any(|m| m == item) {
+                merged.push(item.to_string());
+            }
+        }
+    }
+    (!merged.is_empty()).then(|| merged.join(","))
+}
+
+/// Union two `links` JSON values. Arrays merge element-wise (deduped by
+/// serialized form); any other shape keeps the stored value, falling back to
+/// the incoming one.
+fn merge_links_json(stored: Option<&Value>, incoming: Option<&Value>) -> Option<Value> {
+    match (stored, incoming) {
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            let mut merged = a.clone();
+            for item in b {
+                if !merged.contains(item) {
+                    merged.push(item.clone());
+                }
+            }
+            Some(Value::Array(merged))
+        }
+        (Some(v), _) => Some(v.clone()),
+        (None, v) => v.cloned(),
+    }
+}
+
+fn split_list(input: &str) -> Vec<Value> {
+    input
+        .split(',')
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .map(|part| Value::String(part.to_string()))
+        .collect()
 }
 
 fn parse_json_string(input: Option<String>) -> Option<Value> {
@@ -1352,11 +3381,81 @@ fn parse_embedding(embed_s: &str) -> Result<Vec<f32>> {
     Ok(values)
 }
 
+fn l2_normalize(values: &[f32]) -> Option<(Vec<f32>, f32)> {
+    let mut acc = 0f32;
+    for v in values {
+        acc += v * v;
+    }
+    let norm = acc.sqrt();
+    if norm == 0f32 || !norm.is_finite() {
+        return None;
+    }
+    Some((values.iter().map(|v| v / norm).collect(), norm))
+}
+
+/// Width of the inner accumulation lanes in the vector routines below. The
+/// fixed-size chunks plus independent accumulators remove the loop-carried
+/// dependency, which lets LLVM autovectorize to SIMD on every target
+/// without arch-specific intrinsics.
+const SIMD_LANES: usize = 8;
+
+fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    let mut lanes = [0f32; SIMD_LANES];
+    let chunks = a.len() / SIMD_LANES * SIMD_LANES;
+    for (ca, cb) in a[..chunks]
+        .chunks_exact(SIMD_LANES)
+        .zip(b[..chunks].chunks_exact(SIMD_LANES))
+    {
+        for i in 0..SIMD_LANES {
+            lanes[i] += ca[i] * cb[i];
+        }
+    }
+    let mut dot: f32 = lanes.iter().sum();
+    for i in chunks..a.len() {
+        dot += a[i] * b[i];
+    }
+    dot
+}
+
+fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
+    let mut lanes = [0f32; SIMD_LANES];
+    let chunks = a.len() / SIMD_LANES * SIMD_LANES;
+    for (ca, cb) in a[..chunks]
+        .chunks_exact(SIMD_LANES)
+        .zip(b[..chunks].chunks_exact(SIMD_LANES))
+    {
+        for i in 0..SIMD_LANES {
+            let d = ca[i] - cb[i];
+            lanes[i] += d * d;
+        }
+    }
+    let mut acc: f32 = lanes.iter().sum();
+    for i in chunks..a.len() {
+        let d = a[i] - b[i];
+        acc += d * d;
+    }
+    acc.sqrt()
+}
+
 fn cosine_sim(a: &[f32], b: &[f32]) -> f32 {
-    let mut dot = 0f32;
-    let mut na = 0f32;
-    let mut nb = 0f32;
-    for i in 0..a.len() {
+    let mut dot_lanes = [0f32; SIMD_LANES];
+    let mut na_lanes = [0f32; SIMD_LANES];
+    let mut nb_lanes = [0f32; SIMD_LANES];
+    let chunks = a.len() / SIMD_LANES * SIMD_LANES;
+    for (ca, cb) in a[..chunks]
+        .chunks_exact(SIMD_LANES)
+        .zip(b[..chunks].chunks_exact(SIMD_LANES))
+    {
+        for i in 0..SIMD_LANES {
+            dot_lanes[i] += ca[i] * cb[i];
+            na_lanes[i] += ca[i] * ca[i];
+            nb_lanes[i] += cb[i] * cb[i];
+        }
+    }
+    let mut dot: f32 = dot_lanes.iter().sum();
+    let mut na: f32 = na_lanes.iter().sum();
+    let mut nb: f32 = nb_lanes.iter().sum();
+    for i in chunks..a.len() {
         dot += a[i] * b[i];
         na += a[i] * a[i];
         nb += b[i] * b[i];
@@ -1368,6 +3467,50 @@ fn cosine_sim(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
+/// Number of LSH hyperplanes; buckets span `2^ANN_PLANES` values.
+const ANN_PLANES: usize = 8;
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Sign-bit locality-sensitive hash of `embed`. The hyperplane components
+/// are derived deterministically from the dimension and plane index, so a
+/// vector's bucket never changes across processes or restarts and nothing
+/// has to be persisted beyond the bucket itself.
+fn ann_bucket(embed: &[f32]) -> i64 {
+    let mut bucket = 0i64;
+    for plane in 0..ANN_PLANES {
+        let mut state = (embed.len() as u64).rotate_left(17)
+            ^ (plane as u64 + 1).wrapping_mul(0xA076_1D64_78BD_642F);
+        let mut dot = 0f64;
+        for &v in embed {
+            let r = splitmix64(&mut state);
+            let h = (r >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            dot += v as f64 * h;
+        }
+        if dot >= 0.0 {
+            bucket |= 1 << plane;
+        }
+    }
+    bucket
+}
+
+/// The query's bucket plus every bucket one bit-flip away, covering near
+/// misses on each hyperplane boundary.
+fn ann_probe_buckets(bucket: i64) -> Vec<i64> {
+    let mut probes = Vec::with_capacity(ANN_PLANES + 1);
+    probes.push(bucket);
+    for plane in 0..ANN_PLANES {
+        probes.push(bucket ^ (1 << plane));
+    }
+    probes
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1389,6 +3532,7 @@ mod tests {
             value,
             embed: None,
             embed_hint: None,
+            normalize_on_insert: false,
             tags: None,
             score: None,
             prob: None,
@@ -1406,6 +3550,7 @@ mod tests {
             links: None,
             extra: None,
             hash: None,
+            on_conflict: OnConflict::default(),
         }
     }
 
@@ -1421,6 +3566,7 @@ mod tests {
             value: serde_json::json!({"text":"hello"}),
             embed: None,
             embed_hint: None,
+            normalize_on_insert: false,
             tags: Some(vec!["tag1".to_string()]),
             score: Some(0.9),
             prob: Some(0.8),
@@ -1438,6 +3584,7 @@ mod tests {
             links: None,
             extra: None,
             hash: None,
+            on_conflict: OnConflict::default(),
         };
         let args = insert_owned.to_args();
         let id = store.insert_memory(&args).unwrap();
@@ -1445,6 +3592,693 @@ mod tests {
         assert_eq!(fetched["lane"], "episodic");
     }
 
+    #[test]
+    fn test_plan_memory_gc_reports_without_deleting() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let now = Utc::now();
+        // One TTL-expired episodic record.
+        let mut expired = make_owned(Some("gc-ttl"), "episodic", json!({"t": "stale"}));
+        expired.ttl_s = Some(60);
+        store
+            .insert_memory_at(&expired.to_args(), now - Duration::minutes(10))
+            .unwrap();
+        // Semantic lane capped at one with two records.
+        store
+            .set_lane_config(&MemoryLaneConfig {
+                lane: "semantic".into(),
+                cap: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        for id in ["gc-s1", "gc-s2"] {
+            let owned = make_owned(Some(id), "semantic", json!({"t": id}));
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+
+        let plan = store.plan_memory_gc(now, 100).unwrap();
+        assert_eq!(plan["total"], json!(2));
+        assert_eq!(plan["by_reason"]["ttl_expired"], json!(1));
+        assert_eq!(plan["by_reason"]["lane_cap"], json!(1));
+        assert_eq!(plan["by_lane"]["episodic"]["count"], json!(1));
+        assert_eq!(plan["by_lane"]["semantic"]["count"], json!(1));
+        assert!(plan["estimated_bytes"].as_i64().unwrap() > 0);
+        assert_eq!(plan["candidates"].as_array().unwrap().len(), 2);
+
+        // Dry run: nothing was deleted.
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM memory_records", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 3);
+    }
+
+    #[test]
+    fn test_read_context_filters_and_redacts() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        for (id, privacy) in [
+            ("p-pub", Some("public")),
+            ("p-priv", Some("private")),
+            ("p-sec", Some("secret")),
+            ("p-odd", Some("classified-x")),
+            ("p-none", None),
+        ] {
+            let mut owned = make_owned(Some(id), "semantic", json!({"note": "shared term"}));
+            owned.privacy = privacy.map(String::from);
+            owned.text = Some("payload".into());
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+
+        // Internal clearance only admits the public record.
+        let ctx = ReadContext {
+            max_privacy: Some("internal".into()),
+            ..Default::default()
+        };
+        let hits = store
+            .search_memory_with_context("shared", None, 10, &ctx)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], json!("p-pub"));
+        assert!(store
+            .get_memory_with_context("p-sec", &ctx)
+            .unwrap()
+            .is_none());
+
+        // Unlabeled records count as private; unknown labels outrank secret.
+        let ctx = ReadContext {
+            max_privacy: Some("secret".into()),
+            ..Default::default()
+        };
+        let hits = store
+            .search_memory_with_context("shared", None, 10, &ctx)
+            .unwrap();
+        assert_eq!(hits.len(), 4);
+        assert!(!hits.iter().any(|h| h["id"] == json!("p-odd")));
+
+        // Redaction masks fields without dropping the record.
+        let ctx = ReadContext {
+            max_privacy: None,
+            redact_fields: vec!["text".into(), "value".into()],
+        };
+        let rec = store
+            .get_memory_with_context("p-priv", &ctx)
+            .unwrap()
+            .expect("record");
+        assert_eq!(rec["text"], json!("[redacted]"));
+        assert_eq!(rec["value"], json!("[redacted]"));
+        assert_eq!(rec["id"], json!("p-priv"));
+    }
+
+    #[test]
+    fn test_lane_half_life_override_shapes_ranking() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let now = Utc::now();
+        let mut old = make_owned(Some("hl-old"), "episodic", json!({"t": "old"}));
+        old.score = Some(1.0);
+        store
+            .insert_memory_at(&old.to_args(), now - Duration::minutes(10))
+            .unwrap();
+        let fresh = make_owned(Some("hl-new"), "episodic", json!({"t": "new"}));
+        store.insert_memory_at(&fresh.to_args(), now).unwrap();
+
+        // Slow decay: ten minutes is noise, so utility wins.
+        store
+            .set_lane_config(&MemoryLaneConfig {
+                lane: "episodic".into(),
+                half_life_s: Some(7 * 24 * 3600),
+                ..Default::default()
+            })
+            .unwrap();
+        let res = store
+            .select_memory_hybrid(None, None, Some("episodic"), 10)
+            .unwrap();
+        assert_eq!(res[0]["id"], json!("hl-old"));
+        assert_eq!(res[0]["_half_life_s"], json!(7.0 * 24.0 * 3600.0));
+
+        // Fast decay: the old record's recency collapses and the fresh
+        // one wins.
+        store
+            .set_lane_config(&MemoryLaneConfig {
+                lane: "episodic".into(),
+                half_life_s: Some(60),
+                ..Default::default()
+            })
+            .unwrap();
+        let res = store
+            .select_memory_hybrid(None, None, Some("episodic"), 10)
+            .unwrap();
+        assert_eq!(res[0]["id"], json!("hl-new"));
+
+        // Lanes without an override surface the built-in default.
+        let other = make_owned(Some("hl-sem"), "semantic", json!({"t": "s"}));
+        store.insert_memory(&other.to_args()).unwrap();
+        let res = store
+            .select_memory_hybrid(None, None, Some("semantic"), 10)
+            .unwrap();
+        assert_eq!(res[0]["_half_life_s"], json!(DEFAULT_HALF_LIFE_S));
+    }
+
+    #[test]
+    fn test_insert_memory_batch_single_transaction() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let items: Vec<MemoryInsertOwned> = (0..5)
+            .map(|i| make_owned(Some(&format!("batch-{i}")), "episodic", json!({"n": i})))
+            .collect();
+        let results = store.insert_memory_batch(&items).unwrap();
+        assert_eq!(results.len(), 5);
+        for (i, res) in results.iter().enumerate() {
+            let (id, record) = res.as_ref().expect("insert ok");
+            assert_eq!(id, &format!("batch-{i}"));
+            assert_eq!(record["value"]["n"], json!(i));
+        }
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM memory_records", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 5);
+        // FTS lands in the same transaction.
+        let fts: i64 = conn
+            .query_row("SELECT COUNT(*) FROM memory_fts", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(fts, 5);
+        assert!(store.insert_memory_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tag_search_matches_exactly() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let mut a = make_owned(Some("tag-a"), "semantic", json!({"n": 1}));
+        a.tags = Some(vec!["Rust".into(), "parser".into()]);
+        store.insert_memory(&a.to_args()).unwrap();
+        let mut b = make_owned(Some("tag-b"), "semantic", json!({"n": 2}));
+        b.tags = Some(vec!["rust".into()]);
+        store.insert_memory(&b.to_args()).unwrap();
+        let mut c = make_owned(Some("tag-c"), "semantic", json!({"n": 3}));
+        c.tags = Some(vec!["rustic".into()]);
+        store.insert_memory(&c.to_args()).unwrap();
+
+        // Exact match, case-insensitive; no LIKE-style prefix bleed from
+        // "rustic".
+        let hits = store
+            .search_memory_by_tags(&["RUST".to_string()], TagMatch::Any, None, 10)
+            .unwrap();
+        let ids: Vec<&str> = hits.iter().map(|h| h["id"].as_str().unwrap()).collect();
+        assert_eq!(hits.len(), 2);
+        assert!(ids.contains(&"tag-a") && ids.contains(&"tag-b"));
+
+        // All-mode needs every queried tag on the record.
+        let hits = store
+            .search_memory_by_tags(
+                &["rust".to_string(), "parser".to_string()],
+                TagMatch::All,
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], json!("tag-a"));
+
+        // Overwrites rebuild the index, so stale tags stop matching.
+        let mut a2 = make_owned(Some("tag-a"), "semantic", json!({"n": 1}));
+        a2.tags = Some(vec!["lexer".into()]);
+        store.insert_memory(&a2.to_args()).unwrap();
+        let hits = store
+            .search_memory_by_tags(&["parser".to_string()], TagMatch::Any, None, 10)
+            .unwrap();
+        assert!(hits.is_empty());
+
+        // Legacy rows (junction wiped) come back via the backfill.
+        conn.execute("DELETE FROM memory_tags", []).unwrap();
+        assert_eq!(store.pending_tag_backfill().unwrap(), 3);
+        assert_eq!(store.backfill_tag_index(16).unwrap(), 3);
+        assert_eq!(store.pending_tag_backfill().unwrap(), 0);
+        let hits = store
+            .search_memory_by_tags(&["lexer".to_string()], TagMatch::Any, None, 10)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn test_lane_registry_applies_defaults() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        store
+            .set_lane_config(&MemoryLaneConfig {
+                lane: "episodic".into(),
+                default_ttl_s: Some(3600),
+                cap: Some(1),
+                default_durability: Some("short".into()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Unset fields pick up the lane defaults; explicit values win.
+        let defaulted = make_owned(None, "episodic", json!({"n": 1}));
+        let (id, rec) = store
+            .insert_memory_with_record(&defaulted.to_args())
+            .unwrap();
+        assert_eq!(rec["ttl_s"], json!(3600));
+        assert_eq!(rec["durability"], json!("short"));
+        let mut explicit = make_owned(None, "episodic", json!({"n": 2}));
+        explicit.ttl_s = Some(60);
+        explicit.durability = Some("pinned".into());
+        let (_, rec) = store
+            .insert_memory_with_record(&explicit.to_args())
+            .unwrap();
+        assert_eq!(rec["ttl_s"], json!(60));
+        assert_eq!(rec["durability"], json!("pinned"));
+        let stored = store.get_memory(&id).unwrap().expect("record");
+        assert_eq!(stored["ttl_s"], json!(3600));
+
+        // Two records against a cap of one: the registry-driven sweep
+        // reports the overflow without a caller-supplied cap.
+        let over = store.configured_lane_overflow_candidates(10).unwrap();
+        assert_eq!(over.len(), 1);
+        assert!(matches!(
+            over[0].reason,
+            MemoryGcReason::LaneCap { cap: 1, .. }
+        ));
+
+        let listed = store.list_lane_configs().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].default_ttl_s, Some(3600));
+        assert!(store.remove_lane_config("episodic").unwrap());
+        assert!(store.get_lane_config("episodic").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_refresh_ttl_on_access_slides_expiry() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        store
+            .set_lane_config(&MemoryLaneConfig {
+                lane: "cache".into(),
+                refresh_ttl_on_access: true,
+                ..Default::default()
+            })
+            .unwrap();
+        let now = Utc::now();
+        // Both records are a full TTL past their creation time.
+        for (id, lane) in [("sl-hot", "cache"), ("sl-cold", "episodic")] {
+            let mut owned = make_owned(Some(id), lane, json!({"t": id}));
+            owned.ttl_s = Some(60);
+            store
+                .insert_memory_at(&owned.to_args(), now - Duration::minutes(10))
+                .unwrap();
+            // Reads reset the clock only in the sliding lane.
+            store.get_memory(id).unwrap().expect("record");
+        }
+        let expired = store.expired_candidates(now, 100).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, "sl-cold");
+
+        // An idle sliding record still ages out from its anchor.
+        let later = now + Duration::minutes(10);
+        let expired = store.expired_candidates(later, 100).unwrap();
+        assert_eq!(expired.len(), 2);
+    }
+
+    #[test]
+    fn test_memory_export_import_round_trips() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let mut a = make_owned(Some("exp-a"), "semantic", json!({"fact": "alpha"}));
+        a.tags = Some(vec!["t1".into(), "t2".into()]);
+        a.embed = Some(vec![0.5, 0.25]);
+        store.insert_memory(&a.to_args()).unwrap();
+        let b = make_owned(Some("exp-b"), "semantic", json!({"fact": "beta"}));
+        store.insert_memory(&b.to_args()).unwrap();
+        let other = make_owned(Some("exp-c"), "episodic", json!({"fact": "gamma"}));
+        store.insert_memory(&other.to_args()).unwrap();
+        store
+            .insert_memory_link("exp-a", "exp-b", Some("supports"), Some(0.5))
+            .unwrap();
+
+        // Lane filter keeps the episodic record and its edges out.
+        let mut buf: Vec<u8> = Vec::new();
+        let lines = store
+            .export_memory(Some("semantic"), None, &mut buf)
+            .unwrap();
+        assert_eq!(lines, 3);
+
+        let dest_conn = setup_conn();
+        let dest = MemoryStore::new(&dest_conn);
+        let (records, links) = dest
+            .import_memory(&mut buf.as_slice(), &MemoryImportOptions::default())
+            .unwrap();
+        assert_eq!((records, links), (2, 1));
+        let restored = dest.get_memory("exp-a").unwrap().expect("record");
+        assert_eq!(restored["value"]["fact"], json!("alpha"));
+        assert_eq!(restored["tags"], json!(["t1", "t2"]));
+        assert_eq!(restored["embed"], json!([0.5, 0.25]));
+        assert_eq!(dest.list_memory_links("exp-a", 10).unwrap().len(), 1);
+        assert!(dest.get_memory("exp-c").unwrap().is_none());
+
+        // Embedded rows land in the ANN side table via the normal insert path.
+        let ann: i64 = dest_conn
+            .query_row("SELECT COUNT(*) FROM memory_ann", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(ann, 1);
+    }
+
+    #[test]
+    fn test_backlinks_list_incoming_edges() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        for id in ["x", "y", "z"] {
+            let owned = make_owned(Some(id), "semantic", json!({"node": id}));
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+        store
+            .insert_memory_link("x", "z", Some("supports"), None)
+            .unwrap();
+        store
+            .insert_memory_link("y", "z", Some("refines"), None)
+            .unwrap();
+        store
+            .insert_memory_link("z", "x", Some("supports"), None)
+            .unwrap();
+
+        let back = store.list_memory_backlinks("z", 10).unwrap();
+        let srcs: Vec<&str> = back.iter().map(|e| e["src_id"].as_str().unwrap()).collect();
+        assert_eq!(back.len(), 2);
+        assert!(srcs.contains(&"x") && srcs.contains(&"y"));
+
+        let many = store
+            .list_memory_backlinks_many(&["z".to_string(), "x".to_string()], 1)
+            .unwrap();
+        assert_eq!(many.get("z").map(|v| v.len()), Some(1));
+        assert_eq!(
+            many.get("x").and_then(|v| v[0]["src_id"].as_str()),
+            Some("z")
+        );
+    }
+
+    #[test]
+    fn test_traverse_memory_graph_bounded_bfs() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        for id in ["a", "b", "c", "d"] {
+            let owned = make_owned(Some(id), "semantic", json!({"node": id}));
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+        store
+            .insert_memory_link("a", "b", Some("supports"), None)
+            .unwrap();
+        store
+            .insert_memory_link("b", "c", Some("supports"), None)
+            .unwrap();
+        store
+            .insert_memory_link("c", "d", Some("contradicts"), None)
+            .unwrap();
+        // Cycle back to the start; traversal must still terminate.
+        store
+            .insert_memory_link("d", "a", Some("supports"), None)
+            .unwrap();
+
+        let start = vec!["a".to_string()];
+        let out = store.traverse_memory_graph(&start, 2, None, 100).unwrap();
+        let nodes = out["nodes"].as_array().unwrap();
+        let ids: Vec<&str> = nodes.iter().map(|n| n["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(nodes[0]["depth"], json!(0));
+        assert_eq!(nodes[2]["depth"], json!(2));
+        assert_eq!(out["edges"].as_array().unwrap().len(), 2);
+
+        // Deep enough to loop; cycle protection visits each node once.
+        let out = store.traverse_memory_graph(&start, 8, None, 100).unwrap();
+        assert_eq!(out["nodes"].as_array().unwrap().len(), 4);
+
+        // rel filter prunes the contradicts edge, cutting the walk at c.
+        let out = store
+            .traverse_memory_graph(&start, 8, Some("supports"), 100)
+            .unwrap();
+        assert_eq!(out["nodes"].as_array().unwrap().len(), 3);
+
+        // The node limit bounds discovery.
+        let out = store.traverse_memory_graph(&start, 8, None, 2).unwrap();
+        assert_eq!(out["nodes"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_on_conflict_dedupes_by_hash() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let mut first = make_owned(None, "semantic", json!({"fact": "dup"}));
+        first.tags = Some(vec!["alpha".into()]);
+        first.score = Some(0.4);
+        let (first_id, _) = store.insert_memory_with_record(&first.to_args()).unwrap();
+
+        // Skip returns the existing record without writing a twin.
+        let mut dup = make_owned(None, "semantic", json!({"fact": "dup"}));
+        dup.on_conflict = OnConflict::Skip;
+        let (skip_id, _) = store.insert_memory_with_record(&dup.to_args()).unwrap();
+        assert_eq!(skip_id, first_id);
+
+        // MergeTags unions the incoming tags into the survivor.
+        let mut merge = make_owned(None, "semantic", json!({"fact": "dup"}));
+        merge.tags = Some(vec!["beta".into()]);
+        merge.on_conflict = OnConflict::MergeTags;
+        let (merge_id, merged) = store.insert_memory_with_record(&merge.to_args()).unwrap();
+        assert_eq!(merge_id, first_id);
+        assert_eq!(merged["tags"], json!(["alpha", "beta"]));
+
+        // BumpScore keeps the higher of the stored and incoming scores.
+        let mut bump = make_owned(None, "semantic", json!({"fact": "dup"}));
+        bump.score = Some(0.9);
+        bump.on_conflict = OnConflict::BumpScore;
+        let (bump_id, bumped) = store.insert_memory_with_record(&bump.to_args()).unwrap();
+        assert_eq!(bump_id, first_id);
+        assert_eq!(bumped["score"], json!(0.9));
+
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM memory_records", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+
+        // The default policy still inserts duplicates.
+        let plain = make_owned(None, "semantic", json!({"fact": "dup"}));
+        let (plain_id, _) = store.insert_memory_with_record(&plain.to_args()).unwrap();
+        assert_ne!(plain_id, first_id);
+    }
+
+    #[test]
+    fn test_revision_history_snapshots_overwrites_and_restores() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+
+        // A fresh insert with an explicit id has nothing to snapshot.
+        let first = make_owned(Some("rev-1"), "semantic", json!({"fact": "v1"}));
+        store.insert_memory(&first.to_args()).unwrap();
+        assert!(store.list_memory_revisions("rev-1", 10).unwrap().is_empty());
+
+        // Overwriting the id captures the outgoing content as revision 1.
+        let second = make_owned(Some("rev-1"), "semantic", json!({"fact": "v2"}));
+        store.insert_memory(&second.to_args()).unwrap();
+        let revisions = store.list_memory_revisions("rev-1", 10).unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0]["revision"], json!(1));
+        assert_eq!(revisions[0]["record"]["value"]["fact"], json!("v1"));
+
+        // Restoring replays the snapshot and snapshots the replaced state.
+        let restored = store.restore_memory_revision("rev-1", 1).unwrap();
+        assert_eq!(restored["value"]["fact"], json!("v1"));
+        let current = store.get_memory("rev-1").unwrap().expect("record");
+        assert_eq!(current["value"]["fact"], json!("v1"));
+        let revisions = store.list_memory_revisions("rev-1", 10).unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0]["revision"], json!(2));
+        assert_eq!(revisions[0]["record"]["value"]["fact"], json!("v2"));
+
+        let err = store.restore_memory_revision("rev-1", 9).unwrap_err();
+        assert!(err.to_string().contains("no revision"));
+
+        // Deleting the record sweeps its history with it.
+        store.delete_records(&["rev-1".to_string()]).unwrap();
+        assert!(store.list_memory_revisions("rev-1", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_access_tracking_drives_lane_eviction_order() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let now = Utc::now();
+        for (idx, id) in ["old", "middle", "new"].iter().enumerate() {
+            let owned = make_owned(Some(id), "episodic", json!({ "text": id }));
+            store
+                .insert_memory_at(
+                    &owned.to_args(),
+                    now - Duration::minutes(30 - idx as i64 * 10),
+                )
+                .unwrap();
+        }
+        // Reads stamp last_accessed and bump access_count in one batch.
+        store.get_memory("old").unwrap();
+        store.get_memory("old").unwrap();
+        let rec = store.get_memory("old").unwrap().unwrap();
+        assert_eq!(rec["access_count"], json!(2));
+        assert!(rec["last_accessed"].is_string());
+
+        // The never-read rows are evicted first even though "old" has the
+        // oldest `updated`; frequency breaks the remaining tie.
+        let overflow = store.lane_overflow_candidates("episodic", 1, 10).unwrap();
+        assert_eq!(overflow.len(), 2);
+        assert_eq!(overflow[0].id, "middle");
+        assert_eq!(overflow[1].id, "new");
+    }
+
+    #[test]
+    fn test_mmr_rerank_suppresses_near_duplicates() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        for (id, embed) in [
+            ("dup-a", vec![1.0, 0.0, 0.0]),
+            ("dup-b", vec![0.999, 0.01, 0.0]),
+            ("distinct", vec![0.6, 0.0, 0.8]),
+        ] {
+            let mut owned = make_owned(Some(id), "semantic", json!({ "id": id }));
+            owned.embed = Some(embed);
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+        let query = [1.0f32, 0.0, 0.0];
+
+        // lambda = 1.0 degenerates to the plain hybrid order: both
+        // near-duplicates outrank the distinct record.
+        let hits = store
+            .select_memory_hybrid_mmr(None, Some(&query), Some("semantic"), 2, 1.0)
+            .unwrap();
+        let ids: Vec<&str> = hits.iter().map(|h| h["id"].as_str().unwrap()).collect();
+        assert!(ids.contains(&"dup-a") && ids.contains(&"dup-b"));
+
+        // A balanced lambda keeps the best match but swaps its near twin
+        // for the diverse record.
+        let hits = store
+            .select_memory_hybrid_mmr(None, Some(&query), Some("semantic"), 2, 0.5)
+            .unwrap();
+        let ids: Vec<&str> = hits.iter().map(|h| h["id"].as_str().unwrap()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"distinct"), "got {ids:?}");
+    }
+
+    #[test]
+    fn test_hybrid_blends_bm25_rank_over_recency() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let now = Utc::now();
+        // Older record matches both query terms; newer one matches only one.
+        let full = make_owned(
+            Some("full-match"),
+            "semantic",
+            json!({ "text": "alpha beta" }),
+        );
+        store
+            .insert_memory_at(&full.to_args(), now - Duration::minutes(30))
+            .unwrap();
+        let partial = make_owned(
+            Some("partial-match"),
+            "semantic",
+            json!({ "text": "alpha" }),
+        );
+        store.insert_memory_at(&partial.to_args(), now).unwrap();
+
+        let hits = store
+            .select_memory_hybrid(Some("alpha OR beta"), None, Some("semantic"), 2)
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+        // With 0/1 FTS scoring the newer partial match would win on recency;
+        // the bm25 blend puts the better textual match first.
+        assert_eq!(hits[0]["id"], "full-match");
+        assert!(hits[0]["cscore"].as_f64().unwrap() > hits[1]["cscore"].as_f64().unwrap());
+        assert_eq!(hits[0]["_fts_hit"], json!(true));
+    }
+
+    #[test]
+    fn test_inserts_store_packed_blob_without_text_column() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let mut owned = make_owned(Some("blob-row"), "semantic", json!({ "t": 1 }));
+        owned.embed = Some(vec![0.25, -1.5, 3.0]);
+        store.insert_memory(&owned.to_args()).unwrap();
+        let (embed_s, blob): (Option<String>, Option<Vec<u8>>) = conn
+            .query_row(
+                "SELECT embed, embed_blob FROM memory_records WHERE id='blob-row'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert!(embed_s.is_none(), "legacy text column no longer written");
+        let blob = blob.expect("blob stored");
+        assert_eq!(blob.len(), 3 * std::mem::size_of::<f32>());
+        assert_eq!(decode_embed_blob(&blob).unwrap(), vec![0.25, -1.5, 3.0]);
+        // Reads hydrate the embedding from the blob.
+        let rec = store.get_memory("blob-row").unwrap().unwrap();
+        assert_eq!(rec["embed"], json!([0.25, -1.5, 3.0]));
+        assert!(store.list_unembedded(None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_vector_math_handles_lane_remainders() {
+        // 11 elements exercises both the 8-wide lanes and the scalar tail.
+        let a: Vec<f32> = (0..11).map(|i| (i as f32) * 0.5 - 2.0).collect();
+        let b: Vec<f32> = (0..11).map(|i| 1.5 - (i as f32) * 0.25).collect();
+        let dot_ref: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+        assert!((dot_product(&a, &b) - dot_ref).abs() < 1e-4);
+        let l2_ref: f32 = a
+            .iter()
+            .zip(&b)
+            .map(|(x, y)| (x - y) * (x - y))
+            .sum::<f32>()
+            .sqrt();
+        assert!((l2_distance(&a, &b) - l2_ref).abs() < 1e-4);
+        let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let cos_ref = dot_ref / (na * nb);
+        assert!((cosine_sim(&a, &b) - cos_ref).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_ann_index_tracks_writes_and_falls_back_when_stale() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        for i in 0..20 {
+            let mut owned = make_owned(Some(&format!("m{i}")), "semantic", json!({ "i": i }));
+            owned.embed = Some(vec![1.0, 0.01 * i as f32, 0.0]);
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+        let mut target = make_owned(Some("target"), "semantic", json!({ "target": true }));
+        target.embed = Some(vec![0.0, 0.0, 1.0]);
+        store.insert_memory(&target.to_args()).unwrap();
+        // Inserts keep the side table in step without a backfill pass.
+        let indexed: i64 = conn
+            .query_row("SELECT COUNT(*) FROM memory_ann", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(indexed, 21);
+        let hits = store
+            .search_memory_by_embedding(&[0.0, 0.0, 1.0], Some("semantic"), 3)
+            .unwrap();
+        assert_eq!(hits[0]["id"], "target");
+
+        // Rows predating the index force the brute-force fallback until the
+        // backfill drains them; results stay correct either way.
+        conn.execute("DELETE FROM memory_ann", []).unwrap();
+        assert_eq!(store.pending_ann_backfill().unwrap(), 21);
+        let hits = store
+            .search_memory_by_embedding(&[0.0, 0.0, 1.0], Some("semantic"), 3)
+            .unwrap();
+        assert_eq!(hits[0]["id"], "target");
+        assert_eq!(store.backfill_ann_index(100).unwrap(), 21);
+        assert_eq!(store.pending_ann_backfill().unwrap(), 0);
+
+        // Deletes drop the index rows alongside the records.
+        store.delete_records(&["target".to_string()]).unwrap();
+        let indexed: i64 = conn
+            .query_row("SELECT COUNT(*) FROM memory_ann", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(indexed, 20);
+    }
+
     #[test]
     fn test_search_memory_by_embedding_yields_sim() {
         let conn = setup_conn();
@@ -1457,6 +4291,7 @@ mod tests {
             value: json!({ "text": "vector memo" }),
             embed: Some(vec![1.0, 0.0]),
             embed_hint: None,
+            normalize_on_insert: false,
             tags: None,
             score: None,
             prob: None,
@@ -1474,6 +4309,7 @@ mod tests {
             links: None,
             extra: None,
             hash: None,
+            on_conflict: OnConflict::default(),
         };
         let args = insert_owned.to_args();
         let id = store.insert_memory(&args).unwrap();
@@ -1485,6 +4321,518 @@ mod tests {
         assert!(hits[0]["sim"].as_f64().unwrap() > 0.99);
     }
 
+    #[test]
+    fn test_compact_memory_candidates_summarizes_then_deletes() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        store
+            .set_lane_config(&MemoryLaneConfig {
+                lane: "episodic".into(),
+                cap: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        for i in 0..4 {
+            let owned = make_owned(
+                Some(&format!("cp-{i}")),
+                "episodic",
+                json!({"t": format!("step {i}")}),
+            );
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+        let batch = store.configured_lane_overflow_candidates(10).unwrap();
+        assert_eq!(batch.len(), 2);
+
+        // A vetoing callback leaves the batch untouched.
+        let report = store
+            .compact_memory_candidates(&batch, &|_records| Ok(None))
+            .unwrap();
+        assert_eq!(report["deleted"], json!(0));
+        assert!(report["summary_id"].is_null());
+
+        let report = store
+            .compact_memory_candidates(&batch, &|records| {
+                let joined = records
+                    .iter()
+                    .map(|r| r["value"]["t"].as_str().unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                Ok(Some(make_owned(
+                    None,
+                    "semantic",
+                    json!({"summary": joined}),
+                )))
+            })
+            .unwrap();
+        assert_eq!(report["deleted"], json!(2));
+        let summary_id = report["summary_id"].as_str().expect("summary id");
+        let summary = store.get_memory(summary_id).unwrap().expect("summary");
+        let summarized = summary["links"]["summarizes"].as_array().unwrap();
+        assert_eq!(summarized.len(), 2);
+        for c in &batch {
+            assert!(summarized.contains(&json!(c.id)));
+            assert!(store.get_memory(&c.id).unwrap().is_none());
+        }
+        // The lane is back under its cap.
+        assert!(store
+            .configured_lane_overflow_candidates(10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_filter_memory_by_keywords_and_entities() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let mut a = make_owned(Some("fl-a"), "semantic", json!({"t": "a"}));
+        a.keywords = Some(vec!["rust".into(), "sqlite".into()]);
+        a.entities = Some(json!([{"kind": "person", "name": "Ada"}, "plain-tag"]));
+        store.insert_memory(&a.to_args()).unwrap();
+        let mut b = make_owned(Some("fl-b"), "semantic", json!({"t": "b"}));
+        b.keywords = Some(vec!["trust".into()]);
+        b.entities = Some(json!(["Ada"]));
+        store.insert_memory(&b.to_args()).unwrap();
+        let c = make_owned(Some("fl-c"), "semantic", json!({"t": "c"}));
+        store.insert_memory(&c.to_args()).unwrap();
+
+        let ids = |filters: &MemoryFilters| -> Vec<String> {
+            store
+                .filter_memory(filters, None, 10)
+                .unwrap()
+                .iter()
+                .map(|v| v["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Keyword match is per-token: "rust" must not match "trust".
+        let rust = ids(&MemoryFilters {
+            keywords: vec!["rust".into()],
+            ..Default::default()
+        });
+        assert_eq!(rust, vec!["fl-a"]);
+        let both = ids(&MemoryFilters {
+            keywords: vec!["rust".into(), "sqlite".into()],
+            ..Default::default()
+        });
+        assert_eq!(both, vec!["fl-a"]);
+
+        // Entity value matches bare strings and object names alike.
+        let mut ada = ids(&MemoryFilters {
+            entity_value: Some("Ada".into()),
+            ..Default::default()
+        });
+        ada.sort();
+        assert_eq!(ada, vec!["fl-a", "fl-b"]);
+        let person = ids(&MemoryFilters {
+            entity_kind: Some("person".into()),
+            ..Default::default()
+        });
+        assert_eq!(person, vec!["fl-a"]);
+        // Kind and value must hold for the same entity.
+        let bob = ids(&MemoryFilters {
+            entity_kind: Some("person".into()),
+            entity_value: Some("Bob".into()),
+            ..Default::default()
+        });
+        assert!(bob.is_empty());
+
+        // No filters degenerates to a recency listing.
+        assert_eq!(ids(&MemoryFilters::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_search_memory_page_walks_keyset_cursor() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let base = Utc::now() - Duration::minutes(10);
+        for i in 0..5 {
+            let owned = make_owned(
+                Some(&format!("pg-{i}")),
+                "semantic",
+                json!({"t": format!("paged note {i}")}),
+            );
+            // Two records share an `updated` stamp to exercise the id
+            // tie-break.
+            let at = base + Duration::seconds((i / 2) as i64);
+            store.insert_memory_at(&owned.to_args(), at).unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut pages = 0;
+        loop {
+            let (items, next) = store
+                .search_memory_page("paged note", Some("semantic"), 2, cursor.as_deref())
+                .unwrap();
+            pages += 1;
+            for item in &items {
+                seen.push(item["id"].as_str().unwrap().to_string());
+            }
+            match next {
+                Some(c) => cursor = Some(c),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(seen.len(), 5);
+        let mut unique = seen.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 5, "no row skipped or repeated: {seen:?}");
+
+        assert!(store
+            .search_memory_page("paged note", None, 2, Some("garbage"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_embed_model_registry_validates_and_partitions() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        store.register_embed_model("model-a", 2).unwrap();
+
+        // Wrong dimension under a registered hint is rejected outright.
+        let mut bad = make_owned(None, "semantic", json!({"t": "bad"}));
+        bad.embed = Some(vec![1.0, 0.0, 0.0]);
+        bad.embed_hint = Some("model-a".into());
+        assert!(store.insert_memory(&bad.to_args()).is_err());
+
+        let mut a = make_owned(Some("em-a"), "semantic", json!({"t": "a"}));
+        a.embed = Some(vec![1.0, 0.0]);
+        a.embed_hint = Some("model-a".into());
+        store.insert_memory(&a.to_args()).unwrap();
+
+        // The first insert under an unregistered hint pins its dimension.
+        let mut b = make_owned(Some("em-b"), "semantic", json!({"t": "b"}));
+        b.embed = Some(vec![1.0, 0.0, 0.0]);
+        b.embed_hint = Some("model-b".into());
+        store.insert_memory(&b.to_args()).unwrap();
+        assert_eq!(store.embed_model_dim("model-b").unwrap(), Some(3));
+        let mut b2 = make_owned(None, "semantic", json!({"t": "b2"}));
+        b2.embed = Some(vec![1.0, 0.0]);
+        b2.embed_hint = Some("model-b".into());
+        assert!(store.insert_memory(&b2.to_args()).is_err());
+
+        // Partitioned search only compares model-a vectors, and checks the
+        // query vector against the registry too.
+        let hits = store
+            .search_memory_by_embedding_for_model(&[1.0, 0.0], "model-a", None, 10)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], "em-a");
+        assert!(store
+            .search_memory_by_embedding_for_model(&[1.0], "model-a", None, 10)
+            .is_err());
+
+        let listed = store.list_embed_models().unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+        assert!(store.remove_embed_model("model-b").unwrap());
+    }
+
+    #[test]
+    fn test_embedding_metric_changes_ordering() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        // "aligned" points the same way as the query but has a tiny norm;
+        // "large" is off-axis with a much bigger norm.
+        let mut aligned = make_owned(Some("aligned"), "semantic", json!({"text":"aligned"}));
+        aligned.embed = Some(vec![0.1, 0.0]);
+        store.insert_memory(&aligned.to_args()).unwrap();
+        let mut large = make_owned(Some("large"), "semantic", json!({"text":"large"}));
+        large.embed = Some(vec![5.0, 5.0]);
+        store.insert_memory(&large.to_args()).unwrap();
+
+        let query = [1.0f32, 0.0];
+        let cosine = store
+            .search_memory_by_embedding_with_metric(&query, Some("semantic"), 2, Metric::Cosine)
+            .unwrap();
+        assert_eq!(cosine[0]["id"], "aligned");
+        let dot = store
+            .search_memory_by_embedding_with_metric(&query, Some("semantic"), 2, Metric::Dot)
+            .unwrap();
+        assert_eq!(dot[0]["id"], "large");
+        let l2 = store
+            .search_memory_by_embedding_with_metric(&query, Some("semantic"), 2, Metric::L2)
+            .unwrap();
+        // L2 favors the closer vector; sim is the negated distance.
+        assert_eq!(l2[0]["id"], "aligned");
+        assert!(l2[0]["sim"].as_f64().unwrap() < 0.0);
+    }
+
+    #[test]
+    fn test_insert_at_drives_ttl_expiry_boundary() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let created = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut owned = make_owned(Some("ttl-1"), "episodic", json!({"text": "expiring"}));
+        owned.ttl_s = Some(60);
+        store.insert_memory_at(&owned.to_args(), created).unwrap();
+
+        let rec = store.get_memory("ttl-1").unwrap().unwrap();
+        assert_eq!(rec["created"], "2026-01-01T00:00:00.000Z");
+
+        let just_before = created + Duration::seconds(59);
+        assert!(store
+            .expired_candidates(just_before, 10)
+            .unwrap()
+            .is_empty());
+
+        let at_expiry = created + Duration::seconds(60);
+        let expired = store.expired_candidates(at_expiry, 10).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, "ttl-1");
+        match &expired[0].reason {
+            MemoryGcReason::TtlExpired { ttl_s, expired_at } => {
+                assert_eq!(*ttl_s, 60);
+                assert_eq!(expired_at, "2026-01-01T00:01:00.000Z");
+            }
+            other => panic!("unexpected reason: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_list_memory_in_window() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let stamps = [
+            ("w-1", "2026-01-01T00:00:00.000Z"),
+            ("w-2", "2026-01-02T00:00:00.000Z"),
+            ("w-3", "2026-01-03T00:00:00.000Z"),
+        ];
+        for (id, updated) in stamps {
+            let owned = make_owned(Some(id), "episodic", json!({"id": id}));
+            store.insert_memory(&owned.to_args()).unwrap();
+            conn.execute(
+                "UPDATE memory_records SET updated=? WHERE id=?",
+                params![updated, id],
+            )
+            .unwrap();
+        }
+
+        let hits = store
+            .list_memory_in_window(
+                Some("episodic"),
+                "2026-01-01T12:00:00.000Z",
+                "2026-01-03T12:00:00.000Z",
+                10,
+            )
+            .unwrap();
+        let ids: Vec<&str> = hits.iter().map(|v| v["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["w-2", "w-3"], "ascending order within window");
+
+        assert!(store
+            .list_memory_in_window(None, "2026-02-01T00:00:00Z", "2026-01-01T00:00:00Z", 10)
+            .is_err());
+    }
+
+    #[test]
+    fn test_top_per_lane_caps_each_lane_at_k() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let seeded = [("episodic", 4), ("semantic", 2), ("procedural", 1)];
+        for (lane, count) in seeded {
+            for i in 0..count {
+                let id = format!("{lane}-{i}");
+                let owned = make_owned(Some(&id), lane, json!({"id": id}));
+                store.insert_memory(&owned.to_args()).unwrap();
+                conn.execute(
+                    "UPDATE memory_records SET updated=? WHERE id=?",
+                    params![format!("2026-01-0{}T00:00:00.000Z", i + 1), id],
+                )
+                .unwrap();
+            }
+        }
+
+        let lanes: Vec<String> = seeded.iter().map(|(l, _)| l.to_string()).collect();
+        let by_lane = store.top_per_lane(&lanes, 2).unwrap();
+        assert_eq!(by_lane.len(), 3);
+        assert_eq!(by_lane["episodic"].len(), 2, "capped at k");
+        assert_eq!(by_lane["semantic"].len(), 2);
+        assert_eq!(
+            by_lane["procedural"].len(),
+            1,
+            "short lanes return all rows"
+        );
+        let episodic_ids: Vec<&str> = by_lane["episodic"]
+            .iter()
+            .map(|v| v["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            episodic_ids,
+            vec!["episodic-3", "episodic-2"],
+            "newest first"
+        );
+
+        assert!(store.top_per_lane(&[], 2).unwrap().is_empty());
+        assert!(store.top_per_lane(&lanes, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_unembedded_targets_missing_embeddings_oldest_first() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let mut embedded = make_owned(Some("has-embed"), "semantic", json!({"id": "has-embed"}));
+        embedded.embed = Some(vec![0.1, 0.2]);
+        store.insert_memory(&embedded.to_args()).unwrap();
+        for (i, id) in ["bare-1", "bare-2"].iter().enumerate() {
+            let mut owned = make_owned(Some(id), "semantic", json!({"id": id}));
+            owned.text = Some(format!("needs embedding {i}"));
+            store.insert_memory(&owned.to_args()).unwrap();
+            conn.execute(
+                "UPDATE memory_records SET updated=? WHERE id=?",
+                params![format!("2026-01-0{}T00:00:00.000Z", i + 1), id],
+            )
+            .unwrap();
+        }
+
+        let pending = store.list_unembedded(Some("semantic"), 10).unwrap();
+        let ids: Vec<&str> = pending.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["bare-1", "bare-2"],
+            "oldest first, embedded row excluded"
+        );
+        assert_eq!(pending[0].1.as_deref(), Some("needs embedding 0"));
+        assert!(store
+            .list_unembedded(Some("episodic"), 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_merge_records_repoints_links_and_drops_duplicate() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        for (id, tags) in [("a", Some("alpha")), ("b", Some("beta")), ("c", None)] {
+            let mut owned = make_owned(Some(id), "semantic", json!({"id": id}));
+            owned.tags = tags.map(|t| vec![t.to_string()]);
+            store.insert_memory(&owned.to_args()).unwrap();
+        }
+        store
+            .insert_memory_link("c", "b", Some("supports"), None)
+            .unwrap();
+        store
+            .insert_memory_link("c", "a", Some("refines"), None)
+            .unwrap();
+        store
+            .insert_memory_link("b", "a", Some("supports"), None)
+            .unwrap();
+
+        store.merge_records("a", "b").unwrap();
+
+        assert!(
+            store.get_memory("b").unwrap().is_none(),
+            "dropped record gone"
+        );
+        let merged = store.get_memory("a").unwrap().expect("kept record");
+        assert_eq!(merged["tags"], json!(["alpha", "beta"]), "tags unioned");
+
+        let links = store.list_memory_links("c", 10).unwrap();
+        let edges: Vec<(String, String)> = links
+            .iter()
+            .map(|l| {
+                (
+                    l["dst_id"].as_str().unwrap().to_string(),
+                    l["rel"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert!(
+            edges.contains(&("a".to_string(), "supports".to_string())),
+            "c->b repointed to c->a"
+        );
+        assert!(edges.contains(&("a".to_string(), "refines".to_string())));
+        assert_eq!(edges.len(), 2, "no duplicate edges after repointing");
+        // The b->a edge collapsed into a self-loop and was removed.
+        assert!(store.list_memory_links("a", 10).unwrap().is_empty());
+        assert!(store.merge_records("a", "a").is_err());
+    }
+
+    #[test]
+    fn test_compact_reclaims_space_and_keeps_search_working() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("memory.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        MemoryStore::migrate(&conn).unwrap();
+        let store = MemoryStore::new(&conn);
+
+        let filler = "x".repeat(4096);
+        let mut ids = Vec::new();
+        for i in 0..200 {
+            let id = format!("bulk-{i}");
+            let owned = make_owned(
+                Some(&id),
+                "episodic",
+                json!({"filler": filler, "note": format!("record {i} searchable")}),
+            );
+            store.insert_memory(&owned.to_args()).unwrap();
+            ids.push(id);
+        }
+        // Flush WAL so file size reflects the inserted pages.
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+            .unwrap();
+        let before = std::fs::metadata(&path).unwrap().len();
+
+        store.delete_records(&ids[..190]).unwrap();
+        store.compact().unwrap();
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+            .unwrap();
+        let after = std::fs::metadata(&path).unwrap().len();
+        assert!(
+            after < before,
+            "compact should shrink the file ({after} >= {before})"
+        );
+
+        let hits = store
+            .search_memory("searchable", Some("episodic"), 20)
+            .unwrap();
+        assert!(!hits.is_empty(), "FTS search survives compaction");
+    }
+
+    #[test]
+    fn test_normalized_dot_matches_raw_cosine() {
+        let conn = setup_conn();
+        let store = MemoryStore::new(&conn);
+        let vectors: &[(&str, Vec<f32>)] = &[
+            ("a", vec![3.0, 4.0]),
+            ("b", vec![-1.0, 2.0]),
+            ("c", vec![0.5, 0.1]),
+        ];
+        for (name, vec) in vectors {
+            let mut raw = make_owned(Some(&format!("raw-{name}")), "raw", json!({"v": name}));
+            raw.embed = Some(vec.clone());
+            store.insert_memory(&raw.to_args()).unwrap();
+            let mut unit = make_owned(Some(&format!("unit-{name}")), "unit", json!({"v": name}));
+            unit.embed = Some(vec.clone());
+            unit.normalize_on_insert = true;
+            store.insert_memory(&unit.to_args()).unwrap();
+        }
+
+        let query = [2.0f32, 1.0];
+        let cosine = store
+            .search_memory_by_embedding(&query, Some("raw"), 3)
+            .unwrap();
+        let fast = store
+            .search_memory_by_embedding_normalized(&query, Some("unit"), 3)
+            .unwrap();
+        assert_eq!(cosine.len(), 3);
+        assert_eq!(fast.len(), 3);
+        for (lhs, rhs) in cosine.iter().zip(fast.iter()) {
+            let lhs_name = lhs["id"].as_str().unwrap().trim_start_matches("raw-");
+            let rhs_name = rhs["id"].as_str().unwrap().trim_start_matches("unit-");
+            assert_eq!(lhs_name, rhs_name, "rankings agree across the two paths");
+            let lhs_sim = lhs["sim"].as_f64().unwrap();
+            let rhs_sim = rhs["sim"].as_f64().unwrap();
+            assert!((lhs_sim - rhs_sim).abs() < 1e-5, "{lhs_sim} vs {rhs_sim}");
+        }
+        // The original norm is preserved for callers that need magnitudes.
+        let rec = store.get_memory("unit-a").unwrap().unwrap();
+        let norm = rec["extra"]["embed_norm"].as_f64().unwrap();
+        assert!((norm - 5.0).abs() < 1e-6);
+    }
+
     #[test]
     fn test_fts_index_stays_in_sync_on_upsert() {
         let conn = setup_conn();
@@ -1498,6 +4846,7 @@ mod tests {
             value: json!("first note"),
             embed: None,
             embed_hint: None,
+            normalize_on_insert: false,
             tags: None,
             score: None,
             prob: None,
@@ -1515,6 +4864,7 @@ mod tests {
             links: None,
             extra: None,
             hash: None,
+            on_conflict: OnConflict::default(),
         };
         let args = insert_owned.to_args();
         let id = store.insert_memory(&args).unwrap();
@@ -1532,6 +4882,7 @@ mod tests {
             value: json!("second memo"),
             embed: None,
             embed_hint: None,
+            normalize_on_insert: false,
             tags: None,
             score: None,
             prob: None,
@@ -1549,6 +4900,7 @@ mod tests {
             links: None,
             extra: None,
             hash: None,
+            on_conflict: OnConflict::default(),
         };
         let args_again = insert_owned.to_args();
         let id_again = store.insert_memory(&args_again).unwrap();
@@ -1702,8 +5054,9 @@ mod tests {
         owned.embed = Some(vec![0.5, -0.5, 0.25]);
         let args = owned.to_args();
         let id = store.insert_memory(&args).unwrap();
+        // Rewrite the row into the legacy text-only shape the backfill targets.
         conn.execute(
-            "UPDATE memory_records SET embed_blob = NULL WHERE id = ?",
+            "UPDATE memory_records SET embed_blob = NULL, embed = '[0.5,-0.5,0.25]' WHERE id = ?",
             params![&id],
         )
         .unwrap();
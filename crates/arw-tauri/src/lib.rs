@@ -41,6 +41,19 @@ struct ServiceProcess {
     writer: Option<SharedLogWriter>,
 }
 
+impl ServiceProcess {
+    /// Kill the child, wait for it to exit, and join the log pump threads.
+    /// Returns the log plumbing so callers can record a final line.
+    fn shutdown(mut self) -> (Option<SharedLogWriter>, Option<PathBuf>) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        for handle in self.threads.drain(..) {
+            let _ = handle.join();
+        }
+        (self.writer, self.log_path)
+    }
+}
+
 #[derive(Clone)]
 struct LogRecord {
     stream: &'static str,
@@ -692,6 +705,191 @@ pub fn save_prefs(namespace: Option<&str>, value: &Value) -> Result<()> {
     Ok(())
 }
 
+/// Canonicalize an appearance mode string, accepting only `system|light|dark`.
+fn normalize_appearance_mode(mode: &str) -> Option<&'static str> {
+    match mode.trim().to_ascii_lowercase().as_str() {
+        "system" => Some("system"),
+        "light" => Some("light"),
+        "dark" => Some("dark"),
+        _ => None,
+    }
+}
+
+/// Record the appearance choice in a prefs document, replacing any
+/// non-object root so a corrupt prefs file cannot wedge the setting.
+fn set_appearance_pref(prefs: &mut Value, mode: &str) {
+    if !prefs.is_object() {
+        *prefs = json!({});
+    }
+    prefs["appearance"] = json!(mode);
+}
+
+/// Clamp a window's top-left corner so the window stays inside a monitor
+/// rect, pinning to the monitor origin when the window is larger than the
+/// monitor.
+fn clamp_rect_to_monitor(
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32,
+    mon_x: i32,
+    mon_y: i32,
+    mon_w: u32,
+    mon_h: u32,
+) -> (i32, i32) {
+    let w = i32::try_from(w).unwrap_or(i32::MAX);
+    let h = i32::try_from(h).unwrap_or(i32::MAX);
+    let mon_w = i32::try_from(mon_w).unwrap_or(i32::MAX);
+    let mon_h = i32::try_from(mon_h).unwrap_or(i32::MAX);
+    let max_x = mon_x.saturating_add(mon_w.saturating_sub(w)).max(mon_x);
+    let max_y = mon_y.saturating_add(mon_h.saturating_sub(h)).max(mon_y);
+    (x.clamp(mon_x, max_x), y.clamp(mon_y, max_y))
+}
+
+/// Store a named layout preset in a launcher prefs document.
+fn set_layout_preset(prefs: &mut Value, name: &str, layout: Value) {
+    if !prefs.is_object() {
+        *prefs = json!({});
+    }
+    let presets = prefs
+        .as_object_mut()
+        .expect("prefs coerced to object above")
+        .entry("layout_presets")
+        .or_insert_with(|| json!({}));
+    if !presets.is_object() {
+        *presets = json!({});
+    }
+    presets[name] = layout;
+}
+
+/// Fetch a named layout preset (label -> geometry map) from prefs.
+fn layout_preset(prefs: &Value, name: &str) -> Option<Value> {
+    prefs.get("layout_presets")?.get(name).cloned()
+}
+
+/// All preset names currently stored in prefs, sorted for stable UI lists.
+fn layout_preset_names(prefs: &Value) -> Vec<String> {
+    let mut names: Vec<String> = prefs
+        .get("layout_presets")
+        .and_then(|v| v.as_object())
+        .map(|m| m.keys().cloned().collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Short-lived cache for idempotent admin GETs, keyed by path+port, so
+/// several open windows polling the same endpoints within the TTL share
+/// one network round-trip.
+fn admin_cache() -> &'static Mutex<BTreeMap<(String, Option<u16>), (std::time::Instant, Value)>> {
+    static CACHE: OnceCell<Mutex<BTreeMap<(String, Option<u16>), (std::time::Instant, Value)>>> =
+        OnceCell::new();
+    CACHE.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Cache TTL; `ARW_ADMIN_CACHE_MS` overrides the 500ms default (0 disables).
+fn admin_cache_ttl() -> Duration {
+    let ms = std::env::var("ARW_ADMIN_CACHE_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(500);
+    Duration::from_millis(ms)
+}
+
+fn admin_cache_clear() {
+    admin_cache()
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .clear();
+}
+
+fn admin_cache_lookup(path: &str, port: Option<u16>, ttl: Duration) -> Option<Value> {
+    if ttl.is_zero() {
+        return None;
+    }
+    let cache = admin_cache()
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    cache
+        .get(&(path.to_string(), port))
+        .filter(|(stored, _)| stored.elapsed() < ttl)
+        .map(|(_, value)| value.clone())
+}
+
+fn admin_cache_store(path: &str, port: Option<u16>, value: &Value) {
+    admin_cache()
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(
+            (path.to_string(), port),
+            (std::time::Instant::now(), value.clone()),
+        );
+}
+
+/// Serve `path`+`port` from cache when fresh, otherwise run `fetch` and
+/// remember its result. Split from the async command path so tests can
+/// inject a fetcher.
+fn admin_cached_or_fetch<F>(
+    path: &str,
+    port: Option<u16>,
+    ttl: Duration,
+    fetch: F,
+) -> Result<Value, String>
+where
+    F: FnOnce() -> Result<Value, String>,
+{
+    if let Some(value) = admin_cache_lookup(path, port, ttl) {
+        return Ok(value);
+    }
+    let value = fetch()?;
+    if !ttl.is_zero() {
+        admin_cache_store(path, port, &value);
+    }
+    Ok(value)
+}
+
+/// Cancellation registry for the models progress watcher. Only one watcher
+/// runs per process; starting a new one displaces (and stops) the previous
+/// task so windows never receive duplicate event streams.
+fn models_progress_watch() -> &'static Mutex<Option<Arc<std::sync::atomic::AtomicBool>>> {
+    static WATCH: OnceCell<Mutex<Option<Arc<std::sync::atomic::AtomicBool>>>> = OnceCell::new();
+    WATCH.get_or_init(|| Mutex::new(None))
+}
+
+/// Swap in a new cancellation flag, signalling any previous watcher to stop.
+fn register_models_progress_watch(
+    cancel: Arc<std::sync::atomic::AtomicBool>,
+) -> Option<Arc<std::sync::atomic::AtomicBool>> {
+    let mut slot = models_progress_watch()
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let prev = slot.replace(cancel);
+    if let Some(flag) = &prev {
+        flag.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+    prev
+}
+
+/// Signal the active watcher (if any) to stop. Returns whether one was running.
+fn cancel_models_progress_watch() -> bool {
+    let mut slot = models_progress_watch()
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    match slot.take() {
+        Some(flag) => {
+            flag.store(true, std::sync::atomic::Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Keep poll intervals sane: fast enough for a progress bar, slow enough to
+/// avoid hammering the service.
+fn clamp_watch_interval(interval_ms: Option<u64>) -> Duration {
+    Duration::from_millis(interval_ms.unwrap_or(1_000).clamp(250, 60_000))
+}
+
 mod cmds {
     use super::*;
 
@@ -1636,24 +1834,46 @@ mod cmds {
         state: tauri::State<'_, ServiceState>,
         _port: Option<u16>,
     ) -> Result<(), String> {
-        if let Some(mut process) = state.inner.lock().map_err(|e| e.to_string())?.take() {
-            let _ = process.child.kill();
-            let _ = process.child.wait();
-            for handle in process.threads.drain(..) {
-                let _ = handle.join();
-            }
+        if let Some(process) = state.inner.lock().map_err(|e| e.to_string())?.take() {
+            let (writer, log_path) = process.shutdown();
             capture_line(
                 &app,
                 "launcher",
                 "launcher requested service stop",
-                process.writer.as_ref(),
+                writer.as_ref(),
                 &state.recent,
-                process.log_path.as_deref(),
+                log_path.as_deref(),
             );
         }
         Ok(())
     }
 
+    /// Relaunch the launcher process itself (e.g. after an in-place update).
+    /// Stops the managed service first so no orphaned child survives the
+    /// restart. `confirm` must be true; callers should prompt the user.
+    #[tauri::command]
+    pub async fn relaunch_launcher<R: tauri::Runtime + 'static>(
+        app: tauri::AppHandle<R>,
+        state: tauri::State<'_, ServiceState>,
+        confirm: bool,
+    ) -> Result<(), String> {
+        if !confirm {
+            return Err("relaunch requires confirmation".into());
+        }
+        if let Some(process) = state.inner.lock().map_err(|e| e.to_string())?.take() {
+            let (writer, log_path) = process.shutdown();
+            capture_line(
+                &app,
+                "launcher",
+                "launcher stopping service before relaunch",
+                writer.as_ref(),
+                &state.recent,
+                log_path.as_deref(),
+            );
+        }
+        app.restart();
+    }
+
     #[tauri::command]
     pub fn get_prefs(namespace: Option<String>) -> Result<Value, String> {
         Ok(load_prefs(namespace.as_deref()))
@@ -1664,6 +1884,146 @@ mod cmds {
         save_prefs(namespace.as_deref(), &value).map_err(|e| e.to_string())
     }
 
+    #[tauri::command]
+    pub fn set_appearance<R: tauri::Runtime>(
+        app: tauri::AppHandle<R>,
+        mode: String,
+    ) -> Result<(), String> {
+        let mode = normalize_appearance_mode(&mode)
+            .ok_or_else(|| format!("invalid appearance mode: {} (use system|light|dark)", mode))?;
+        let mut prefs = load_prefs(Some("launcher"));
+        set_appearance_pref(&mut prefs, mode);
+        save_prefs(Some("launcher"), &prefs).map_err(|e| e.to_string())?;
+        let theme = match mode {
+            "light" => Some(tauri::Theme::Light),
+            "dark" => Some(tauri::Theme::Dark),
+            _ => None,
+        };
+        for window in app.webview_windows().values() {
+            let _ = window.set_theme(theme);
+        }
+        let _ = app.emit(
+            "launcher://appearance",
+            json!({
+                "mode": mode,
+                "timestamp": SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or_default()
+            }),
+        );
+        Ok(())
+    }
+
+    /// Capture every open window's label, URL, and geometry as a named
+    /// layout preset in launcher prefs.
+    #[tauri::command]
+    pub fn save_layout_preset<R: tauri::Runtime>(
+        app: tauri::AppHandle<R>,
+        name: String,
+    ) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("preset name required".into());
+        }
+        let mut layout = Map::new();
+        for (label, window) in app.webview_windows() {
+            let (Ok(pos), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+                continue;
+            };
+            layout.insert(
+                label,
+                json!({
+                    "x": pos.x,
+                    "y": pos.y,
+                    "w": size.width,
+                    "h": size.height,
+                    "url": window.url().ok().map(|u| u.to_string()),
+                }),
+            );
+        }
+        let mut prefs = load_prefs(Some("launcher"));
+        set_layout_preset(&mut prefs, name, Value::Object(layout));
+        save_prefs(Some("launcher"), &prefs).map_err(|e| e.to_string())
+    }
+
+    /// Re-open and position windows from a saved preset, clamping each to
+    /// the monitor it lands on so stale multi-monitor coordinates don't
+    /// strand windows off-screen.
+    #[tauri::command]
+    pub fn apply_layout_preset<R: tauri::Runtime>(
+        app: tauri::AppHandle<R>,
+        name: String,
+    ) -> Result<(), String> {
+        let prefs = load_prefs(Some("launcher"));
+        let layout = layout_preset(&prefs, name.trim())
+            .ok_or_else(|| format!("unknown layout preset: {}", name.trim()))?;
+        let Some(entries) = layout.as_object() else {
+            return Err("malformed layout preset".into());
+        };
+        let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+        for (label, geom) in entries {
+            let (Some(x), Some(y)) = (
+                geom.get("x").and_then(|v| v.as_i64()),
+                geom.get("y").and_then(|v| v.as_i64()),
+            ) else {
+                continue;
+            };
+            let w = geom.get("w").and_then(|v| v.as_u64()).unwrap_or(800) as u32;
+            let h = geom.get("h").and_then(|v| v.as_u64()).unwrap_or(600) as u32;
+            let window = match app.get_webview_window(label) {
+                Some(w) => w,
+                None => {
+                    let Some(url) = geom.get("url").and_then(|v| v.as_str()) else {
+                        continue;
+                    };
+                    match tauri::WebviewWindowBuilder::new(
+                        &app,
+                        label,
+                        tauri::WebviewUrl::App(url.into()),
+                    )
+                    .build()
+                    {
+                        Ok(w) => w,
+                        Err(_) => continue,
+                    }
+                }
+            };
+            let _ = window.set_size(tauri::PhysicalSize::new(w, h));
+            let target = monitors
+                .iter()
+                .find(|m| {
+                    let rect = m.position();
+                    let size = m.size();
+                    x >= rect.x as i64
+                        && x < rect.x as i64 + size.width as i64
+                        && y >= rect.y as i64
+                        && y < rect.y as i64 + size.height as i64
+                })
+                .or_else(|| monitors.first());
+            let (x, y) = match target {
+                Some(mon) => clamp_rect_to_monitor(
+                    x as i32,
+                    y as i32,
+                    w,
+                    h,
+                    mon.position().x,
+                    mon.position().y,
+                    mon.size().width,
+                    mon.size().height,
+                ),
+                None => (x as i32, y as i32),
+            };
+            let _ = window.set_position(tauri::PhysicalPosition::new(x, y));
+        }
+        Ok(())
+    }
+
+    #[tauri::command]
+    pub fn list_layout_presets() -> Result<Vec<String>, String> {
+        Ok(layout_preset_names(&load_prefs(Some("launcher"))))
+    }
+
     #[tauri::command]
     pub fn launcher_service_log_path() -> Result<Option<String>, String> {
         Ok(service_log_path(true).map(|p| p.display().to_string()))
@@ -2085,23 +2445,108 @@ mod cmds {
         Ok(v)
     }
 
+    /// Import a project folder and surface the result in a typed form the
+    /// hub UI can act on. The current server answers in one shot, so a
+    /// single `launcher://projects-import` completion event is emitted;
+    /// when the endpoint learns to stream, intermediate progress events
+    /// slot in here without changing the command surface.
+    #[tauri::command]
+    pub async fn projects_import_detailed<R: tauri::Runtime>(
+        app: tauri::AppHandle<R>,
+        proj: String,
+        dest: String,
+        src_path: String,
+        mode: Option<String>,
+        port: Option<u16>,
+    ) -> Result<ProjectsImportReport, String> {
+        let body = serde_json::json!({ "dest": dest, "src_path": src_path, "mode": mode });
+        let path = format!("projects/{}/import", urlencoding::encode(&proj));
+        let resp = admin_post_json(&path, body, port).await?;
+        let raw = resp.json::<Value>().await.map_err(|e| e.to_string())?;
+        let report: ProjectsImportReport =
+            serde_json::from_value(raw).map_err(|e| e.to_string())?;
+        let _ = app.emit(
+            "launcher://projects-import",
+            json!({
+                "proj": proj,
+                "imported": report.imported,
+                "skipped": report.skipped,
+                "conflicts": report.conflicts,
+                "done": true,
+                "timestamp": SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or_default()
+            }),
+        );
+        Ok(report)
+    }
+
+    /// Cached JSON GET for read-only admin endpoints (see `admin_cache`).
+    async fn admin_get_json_cached(path: &str, port: Option<u16>) -> Result<Value, String> {
+        let ttl = admin_cache_ttl();
+        if let Some(value) = admin_cache_lookup(path, port, ttl) {
+            return Ok(value);
+        }
+        let resp = admin_get(path, port).await?;
+        let value = resp.json::<Value>().await.map_err(|e| e.to_string())?;
+        if !ttl.is_zero() {
+            admin_cache_store(path, port, &value);
+        }
+        Ok(value)
+    }
+
+    /// Drop all cached admin GET responses so the next call re-fetches.
+    #[tauri::command]
+    pub fn bust_admin_cache() -> Result<(), String> {
+        admin_cache_clear();
+        Ok(())
+    }
+
     #[tauri::command]
     pub async fn models_list(port: Option<u16>) -> Result<Value, String> {
-        let resp = admin_get("admin/models", port).await?;
-        let v = resp.json::<Value>().await.map_err(|e| e.to_string())?;
-        Ok(v)
+        admin_get_json_cached("admin/models", port).await
     }
 
     #[tauri::command]
     pub async fn models_summary(port: Option<u16>) -> Result<Value, String> {
-        let resp = admin_get("admin/models/summary", port).await?;
-        let env = resp.json::<Value>().await.map_err(|e| e.to_string())?;
+        let env = admin_get_json_cached("admin/models/summary", port).await?;
         let summary_raw = env.get("data").cloned().unwrap_or(env);
         let summary: ModelsSummary =
             serde_json::from_value(summary_raw).map_err(|e| e.to_string())?;
         serde_json::to_value(summary).map_err(|e| e.to_string())
     }
 
+    /// Typed view over the recent egress ledger (`/state/egress`), so the
+    /// connections window doesn't re-parse raw JSON.
+    #[tauri::command]
+    pub async fn egress_ledger(
+        port: Option<u16>,
+        limit: Option<i64>,
+    ) -> Result<Vec<EgressEntry>, String> {
+        let path = format!("state/egress?limit={}", limit.unwrap_or(200).clamp(1, 2000));
+        let resp = admin_get(&path, port).await?;
+        let env = resp.json::<Value>().await.map_err(|e| e.to_string())?;
+        let items = env
+            .get("items")
+            .cloned()
+            .unwrap_or_else(|| Value::Array(Vec::new()));
+        serde_json::from_value(items).map_err(|e| e.to_string())
+    }
+
+    /// Aggregate egress metrics from the same endpoint (lease mint/refresh
+    /// counters grouped by scope).
+    #[tauri::command]
+    pub async fn egress_summary(port: Option<u16>) -> Result<EgressSummarySnapshot, String> {
+        let resp = admin_get("state/egress?limit=1", port).await?;
+        let env = resp.json::<Value>().await.map_err(|e| e.to_string())?;
+        let metrics = env
+            .get("metrics")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({}));
+        serde_json::from_value(metrics).map_err(|e| e.to_string())
+    }
+
     #[tauri::command]
     pub async fn models_concurrency_get(
         port: Option<u16>,
@@ -2131,9 +2576,7 @@ mod cmds {
 
     #[tauri::command]
     pub async fn models_jobs(port: Option<u16>) -> Result<Value, String> {
-        let resp = admin_get("admin/models/jobs", port).await?;
-        let v = resp.json::<Value>().await.map_err(|e| e.to_string())?;
-        Ok(v)
+        admin_get_json_cached("admin/models/jobs", port).await
     }
 
     #[tauri::command]
@@ -2257,6 +2700,50 @@ mod cmds {
         Ok(())
     }
 
+    /// Poll the models jobs/summary endpoints in the background and push
+    /// `launcher://models-progress` events so windows don't poll individually.
+    #[tauri::command]
+    pub async fn watch_models_progress<R: tauri::Runtime>(
+        app: tauri::AppHandle<R>,
+        port: Option<u16>,
+        interval_ms: Option<u64>,
+    ) -> Result<(), String> {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        let interval = clamp_watch_interval(interval_ms);
+        let cancel = Arc::new(AtomicBool::new(false));
+        register_models_progress_watch(cancel.clone());
+        tauri::async_runtime::spawn(async move {
+            loop {
+                if cancel.load(Ordering::SeqCst) {
+                    break;
+                }
+                let jobs = models_jobs(port).await.unwrap_or(Value::Null);
+                let summary = models_summary(port).await.unwrap_or(Value::Null);
+                if cancel.load(Ordering::SeqCst) {
+                    break;
+                }
+                let _ = app.emit(
+                    "launcher://models-progress",
+                    json!({
+                        "jobs": jobs,
+                        "summary": summary,
+                        "timestamp": SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_secs_f64())
+                            .unwrap_or_default()
+                    }),
+                );
+                tokio::time::sleep(interval).await;
+            }
+        });
+        Ok(())
+    }
+
+    #[tauri::command]
+    pub fn stop_watch_models_progress() -> Result<bool, String> {
+        Ok(cancel_models_progress_watch())
+    }
+
     #[tauri::command]
     pub async fn models_download_cancel(id: String, port: Option<u16>) -> Result<(), String> {
         let body = serde_json::json!({"id": id});
@@ -2293,10 +2780,15 @@ mod cmds {
                 position_window,
                 smart_snap_window,
                 run_trials_preflight,
+                egress_ledger,
+                egress_summary,
                 models_summary,
                 models_concurrency_get,
                 models_concurrency_set,
                 models_jobs,
+                watch_models_progress,
+                stop_watch_models_progress,
+                bust_admin_cache,
                 state_models_hashes,
                 models_list,
                 models_refresh,
@@ -2310,12 +2802,18 @@ mod cmds {
                 models_download_cancel,
                 run_tool_admin,
                 projects_import,
+                projects_import_detailed,
                 projects_file_get,
                 projects_file_set,
                 start_service,
                 stop_service,
+                relaunch_launcher,
                 get_prefs,
                 set_prefs,
+                set_appearance,
+                save_layout_preset,
+                apply_layout_preset,
+                list_layout_presets,
                 launcher_service_log_path,
                 launcher_recent_service_logs,
                 launcher_autostart_status,
@@ -2333,6 +2831,65 @@ mod cmds {
 
 // Re-export commands at crate root for existing callers
 pub use cmds::*;
+/// Typed result of a project folder import, tolerating servers that omit
+/// counters or report extra fields.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ProjectsImportReport {
+    pub imported: u64,
+    pub skipped: u64,
+    pub conflicts: Vec<String>,
+    #[serde(flatten, default)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// One egress ledger row as served by `/state/egress`, mirroring the
+/// kernel's `egress_ledger` columns plus the scope metadata the server
+/// hoists out of `meta`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct EgressEntry {
+    pub id: i64,
+    pub time: String,
+    pub decision: String,
+    pub reason: Option<String>,
+    pub dest_host: Option<String>,
+    pub dest_port: Option<i64>,
+    pub protocol: Option<String>,
+    pub bytes_in: Option<i64>,
+    pub bytes_out: Option<i64>,
+    pub corr_id: Option<String>,
+    pub proj: Option<String>,
+    pub posture: Option<String>,
+    pub allowed_via: Option<String>,
+    pub policy_scope: Option<Value>,
+    pub meta: Option<Value>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct EgressScopeLeaseSummary {
+    pub minted: u64,
+    pub refreshed: u64,
+    pub last_capability: Option<String>,
+    pub last_reason: Option<String>,
+    pub last_ttl_until: Option<String>,
+    pub last_minted_at: Option<String>,
+    pub last_minted_epoch: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct EgressSummarySnapshot {
+    pub minted_total: u64,
+    pub refreshed_total: u64,
+    pub scope_leases: BTreeMap<String, EgressScopeLeaseSummary>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ModelsConcurrencySnapshot {
     pub configured_max: u64,
@@ -2407,3 +2964,230 @@ pub struct ModelsSummary {
     #[serde(flatten, default)]
     pub extra: BTreeMap<String, Value>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appearance_modes_validate_and_persist_into_prefs() {
+        assert_eq!(normalize_appearance_mode(" Dark "), Some("dark"));
+        assert_eq!(normalize_appearance_mode("SYSTEM"), Some("system"));
+        assert_eq!(normalize_appearance_mode("sepia"), None);
+        assert_eq!(normalize_appearance_mode(""), None);
+
+        let mut prefs = json!({"other": true});
+        set_appearance_pref(&mut prefs, "light");
+        assert_eq!(prefs["appearance"], "light");
+        assert_eq!(prefs["other"], true);
+
+        // A corrupt (non-object) prefs root is replaced rather than erroring.
+        let mut prefs = json!("garbage");
+        set_appearance_pref(&mut prefs, "dark");
+        assert_eq!(prefs, json!({"appearance": "dark"}));
+    }
+
+    #[test]
+    fn models_progress_watch_lifecycle_replaces_and_cancels() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        assert_eq!(
+            clamp_watch_interval(Some(1)),
+            Duration::from_millis(250),
+            "interval floor"
+        );
+        assert_eq!(
+            clamp_watch_interval(Some(600_000)),
+            Duration::from_millis(60_000),
+            "interval ceiling"
+        );
+        assert_eq!(clamp_watch_interval(None), Duration::from_millis(1_000));
+
+        // Starting a watcher registers its flag; a replacement cancels it.
+        let first = Arc::new(AtomicBool::new(false));
+        assert!(register_models_progress_watch(first.clone()).is_none());
+        let second = Arc::new(AtomicBool::new(false));
+        let displaced = register_models_progress_watch(second.clone()).expect("first displaced");
+        assert!(displaced.load(Ordering::SeqCst), "displaced watcher stopped");
+        assert!(first.load(Ordering::SeqCst));
+        assert!(!second.load(Ordering::SeqCst), "active watcher keeps running");
+
+        // Stop cancels the active watcher; a second stop is a no-op.
+        assert!(cancel_models_progress_watch());
+        assert!(second.load(Ordering::SeqCst));
+        assert!(!cancel_models_progress_watch());
+    }
+
+    #[test]
+    fn admin_cache_serves_second_call_without_refetching() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        admin_cache_clear();
+        let ttl = Duration::from_millis(60_000);
+        let fetches = AtomicUsize::new(0);
+        let fetch = |payload: &'static str| {
+            let fetches = &fetches;
+            move || -> Result<Value, String> {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                Ok(json!({"payload": payload}))
+            }
+        };
+
+        let first = admin_cached_or_fetch("admin/models", Some(8091), ttl, fetch("a")).unwrap();
+        let second = admin_cached_or_fetch("admin/models", Some(8091), ttl, fetch("b")).unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1, "second call served from cache");
+        assert_eq!(first, second);
+
+        // Different port is a different key; zero TTL bypasses the cache.
+        admin_cached_or_fetch("admin/models", Some(8092), ttl, fetch("c")).unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+        admin_cached_or_fetch("admin/models", Some(8091), Duration::ZERO, fetch("d")).unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 3, "TTL 0 always refetches");
+
+        // Busting forces the next call back to the network.
+        admin_cache_clear();
+        let busted = admin_cached_or_fetch("admin/models", Some(8091), ttl, fetch("e")).unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 4);
+        assert_eq!(busted["payload"], "e");
+        admin_cache_clear();
+    }
+
+    #[test]
+    fn layout_presets_round_trip_two_window_coordinates() {
+        let mut prefs = json!({});
+        let layout = json!({
+            "main": {"x": 100, "y": 80, "w": 1000, "h": 800, "url": "index.html"},
+            "logs": {"x": 1200, "y": 40, "w": 900, "h": 700, "url": "logs.html"}
+        });
+        set_layout_preset(&mut prefs, "dev", layout.clone());
+        set_layout_preset(&mut prefs, "demo", json!({}));
+
+        assert_eq!(layout_preset(&prefs, "dev"), Some(layout));
+        assert_eq!(layout_preset(&prefs, "missing"), None);
+        assert_eq!(layout_preset_names(&prefs), vec!["demo", "dev"]);
+
+        let restored = layout_preset(&prefs, "dev").unwrap();
+        assert_eq!(restored["main"]["x"], 100);
+        assert_eq!(restored["logs"]["w"], 900);
+
+        // Geometry clamps into the monitor it targets.
+        assert_eq!(
+            clamp_rect_to_monitor(100, 80, 1000, 800, 0, 0, 1920, 1080),
+            (100, 80),
+            "in-bounds rect untouched"
+        );
+        assert_eq!(
+            clamp_rect_to_monitor(1500, 900, 1000, 800, 0, 0, 1920, 1080),
+            (920, 280),
+            "overflowing rect pulled back inside"
+        );
+        assert_eq!(
+            clamp_rect_to_monitor(-50, -50, 3000, 2000, 0, 0, 1920, 1080),
+            (0, 0),
+            "oversized window pins to monitor origin"
+        );
+    }
+
+    #[test]
+    fn projects_import_report_deserializes_sample_response() {
+        let sample = serde_json::json!({
+            "imported": 42,
+            "skipped": 3,
+            "conflicts": ["notes/readme.md", "src/main.rs"],
+            "elapsed_ms": 1250
+        });
+        let report: ProjectsImportReport =
+            serde_json::from_value(sample).expect("report decodes");
+        assert_eq!(report.imported, 42);
+        assert_eq!(report.skipped, 3);
+        assert_eq!(report.conflicts, vec!["notes/readme.md", "src/main.rs"]);
+        assert_eq!(report.extra["elapsed_ms"], 1250);
+
+        // Sparse responses fall back to defaults instead of failing.
+        let sparse: ProjectsImportReport =
+            serde_json::from_value(serde_json::json!({"imported": 1})).expect("sparse decodes");
+        assert_eq!(sparse.imported, 1);
+        assert_eq!(sparse.skipped, 0);
+        assert!(sparse.conflicts.is_empty());
+    }
+
+    #[test]
+    fn egress_response_deserializes_into_typed_rows() {
+        let sample = serde_json::json!({
+            "version": 7,
+            "count": 1,
+            "items": [{
+                "id": 7,
+                "time": "2026-01-01T00:00:00.000Z",
+                "decision": "allow",
+                "reason": null,
+                "dest_host": "example.test",
+                "dest_port": 443,
+                "protocol": "https",
+                "bytes_in": 1024,
+                "bytes_out": 256,
+                "corr_id": "corr-1",
+                "proj": "demo",
+                "posture": "standard",
+                "allowed_via": "lease",
+                "meta": {"allowed_via": "lease"}
+            }],
+            "metrics": {
+                "minted_total": 3,
+                "refreshed_total": 1,
+                "scope_leases": {
+                    "net:http": {"minted": 2, "refreshed": 1, "last_capability": "net:http"}
+                }
+            }
+        });
+        let rows: Vec<EgressEntry> =
+            serde_json::from_value(sample["items"].clone()).expect("rows decode");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, 7);
+        assert_eq!(rows[0].decision, "allow");
+        assert_eq!(rows[0].dest_host.as_deref(), Some("example.test"));
+        assert_eq!(rows[0].allowed_via.as_deref(), Some("lease"));
+
+        let summary: EgressSummarySnapshot =
+            serde_json::from_value(sample["metrics"].clone()).expect("summary decodes");
+        assert_eq!(summary.minted_total, 3);
+        assert_eq!(summary.scope_leases["net:http"].minted, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shutdown_stops_child_and_joins_threads() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let child = Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("spawn mock child");
+        let joined = Arc::new(AtomicBool::new(false));
+        let flag = joined.clone();
+        let pump = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            flag.store(true, Ordering::SeqCst);
+        });
+        let process = ServiceProcess {
+            child,
+            threads: vec![pump],
+            log_path: None,
+            writer: None,
+        };
+        let started = std::time::Instant::now();
+        let (writer, log_path) = process.shutdown();
+        assert!(writer.is_none());
+        assert!(log_path.is_none());
+        assert!(
+            joined.load(Ordering::SeqCst),
+            "log pump threads joined before shutdown returned"
+        );
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "child exited promptly instead of running out its sleep"
+        );
+    }
+}